@@ -1,8 +1,24 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::io::IsTerminal;
+use std::time::Duration;
+
+use ntex::{rt, ws};
 use futures::StreamExt;
-use bollard_next::exec::CreateExecOptions;
+use dialoguer::console;
+use indicatif::{MultiProgress, ProgressBar};
 
-use nanocl_utils::io_error::{FromIo, IoResult};
-use nanocld_client::stubs::cargo::{OutputKind, CargoDeleteQuery, CargoLogQuery};
+use nanocl_utils::io_error::{FromIo, IoError, IoResult};
+use nanocld_client::NanocldClient;
+use nanocld_client::stubs::node::NodeContainerSummary;
+use nanocld_client::stubs::cargo::{
+  OutputKind, CargoInspect, CargoSummary, CargoDeleteQuery, CargoLogQuery,
+  CargoExecQuery, CargoStatsQuery,
+};
+use nanocld_client::stubs::cargo_config::{
+  CargoConfigPartial, CargoConfigUpdate, Config as ContainerConfig,
+  ReplicationMode,
+};
 
 use crate::utils;
 use crate::config::CliConfig;
@@ -10,11 +26,133 @@ use crate::models::{
   CargoArg, CargoCreateOpts, CargoCommand, CargoRemoveOpts, CargoRow,
   CargoStartOpts, CargoStopOpts, CargoPatchOpts, CargoInspectOpts,
   CargoExecOpts, CargoHistoryOpts, CargoRevertOpts, CargoLogsOpts,
-  CargoRunOpts, CargoRestartOpts, CargoListOpts,
+  CargoRunOpts, CargoRestartOpts, CargoListOpts, CargoScaleOpts,
+  CargoRevisionRow, CargoStatsRow, CargoStatusSummary, CargoListSort,
+  CargoRenameOpts, display_dns, display_replication, desired_replicas,
 };
 
 use super::cargo_image::{self, exec_cargo_image_pull};
 
+/// ## Validate replication
+///
+/// Validate the replication of a cargo before sending it to the daemon
+///
+/// ## Arguments
+///
+/// * [name](str) The name of the cargo
+/// * [replication](Option<ReplicationMode>) The replication of the cargo
+///
+/// ## Return
+///
+/// * [Result](Result) Result of the operation
+///   * [Ok](()) The replication is valid
+///   * [Err](nanocl_utils::io_error::IoError) The constraint that failed
+///
+pub(crate) fn validate_replication(
+  name: &str,
+  replication: &Option<ReplicationMode>,
+) -> IoResult<()> {
+  if let Some(replication) = replication {
+    replication.validate().map_err(|err| {
+      IoError::invalid_input(format!("Cargo {name} replication"), err)
+    })?;
+  }
+  Ok(())
+}
+
+/// ## Validate image
+///
+/// Ensure the container of a cargo has a non empty image,
+/// otherwise the daemon fail with an opaque error.
+/// When both the image and the cmd are empty a warning is printed
+/// since the container config is most likely misplaced
+///
+/// ## Arguments
+///
+/// * [name](str) The name of the cargo
+/// * [container](ContainerConfig) The container config of the cargo
+///
+/// ## Return
+///
+/// * [Result](Result) Result of the operation
+///   * [Ok](()) The image is set
+///   * [Err](nanocl_utils::io_error::IoError) The image is missing
+///
+pub(crate) fn validate_image(
+  name: &str,
+  container: &ContainerConfig,
+) -> IoResult<()> {
+  let is_empty = |image: &Option<String>| {
+    image
+      .as_deref()
+      .map(str::trim)
+      .unwrap_or_default()
+      .is_empty()
+  };
+  if !is_empty(&container.image) {
+    return Ok(());
+  }
+  if container.cmd.clone().unwrap_or_default().is_empty() {
+    eprintln!(
+      "Warning: cargo {name} has neither Container.Image nor Container.Cmd, \
+      is the container config at the right place?"
+    );
+  }
+  Err(IoError::invalid_input(
+    format!("Cargo {name}"),
+    "missing field Container.Image".to_owned(),
+  ))
+}
+
+/// ## Check hostname conflict
+///
+/// Ensure no other cargo of the namespace already use the hostname
+/// in the same domain, otherwise the conflict would only surface later
+/// when resolving it
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [namespace](Option<String>) The namespace of the cargo
+/// * [cargo](CargoConfigPartial) The cargo to create
+///
+/// ## Return
+///
+/// * [Result](Result) Result of the operation
+///   * [Ok](()) The hostname is free
+///   * [Err](nanocl_utils::io_error::IoError) The hostname is already used
+///
+async fn check_hostname_conflict(
+  cli_conf: &CliConfig,
+  namespace: &Option<String>,
+  cargo: &CargoConfigPartial,
+) -> IoResult<()> {
+  if cargo.container.hostname.is_none() {
+    return Ok(());
+  }
+  // The hostname is compared with its domain name like `web.example.com`
+  let hostname = display_dns(&cargo.container);
+  let cargoes = cli_conf.client.list_cargo(namespace.clone()).await?;
+  let conflict = cargoes.into_iter().find(|existing| {
+    existing.name != cargo.name
+      && existing.config.container.hostname.is_some()
+      && display_dns(&existing.config.container) == hostname
+  });
+  if let Some(existing) = conflict {
+    return Err(IoError::new(
+      "Cargo create",
+      std::io::Error::new(
+        std::io::ErrorKind::AlreadyExists,
+        format!(
+          "hostname {hostname} is already used by cargo {} in namespace {}, use --force to create it anyway",
+          existing.name, existing.namespace_name
+        ),
+      ),
+    ));
+  }
+  Ok(())
+}
+
 /// ## Exec cargo create
 ///
 /// Execute the `nanocl cargo create` command to create a new cargo
@@ -37,15 +175,43 @@ async fn exec_cargo_create(
   opts: &CargoCreateOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
-  let cargo = opts.clone().into();
-  let item = client.create_cargo(&cargo, args.namespace.clone()).await?;
-  println!("{}", &item.key);
+  let mut cargo: CargoConfigPartial = opts.clone().into();
+  cargo.name =
+    utils::naming::resolve_name("Cargo", &opts.name, opts.normalize)?;
+  validate_image(&cargo.name, &cargo.container)?;
+  validate_replication(&cargo.name, &cargo.replication)?;
+  if !opts.force {
+    check_hostname_conflict(
+      cli_conf,
+      &cli_conf.resolve_namespace(&args.namespace),
+      &cargo,
+    )
+    .await?;
+  }
+  let namespace = cli_conf.resolve_namespace(&args.namespace);
+  let (item, existed) = utils::client::retry_create(cli_conf, |key| {
+    let namespace = namespace.clone();
+    let cargo = &cargo;
+    async move { client.create_cargo_idempotent(cargo, namespace, &key).await }
+  })
+  .await?;
+  if !cli_conf.quiet {
+    match existed {
+      true => eprintln!("Cargo {} already existed (idempotent)", item.name),
+      false => eprintln!("Cargo {} created", item.name),
+    }
+    println!("{}", &item.key);
+  }
   Ok(())
 }
 
 /// ## Exec cargo rm
 ///
-/// Execute the `nanocl cargo rm` command to remove a cargo
+/// Execute the `nanocl cargo rm` command to remove a cargo,
+/// with `--all` every cargo of the namespace is removed.
+/// Cargoes are removed concurrently up to the `--concurrency` limit,
+/// every cargo is attempted even if another one failed,
+/// with `--ignore-not-found` the ones that don't exist are not failures.
 ///
 /// ## Arguments
 ///
@@ -65,23 +231,228 @@ async fn exec_cargo_rm(
   opts: &CargoRemoveOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
+  let names = match opts.all {
+    true => client
+      .list_cargo(cli_conf.resolve_namespace(&args.namespace))
+      .await?
+      .into_iter()
+      .map(|cargo| cargo.name)
+      .collect::<Vec<_>>(),
+    false => opts.names.clone(),
+  };
+  if names.is_empty() {
+    return Ok(());
+  }
   if !opts.skip_confirm {
-    utils::dialog::confirm(&format!("Delete cargo  {}?", opts.names.join(",")))
+    utils::dialog::confirm(&format!("Delete cargo  {}?", names.join(",")))
       .map_err(|err| err.map_err_context(|| "Delete cargo images"))?;
   }
   let query = CargoDeleteQuery {
-    namespace: args.namespace.clone(),
+    namespace: cli_conf.resolve_namespace(&args.namespace),
     force: Some(opts.force),
   };
-  for name in &opts.names {
-    client.delete_cargo(name, &query).await?;
+  let query = &query;
+  let deletes = names
+    .iter()
+    .map(|name| async move { (name, client.delete_cargo(name, query).await) });
+  let mut failed = 0;
+  for (name, res) in
+    utils::client::run_concurrent(cli_conf.concurrency, deletes).await
+  {
+    match res {
+      Ok(_) => {}
+      Err(err)
+        if opts.ignore_not_found && utils::client::is_not_found(&err) => {}
+      Err(err) => {
+        eprintln!("Failed to delete cargo {name}: {err}");
+        failed += 1;
+      }
+    }
   }
-  Ok(())
+  check_cargo_failures("delete", failed, names.len())
+}
+
+/// ## Is failed instance
+///
+/// Check if an instance of a cargo is dead, restarting
+/// or exited with a non zero code
+///
+/// ## Arguments
+///
+/// * [instance](NodeContainerSummary) The instance of the cargo
+///
+/// ## Return
+///
+/// * [bool](bool) True if the instance failed
+///
+fn is_failed_instance(instance: &NodeContainerSummary) -> bool {
+  let container = &instance.container;
+  match container.state.as_deref() {
+    Some("dead") | Some("restarting") => true,
+    Some("exited") => !container
+      .status
+      .as_deref()
+      .unwrap_or_default()
+      .starts_with("Exited (0)"),
+    _ => false,
+  }
+}
+
+/// ## Cargo health
+///
+/// Summarize the health reported by the instances of a cargo in their status:
+/// `unhealthy` when one of them is, then `starting`, then `healthy`,
+/// `none` when no instance report a health like when they are stopped
+///
+/// ## Arguments
+///
+/// * [instances](Vec<NodeContainerSummary>) The instances of the cargo
+///
+/// ## Return
+///
+/// * [String](String) The health of the cargo
+///
+fn cargo_health(instances: &[NodeContainerSummary]) -> String {
+  let statuses = instances
+    .iter()
+    .filter_map(|instance| instance.container.status.as_deref())
+    .collect::<Vec<_>>();
+  let health = [
+    ("(unhealthy)", "unhealthy"),
+    ("(health: starting)", "starting"),
+    ("(healthy)", "healthy"),
+  ]
+  .into_iter()
+  .find(|(pattern, _)| statuses.iter().any(|status| status.contains(pattern)))
+  .map_or("none", |(_, health)| health);
+  health.to_owned()
+}
+
+/// ## Health color
+///
+/// Color of a health in the `--show-health` column of `nanocl cargo ls`
+///
+fn health_color(health: &str) -> Option<console::Color> {
+  match health {
+    "healthy" => Some(console::Color::Green),
+    "unhealthy" => Some(console::Color::Red),
+    "starting" => Some(console::Color::Yellow),
+    _ => None,
+  }
+}
+
+/// ## Gen cargo healths
+///
+/// Read the health of the listed cargoes, only the ones with a healthcheck
+/// in their config are inspected, the others are shown as `-`
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [items](Vec<CargoSummary>) The listed cargoes
+///
+/// ## Return
+///
+/// * [Result](Result) Result of the operation
+///   * [Ok](Vec<String>) The health of each cargo
+///   * [Err](nanocl_utils::io_error::IoError) Operation failed
+///
+async fn gen_cargo_healths(
+  cli_conf: &CliConfig,
+  items: &[CargoSummary],
+) -> IoResult<Vec<String>> {
+  let client = &cli_conf.client;
+  let healths = items.iter().map(|cargo| async move {
+    let has_healthcheck = cargo
+      .config
+      .container
+      .healthcheck
+      .as_ref()
+      .and_then(|healthcheck| healthcheck.test.as_ref())
+      .is_some_and(|test| test.first().is_some_and(|kind| kind != "NONE"));
+    if !has_healthcheck {
+      return Ok("-".to_owned());
+    }
+    let namespace = Some(cargo.namespace_name.clone());
+    let cargo = utils::client::with_timeout(
+      cli_conf.timeout,
+      client.inspect_cargo(&cargo.name, namespace),
+    )
+    .await?;
+    Ok::<_, IoError>(cargo_health(&cargo.instances))
+  });
+  utils::client::run_concurrent(cli_conf.concurrency, healths)
+    .await
+    .into_iter()
+    .collect()
+}
+
+/// ## Gen cargo status summary
+///
+/// Count the running, stopped and failed cargoes with their replicas.
+/// A cargo is running when its desired replicas are running,
+/// it's failed when only some of them are running or one of its instances
+/// failed, otherwise it's stopped.
+/// Cargoes without running instances are inspected to find failed ones.
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [items](Vec<CargoSummary>) The listed cargoes
+///
+/// ## Return
+///
+/// * [Result](Result) Result of the operation
+///   * [Ok](CargoStatusSummary) The summary of the cargoes
+///   * [Err](nanocl_utils::io_error::IoError) Operation failed
+///
+async fn gen_cargo_status_summary(
+  cli_conf: &CliConfig,
+  items: &[CargoSummary],
+) -> IoResult<CargoStatusSummary> {
+  let client = &cli_conf.client;
+  let mut summary = CargoStatusSummary {
+    cargoes: items.len(),
+    ..Default::default()
+  };
+  let mut stopped = Vec::new();
+  for cargo in items {
+    let desired =
+      desired_replicas(&cargo.config.replication, cargo.instance_total);
+    summary.desired_replicas += desired;
+    summary.running_replicas += cargo.instance_running;
+    match cargo.instance_running {
+      0 if cargo.instance_total > 0 => stopped.push(cargo),
+      0 => summary.stopped += 1,
+      running if running >= desired => summary.running += 1,
+      _ => summary.failed += 1,
+    }
+  }
+  let inspects = stopped.iter().map(|cargo| {
+    let namespace = Some(cargo.namespace_name.clone());
+    utils::client::with_timeout(
+      cli_conf.timeout,
+      client.inspect_cargo(&cargo.name, namespace),
+    )
+  });
+  for cargo in
+    utils::client::run_concurrent(cli_conf.concurrency, inspects).await
+  {
+    match cargo?.instances.iter().any(is_failed_instance) {
+      true => summary.failed += 1,
+      false => summary.stopped += 1,
+    }
+  }
+  Ok(summary)
 }
 
 /// ## Exec cargo ls
 ///
-/// Execute the `nanocl cargo ls` command to list cargos
+/// Execute the `nanocl cargo ls` command to list cargos,
+/// sorted by name, creation date, replicas or image with `--sort`,
+/// with `--show-health` the cargoes with a healthcheck are inspected
+/// to show the health of their instances,
+/// with `--status-summary` only their status is counted
 ///
 /// ## Arguments
 ///
@@ -101,11 +472,75 @@ async fn exec_cargo_ls(
   opts: &CargoListOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
-  let items = client.list_cargo(args.namespace.clone()).await?;
+  let mut items = utils::client::list_in_namespaces(
+    cli_conf,
+    &args.namespace,
+    opts.all_namespaces,
+    |namespace| client.list_cargo(namespace),
+  )
+  .await?;
+  if opts.all_namespaces {
+    items.sort_by(|a, b| {
+      (&a.namespace_name, &a.name).cmp(&(&b.namespace_name, &b.name))
+    });
+  }
+  if let Some(sort) = &opts.sort {
+    // sort_by is stable so cargoes with equal values keep the previous order
+    items.sort_by(|a, b| {
+      let ordering = match sort {
+        CargoListSort::Name => a.name.cmp(&b.name),
+        CargoListSort::Created => a.created_at.cmp(&b.created_at),
+        CargoListSort::Replicas => {
+          desired_replicas(&a.config.replication, a.instance_total)
+            .cmp(&desired_replicas(&b.config.replication, b.instance_total))
+        }
+        CargoListSort::Image => {
+          a.config.container.image.cmp(&b.config.container.image)
+        }
+      };
+      match opts.reverse {
+        true => ordering.reverse(),
+        false => ordering,
+      }
+    });
+  }
+  if opts.status_summary {
+    let summary = gen_cargo_status_summary(cli_conf, &items).await?;
+    return match cli_conf.structured_output() {
+      Some(format) => {
+        utils::print::display_format_prefixed(format, "CARGO", summary)
+      }
+      None => utils::print::print_table(cli_conf, [summary]),
+    };
+  }
+  // `--output wide` show every optional column
+  let show_image = opts.show_image || cli_conf.is_wide();
+  let show_dns = opts.show_dns || cli_conf.is_wide();
+  let show_replicas = opts.show_replicas || cli_conf.is_wide();
+  let show_health = opts.show_health || cli_conf.is_wide();
+  let healths = match show_health && !opts.quiet {
+    true => gen_cargo_healths(cli_conf, &items).await?,
+    false => Vec::new(),
+  };
+  let mut healths = healths.into_iter();
   let rows = items
     .into_iter()
-    .map(CargoRow::from)
+    .map(|cargo| {
+      let image = show_image
+        .then(|| cargo.config.container.image.clone().unwrap_or_default());
+      let dns = show_dns.then(|| display_dns(&cargo.config.container));
+      let replicas =
+        show_replicas.then(|| display_replication(&cargo.config.replication));
+      CargoRow {
+        image,
+        dns,
+        health: healths.next(),
+        replicas,
+        ..CargoRow::from(cargo)
+      }
+    })
     .collect::<Vec<CargoRow>>();
+  let (rows, footer) = utils::print::paginate(rows, &opts.page);
   match opts.quiet {
     true => {
       for row in rows {
@@ -113,9 +548,34 @@ async fn exec_cargo_ls(
       }
     }
     false => {
-      utils::print::print_table(rows);
+      let mut hidden = Vec::new();
+      if !show_image {
+        hidden.push("image");
+      }
+      if !show_dns {
+        hidden.push("dns");
+      }
+      if !show_replicas {
+        hidden.push("replicas");
+      }
+      if !show_health {
+        hidden.push("health");
+      }
+      let table = utils::print::render_table_without(cli_conf, rows, &hidden)?;
+      match show_health && cli_conf.structured_output().is_none() {
+        true => {
+          print!(
+            "{}",
+            utils::print::color_column(&table, "health", health_color)
+          )
+        }
+        false => print!("{table}"),
+      }
     }
   }
+  if let Some(footer) = footer {
+    eprintln!("{footer}");
+  }
   Ok(())
 }
 
@@ -142,7 +602,7 @@ async fn exec_cargo_start(
 ) -> IoResult<()> {
   let client = &cli_conf.client;
   client
-    .start_cargo(&opts.name, args.namespace.clone())
+    .start_cargo(&opts.name, cli_conf.resolve_namespace(&args.namespace))
     .await?;
   Ok(())
 }
@@ -170,14 +630,121 @@ async fn exec_cargo_stop(
 ) -> IoResult<()> {
   let client = &cli_conf.client;
   for name in &opts.names {
-    client.stop_cargo(name, args.namespace.clone()).await?;
+    client
+      .stop_cargo(name, cli_conf.resolve_namespace(&args.namespace))
+      .await?;
   }
   Ok(())
 }
 
+/// ## Check cargo failures
+///
+/// Summarize the failures of an operation done on multiple cargoes
+///
+/// ## Arguments
+///
+/// * [action](str) The action that was done eg: delete, restart
+/// * [failed](usize) The number of cargoes that failed
+/// * [total](usize) The number of cargoes targeted
+///
+/// ## Return
+///
+/// * [Result](Result) Result of the operation
+///   * [Ok](()) Every cargo succeeded
+///   * [Err](nanocl_utils::io_error::IoError) At least one cargo failed
+///
+fn check_cargo_failures(
+  action: &str,
+  failed: usize,
+  total: usize,
+) -> IoResult<()> {
+  if failed == 0 {
+    return Ok(());
+  }
+  Err(IoError::new(
+    format!("Cargo {action}"),
+    std::io::Error::new(
+      std::io::ErrorKind::Other,
+      format!("{failed} of {total} cargoes failed to {action}"),
+    ),
+  ))
+}
+
+/// ## Wait cargo running
+///
+/// Poll a cargo until every of its instances are running
+/// or the timeout elapsed. With `healthy` the instances reporting
+/// a healthcheck must also be healthy
+///
+/// ## Arguments
+///
+/// * [client](NanocldClient) The client to the daemon
+/// * [name](str) The name of the cargo
+/// * [namespace](Option<String>) The namespace of the cargo
+/// * [timeout](Duration) The maximum time to wait
+/// * [healthy](bool) Also wait for the instances to be healthy
+/// * [pg](ProgressBar) The spinner to update
+///
+/// ## Return
+///
+/// * [Result](Result) Result of the operation
+///   * [Ok](()) The cargo is running
+///   * [Err](nanocl_utils::io_error::IoError) The timeout elapsed
+///
+pub(crate) async fn wait_cargo_running(
+  client: &NanocldClient,
+  name: &str,
+  namespace: Option<String>,
+  timeout: Duration,
+  healthy: bool,
+  pg: ProgressBar,
+) -> IoResult<()> {
+  /// How often the cargo state is polled
+  const POLL_INTERVAL: Duration = Duration::from_millis(500);
+  let start = std::time::Instant::now();
+  loop {
+    let state = match client.inspect_cargo(name, namespace.clone()).await {
+      Ok(cargo)
+        if cargo.instance_total > 0
+          && cargo.instance_running == cargo.instance_total =>
+      {
+        let health = cargo_health(&cargo.instances);
+        if !healthy || matches!(health.as_str(), "healthy" | "none") {
+          pg.finish_with_message(format!("{name} running"));
+          return Ok(());
+        }
+        format!("running but {health}")
+      }
+      Ok(cargo) => format!(
+        "{}/{} instances running",
+        cargo.instance_running, cargo.instance_total
+      ),
+      Err(err) => err.to_string(),
+    };
+    if start.elapsed() >= timeout {
+      pg.abandon_with_message(format!("{name} {state}"));
+      return Err(IoError::new(
+        format!("Cargo {name}"),
+        std::io::Error::new(
+          std::io::ErrorKind::TimedOut,
+          format!(
+            "not running after {}s, last state: {state}",
+            timeout.as_secs()
+          ),
+        ),
+      ));
+    }
+    pg.set_message(format!("{name} {state}"));
+    ntex::time::sleep(POLL_INTERVAL).await;
+  }
+}
+
 /// ## Exec cargo restart
 ///
-/// Execute the `nanocl cargo restart` command to restart a cargo
+/// Execute the `nanocl cargo restart` command to restart cargoes,
+/// with `--all` every cargo of the namespace is restarted.
+/// Every cargo is restarted even if some of them failed,
+/// with `--wait` it wait for their instances to be running.
 ///
 /// ## Arguments
 ///
@@ -197,15 +764,144 @@ async fn exec_cargo_restart(
   opts: &CargoRestartOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
-  for name in &opts.names {
-    client.restart_cargo(name, args.namespace.clone()).await?;
+  let names = match opts.all {
+    true => client
+      .list_cargo(cli_conf.resolve_namespace(&args.namespace))
+      .await?
+      .into_iter()
+      .map(|cargo| cargo.name)
+      .collect::<Vec<_>>(),
+    false => opts.names.clone(),
+  };
+  let mut failed = 0;
+  let mut restarted = Vec::new();
+  for name in &names {
+    if let Err(err) = client
+      .restart_cargo(name, cli_conf.resolve_namespace(&args.namespace))
+      .await
+    {
+      eprintln!("Failed to restart cargo {name}: {err}");
+      failed += 1;
+      continue;
+    }
+    restarted.push(name);
+  }
+  if opts.wait {
+    let timeout = opts.wait_timeout;
+    let multiprogress = MultiProgress::new();
+    let waits = restarted.into_iter().map(|name| {
+      let pg = utils::state::new_spinner(&multiprogress, name);
+      wait_cargo_running(
+        client,
+        name,
+        cli_conf.resolve_namespace(&args.namespace),
+        timeout,
+        false,
+        pg,
+      )
+    });
+    for res in futures::future::join_all(waits).await {
+      if let Err(err) = res {
+        eprintln!("{err}");
+        failed += 1;
+      }
+    }
+  }
+  check_cargo_failures("restart", failed, names.len())
+}
+
+/// ## Read merge patch
+///
+/// Read the JSON Merge Patch given with `--patch` or `--patch-file`
+///
+/// ## Arguments
+///
+/// * [opts](CargoPatchOpts) Cargo patch options
+///
+/// ## Return
+///
+/// * [Result](Result) Result of the operation
+///   * [Ok](Option<serde_json::Value>) The patch if one is given
+///   * [Err](nanocl_utils::io_error::IoError) The patch can't be read or parsed
+///
+fn read_merge_patch(
+  opts: &CargoPatchOpts,
+) -> IoResult<Option<serde_json::Value>> {
+  let content = match (&opts.patch, &opts.patch_file) {
+    (Some(patch), _) => patch.clone(),
+    (None, Some(path)) if path == "-" => {
+      std::io::read_to_string(std::io::stdin()).map_err(|err| {
+        err.map_err_context(|| "Unable to read patch from stdin")
+      })?
+    }
+    (None, Some(path)) => std::fs::read_to_string(path)
+      .map_err(|err| err.map_err_context(|| path.to_owned()))?,
+    (None, None) => return Ok(None),
+  };
+  let patch = serde_json::from_str(&content).map_err(|err| {
+    IoError::invalid_input("Patch", format!("invalid json: {err}").as_str())
+  })?;
+  Ok(Some(patch))
+}
+
+/// ## Exec cargo merge patch
+///
+/// Apply a JSON Merge Patch to the current config of a cargo
+/// and submit the result as its new config.
+/// The result must still be a valid cargo config
+/// and every field of the patch must be known
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [args](CargoArg) Cargo arguments
+/// * [name](str) The name of the cargo
+/// * [patch](serde_json::Value) The merge patch
+///
+/// ## Return
+///
+/// * [Result](Result) Result of the operation
+///   * [Ok](()) Operation was successful
+///   * [Err](nanocl_utils::io_error::IoError) Operation failed
+///
+async fn exec_cargo_merge_patch(
+  cli_conf: &CliConfig,
+  args: &CargoArg,
+  name: &str,
+  patch: serde_json::Value,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  let namespace = cli_conf.resolve_namespace(&args.namespace);
+  let cargo = client.inspect_cargo(name, namespace.clone()).await?;
+  let mut config = serde_json::to_value(CargoConfigPartial::from(cargo))
+    .map_err(|err| err.map_err_context(|| format!("Cargo {name}")))?;
+  utils::state::merge_patch(&mut config, patch.clone());
+  let config =
+    serde_json::from_value::<CargoConfigPartial>(config).map_err(|err| {
+      IoError::invalid_input(
+        "Patch",
+        format!("the patched config of {name} is invalid: {err}").as_str(),
+      )
+    })?;
+  let parsed = serde_json::to_value(&config)
+    .map_err(|err| err.map_err_context(|| format!("Cargo {name}")))?;
+  let unknown = utils::state::unknown_fields(&patch, &parsed);
+  if !unknown.is_empty() {
+    return Err(IoError::invalid_input(
+      "Patch",
+      format!("unknown fields {}", unknown.join(", ")).as_str(),
+    ));
   }
+  validate_replication(name, &config.replication)?;
+  client.put_cargo(name, config, namespace).await?;
   Ok(())
 }
 
 /// ## Exec cargo patch
 ///
-/// Execute the `nanocl cargo patch` command to patch a cargo
+/// Execute the `nanocl cargo patch` command to patch a cargo,
+/// with `--patch` or `--patch-file` a JSON Merge Patch is applied
+/// to its current config instead
 ///
 /// ## Arguments
 ///
@@ -223,18 +919,211 @@ async fn exec_cargo_patch(
   cli_conf: &CliConfig,
   args: &CargoArg,
   opts: &CargoPatchOpts,
+) -> IoResult<()> {
+  if let Some(patch) = read_merge_patch(opts)? {
+    return exec_cargo_merge_patch(cli_conf, args, &opts.name, patch).await;
+  }
+  let client = &cli_conf.client;
+  let cargo: CargoConfigUpdate = opts.clone().into();
+  validate_replication(&opts.name, &cargo.replication)?;
+  client
+    .patch_cargo(
+      &opts.name,
+      cargo,
+      cli_conf.resolve_namespace(&args.namespace),
+    )
+    .await?;
+  Ok(())
+}
+
+/// ## Exec cargo scale
+///
+/// Execute the `nanocl cargo scale` command to change the replication of a cargo
+/// Only the replication is patched, the container config is left untouched
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [args](CargoArg) Cargo arguments
+/// * [opts](CargoScaleOpts) Cargo scale options
+///
+/// ## Return
+///
+/// * [Result](Result) Result of the operation
+///   * [Ok](()) Operation was successful
+///   * [Err](nanocl_utils::io_error::IoError) Operation failed
+///
+async fn exec_cargo_scale(
+  cli_conf: &CliConfig,
+  args: &CargoArg,
+  opts: &CargoScaleOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
-  let cargo = opts.clone().into();
+  let replication = ReplicationMode::from(opts);
+  validate_replication(&opts.name, &Some(replication.clone()))?;
+  let cargo = client
+    .inspect_cargo(&opts.name, cli_conf.resolve_namespace(&args.namespace))
+    .await?;
+  if cargo.config.replication.as_ref() == Some(&replication) {
+    return Ok(());
+  }
+  let update = CargoConfigUpdate {
+    replication: Some(replication),
+    ..Default::default()
+  };
   client
-    .patch_cargo(&opts.name, cargo, args.namespace.clone())
+    .patch_cargo(
+      &opts.name,
+      update,
+      cli_conf.resolve_namespace(&args.namespace),
+    )
     .await?;
   Ok(())
 }
 
+/// ## Rollback cargo rename
+///
+/// Remove the cargo created under the new name and restart the original one
+/// when a rename failed, failures of the rollback are only printed
+/// since the error of the rename is the one returned
+///
+/// ## Arguments
+///
+/// * [client](NanocldClient) The client to the daemon
+/// * [opts](CargoRenameOpts) Cargo rename options
+/// * [namespace](Option<String>) The namespace of the cargo
+/// * [restart](bool) Whether the original cargo was stopped and must be started
+/// * [err](IoError) The error of the rename
+///
+/// ## Return
+///
+/// * [IoError](IoError) The error of the rename
+///
+async fn rollback_cargo_rename(
+  client: &NanocldClient,
+  opts: &CargoRenameOpts,
+  namespace: Option<String>,
+  restart: bool,
+  err: IoError,
+) -> IoError {
+  let query = CargoDeleteQuery {
+    namespace: namespace.clone(),
+    force: Some(true),
+  };
+  if let Err(rollback) = client.delete_cargo(&opts.new_name, &query).await {
+    eprintln!("Failed to remove cargo {}: {rollback}", opts.new_name);
+  }
+  if restart {
+    if let Err(rollback) = client.start_cargo(&opts.name, namespace).await {
+      eprintln!("Failed to restart cargo {}: {rollback}", opts.name);
+    }
+  }
+  err
+}
+
+/// ## Exec cargo rename
+///
+/// Execute the `nanocl cargo rename` command to rename a cargo.
+/// The key of a cargo is derived from its name so a new cargo is created
+/// with the config and replication of the original one,
+/// the original one is stopped and the new one started if it was running,
+/// then the original one is removed with its history.
+/// If a step fails before the removal the new cargo is removed
+/// and the original one restarted.
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [args](CargoArg) Cargo arguments
+/// * [opts](CargoRenameOpts) Cargo rename options
+///
+/// ## Return
+///
+/// * [Result](Result) Result of the operation
+///   * [Ok](()) Operation was successful
+///   * [Err](nanocl_utils::io_error::IoError) Operation failed
+///
+async fn exec_cargo_rename(
+  cli_conf: &CliConfig,
+  args: &CargoArg,
+  opts: &CargoRenameOpts,
+) -> IoResult<()> {
+  utils::naming::validate_name("Cargo", &opts.new_name)?;
+  if opts.name == opts.new_name {
+    return Err(IoError::invalid_input(
+      "Cargo rename",
+      "the new name is the current name",
+    ));
+  }
+  let client = &cli_conf.client;
+  let namespace = cli_conf.resolve_namespace(&args.namespace);
+  if client
+    .inspect_cargo(&opts.new_name, namespace.clone())
+    .await
+    .is_ok()
+  {
+    return Err(IoError::new(
+      "Cargo rename",
+      std::io::Error::new(
+        std::io::ErrorKind::AlreadyExists,
+        format!(
+          "cargo {} already exists in namespace {}",
+          opts.new_name,
+          namespace.clone().unwrap_or("global".into())
+        ),
+      ),
+    ));
+  }
+  let cargo = client.inspect_cargo(&opts.name, namespace.clone()).await?;
+  let running = cargo.instance_running > 0;
+  let mut config: CargoConfigPartial = cargo.into();
+  if config.container.hostname.as_deref() == Some(opts.name.as_str()) {
+    config.container.hostname = Some(opts.new_name.clone());
+  }
+  config.name = opts.new_name.clone();
+  client.create_cargo(&config, namespace.clone()).await?;
+  if running {
+    // The original cargo is stopped first to free its ports
+    if let Err(err) = client.stop_cargo(&opts.name, namespace.clone()).await {
+      return Err(
+        rollback_cargo_rename(client, opts, namespace, false, err.into()).await,
+      );
+    }
+    if let Err(err) =
+      client.start_cargo(&opts.new_name, namespace.clone()).await
+    {
+      return Err(
+        rollback_cargo_rename(client, opts, namespace, true, err.into()).await,
+      );
+    }
+  }
+  let query = CargoDeleteQuery {
+    namespace,
+    force: Some(true),
+  };
+  client
+    .delete_cargo(&opts.name, &query)
+    .await
+    .map_err(|err| {
+      IoError::new(
+        "Cargo rename",
+        std::io::Error::new(
+          std::io::ErrorKind::Other,
+          format!(
+            "cargo {} is created but {} couldn't be removed: {err}",
+            opts.new_name, opts.name
+          ),
+        ),
+      )
+    })?;
+  Ok(())
+}
+
 /// ## Exec cargo inspect
 ///
-/// Execute the `nanocl cargo inspect` command to inspect a cargo
+/// Execute the `nanocl cargo inspect` command to inspect a cargo,
+/// with `--history` it list the previous revisions of the cargo config
+/// and with `--revision` it show the config of a given revision
 ///
 /// ## Arguments
 ///
@@ -254,20 +1143,160 @@ async fn exec_cargo_inspect(
   opts: &CargoInspectOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
-  let cargo = client
-    .inspect_cargo(&opts.name, args.namespace.clone())
-    .await?;
-  let display = opts
-    .display
-    .clone()
-    .unwrap_or(cli_conf.user_config.display_format.clone());
-  utils::print::display_format(&display, cargo)?;
+  let display = cli_conf.get_display_format(&opts.display);
+  if opts.history || opts.revision.is_some() {
+    let histories = client
+      .list_history_cargo(
+        &opts.name,
+        cli_conf.resolve_namespace(&args.namespace),
+      )
+      .await?;
+    if let Some(revision) = &opts.revision {
+      let config = histories
+        .into_iter()
+        .find(|config| config.key.to_string() == *revision)
+        .ok_or_else(|| {
+          IoError::not_fount(
+            "Revision",
+            format!("{revision} not found for cargo {}", opts.name).as_str(),
+          )
+        })?;
+      return utils::print::display_format_prefixed(&display, "CARGO", config);
+    }
+    let rows = histories
+      .into_iter()
+      .map(CargoRevisionRow::from)
+      .collect::<Vec<_>>();
+    return utils::print::print_table(cli_conf, rows);
+  }
+  let cargo = utils::client::with_timeout(
+    cli_conf.timeout,
+    client
+      .inspect_cargo(&opts.name, cli_conf.resolve_namespace(&args.namespace)),
+  )
+  .await?;
+  if let Some(template) = &opts.template {
+    println!("{}", utils::template::render_template(template, cargo)?);
+    return Ok(());
+  }
+  if !opts.stats {
+    return utils::print::display_format_prefixed(&display, "CARGO", cargo);
+  }
+  if !opts.follow {
+    utils::print::display_format_prefixed(&display, "CARGO", &cargo)?;
+    println!();
+  }
+  exec_cargo_stats(cli_conf, args, &cargo, opts.follow).await
+}
+
+/// ## Exec cargo stats
+///
+/// Print the CPU, memory and network usage of every instance of a cargo
+/// for `nanocl cargo inspect --stats`. With `--follow` the table is redrawn
+/// in place each time the daemon sends new stats until Ctrl-C.
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [args](CargoArg) Cargo arguments
+/// * [cargo](CargoInspect) The inspected cargo
+/// * [follow](bool) Keep streaming the stats
+///
+/// ## Return
+///
+/// * [Result](Result) Result of the operation
+///   * [Ok](()) Operation was successful
+///   * [Err](nanocl_utils::io_error::IoError) Operation failed
+///
+async fn exec_cargo_stats(
+  cli_conf: &CliConfig,
+  args: &CargoArg,
+  cargo: &CargoInspect,
+  follow: bool,
+) -> IoResult<()> {
+  let query = CargoStatsQuery {
+    namespace: cli_conf.resolve_namespace(&args.namespace),
+    stream: Some(follow),
+  };
+  let (tx, mut rx) = futures::channel::mpsc::unbounded();
+  let names = (0..cargo.instances.len())
+    .map(|index| match index {
+      0 => cargo.name.clone(),
+      _ => format!("{index}-{}", cargo.name),
+    })
+    .collect::<Vec<_>>();
+  for (index, name) in names.iter().enumerate() {
+    let client = cli_conf.client.clone();
+    let query = query.clone();
+    let name = name.clone();
+    let tx = tx.clone();
+    rt::spawn(async move {
+      let mut stream = match client.stats_cargo(&name, &query).await {
+        Ok(stream) => stream,
+        Err(err) => {
+          eprintln!("Cannot get stats of {name}: {err}");
+          return;
+        }
+      };
+      while let Some(stats) = stream.next().await {
+        match stats {
+          Ok(stats) => {
+            if tx.unbounded_send((index, stats)).is_err() {
+              return;
+            }
+          }
+          Err(err) => {
+            eprintln!("Cannot get stats of {name}: {err}");
+            return;
+          }
+        }
+      }
+    });
+  }
+  drop(tx);
+  let mut rows = names.iter().map(|_| None).collect::<Vec<_>>();
+  if !follow {
+    while let Some((index, stats)) = rx.next().await {
+      rows[index] = Some(CargoStatsRow::from((names[index].clone(), stats)));
+    }
+    return utils::print::print_table(cli_conf, rows.into_iter().flatten());
+  }
+  let term = console::Term::stdout();
+  term
+    .clear_screen()
+    .map_err(|err| err.map_err_context(|| "Cargo stats"))?;
+  term
+    .hide_cursor()
+    .map_err(|err| err.map_err_context(|| "Cargo stats"))?;
+  while let Some((index, stats)) = rx.next().await {
+    rows[index] = Some(CargoStatsRow::from((names[index].clone(), stats)));
+    let res = (|| -> IoResult<()> {
+      let rows = rows.iter().flatten().cloned();
+      let table = utils::print::render_table_without(cli_conf, rows, &[])?;
+      let header = format!(
+        "nanocl cargo inspect --stats {}    {}",
+        cargo.name,
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+      );
+      let lines = std::iter::once(header.as_str())
+        .chain(std::iter::once(""))
+        .chain(table.lines());
+      utils::print::redraw(&term, lines)
+    })();
+    if let Err(err) = res {
+      let _ = term.show_cursor();
+      return Err(err);
+    }
+  }
+  let _ = term.show_cursor();
   Ok(())
 }
 
 /// ## Exec cargo exec
 ///
-/// Execute the `nanocl cargo exec` command to execute a command in a cargo
+/// Execute the `nanocl cargo exec` command to execute a command in a cargo.
+/// With `-i` stdin is forwarded to the command, with `-t` a pseudo-TTY is
+/// allocated and the terminal is set in raw mode until the command exit.
 ///
 /// ## Arguments
 ///
@@ -287,23 +1316,27 @@ async fn exec_cargo_exec(
   opts: &CargoExecOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
-  let exec: CreateExecOptions = opts.clone().into();
-  let mut stream = client
-    .exec_cargo(&opts.name, exec, args.namespace.clone())
-    .await?;
-  while let Some(output) = stream.next().await {
-    let output = output?;
-    match output.kind {
-      OutputKind::StdOut => {
-        print!("{}", &output.data);
-      }
-      OutputKind::StdErr => {
-        eprint!("{}", output.data);
-      }
-      OutputKind::StdIn => println!("TODO: StdIn {}", &output.data),
-      OutputKind::Console => print!("{}", &output.data),
-    }
+  let cmd = serde_json::to_string(&opts.command)
+    .map_err(|err| err.map_err_context(|| "Exec command"))?;
+  let query = CargoExecQuery {
+    namespace: cli_conf.resolve_namespace(&args.namespace),
+    cmd,
+    stdin: Some(opts.interactive),
+    tty: Some(opts.tty),
+    replica: opts.replica,
+  };
+  let conn = client.attach_exec_cargo(&opts.name, &query).await?;
+  // Restore the original terminal settings once dropped
+  let _raw_mode = if opts.tty && std::io::stdin().is_terminal() {
+    Some(utils::tty::RawModeGuard::new()?)
+  } else {
+    None
+  };
+  let current_sink: Rc<RefCell<Option<ws::WsSink>>> = Default::default();
+  if opts.interactive {
+    utils::tty::forward_stdin(current_sink.clone());
   }
+  utils::tty::dispatch(conn, &current_sink, None, None, Duration::ZERO).await?;
   Ok(())
 }
 
@@ -330,15 +1363,18 @@ async fn exec_cargo_history(
 ) -> IoResult<()> {
   let client = &cli_conf.client;
   let histories = client
-    .list_history_cargo(&opts.name, args.namespace.clone())
+    .list_history_cargo(&opts.name, cli_conf.resolve_namespace(&args.namespace))
     .await?;
-  utils::print::print_yml(histories)?;
+  let display = cli_conf.get_display_format(&None);
+  utils::print::display_format(&display, histories)?;
   Ok(())
 }
 
 /// ## Exec cargo logs
 ///
-/// Execute the `nanocl cargo logs` command to list the logs of a cargo
+/// Execute the `nanocl cargo logs` command to list the logs of a cargo.
+/// When the cargo has multiple replicas their logs are multiplexed
+/// with each line prefixed by `[replica-N]` in a distinct color
 ///
 /// ## Arguments
 ///
@@ -358,8 +1394,9 @@ async fn exec_cargo_logs(
   opts: &CargoLogsOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
+  let namespace = cli_conf.resolve_namespace(&args.namespace);
   let query = CargoLogQuery {
-    namespace: args.namespace.clone(),
+    namespace: namespace.clone(),
     tail: opts.tail.clone(),
     since: opts.since,
     until: opts.until,
@@ -368,6 +1405,38 @@ async fn exec_cargo_logs(
     stderr: None,
     stdout: None,
   };
+  let cargo = client.inspect_cargo(&opts.name, namespace).await?;
+  if cargo.instances.len() > 1 {
+    let futures = cargo
+      .instances
+      .iter()
+      .enumerate()
+      .zip(utils::print::PREFIX_COLORS.iter().cycle())
+      .map(|((index, _), color)| {
+        let name = if index == 0 {
+          opts.name.clone()
+        } else {
+          format!("{index}-{}", opts.name)
+        };
+        let client = client.clone();
+        let query = query.clone();
+        let prefix = format!(
+          "{} ",
+          console::style(format!("[replica-{index}]")).fg(*color)
+        );
+        rt::spawn(async move {
+          match client.logs_cargo(&name, &query).await {
+            Ok(stream) => {
+              utils::print::print_prefixed_logs(stream, &prefix).await
+            }
+            Err(err) => eprintln!("{prefix}Cannot get logs: {err}"),
+          }
+        })
+      })
+      .collect::<Vec<_>>();
+    futures::future::join_all(futures).await;
+    return Ok(());
+  }
   let mut stream = client.logs_cargo(&opts.name, &query).await?;
   while let Some(log) = stream.next().await {
     let log = match log {
@@ -414,9 +1483,14 @@ async fn exec_cargo_revert(
 ) -> IoResult<()> {
   let client = &cli_conf.client;
   let cargo = client
-    .revert_cargo(&opts.name, &opts.history_id, args.namespace.clone())
+    .revert_cargo(
+      &opts.name,
+      &opts.history_id,
+      cli_conf.resolve_namespace(&args.namespace),
+    )
     .await?;
-  utils::print::print_yml(cargo)?;
+  let display = cli_conf.get_display_format(&None);
+  utils::print::display_format(&display, cargo)?;
   Ok(())
 }
 
@@ -442,12 +1516,15 @@ async fn exec_cargo_run(
   opts: &CargoRunOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
+  let mut cargo: CargoConfigPartial = opts.clone().into();
+  cargo.name =
+    utils::naming::resolve_name("Cargo", &opts.name, opts.normalize)?;
   // Image is not existing so we donwload it
   if client.inspect_cargo_image(&opts.image).await.is_err() {
     exec_cargo_image_pull(client, &opts.image).await?;
   }
   let cargo = client
-    .create_cargo(&opts.clone().into(), args.namespace.clone())
+    .create_cargo(&cargo, cli_conf.resolve_namespace(&args.namespace))
     .await?;
   client
     .start_cargo(&cargo.name, Some(cargo.namespace_name))
@@ -471,13 +1548,13 @@ async fn exec_cargo_run(
 ///   * [Err](nanocl_utils::io_error::IoError) Operation failed
 ///
 pub async fn exec_cargo(cli_conf: &CliConfig, args: &CargoArg) -> IoResult<()> {
-  let client = &cli_conf.client;
+  cli_conf.print_namespace(&args.namespace);
   match &args.command {
     CargoCommand::List(opts) => exec_cargo_ls(cli_conf, args, opts).await,
     CargoCommand::Create(opts) => exec_cargo_create(cli_conf, args, opts).await,
     CargoCommand::Remove(opts) => exec_cargo_rm(cli_conf, args, opts).await,
     CargoCommand::Image(opts) => {
-      cargo_image::exec_cargo_image(client, opts).await
+      cargo_image::exec_cargo_image(cli_conf, opts).await
     }
     CargoCommand::Start(opts) => exec_cargo_start(cli_conf, args, opts).await,
     CargoCommand::Stop(opts) => exec_cargo_stop(cli_conf, args, opts).await,
@@ -495,5 +1572,7 @@ pub async fn exec_cargo(cli_conf: &CliConfig, args: &CargoArg) -> IoResult<()> {
     CargoCommand::Restart(opts) => {
       exec_cargo_restart(cli_conf, args, opts).await
     }
+    CargoCommand::Scale(opts) => exec_cargo_scale(cli_conf, args, opts).await,
+    CargoCommand::Rename(opts) => exec_cargo_rename(cli_conf, args, opts).await,
   }
 }
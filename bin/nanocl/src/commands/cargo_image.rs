@@ -9,6 +9,7 @@ use nanocl_utils::io_error::{IoError, IoResult, FromIo};
 use nanocld_client::NanocldClient;
 
 use crate::utils;
+use crate::config::CliConfig;
 use crate::models::{
   CargoImageArg, CargoImageCommand, CargoImageRemoveOpts,
   CargoImageInspectOpts, CargoImageRow, CargoImageImportOpts,
@@ -18,10 +19,11 @@ use crate::models::{
 /// ## Exec cargo image ls
 ///
 /// Function that execute when running `nanocl cargo image ls`
+/// Image ids are shortened to 12 characters unless `--no-trunc` is set
 ///
 /// ## Arguments
 ///
-/// * [client](NanocldClient) The nanocl daemon client
+/// * [cli_conf](CliConfig) The cli config
 /// * [opts](CargoImageListOpts) The cargo image list options
 ///
 /// ## Return
@@ -31,13 +33,21 @@ use crate::models::{
 ///   * [Err](nanocl_utils::io_error::IoError) An error occured
 ///
 async fn exec_cargo_image_ls(
-  client: &NanocldClient,
+  cli_conf: &CliConfig,
   opts: &CargoImageListOpts,
 ) -> IoResult<()> {
+  let client = &cli_conf.client;
   let items = client.list_cargo_image(Some(opts.clone().into())).await?;
   let rows = items
     .into_iter()
     .map(CargoImageRow::from)
+    .map(|row| match cli_conf.no_trunc {
+      true => row,
+      false => CargoImageRow {
+        id: row.id.chars().take(12).collect(),
+        ..row
+      },
+    })
     .collect::<Vec<CargoImageRow>>();
   match opts.quiet {
     true => {
@@ -46,7 +56,7 @@ async fn exec_cargo_image_ls(
       }
     }
     false => {
-      utils::print::print_table(rows);
+      utils::print::print_table(cli_conf, rows)?;
     }
   }
   Ok(())
@@ -195,7 +205,7 @@ pub(crate) async fn exec_cargo_image_pull(
 ///
 /// ## Arguments
 ///
-/// * [client](NanocldClient) The nanocl daemon client
+/// * [cli_conf](CliConfig) The cli config
 /// * [opts](CargoImageInspectOpts) The cargo image inspect options
 ///
 /// ## Return
@@ -205,11 +215,13 @@ pub(crate) async fn exec_cargo_image_pull(
 ///   * [Err](nanocl_utils::io_error::IoError) An error occured
 ///
 async fn exec_cargo_image_inspect(
-  client: &NanocldClient,
+  cli_conf: &CliConfig,
   opts: &CargoImageInspectOpts,
 ) -> IoResult<()> {
+  let client = &cli_conf.client;
   let image = client.inspect_cargo_image(&opts.name).await?;
-  utils::print::print_yml(image)?;
+  let display = cli_conf.get_display_format(&None);
+  utils::print::display_format_prefixed(&display, "CARGO_IMAGE", image)?;
   Ok(())
 }
 
@@ -249,7 +261,7 @@ async fn exec_cargo_image_import(
 ///
 /// ## Arguments
 ///
-/// * [client](NanocldClient) The nanocl daemon client
+/// * [cli_conf](CliConfig) The cli config
 /// * [opts](CargoImageArg) The cargo image options
 ///
 /// ## Return
@@ -259,13 +271,14 @@ async fn exec_cargo_image_import(
 ///   * [Err](nanocl_utils::io_error::IoError) An error occured
 ///
 pub async fn exec_cargo_image(
-  client: &NanocldClient,
+  cli_conf: &CliConfig,
   opts: &CargoImageArg,
 ) -> IoResult<()> {
+  let client = &cli_conf.client;
   match &opts.command {
-    CargoImageCommand::List(opts) => exec_cargo_image_ls(client, opts).await,
+    CargoImageCommand::List(opts) => exec_cargo_image_ls(cli_conf, opts).await,
     CargoImageCommand::Inspect(opts) => {
-      exec_cargo_image_inspect(client, opts).await
+      exec_cargo_image_inspect(cli_conf, opts).await
     }
     CargoImageCommand::Pull(opts) => {
       exec_cargo_image_pull(client, &opts.name).await
@@ -0,0 +1,209 @@
+use std::time::Duration;
+
+use clap::CommandFactory;
+use clap_complete::{generate, Shell};
+
+use nanocl_utils::io_error::{IoError, IoResult};
+
+use crate::utils;
+use crate::config::CliConfig;
+use crate::models::{Cli, CompleteOpts};
+use crate::utils::complete::CompleteKind;
+
+/// Deadline of the request listing the candidates,
+/// a slow daemon must not freeze the shell
+const COMPLETE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Bash hook completing the names with `nanocl complete`
+/// before falling back to the generated completion
+const BASH_DYNAMIC: &str = r#"
+_nanocl_dynamic() {
+  local IFS=$'\n'
+  local names
+  names=($(nanocl complete "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null))
+  if [ ${#names[@]} -gt 0 ]; then
+    COMPREPLY=("${names[@]}")
+    return 0
+  fi
+  _nanocl "$@"
+}
+complete -F _nanocl_dynamic -o bashdefault -o default nanocl
+"#;
+
+/// Zsh hook completing the names with `nanocl complete`
+/// before falling back to the generated completion
+const ZSH_DYNAMIC: &str = r#"
+_nanocl_dynamic() {
+  local -a names
+  names=(${(f)"$(nanocl complete "${(@)words[2,CURRENT]}" 2>/dev/null)"})
+  if (( ${#names} )); then
+    compadd -a names
+    return
+  fi
+  _nanocl "$@"
+}
+compdef _nanocl_dynamic nanocl
+"#;
+
+/// Fish hook adding the names printed by `nanocl complete`
+/// to the generated completion
+const FISH_DYNAMIC: &str = r#"
+complete -c nanocl -f -a '(nanocl complete (commandline -opc)[2..-1] (commandline -ct) 2>/dev/null)'
+"#;
+
+/// ## Exec completion
+///
+/// Function that execute when running `nanocl completion`
+/// Will print the completion script for the given shell on stdout
+/// The script is generated from the current [Cli](Cli) definition
+/// so it always reflect the available subcommands.
+/// With `--dynamic` a hook calling `nanocl complete` is appended
+/// to also complete the names known by the daemon
+///
+/// ## Arguments
+///
+/// * [shell](Shell) The shell to generate the completion for
+/// * [dynamic](bool) Append the hook completing the names
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+pub fn exec_completion(shell: &Shell, dynamic: bool) -> IoResult<()> {
+  let hook = match (dynamic, shell) {
+    (false, _) => None,
+    (true, Shell::Bash) => Some(BASH_DYNAMIC),
+    (true, Shell::Zsh) => Some(ZSH_DYNAMIC),
+    (true, Shell::Fish) => Some(FISH_DYNAMIC),
+    (true, shell) => {
+      return Err(IoError::invalid_input(
+        "completion --dynamic".to_owned(),
+        format!("is only supported for bash, zsh and fish, not {shell}"),
+      ))
+    }
+  };
+  let mut cmd = Cli::command();
+  let name = cmd.get_name().to_owned();
+  generate(*shell, &mut cmd, name, &mut std::io::stdout());
+  if let Some(hook) = hook {
+    print!("{hook}");
+  }
+  Ok(())
+}
+
+/// ## List names
+///
+/// List the names of a kind known by the daemon
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [kind](CompleteKind) The kind of names to list
+/// * [namespace](Option<String>) The namespace of the cargoes and vms
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Vec<String>) The names
+///   * [Err](IoError) The daemon couldn't be reached
+///
+async fn list_names(
+  cli_conf: &CliConfig,
+  kind: CompleteKind,
+  namespace: Option<String>,
+) -> IoResult<Vec<String>> {
+  let client = &cli_conf.client;
+  let names = match kind {
+    CompleteKind::Namespace => client
+      .list_namespace()
+      .await?
+      .into_iter()
+      .map(|namespace| namespace.name)
+      .collect(),
+    CompleteKind::Cargo => client
+      .list_cargo(namespace)
+      .await?
+      .into_iter()
+      .map(|cargo| cargo.name)
+      .collect(),
+    CompleteKind::Vm => client
+      .list_vm(namespace)
+      .await?
+      .into_iter()
+      .map(|vm| vm.name)
+      .collect(),
+    CompleteKind::Resource => client
+      .list_resource(None)
+      .await?
+      .into_iter()
+      .map(|resource| resource.name)
+      .collect(),
+    CompleteKind::Node => client
+      .list_node()
+      .await?
+      .into_iter()
+      .map(|node| node.name)
+      .collect(),
+  };
+  Ok(names)
+}
+
+/// ## Exec complete
+///
+/// Function that execute when running the hidden `nanocl complete`
+/// called by the hook of `nanocl completion --dynamic`.
+/// Print the names completing the last word one per line,
+/// the names are cached a few seconds since the shell ask for them
+/// on every tab press. Nothing is printed when the daemon can't be reached
+/// so the shell fall back to its own completion
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [opts](CompleteOpts) The words of the command line
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) Never, errors are silenced for the shell
+///
+pub async fn exec_complete(
+  cli_conf: &CliConfig,
+  opts: &CompleteOpts,
+) -> IoResult<()> {
+  let Some(target) = utils::complete::complete_target(&opts.words) else {
+    return Ok(());
+  };
+  let namespace = if target.kind.is_namespaced() {
+    cli_conf.resolve_namespace(&target.namespace)
+  } else {
+    None
+  };
+  let key = format!(
+    "{}-{}-{}",
+    cli_conf.host,
+    target.kind.as_str(),
+    namespace.as_deref().unwrap_or("global")
+  );
+  let names = match utils::complete::read_cache(&key) {
+    Some(names) => names,
+    None => {
+      let fut = list_names(cli_conf, target.kind, namespace);
+      match utils::client::with_timeout(COMPLETE_TIMEOUT, fut).await {
+        Ok(names) => {
+          utils::complete::write_cache(&key, &names);
+          names
+        }
+        Err(_) => return Ok(()),
+      }
+    }
+  };
+  let current = opts.words.last().map(String::as_str).unwrap_or_default();
+  for name in names.iter().filter(|name| name.starts_with(current)) {
+    println!("{name}");
+  }
+  Ok(())
+}
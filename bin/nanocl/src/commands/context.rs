@@ -1,4 +1,4 @@
-use nanocl_utils::io_error::IoResult;
+use nanocl_utils::io_error::{IoError, IoResult, FromIo};
 
 use crate::utils;
 use crate::config::CliConfig;
@@ -7,11 +7,11 @@ use crate::models::{Context, ContextArg, ContextCommand, ContextRow};
 /// ## Exec context list
 ///
 /// Function that execute when running `nanocl context ls`
-/// Will print the list of contexts
+/// Will print the list of contexts, the current one is marked with an asterisk
 ///
 /// ## Arguments
 ///
-/// * [context](Context) The context
+/// * [cli_conf](CliConfig) The cli config
 ///
 /// ## Return
 ///
@@ -19,23 +19,42 @@ use crate::models::{Context, ContextArg, ContextCommand, ContextRow};
 ///   * [Ok](()) The operation was successful
 ///   * [Err](nanocl_utils::io_error::IoError) An error occured
 ///
-fn exec_context_list(context: &Context) -> IoResult<()> {
+fn exec_context_list(cli_conf: &CliConfig) -> IoResult<()> {
+  let context = &cli_conf.context;
   let list = Context::list()?;
   let list = list
     .iter()
     .map(|row| {
       if row.name == context.name {
         return ContextRow {
-          name: format!("{} *", row.name),
-          description: row.description.clone(),
-          endpoint: row.endpoint.clone(),
-          current: "✓".into(),
+          current: "*".into(),
+          ..row.clone()
         };
       }
       row.clone()
     })
     .collect::<Vec<ContextRow>>();
-  utils::print::print_table(list);
+  utils::print::print_table(cli_conf, list)?;
+  Ok(())
+}
+
+/// ## Exec context current
+///
+/// Function that execute when running `nanocl context current`
+/// Will print the name of the current context
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+fn exec_context_current(cli_conf: &CliConfig) -> IoResult<()> {
+  println!("{}", cli_conf.context.name);
   Ok(())
 }
 
@@ -43,10 +62,13 @@ fn exec_context_list(context: &Context) -> IoResult<()> {
 ///
 /// Function that execute when running `nanocl context use`
 /// Will use the selected context as the current context
+/// and persist it in the user configuration
 ///
 /// ## Arguments
 ///
+/// * [cli_conf](CliConfig) The cli config
 /// * [name](str) The name of the context to use
+/// * [namespace](Option<String>) The default namespace of the context
 ///
 /// ## Return
 ///
@@ -54,8 +76,21 @@ fn exec_context_list(context: &Context) -> IoResult<()> {
 ///   * [Ok](()) The operation was successful
 ///   * [Err](nanocl_utils::io_error::IoError) An error occured
 ///
-fn exec_context_use(name: &str) -> IoResult<()> {
-  Context::r#use(name)?;
+fn exec_context_use(
+  cli_conf: &CliConfig,
+  name: &str,
+  namespace: &Option<String>,
+) -> IoResult<()> {
+  Context::r#use(cli_conf.config_path.as_deref(), name, namespace.as_deref())?;
+  if cli_conf.quiet {
+    return Ok(());
+  }
+  match namespace.as_deref() {
+    None | Some("") => println!("Switched to context {name}"),
+    Some(namespace) => {
+      println!("Switched to context {name} with namespace {namespace}")
+    }
+  }
   Ok(())
 }
 
@@ -80,6 +115,78 @@ fn exec_context_from(path: &str) -> IoResult<()> {
   Ok(())
 }
 
+/// ## Exec context export
+///
+/// Function that execute when running `nanocl context export`
+/// Will print the context as yaml, tls files are kept as paths
+///
+/// ## Arguments
+///
+/// * [name](str) The name of the context to export
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+fn exec_context_export(name: &str) -> IoResult<()> {
+  let context = match name {
+    "default" => Context::new(),
+    _ => Context::read_by_name(name)?,
+  };
+  let data = serde_yaml::to_string(&context)
+    .map_err(|err| err.map_err_context(|| format!("Context {name}")))?;
+  print!("{data}");
+  Ok(())
+}
+
+/// ## Exec context import
+///
+/// Function that execute when running `nanocl context import`
+/// Will add a context exported with `nanocl context export`,
+/// an existing context is only replaced with `--overwrite`
+///
+/// ## Arguments
+///
+/// * [file](str) The path to the exported context
+/// * [overwrite](bool) Replace an existing context with the same name
+/// * [quiet](bool) Do not print the confirmation
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+fn exec_context_import(
+  file: &str,
+  overwrite: bool,
+  quiet: bool,
+) -> IoResult<()> {
+  let context = Context::read(file)?;
+  if context.name == "default" {
+    return Err(IoError::invalid_input(
+      "Context",
+      "default is reserved and cannot be imported",
+    ));
+  }
+  if !overwrite && Context::read_by_name(&context.name).is_ok() {
+    return Err(IoError::new(
+      format!("Context {}", context.name),
+      std::io::Error::new(
+        std::io::ErrorKind::AlreadyExists,
+        "already exists, use --overwrite to replace it",
+      ),
+    ));
+  }
+  Context::write(&context)?;
+  if !quiet {
+    println!("Imported context {}", context.name);
+  }
+  Ok(())
+}
+
 /// ## Exec context
 ///
 /// Function that execute when running `nanocl context`
@@ -99,11 +206,17 @@ pub async fn exec_context(
   cli_conf: &CliConfig,
   args: &ContextArg,
 ) -> IoResult<()> {
-  let context = &cli_conf.context;
   match &args.command {
-    ContextCommand::List => exec_context_list(context)?,
-    ContextCommand::Use { name } => exec_context_use(name)?,
+    ContextCommand::List => exec_context_list(cli_conf)?,
+    ContextCommand::Current => exec_context_current(cli_conf)?,
+    ContextCommand::Use { name, namespace } => {
+      exec_context_use(cli_conf, name, namespace)?
+    }
     ContextCommand::From { path } => exec_context_from(path)?,
+    ContextCommand::Export { name } => exec_context_export(name)?,
+    ContextCommand::Import { file, overwrite } => {
+      exec_context_import(file, *overwrite, cli_conf.quiet)?
+    }
   }
   Ok(())
 }
@@ -1,17 +1,32 @@
+use std::io::Write;
+use std::time::Duration;
+
+use ntex::time;
+use chrono::Utc;
 use futures::StreamExt;
+use dialoguer::console::style;
 
-use nanocl_utils::io_error::IoResult;
+use nanocl_utils::io_error::{FromIo, IoError, IoResult};
 
-use crate::{utils::print::print_yml, config::CliConfig};
+use crate::utils;
+use crate::config::CliConfig;
+use crate::models::{EventsOpts, EventsFormat};
 
-/// ## Exec events
+/// ## Stream events
 ///
-/// Function that execute when running `nanocl events`
-/// Will print the events emited by the daemon
+/// Print the events emited by the daemon
+/// With the `jsonl` format every event is printed as one json line
+/// and flushed as soon as it arrives.
+/// When the stream ends or fails it resubscribe with an exponential backoff
+/// unless `--no-reconnect` is set, after too many failed attempts
+/// the error of the last one is returned.
+/// Events don't carry a timestamp so `--since` is compared to the time
+/// they are received.
 ///
 /// ## Arguments
 ///
 /// * [cli_conf](CliConfig) The cli config
+/// * [opts](EventsOpts) The events options
 ///
 /// ## Return
 ///
@@ -19,12 +34,105 @@ use crate::{utils::print::print_yml, config::CliConfig};
 ///   * [Ok](()) The operation was successful
 ///   * [Err](nanocl_utils::io_error::IoError) An error occured
 ///
-pub async fn exec_events(cli_conf: &CliConfig) -> IoResult<()> {
+async fn stream_events(
+  cli_conf: &CliConfig,
+  opts: &EventsOpts,
+) -> IoResult<()> {
+  /// Delay before the first resubscription attempt
+  const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(500);
+  /// Maximum delay between two resubscription attempts
+  const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+  /// Number of failed resubscription attempts before giving up
+  const RECONNECT_MAX_ATTEMPTS: usize = 10;
   let client = &cli_conf.client;
+  let display = cli_conf.get_display_format(&None);
   let mut stream = client.watch_events().await?;
-  while let Some(event) = stream.next().await {
-    let event = event?;
-    print_yml(event)?;
+  let mut stdout = std::io::stdout();
+  loop {
+    while let Some(event) = stream.next().await {
+      let event = match event {
+        Ok(event) => event,
+        Err(err) if opts.no_reconnect => return Err(err.into()),
+        Err(_) => break,
+      };
+      if opts.since.is_some_and(|since| Utc::now() < since) {
+        continue;
+      }
+      match opts.format {
+        EventsFormat::Human => utils::print::display_format(&display, event)?,
+        EventsFormat::Jsonl => {
+          let line = serde_json::to_string(&event)
+            .map_err(|err| err.map_err_context(|| "Event to json"))?;
+          writeln!(stdout, "{line}")?;
+          stdout.flush()?;
+        }
+      }
+    }
+    if opts.no_reconnect {
+      return Ok(());
+    }
+    let mut delay = RECONNECT_BASE_DELAY;
+    let mut attempts = 0;
+    stream = loop {
+      time::sleep(delay).await;
+      match client.watch_events().await {
+        Ok(stream) => break stream,
+        Err(err) => {
+          attempts += 1;
+          if attempts >= RECONNECT_MAX_ATTEMPTS {
+            return Err(err.into());
+          }
+          delay = std::cmp::min(delay * 2, RECONNECT_MAX_DELAY);
+        }
+      }
+    };
+    eprintln!("{}", style("event stream reconnected").dim().for_stderr());
+  }
+}
+
+/// ## Exec events
+///
+/// Function that execute when running `nanocl events`
+/// Will print the events emited by the daemon until `--until` is reached
+/// if set, or forever otherwise.
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+/// * [opts](EventsOpts) The events options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+pub async fn exec_events(
+  cli_conf: &CliConfig,
+  opts: &EventsOpts,
+) -> IoResult<()> {
+  let Some(until) = opts.until else {
+    return stream_events(cli_conf, opts).await;
+  };
+  if let Some(since) = opts.since {
+    if until < since {
+      return Err(IoError::invalid_input(
+        "Events",
+        format!(
+          "--until {} is earlier than --since {}",
+          until.to_rfc3339(),
+          since.to_rfc3339()
+        )
+        .as_str(),
+      ));
+    }
+  }
+  let remaining = (until - Utc::now()).to_std().unwrap_or_default();
+  if remaining.is_zero() {
+    return Ok(());
+  }
+  match time::timeout(remaining, stream_events(cli_conf, opts)).await {
+    Ok(res) => res,
+    Err(_) => Ok(()),
   }
-  Ok(())
 }
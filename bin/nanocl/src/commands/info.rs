@@ -1,16 +1,129 @@
-use nanocl_utils::io_error::IoResult;
+use std::time::Duration;
+
+use dialoguer::console::Term;
+use nanocl_utils::io_error::{FromIo, IoResult};
+use nanocld_client::NanocldClient;
 
 use crate::utils;
 use crate::config::CliConfig;
+use crate::models::{InfoOpts, InfoOutput};
+
+/// ## Gen info output
+///
+/// Gather the information of the host printed with `--output`
+///
+/// ## Arguments
+///
+/// * [client](NanocldClient) The nanocl daemon client
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](InfoOutput) The information of the host
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+async fn gen_info_output(client: &NanocldClient) -> IoResult<InfoOutput> {
+  let info = client.info().await?;
+  let version = client.get_version().await?;
+  let nodes = client.list_node().await?;
+  let namespaces = client.list_namespace().await?;
+  Ok(InfoOutput {
+    version: version.version,
+    commit_id: version.commit_id,
+    channel: version.channel,
+    hostname: info.config.hostname,
+    advertise_addr: info.config.advertise_addr,
+    host_gateway: info.host_gateway,
+    nodes: nodes.len(),
+    namespaces: namespaces.len(),
+    cargoes: namespaces.iter().map(|namespace| namespace.cargoes).sum(),
+    arch: info.docker.architecture,
+    os: info.docker.operating_system,
+    kernel: info.docker.kernel_version,
+    cpus: info.docker.ncpu,
+    memory: info.docker.mem_total,
+    docker_version: info.docker.server_version,
+    storage_driver: info.docker.driver,
+    containers: info.docker.containers,
+    containers_running: info.docker.containers_running,
+    images: info.docker.images,
+  })
+}
+
+/// ## Gen info
+///
+/// Format the info of the daemon to render them with `--watch`,
+/// as an [InfoOutput](InfoOutput) with `--output`
+/// or as returned by the daemon otherwise
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](String) The formatted info
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+async fn gen_info(cli_conf: &CliConfig) -> IoResult<String> {
+  let client = &cli_conf.client;
+  if let Some(format) = cli_conf.structured_output() {
+    let output = gen_info_output(client).await?;
+    return utils::print::to_display_format(format, output);
+  }
+  let info = client.info().await?;
+  let display = cli_conf.get_display_format(&None);
+  utils::print::to_display_format(&display, info)
+}
+
+/// ## Render info
+///
+/// Render the info of the daemon in place of the previous render
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+/// * [term](Term) The terminal to render to
+/// * [interval](Duration) The refresh interval
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+async fn render_info(
+  cli_conf: &CliConfig,
+  term: &Term,
+  interval: Duration,
+) -> IoResult<()> {
+  let content = match gen_info(cli_conf).await {
+    Ok(content) => content,
+    Err(err) => format!("{err}\n"),
+  };
+  let header = format!(
+    "Every {interval:?}: nanocl info    {}",
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+  );
+  let lines = std::iter::once(header.as_str())
+    .chain(std::iter::once(""))
+    .chain(content.lines());
+  utils::print::redraw(term, lines)?;
+  Ok(())
+}
 
 /// ## Exec info
 ///
 /// Function that execute when running `nanocl info`
-/// Will print the info of the daemon
+/// Will print the info of the daemon, with `--watch` the info are refreshed
+/// on an interval until Ctrl-C that restore the cursor.
+/// With `--output` the info are printed as an [InfoOutput](InfoOutput)
 ///
 /// ## Arguments
 ///
 /// * [cli_conf](CliConfig) The cli config
+/// * [opts](InfoOpts) The info options
 ///
 /// ## Return
 ///
@@ -18,9 +131,30 @@ use crate::config::CliConfig;
 ///   * [Ok](()) The operation was successful
 ///   * [Err](nanocl_utils::io_error::IoError) An error occured
 ///
-pub async fn exec_info(cli_conf: &CliConfig) -> IoResult<()> {
+pub async fn exec_info(cli_conf: &CliConfig, opts: &InfoOpts) -> IoResult<()> {
   let client = &cli_conf.client;
-  let info = client.info().await?;
-  utils::print::print_yml(info)?;
-  Ok(())
+  let Some(interval) = opts.watch else {
+    if let Some(format) = cli_conf.structured_output() {
+      let output = gen_info_output(client).await?;
+      return utils::print::display_format(format, output);
+    }
+    let info = client.info().await?;
+    let display = cli_conf.get_display_format(&None);
+    utils::print::display_format(&display, info)?;
+    return Ok(());
+  };
+  let term = Term::stdout();
+  term
+    .clear_screen()
+    .map_err(|err| err.map_err_context(|| "Info"))?;
+  term
+    .hide_cursor()
+    .map_err(|err| err.map_err_context(|| "Info"))?;
+  loop {
+    if let Err(err) = render_info(cli_conf, &term, interval).await {
+      let _ = term.show_cursor();
+      return Err(err);
+    }
+    ntex::time::sleep(interval).await;
+  }
 }
@@ -10,6 +10,7 @@ use nanocl_utils::io_error::{IoError, IoResult, FromIo};
 use nanocld_client::stubs::state::StateDeployment;
 
 use crate::utils;
+use crate::config::CliConfig;
 use crate::models::{
   InstallOpts, NanocldArg, Context, ContextMetaData, ContextEndpoint,
 };
@@ -21,6 +22,7 @@ use crate::models::{
 ///
 /// ## Arguments
 ///
+/// * [cli_conf](CliConfig) The cli config
 /// * [args](InstallOpts) The command arguments
 ///
 /// ## Return
@@ -29,7 +31,10 @@ use crate::models::{
 ///   * [Ok](()) The operation was successful
 ///   * [Err](nanocl_utils::io_error::IoError) An error occured
 ///
-pub async fn exec_install(args: &InstallOpts) -> IoResult<()> {
+pub async fn exec_install(
+  cli_conf: &CliConfig,
+  args: &InstallOpts,
+) -> IoResult<()> {
   println!("Installing Nanocl components on your system");
   let home_dir = std::env::var("HOME").map_err(|err| {
     IoError::interupted("Unable to get $HOME env variable", &err.to_string())
@@ -174,15 +179,19 @@ pub async fn exec_install(args: &InstallOpts) -> IoResult<()> {
           "Nanocl".into(),
           ContextEndpoint {
             host: format!("unix://{home_dir}/.nanocl/run/nanocl.sock"),
+            ..Default::default()
           },
         );
         map
       },
+      display_format: None,
     };
     if let Err(err) = Context::write(&context) {
       eprintln!("WARN: Unable to create context for docker desktop: {err}");
     }
-    if let Err(err) = Context::r#use("desktop-linux") {
+    if let Err(err) =
+      Context::r#use(cli_conf.config_path.as_deref(), "desktop-linux", None)
+    {
       eprintln!("WARN: Unable to use context for docker desktop: {err}");
     }
   }
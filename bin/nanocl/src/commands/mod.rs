@@ -14,6 +14,8 @@ mod uninstall;
 mod upgrade;
 mod node;
 mod context;
+mod completion;
+mod schema;
 
 pub use context::exec_context;
 pub use version::exec_version;
@@ -30,3 +32,5 @@ pub use system::exec_process;
 pub use install::exec_install;
 pub use upgrade::exec_upgrade;
 pub use uninstall::exec_uninstall;
+pub use completion::{exec_completion, exec_complete};
+pub use schema::exec_schema;
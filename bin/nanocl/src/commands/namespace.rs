@@ -1,11 +1,16 @@
-use nanocl_utils::io_error::{IoResult, FromIo};
+use nanocl_utils::io_error::{IoError, IoResult, FromIo};
 use nanocld_client::NanocldClient;
+use nanocld_client::stubs::vm::VmSummary;
+use nanocld_client::stubs::cargo::CargoDeleteQuery;
+use nanocld_client::stubs::resource::Resource;
+use nanocld_client::stubs::namespace::NamespaceInspect;
 
 use crate::config::CliConfig;
 use crate::utils;
 use crate::models::{
   NamespaceArg, NamespaceCommand, NamespaceOpts, NamespaceRow,
-  NamespaceDeleteOpts, NamespaceListOpts,
+  NamespaceDeleteOpts, NamespaceListOpts, NamespaceUsage,
+  NamespaceInspectOutput,
 };
 
 /// ## Exec namespace ls
@@ -14,7 +19,7 @@ use crate::models::{
 ///
 /// ## Arguments
 ///
-/// * [client](NanocldClient) The nanocl daemon client
+/// * [cli_conf](CliConfig) The cli config
 /// * [options](NamespaceListOpts) The namespace list options
 ///
 /// ## Return
@@ -24,9 +29,10 @@ use crate::models::{
 ///   * [Err](nanocl_utils::io_error::IoError) An error occured
 ///
 async fn exec_namespace_ls(
-  client: &NanocldClient,
+  cli_conf: &CliConfig,
   options: &NamespaceListOpts,
 ) -> IoResult<()> {
+  let client = &cli_conf.client;
   let items = client.list_namespace().await?;
   let namespaces = items
     .into_iter()
@@ -39,7 +45,7 @@ async fn exec_namespace_ls(
       }
     }
     false => {
-      utils::print::print_table(namespaces);
+      utils::print::print_table(cli_conf, namespaces)?;
     }
   }
   Ok(())
@@ -51,7 +57,7 @@ async fn exec_namespace_ls(
 ///
 /// ## Arguments
 ///
-/// * [client](NanocldClient) The nanocl daemon client
+/// * [cli_conf](CliConfig) The cli config
 /// * [options](NamespaceOpts) The namespace options
 ///
 /// ## Return
@@ -61,21 +67,107 @@ async fn exec_namespace_ls(
 ///   * [Err](nanocl_utils::io_error::IoError) An error occured
 ///
 async fn exec_namespace_create(
-  client: &NanocldClient,
+  cli_conf: &CliConfig,
   options: &NamespaceOpts,
 ) -> IoResult<()> {
-  let item = client.create_namespace(&options.name).await?;
-  println!("{}", item.name);
+  let item = cli_conf.client.create_namespace(&options.name).await?;
+  if !cli_conf.quiet {
+    println!("{}", item.name);
+  }
   Ok(())
 }
 
+/// ## Namespace resources
+///
+/// Filter the resources of a namespace.
+/// A resource belongs to a namespace when its config references one of the
+/// cargoes or virtual machines of the namespace.
+///
+/// ## Arguments
+///
+/// * [namespace](NamespaceInspect) The inspected namespace
+/// * [vms](Vec<VmSummary>) The virtual machines of the namespace
+/// * [resources](Vec<Resource>) The existing resources
+///
+/// ## Return
+///
+/// * [Vec](Vec<Resource>) The resources of the namespace
+///
+pub(crate) fn namespace_resources<'a>(
+  namespace: &NamespaceInspect,
+  vms: &[VmSummary],
+  resources: &'a [Resource],
+) -> Vec<&'a Resource> {
+  let keys = namespace
+    .cargoes
+    .iter()
+    .map(|cargo| format!("{}.c", cargo.key))
+    .chain(vms.iter().map(|vm| format!("{}.v", vm.key)))
+    .collect::<Vec<_>>();
+  resources
+    .iter()
+    .filter(|resource| {
+      let config = resource.config.to_string();
+      keys
+        .iter()
+        .any(|key| config.contains(&format!("\"{key}\"")))
+    })
+    .collect()
+}
+
+/// ## Gen namespace usage
+///
+/// Aggregate the cargoes, virtual machines and resources of a namespace
+/// with their allocated memory and cpus.
+///
+/// ## Arguments
+///
+/// * [namespace](NamespaceInspect) The inspected namespace
+/// * [vms](Vec<VmSummary>) The virtual machines of the namespace
+/// * [resources](Vec<Resource>) The existing resources
+///
+/// ## Return
+///
+/// * [NamespaceUsage](NamespaceUsage) The aggregated usage
+///
+fn gen_namespace_usage(
+  namespace: &NamespaceInspect,
+  vms: &[VmSummary],
+  resources: &[Resource],
+) -> NamespaceUsage {
+  let mut usage = NamespaceUsage {
+    cargoes: namespace.cargoes.len(),
+    vms: vms.len(),
+    resources: namespace_resources(namespace, vms, resources).len(),
+    ..Default::default()
+  };
+  for cargo in &namespace.cargoes {
+    let instances = cargo.instance_total;
+    usage.instances += instances;
+    if let Some(host_config) = &cargo.config.container.host_config {
+      let memory = host_config.memory.unwrap_or_default().max(0) as u64;
+      let nano_cpus = host_config.nano_cpus.unwrap_or_default().max(0);
+      usage.memory += memory / 1024 / 1024 * instances as u64;
+      usage.cpus += nano_cpus as f64 / 1_000_000_000.0 * instances as f64;
+    }
+  }
+  for vm in vms {
+    usage.instances += vm.instances;
+    usage.memory += vm.config.host_config.memory;
+    usage.cpus += vm.config.host_config.cpu as f64;
+  }
+  usage
+}
+
 /// ## Exec namespace inspect
 ///
 /// Function that execute when running `nanocl namespace inspect`
+/// It print the namespace with the aggregated usage of its cargoes,
+/// virtual machines and resources.
 ///
 /// ## Arguments
 ///
-/// * [client](NanocldClient) The nanocl daemon client
+/// * [cli_conf](CliConfig) The cli config
 /// * [options](NamespaceOpts) The namespace options
 ///
 /// ## Return
@@ -85,17 +177,160 @@ async fn exec_namespace_create(
 ///   * [Err](nanocl_utils::io_error::IoError) An error occured
 ///
 async fn exec_namespace_inspect(
-  client: &NanocldClient,
+  cli_conf: &CliConfig,
   options: &NamespaceOpts,
 ) -> IoResult<()> {
-  let namespace = client.inspect_namespace(&options.name).await?;
-  utils::print::print_yml(namespace)?;
+  let client = &cli_conf.client;
+  let namespace = match client.inspect_namespace(&options.name).await {
+    Ok(namespace) => namespace,
+    Err(err) if utils::client::is_not_found(&err) => {
+      return Err(IoError::not_fount(
+        "Namespace",
+        format!("{} doesn't exist", options.name).as_str(),
+      ));
+    }
+    Err(err) => return Err(err.into()),
+  };
+  let vms = client.list_vm(Some(options.name.clone())).await?;
+  let resources = client.list_resource(None).await?;
+  let usage = gen_namespace_usage(&namespace, &vms, &resources);
+  let display = cli_conf.get_display_format(&None);
+  utils::print::display_format_prefixed(
+    &display,
+    "NAMESPACE",
+    NamespaceInspectOutput { namespace, usage },
+  )?;
   Ok(())
 }
 
+/// ## NamespaceContent
+///
+/// The cargoes, virtual machines and resources of a namespace to remove
+///
+struct NamespaceContent {
+  name: String,
+  cargoes: Vec<String>,
+  vms: Vec<String>,
+  resources: Vec<String>,
+}
+
+impl NamespaceContent {
+  /// Whether the namespace has no object
+  fn is_empty(&self) -> bool {
+    self.cargoes.is_empty() && self.vms.is_empty() && self.resources.is_empty()
+  }
+
+  /// Summary of the objects in the namespace eg: `2 cargoes, 1 vms`
+  fn summary(&self) -> String {
+    format!(
+      "{} cargoes, {} vms and {} resources",
+      self.cargoes.len(),
+      self.vms.len(),
+      self.resources.len()
+    )
+  }
+}
+
+/// ## Get namespace content
+///
+/// List the cargoes, virtual machines and resources of a namespace
+///
+/// ## Arguments
+///
+/// * [client](NanocldClient) The nanocl daemon client
+/// * [name](str) The name of the namespace
+/// * [resources](Vec<Resource>) The existing resources
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](NamespaceContent) The content of the namespace
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+async fn get_namespace_content(
+  client: &NanocldClient,
+  name: &str,
+  resources: &[Resource],
+) -> IoResult<NamespaceContent> {
+  let namespace = match client.inspect_namespace(name).await {
+    Ok(namespace) => namespace,
+    Err(err) if utils::client::is_not_found(&err) => {
+      return Err(IoError::not_fount(
+        "Namespace",
+        format!("{name} doesn't exist").as_str(),
+      ));
+    }
+    Err(err) => return Err(err.into()),
+  };
+  let vms = client.list_vm(Some(name.to_owned())).await?;
+  let resources = namespace_resources(&namespace, &vms, resources)
+    .into_iter()
+    .map(|resource| resource.name.clone())
+    .collect();
+  Ok(NamespaceContent {
+    name: name.to_owned(),
+    cargoes: namespace
+      .cargoes
+      .into_iter()
+      .map(|cargo| cargo.name)
+      .collect(),
+    vms: vms.into_iter().map(|vm| vm.name).collect(),
+    resources,
+  })
+}
+
+/// ## Delete namespace content
+///
+/// Delete the resources, cargoes and virtual machines of a namespace.
+/// Every object is attempted even if a previous one failed.
+///
+/// ## Arguments
+///
+/// * [client](NanocldClient) The nanocl daemon client
+/// * [content](NamespaceContent) The content of the namespace
+///
+/// ## Return
+///
+/// * [usize](usize) The number of objects that failed to be deleted
+///
+async fn delete_namespace_content(
+  client: &NanocldClient,
+  content: &NamespaceContent,
+) -> usize {
+  let mut failed = 0;
+  // Resources first as they reference the cargoes and virtual machines
+  for name in &content.resources {
+    if let Err(err) = client.delete_resource(name).await {
+      eprintln!("Failed to delete resource {name}: {err}");
+      failed += 1;
+    }
+  }
+  let query = CargoDeleteQuery {
+    namespace: Some(content.name.clone()),
+    force: Some(true),
+  };
+  for name in &content.cargoes {
+    if let Err(err) = client.delete_cargo(name, &query).await {
+      eprintln!("Failed to delete cargo {name}: {err}");
+      failed += 1;
+    }
+  }
+  for name in &content.vms {
+    let namespace = Some(content.name.clone());
+    if let Err(err) = client.delete_vm(name, namespace).await {
+      eprintln!("Failed to delete vm {name}: {err}");
+      failed += 1;
+    }
+  }
+  failed
+}
+
 /// ## Exec namespace rm
 ///
 /// Function that execute when running `nanocl namespace rm`
+/// A namespace that still contains cargoes, virtual machines or resources
+/// is only removed with `--cascade`, which delete them first.
+/// A namespace is kept if one of its objects failed to be deleted.
 ///
 /// ## Arguments
 ///
@@ -112,17 +347,54 @@ async fn exec_namespace_rm(
   client: &NanocldClient,
   options: &NamespaceDeleteOpts,
 ) -> IoResult<()> {
+  let resources = client.list_resource(None).await?;
+  let mut contents = Vec::new();
+  for name in &options.names {
+    let content = get_namespace_content(client, name, &resources).await?;
+    if !options.cascade && !content.is_empty() {
+      return Err(IoError::invalid_input(
+        "Namespace".to_owned(),
+        format!(
+          "{name} still contains {}, use --cascade to remove them",
+          content.summary()
+        ),
+      ));
+    }
+    contents.push(content);
+  }
   if !options.skip_confirm {
+    let details = contents
+      .iter()
+      .filter(|content| !content.is_empty())
+      .map(|content| format!("\n  {} with {}", content.name, content.summary()))
+      .collect::<String>();
     utils::dialog::confirm(&format!(
-      "Delete namespace {}?",
+      "Delete namespace {}?{details}",
       options.names.join(",")
     ))
     .map_err(|err| err.map_err_context(|| "Delete namespace"))?;
   }
-  for name in &options.names {
-    client.delete_namespace(name).await?;
+  let mut failed = Vec::new();
+  for content in &contents {
+    if delete_namespace_content(client, content).await > 0 {
+      failed.push(content.name.clone());
+      continue;
+    }
+    client.delete_namespace(&content.name).await?;
   }
-  Ok(())
+  if failed.is_empty() {
+    return Ok(());
+  }
+  Err(IoError::new(
+    "Namespace delete",
+    std::io::Error::new(
+      std::io::ErrorKind::Other,
+      format!(
+        "{} kept because some of their objects failed to delete",
+        failed.join(",")
+      ),
+    ),
+  ))
 }
 
 /// ## Exec namespace
@@ -146,12 +418,14 @@ pub async fn exec_namespace(
 ) -> IoResult<()> {
   let client = &cli_conf.client;
   match &args.command {
-    NamespaceCommand::List(options) => exec_namespace_ls(client, options).await,
+    NamespaceCommand::List(options) => {
+      exec_namespace_ls(cli_conf, options).await
+    }
     NamespaceCommand::Create(options) => {
-      exec_namespace_create(client, options).await
+      exec_namespace_create(cli_conf, options).await
     }
     NamespaceCommand::Inspect(options) => {
-      exec_namespace_inspect(client, options).await
+      exec_namespace_inspect(cli_conf, options).await
     }
     NamespaceCommand::Remove(options) => {
       exec_namespace_rm(client, options).await
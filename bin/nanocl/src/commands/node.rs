@@ -1,8 +1,433 @@
-use nanocl_utils::io_error::IoResult;
+use std::time::Duration;
+
+use nanocl_utils::io_error::{FromIo, IoError, IoResult};
+use nanocld_client::NanocldClient;
+use nanocld_client::stubs::node::Node;
+use nanocld_client::stubs::cargo::CargoScale;
 
 use crate::utils;
 use crate::config::CliConfig;
-use crate::models::{NodeArg, NodeCommand, NodeRow};
+use crate::models::{
+  NodeArg, NodeCommand, NodeListOpts, NodeRow, NodeCordonOpts, NodeDrainOpts,
+  NodeDrainRow,
+};
+
+use super::vm::migrate_vm;
+
+/// Port used by the daemons to reach each other
+const NODE_PORT: u16 = 8081;
+
+/// Deadline of the ping used to check if a node is reachable
+const NODE_PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Namespace of the components of nanocl running on every node,
+/// its workloads are never moved by a drain
+const SYSTEM_NAMESPACE: &str = "system";
+
+/// ## Connect node
+///
+/// Create a client to the daemon of a node on its node address
+///
+/// ## Arguments
+///
+/// * [node](Node) The node to connect to
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](NanocldClient) The client of the node
+///   * [Err](nanocl_utils::io_error::IoError) The node address is invalid
+///
+pub(crate) fn connect_node(node: &Node) -> IoResult<NanocldClient> {
+  let url = Box::leak(
+    format!("http://{}:{NODE_PORT}", node.ip_address).into_boxed_str(),
+  );
+  NanocldClient::connect_to_with_ssl(url, None, None)
+}
+
+/// ## Ping node
+///
+/// Check if the daemon of a node answer a ping on its node address
+///
+/// ## Arguments
+///
+/// * [node](Node) The node to ping
+///
+/// ## Return
+///
+/// * [bool](bool) True if the node is reachable
+///
+async fn ping_node(node: &Node) -> bool {
+  let client = match connect_node(node) {
+    Ok(client) => client,
+    Err(_) => return false,
+  };
+  utils::client::with_timeout(NODE_PING_TIMEOUT, client.ping())
+    .await
+    .is_ok()
+}
+
+/// ## Exec node ls
+///
+/// Function that execute when running `nanocl node ls`
+/// The node the cli is connected to is reported as `local`,
+/// the others are `peer` and are pinged to check if they are reachable
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+/// * [opts](NodeListOpts) The node list options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+async fn exec_node_ls(
+  cli_conf: &CliConfig,
+  opts: &NodeListOpts,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  let nodes =
+    utils::client::with_timeout(cli_conf.timeout, client.list_node()).await?;
+  if opts.quiet {
+    for node in nodes {
+      println!("{}", node.name);
+    }
+    return Ok(());
+  }
+  let info =
+    utils::client::with_timeout(cli_conf.timeout, client.info()).await?;
+  let rows = nodes.into_iter().map(|node| async {
+    if node.name == info.config.hostname {
+      return NodeRow::new(node, "local", true);
+    }
+    let reachable = ping_node(&node).await;
+    NodeRow::new(node, "peer", reachable)
+  });
+  let rows = utils::client::run_concurrent(cli_conf.concurrency, rows).await;
+  utils::print::print_table(cli_conf, rows)?;
+  Ok(())
+}
+
+/// ## Exec node cordon
+///
+/// Function that execute when running `nanocl node cordon`
+/// or `nanocl node uncordon`, the daemon of a cordoned node
+/// refuse to create cargoes, cargo instances and vms
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+/// * [opts](NodeCordonOpts) The node cordon options
+/// * [unschedulable](bool) True to cordon the node, false to uncordon it
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+async fn exec_node_cordon(
+  cli_conf: &CliConfig,
+  opts: &NodeCordonOpts,
+  unschedulable: bool,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  let node = match unschedulable {
+    true => client.cordon_node(&opts.name).await?,
+    false => client.uncordon_node(&opts.name).await?,
+  };
+  if !cli_conf.quiet {
+    println!("{}", node.name);
+  }
+  Ok(())
+}
+
+/// ## Drain targets
+///
+/// List the nodes the workloads of a drained node can be moved to:
+/// the other nodes that aren't cordoned and answer a ping,
+/// only the one given with `--to-node` when set
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+/// * [nodes](Vec<Node>) The nodes of the cluster
+/// * [opts](NodeDrainOpts) The node drain options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Vec<Node>) The nodes to move the workloads to
+///   * [Err](nanocl_utils::io_error::IoError) No node can receive the workloads
+///
+async fn drain_targets(
+  cli_conf: &CliConfig,
+  nodes: &[Node],
+  opts: &NodeDrainOpts,
+) -> IoResult<Vec<Node>> {
+  if let Some(to_node) = &opts.to_node {
+    let Some(node) = nodes.iter().find(|node| &node.name == to_node) else {
+      return Err(IoError::not_fount(
+        format!("Node {to_node}"),
+        "doesn't exist, see `nanocl node ls`".to_owned(),
+      ));
+    };
+    if node.name == opts.name || node.unschedulable {
+      return Err(IoError::invalid_input(
+        format!("Node {to_node}"),
+        "is cordoned or is the drained node".to_owned(),
+      ));
+    }
+  }
+  let candidates = nodes
+    .iter()
+    .filter(|node| node.name != opts.name && !node.unschedulable)
+    .filter(|node| match &opts.to_node {
+      Some(to_node) => &node.name == to_node,
+      None => true,
+    })
+    .map(|node| async move { (node, ping_node(node).await) });
+  let targets = utils::client::run_concurrent(cli_conf.concurrency, candidates)
+    .await
+    .into_iter()
+    .filter_map(|(node, reachable)| reachable.then(|| node.clone()))
+    .collect::<Vec<_>>();
+  if targets.is_empty() {
+    return Err(IoError::invalid_input(
+      format!("Node drain {}", opts.name),
+      "no reachable and schedulable node to move its workloads to".to_owned(),
+    ));
+  }
+  Ok(targets)
+}
+
+/// ## Move cargo instances
+///
+/// Move the instances of a cargo from a node to another one,
+/// they are created on the target before being removed from the source
+/// and removed from the target again when the source can't be scaled down
+///
+/// ## Arguments
+///
+/// * [source](NanocldClient) The client of the drained node
+/// * [target](NanocldClient) The client of the node to move the instances to
+/// * [name](str) The name of the cargo
+/// * [namespace](str) The namespace of the cargo
+/// * [count](usize) The number of instances to move
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The instances were moved
+///   * [Err](nanocl_utils::io_error::IoError) The instances stay on the source
+///
+async fn move_cargo_instances(
+  source: &NanocldClient,
+  target: &NanocldClient,
+  name: &str,
+  namespace: &str,
+  count: usize,
+) -> IoResult<()> {
+  let namespace = Some(namespace.to_owned());
+  let replicas = count as isize;
+  target
+    .scale_cargo(name, &CargoScale { replicas }, namespace.clone())
+    .await?;
+  let res = source
+    .scale_cargo(
+      name,
+      &CargoScale {
+        replicas: -replicas,
+      },
+      namespace.clone(),
+    )
+    .await;
+  if let Err(err) = res {
+    let res = target
+      .scale_cargo(
+        name,
+        &CargoScale {
+          replicas: -replicas,
+        },
+        namespace,
+      )
+      .await;
+    if let Err(err) = res {
+      eprintln!("Warning: unable to remove the new instances of {name}: {err}");
+    }
+    return Err(err.into());
+  }
+  Ok(())
+}
+
+/// ## Exec node drain
+///
+/// Function that execute when running `nanocl node drain`
+/// The node is cordoned then its vms are migrated like with `nanocl vm migrate`
+/// and its cargo instances are created on other nodes before being removed.
+/// Workloads are spread in turn over the reachable nodes that aren't cordoned,
+/// the ones of the `system` namespace are kept as every node runs them.
+/// Every workload is moved even if some of them failed,
+/// the node stays cordoned so the drain can be run again.
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+/// * [opts](NodeDrainOpts) The node drain options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) Every workload was moved
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+async fn exec_node_drain(
+  cli_conf: &CliConfig,
+  opts: &NodeDrainOpts,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  let nodes = client.list_node().await?;
+  let Some(node) = nodes.iter().find(|node| node.name == opts.name) else {
+    return Err(IoError::not_fount(
+      format!("Node {}", opts.name),
+      "doesn't exist, see `nanocl node ls`".to_owned(),
+    ));
+  };
+  let targets = drain_targets(cli_conf, &nodes, opts).await?;
+  if !opts.skip_confirm {
+    let names = targets
+      .iter()
+      .map(|node| node.name.clone())
+      .collect::<Vec<_>>();
+    utils::dialog::confirm(&format!(
+      "Drain node {}? Its vms and cargo instances are moved to {}",
+      node.name,
+      names.join(",")
+    ))
+    .map_err(|err| err.map_err_context(|| "Drain node"))?;
+  }
+  client.cordon_node(&node.name).await?;
+  let info = client.info().await?;
+  let source = match node.name == info.config.hostname {
+    true => client.clone(),
+    false => connect_node(node)?,
+  };
+  let mut targets = targets.iter().cycle();
+  let mut rows = Vec::new();
+  let mut failed = 0;
+  let vms = utils::client::list_in_namespaces(cli_conf, &None, true, |nsp| {
+    source.list_vm(nsp)
+  })
+  .await?;
+  let vms = vms
+    .into_iter()
+    .filter(|vm| vm.instances > 0 && vm.namespace_name != SYSTEM_NAMESPACE);
+  for vm in vms {
+    let Some(target) = targets.next() else {
+      break;
+    };
+    let namespace = vm.namespace_name;
+    if !cli_conf.quiet {
+      eprintln!("Moving vm {namespace}/{} to node {}", vm.name, target.name);
+    }
+    let res = migrate_vm(
+      cli_conf,
+      &source,
+      Some(namespace.clone()),
+      &vm.name,
+      target,
+      true,
+    )
+    .await;
+    match res {
+      Ok(_) => rows.push(NodeDrainRow {
+        kind: "vm".to_owned(),
+        namespace,
+        name: vm.name,
+        instances: 1,
+        to_node: target.name.clone(),
+      }),
+      Err(err) => {
+        eprintln!("Failed to move vm {namespace}/{}: {err}", vm.name);
+        failed += 1;
+      }
+    }
+  }
+  let cargoes =
+    utils::client::list_in_namespaces(cli_conf, &None, true, |nsp| {
+      source.list_cargo(nsp)
+    })
+    .await?;
+  let cargoes = cargoes
+    .into_iter()
+    .filter(|cargo| cargo.namespace_name != SYSTEM_NAMESPACE);
+  for cargo in cargoes {
+    let namespace = cargo.namespace_name;
+    let instances = match source
+      .list_cargo_instance(&cargo.name, Some(namespace.clone()))
+      .await
+    {
+      Ok(instances) => instances.len(),
+      Err(err) => {
+        eprintln!("Failed to list cargo {namespace}/{}: {err}", cargo.name);
+        failed += 1;
+        continue;
+      }
+    };
+    if instances == 0 {
+      continue;
+    }
+    let Some(target) = targets.next() else {
+      break;
+    };
+    if !cli_conf.quiet {
+      eprintln!(
+        "Moving {instances} instances of cargo {namespace}/{} to node {}",
+        cargo.name, target.name
+      );
+    }
+    let res = match connect_node(target) {
+      Ok(target) => {
+        move_cargo_instances(
+          &source,
+          &target,
+          &cargo.name,
+          &namespace,
+          instances,
+        )
+        .await
+      }
+      Err(err) => Err(err),
+    };
+    match res {
+      Ok(_) => rows.push(NodeDrainRow {
+        kind: "cargo".to_owned(),
+        namespace,
+        name: cargo.name,
+        instances,
+        to_node: target.name.clone(),
+      }),
+      Err(err) => {
+        eprintln!("Failed to move cargo {namespace}/{}: {err}", cargo.name);
+        failed += 1;
+      }
+    }
+  }
+  utils::print::print_table(cli_conf, rows)?;
+  if failed > 0 {
+    return Err(IoError::new(
+      format!("Node drain {}", node.name),
+      std::io::Error::new(
+        std::io::ErrorKind::Other,
+        format!("{failed} workloads failed to move, the node stays cordoned"),
+      ),
+    ));
+  }
+  Ok(())
+}
 
 /// ## Exec node
 ///
@@ -20,17 +445,12 @@ use crate::models::{NodeArg, NodeCommand, NodeRow};
 ///   * [Err](nanocl_utils::io_error::IoError) An error occured
 ///
 pub async fn exec_node(cli_conf: &CliConfig, args: &NodeArg) -> IoResult<()> {
-  let client = &cli_conf.client;
-  match args.command {
-    NodeCommand::List => {
-      let nodes = client
-        .list_node()
-        .await?
-        .into_iter()
-        .map(NodeRow::from)
-        .collect::<Vec<_>>();
-      utils::print::print_table(nodes);
+  match &args.command {
+    NodeCommand::List(opts) => exec_node_ls(cli_conf, opts).await,
+    NodeCommand::Cordon(opts) => exec_node_cordon(cli_conf, opts, true).await,
+    NodeCommand::Uncordon(opts) => {
+      exec_node_cordon(cli_conf, opts, false).await
     }
+    NodeCommand::Drain(opts) => exec_node_drain(cli_conf, opts).await,
   }
-  Ok(())
 }
@@ -1,17 +1,25 @@
-use nanocl_utils::io_error::{IoResult, FromIo};
+use nanocl_utils::io_error::{IoError, IoResult, FromIo};
+use nanocld_client::NanocldClient;
+use nanocld_client::stubs::resource::{
+  Resource, ResourceConfig, ResourcePartial, ResourceUpdate,
+};
 
 use crate::utils;
 use crate::config::CliConfig;
 use crate::models::{
   ResourceArg, ResourceCommand, ResourceRow, ResourceRemoveOpts,
   ResourceInspectOpts, ResourceRevertOpts, ResourceHistoryOpts,
-  ResourceListOpts,
+  ResourceListOpts, ResourceApplyOpts, ResourceRevisionRow,
+  ResourceRollbackOpts,
 };
 
+use super::state::parse_state_file;
+
 /// ## Exec resource ls
 ///
 /// Function that execute when running `nanocl resource ls`
-/// Will list available resources
+/// Will list available resources filtered by kind and name glob pattern
+/// when given, then paged with `--limit` and `--offset`
 ///
 /// ## Arguments
 ///
@@ -29,11 +37,33 @@ async fn exec_resource_ls(
   opts: &ResourceListOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
+  let matcher = match &opts.name {
+    Some(pattern) => Some(
+      globset::Glob::new(pattern)
+        .map_err(|err| {
+          IoError::invalid_input(
+            "Pattern",
+            format!("{pattern}: {err}").as_str(),
+          )
+        })?
+        .compile_matcher(),
+    ),
+    None => None,
+  };
   let resources = client.list_resource(None).await?;
   let row = resources
     .into_iter()
     .map(ResourceRow::from)
+    .filter(|row| match &opts.kind {
+      Some(kind) => row.kind.eq_ignore_ascii_case(kind),
+      None => true,
+    })
+    .filter(|row| match &matcher {
+      Some(matcher) => matcher.is_match(&row.name),
+      None => true,
+    })
     .collect::<Vec<ResourceRow>>();
+  let (row, footer) = utils::print::paginate(row, &opts.page);
   match opts.quiet {
     true => {
       for row in row {
@@ -41,15 +71,147 @@ async fn exec_resource_ls(
       }
     }
     false => {
-      utils::print::print_table(row);
+      utils::print::print_table(cli_conf, row)?;
     }
   }
+  if let Some(footer) = footer {
+    eprintln!("{footer}");
+  }
   Ok(())
 }
 
+/// ## Read resource file
+///
+/// Read the `Resources` declared in a file, the file is parsed like a Statefile
+/// so yaml, json and toml are supported
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+/// * [file](str) The path or url of the file, `-` to read from stdin
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Vec<ResourcePartial>) The resources of the file
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+async fn read_resource_file(
+  cli_conf: &CliConfig,
+  file: &str,
+) -> IoResult<Vec<ResourcePartial>> {
+  let format = cli_conf.user_config.display_format.clone();
+  let state_ref = parse_state_file::<serde_json::Value>(
+    &Some(file.to_owned()),
+    &format,
+    true,
+  )
+  .await?;
+  let Some(resources) = state_ref.data.get("Resources") else {
+    return Err(IoError::invalid_data(
+      format!("Resource file {file}").as_str(),
+      "has no Resources",
+    ));
+  };
+  let resources = serde_json::from_value(resources.clone())
+    .map_err(|err| err.map_err_context(|| format!("Resource file {file}")))?;
+  Ok(resources)
+}
+
+/// ## Check resource failures
+///
+/// Summarize the failures of an operation done on multiple resources
+///
+/// ## Arguments
+///
+/// * [action](str) The action that was done eg: apply, delete
+/// * [failed](usize) The number of resources that failed
+/// * [total](usize) The number of resources targeted
+///
+/// ## Return
+///
+/// * [Result](Result) Result of the operation
+///   * [Ok](()) Every resource succeeded
+///   * [Err](nanocl_utils::io_error::IoError) At least one resource failed
+///
+fn check_resource_failures(
+  action: &str,
+  failed: usize,
+  total: usize,
+) -> IoResult<()> {
+  if failed == 0 {
+    return Ok(());
+  }
+  Err(IoError::new(
+    format!("Resource {action}"),
+    std::io::Error::new(
+      std::io::ErrorKind::Other,
+      format!("{failed} of {total} resources failed to {action}"),
+    ),
+  ))
+}
+
+/// ## Exec resource apply
+///
+/// Function that execute when running `nanocl resource apply`
+/// Will create the resources declared in a file or update them if they exist,
+/// every resource is applied even if some of them failed
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+/// * [opts](ResourceApplyOpts) The resource apply options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+async fn exec_resource_apply(
+  cli_conf: &CliConfig,
+  opts: &ResourceApplyOpts,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  let resources = read_resource_file(cli_conf, &opts.file).await?;
+  if !opts.skip_confirm {
+    let names = resources
+      .iter()
+      .map(|resource| resource.name.clone())
+      .collect::<Vec<_>>();
+    utils::dialog::confirm(&format!("Apply resource {}?", names.join(",")))
+      .map_err(|err| err.map_err_context(|| "Apply resource"))?;
+  }
+  let mut failed = 0;
+  for resource in &resources {
+    let name = &resource.name;
+    let res = match client.inspect_resource(name).await {
+      Ok(_) => client
+        .put_resource(name, &ResourceUpdate::from(resource.clone()))
+        .await
+        .map(|_| "updated"),
+      Err(_) => client.create_resource(resource).await.map(|_| "created"),
+    };
+    match res {
+      Ok(action) => {
+        if !cli_conf.quiet {
+          println!("Resource {name} {action}");
+        }
+      }
+      Err(err) => {
+        eprintln!("Failed to apply resource {name}: {err}");
+        failed += 1;
+      }
+    }
+  }
+  check_resource_failures("apply", failed, resources.len())
+}
+
 /// ## Exec resource rm
 ///
 /// Function that execute when running `nanocl resource rm`
+/// Will delete the given resources or the ones declared in a file,
+/// every resource is deleted even if some of them failed
 ///
 /// ## Arguments
 ///
@@ -67,22 +229,40 @@ async fn exec_resource_rm(
   options: &ResourceRemoveOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
+  let names = match &options.file {
+    Some(file) => read_resource_file(cli_conf, file)
+      .await?
+      .into_iter()
+      .map(|resource| resource.name)
+      .collect::<Vec<_>>(),
+    None => options.names.clone(),
+  };
   if !options.skip_confirm {
-    utils::dialog::confirm(&format!(
-      "Delete resource {}?",
-      options.names.join(",")
-    ))
-    .map_err(|err| err.map_err_context(|| "Delete resource"))?;
+    utils::dialog::confirm(&format!("Delete resource {}?", names.join(",")))
+      .map_err(|err| err.map_err_context(|| "Delete resource"))?;
   }
-  for name in &options.names {
-    client.delete_resource(name).await?;
+  let mut failed = 0;
+  for name in &names {
+    match client.delete_resource(name).await {
+      Ok(_) => {
+        if !cli_conf.quiet && options.file.is_some() {
+          println!("Resource {name} deleted");
+        }
+      }
+      Err(err) => {
+        eprintln!("Failed to delete resource {name}: {err}");
+        failed += 1;
+      }
+    }
   }
-  Ok(())
+  check_resource_failures("delete", failed, names.len())
 }
 
 /// ## Exec resource inspect
 ///
 /// Function that execute when running `nanocl resource inspect`
+/// With `--kind-schema` the JSON schema of the resource kind is printed
+/// to help writing resources of this kind
 ///
 /// ## Arguments
 ///
@@ -100,18 +280,104 @@ async fn exec_resource_inspect(
   opts: &ResourceInspectOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
+  let display = cli_conf.get_display_format(&opts.display);
+  if opts.kind_schema {
+    let schema = client.inspect_resource_kind_schema(&opts.name).await?;
+    return utils::print::display_format(&display, schema);
+  }
   let resource = client.inspect_resource(&opts.name).await?;
-  let display = opts
-    .display
-    .clone()
-    .unwrap_or(cli_conf.user_config.display_format.clone());
-  utils::print::display_format(&display, resource)?;
+  utils::print::display_format_prefixed(&display, "RESOURCE", resource)?;
   Ok(())
 }
 
+/// ## Resource revisions
+///
+/// Get a resource and the revisions of its config tracked by the daemon,
+/// a resource without history can't be rolled back
+///
+/// ## Arguments
+///
+/// * [client](NanocldClient) The nanocl daemon client
+/// * [name](str) The name of the resource
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok]((Resource, Vec<ResourceConfig>)) The resource and its revisions
+///   * [Err](nanocl_utils::io_error::IoError) The daemon doesn't track its history
+///
+async fn resource_revisions(
+  client: &NanocldClient,
+  name: &str,
+) -> IoResult<(Resource, Vec<ResourceConfig>)> {
+  let resource = client.inspect_resource(name).await?;
+  let history = match client.list_history_resource(name).await {
+    Ok(history) => history,
+    Err(err) if utils::client::is_not_found(&err) => Vec::new(),
+    Err(err) => return Err(err.into()),
+  };
+  if history.is_empty() {
+    return Err(IoError::invalid_input(
+      "Resource history",
+      format!(
+        "the daemon doesn't track the history of {} resources like {name}",
+        resource.kind
+      )
+      .as_str(),
+    ));
+  }
+  Ok((resource, history))
+}
+
+/// ## Find revision
+///
+/// Find a revision by its key or by its version when it's unique
+///
+/// ## Arguments
+///
+/// * [name](str) The name of the resource
+/// * [history](Vec<ResourceConfig>) The revisions of the resource
+/// * [revision](str) The key or the version of the revision
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](ResourceConfig) The revision
+///   * [Err](nanocl_utils::io_error::IoError) No or many revisions match
+///
+fn find_revision(
+  name: &str,
+  history: Vec<ResourceConfig>,
+  revision: &str,
+) -> IoResult<ResourceConfig> {
+  if let Some(config) = history
+    .iter()
+    .find(|config| config.key.to_string() == revision)
+  {
+    return Ok(config.clone());
+  }
+  let mut found = history
+    .into_iter()
+    .filter(|config| config.version == revision)
+    .collect::<Vec<_>>();
+  match found.len() {
+    0 => Err(IoError::not_fount(
+      "Revision",
+      format!("{revision} not found for resource {name}").as_str(),
+    )),
+    1 => Ok(found.remove(0)),
+    count => Err(IoError::invalid_input(
+      "Revision",
+      format!("{count} revisions of {name} have version {revision}, use a key")
+        .as_str(),
+    )),
+  }
+}
+
 /// ## Exec resource history
 ///
-/// Function that execute when running `nanocl resource history`
+/// Function that execute when running `nanocl resource history`,
+/// the revision used by the resource is marked as current
 ///
 /// ## Arguments
 ///
@@ -129,8 +395,69 @@ async fn exec_resource_history(
   opts: &ResourceHistoryOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
-  let history = client.list_history_resource(&opts.name).await?;
-  utils::print::print_yml(history)?;
+  let (resource, history) = resource_revisions(client, &opts.name).await?;
+  let rows = history
+    .into_iter()
+    .map(|config| {
+      let current = config.key == resource.config_key;
+      ResourceRevisionRow::new(config, current)
+    })
+    .collect::<Vec<_>>();
+  utils::print::print_table(cli_conf, rows)
+}
+
+/// ## Exec resource rollback
+///
+/// Function that execute when running `nanocl resource rollback`,
+/// the changes between the current config and the revision are shown
+/// and confirmed before the resource is reverted
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+/// * [opts](ResourceRollbackOpts) The resource rollback options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+async fn exec_resource_rollback(
+  cli_conf: &CliConfig,
+  opts: &ResourceRollbackOpts,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  let (resource, history) = resource_revisions(client, &opts.name).await?;
+  let target = find_revision(&opts.name, history, &opts.to)?;
+  if target.key == resource.config_key {
+    eprintln!("Resource {} already uses revision {}", opts.name, opts.to);
+    return Ok(());
+  }
+  let current = serde_json::json!({
+    "Version": resource.version,
+    "Config": resource.config,
+  });
+  let wanted = serde_json::json!({
+    "Version": target.version,
+    "Config": target.config,
+  });
+  let lines = utils::state::diff_lines_labeled(
+    &format!("Resource {}", opts.name),
+    Some(&current),
+    &wanted,
+    ("current", &format!("revision {}", target.version)),
+  );
+  utils::print::print_diff(lines);
+  if !opts.skip_confirm {
+    utils::dialog::confirm(&format!(
+      "Roll back resource {} to revision {}?",
+      opts.name, target.version
+    ))?;
+  }
+  client
+    .revert_resource(&opts.name, &target.key.to_string())
+    .await?;
   Ok(())
 }
 
@@ -155,7 +482,8 @@ async fn exec_resource_revert(
 ) -> IoResult<()> {
   let client = &cli_conf.client;
   let resource = client.revert_resource(&opts.name, &opts.key).await?;
-  utils::print::print_yml(resource)?;
+  let display = cli_conf.get_display_format(&None);
+  utils::print::display_format(&display, resource)?;
   Ok(())
 }
 
@@ -179,6 +507,7 @@ pub async fn exec_resource(
   args: &ResourceArg,
 ) -> IoResult<()> {
   match &args.command {
+    ResourceCommand::Apply(opts) => exec_resource_apply(cli_conf, opts).await,
     ResourceCommand::List(opts) => exec_resource_ls(cli_conf, opts).await,
     ResourceCommand::Remove(opts) => exec_resource_rm(cli_conf, opts).await,
     ResourceCommand::Inspect(opts) => {
@@ -188,5 +517,8 @@ pub async fn exec_resource(
       exec_resource_history(cli_conf, opts).await
     }
     ResourceCommand::Revert(opts) => exec_resource_revert(cli_conf, opts).await,
+    ResourceCommand::Rollback(opts) => {
+      exec_resource_rollback(cli_conf, opts).await
+    }
   }
 }
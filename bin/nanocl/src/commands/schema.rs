@@ -0,0 +1,149 @@
+use utoipa::OpenApi;
+use bollard_next::container::Config;
+use bollard_next::service::{
+  PortBinding, MountBindOptionsPropagationEnum, MountVolumeOptionsDriverConfig,
+  MountBindOptions, MountTmpfsOptions, MountTypeEnum, MountVolumeOptions,
+  RestartPolicyNameEnum, ThrottleDevice, ResourcesBlkioWeightDevice,
+  HostConfigCgroupnsModeEnum, DeviceRequest, DeviceMapping,
+  HostConfigIsolationEnum, HostConfigLogConfig, Mount, RestartPolicy,
+  ResourcesUlimits, HostConfig, NetworkingConfig, EndpointIpamConfig,
+  EndpointSettings, HealthConfig,
+};
+
+use nanocl_utils::io_error::{FromIo, IoResult};
+use nanocld_client::stubs::cargo_config::{
+  CargoConfigPartial, ReplicationMode, ReplicationStatic, ReplicationPercentage,
+};
+use nanocld_client::stubs::vm_config::{
+  VmConfigPartial, VmDiskConfig, VmHostConfig,
+};
+use nanocld_client::stubs::resource::ResourcePartial;
+
+use crate::utils;
+use crate::models::{SchemaOpts, SchemaKind};
+
+/// Prefix of the references between OpenAPI components
+const OPENAPI_REF_PREFIX: &str = "#/components/schemas/";
+
+/// Prefix of the references between json schema definitions
+const JSON_SCHEMA_REF_PREFIX: &str = "#/definitions/";
+
+/// Components of the configs that can be exported,
+/// it mirror the component list of the daemon OpenAPI documentation
+#[derive(OpenApi)]
+#[openapi(components(schemas(
+  // Cargo
+  CargoConfigPartial,
+  ReplicationMode,
+  ReplicationStatic,
+  ReplicationPercentage,
+  // Container
+  Config,
+  NetworkingConfig,
+  HostConfig,
+  HealthConfig,
+  PortBinding,
+  EndpointSettings,
+  EndpointIpamConfig,
+  ThrottleDevice,
+  ResourcesBlkioWeightDevice,
+  HostConfigCgroupnsModeEnum,
+  DeviceRequest,
+  DeviceMapping,
+  HostConfigIsolationEnum,
+  HostConfigLogConfig,
+  Mount,
+  RestartPolicy,
+  ResourcesUlimits,
+  MountBindOptions,
+  MountTmpfsOptions,
+  MountTypeEnum,
+  MountVolumeOptions,
+  RestartPolicyNameEnum,
+  MountBindOptionsPropagationEnum,
+  MountVolumeOptionsDriverConfig,
+  // Vm
+  VmConfigPartial,
+  VmDiskConfig,
+  VmHostConfig,
+  // Resource
+  ResourcePartial,
+)))]
+struct ConfigSchemas;
+
+/// ## Rewrite refs
+///
+/// Rewrite the OpenAPI references of a schema to json schema definitions
+/// and collect the name of every referenced definition
+///
+/// ## Arguments
+///
+/// * [value](serde_json::Value) The schema to rewrite
+/// * [refs](Vec<String>) The referenced definitions
+///
+fn rewrite_refs(value: &mut serde_json::Value, refs: &mut Vec<String>) {
+  match value {
+    serde_json::Value::Object(map) => {
+      if let Some(serde_json::Value::String(reference)) = map.get_mut("$ref") {
+        if let Some(name) = reference.strip_prefix(OPENAPI_REF_PREFIX) {
+          refs.push(name.to_owned());
+          *reference = format!("{JSON_SCHEMA_REF_PREFIX}{name}");
+        }
+      }
+      map.values_mut().for_each(|value| rewrite_refs(value, refs));
+    }
+    serde_json::Value::Array(items) => {
+      items.iter_mut().for_each(|value| rewrite_refs(value, refs));
+    }
+    _ => {}
+  }
+}
+
+/// ## Exec schema
+///
+/// Function that execute when running `nanocl schema`
+/// Print the json schema of a config type generated from its OpenAPI schema,
+/// it can be given to a yaml language server for autocompletion.
+/// Types only known by the daemon documentation like the free form
+/// config of a resource are left open with an empty schema.
+///
+/// ## Arguments
+///
+/// * [opts](SchemaOpts) The schema options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+pub fn exec_schema(opts: &SchemaOpts) -> IoResult<()> {
+  let root = match opts.kind {
+    SchemaKind::Cargo => "CargoConfigPartial",
+    SchemaKind::Vm => "VmConfigPartial",
+    SchemaKind::Resource => "ResourcePartial",
+  };
+  let schemas = ConfigSchemas::openapi()
+    .components
+    .map(|components| components.schemas)
+    .unwrap_or_default();
+  let mut definitions = serde_json::to_value(schemas)
+    .map_err(|err| err.map_err_context(|| "Schema"))?;
+  let mut refs = Vec::new();
+  rewrite_refs(&mut definitions, &mut refs);
+  if let serde_json::Value::Object(definitions) = &mut definitions {
+    for name in refs {
+      definitions
+        .entry(name)
+        .or_insert_with(|| serde_json::json!({}));
+    }
+  }
+  let schema = serde_json::json!({
+    "$schema": "http://json-schema.org/draft-07/schema#",
+    "$ref": format!("{JSON_SCHEMA_REF_PREFIX}{root}"),
+    "definitions": definitions,
+  });
+  let schema = utils::print::to_json(&schema)?;
+  println!("{schema}");
+  Ok(())
+}
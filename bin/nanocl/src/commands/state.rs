@@ -1,29 +1,38 @@
 use std::fs;
-use std::collections::HashMap;
+use std::io::{Read, IsTerminal};
+use std::time::Duration;
+use std::collections::{HashMap, HashSet};
 
 use ntex::rt;
 use futures::StreamExt;
+use dialoguer::console;
 use clap::{Arg, Command};
 use serde::Serialize;
 use serde::de::DeserializeOwned;
-use indicatif::{MultiProgress, ProgressBar};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget};
 use bollard_next::service::HostConfig;
 
 use nanocl_utils::io_error::{IoError, FromIo, IoResult};
 use nanocld_client::NanocldClient;
 use nanocld_client::stubs::state::StateMeta;
-use nanocld_client::stubs::cargo::{OutputKind, CargoLogQuery};
+use nanocld_client::stubs::cargo::{OutputKind, CargoLogQuery, CargoDeleteQuery};
 use nanocld_client::stubs::cargo_config::{
   CargoConfigPartial, Config as ContainerConfig,
 };
+use nanocld_client::stubs::vm_config::{VmConfigPartial, VmDiskConfig};
+use nanocld_client::stubs::resource::ResourcePartial;
 
 use crate::utils;
 use crate::config::CliConfig;
 use crate::models::{
-  StateArg, StateCommand, StateApplyOpts, StateRemoveOpts, StateBuildArg,
-  DisplayFormat, StateRef,
+  StateArg, StateCommand, StateApplyOpts, StateRemoveOpts, StateLogsOpts,
+  StateDiffOpts, StateBuildArg, DisplayFormat, StateRef, StatePlan,
+  StatePlanItem, StatePrepared, StateTemplateOpts, StateApplySummary,
 };
 
+use super::cargo::{validate_image, validate_replication, wait_cargo_running};
+use super::vm::wait_vm_running;
+use super::namespace::namespace_resources;
 use super::cargo_image::exec_cargo_image_pull;
 
 /// ## Get from url
@@ -33,6 +42,7 @@ use super::cargo_image::exec_cargo_image_pull;
 /// ## Arguments
 ///
 /// * [url](str) The url of the Statefile
+/// * [env_subst](bool) Expand `${VAR}` environment variables
 ///
 /// ## Return
 ///
@@ -40,7 +50,7 @@ use super::cargo_image::exec_cargo_image_pull;
 ///   * [Ok](StateRef) The StateRef
 ///   * [Err](IoError) An error occured
 ///
-async fn get_from_url<T>(url: &str) -> IoResult<StateRef<T>>
+async fn get_from_url<T>(url: &str, env_subst: bool) -> IoResult<StateRef<T>>
 where
   T: serde::Serialize + serde::de::DeserializeOwned,
 {
@@ -76,7 +86,11 @@ where
     .split('.')
     .last()
     .ok_or_else(|| IoError::invalid_data("Statefile", "has no extension"))?;
-  let state_ref = utils::state::get_state_ref(ext, data)?;
+  let data = match env_subst {
+    true => utils::state::expand_env(data)?,
+    false => data.to_owned(),
+  };
+  let state_ref = utils::state::get_state_ref(ext, &data)?;
   Ok(state_ref)
 }
 
@@ -88,6 +102,7 @@ where
 ///
 /// * [path](std::path::Path) The path of the Statefile
 /// * [format](DisplayFormat) The format of the Statefile
+/// * [env_subst](bool) Expand `${VAR}` environment variables
 ///
 /// ## Return
 ///
@@ -98,6 +113,7 @@ where
 fn read_from_file<T>(
   path: &std::path::Path,
   format: &DisplayFormat,
+  env_subst: bool,
 ) -> IoResult<StateRef<T>>
 where
   T: serde::Serialize + serde::de::DeserializeOwned,
@@ -109,12 +125,71 @@ where
     .to_str();
 
   let ext = ext.unwrap_or_default();
-  let data = fs::read_to_string(path)?;
+  let mut data = fs::read_to_string(path)?;
+  if env_subst {
+    data = utils::state::expand_env(&data)?;
+  }
   let state_ref = utils::state::get_state_ref::<T>(ext, &data)?;
 
   Ok(state_ref)
 }
 
+/// ## Read from stdin
+///
+/// Read Statefile from stdin and return a StateRef with the raw data and the format
+/// The format is detected from the content since there is no file extension
+///
+/// ## Arguments
+///
+/// * [env_subst](bool) Expand `${VAR}` environment variables
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](StateRef) The StateRef
+///   * [Err](IoError) An error occured
+///
+fn read_from_stdin<T>(env_subst: bool) -> IoResult<StateRef<T>>
+where
+  T: serde::Serialize + serde::de::DeserializeOwned,
+{
+  let mut data = read_stdin()?;
+  if env_subst {
+    data = utils::state::expand_env(&data)?;
+  }
+  let ext = utils::state::detect_ext(&data);
+  let state_ref = utils::state::get_state_ref::<T>(ext, &data)?;
+  Ok(state_ref)
+}
+
+/// ## Read stdin
+///
+/// Read a Statefile piped on stdin
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](String) The raw Statefile
+///   * [Err](IoError) Stdin is a terminal, empty or unreadable
+///
+fn read_stdin() -> IoResult<String> {
+  let mut stdin = std::io::stdin();
+  if stdin.is_terminal() {
+    return Err(IoError::invalid_input(
+      "Statefile",
+      "expected to be piped on stdin",
+    ));
+  }
+  let mut data = String::new();
+  stdin
+    .read_to_string(&mut data)
+    .map_err(|err| err.map_err_context(|| "Unable to read stdin"))?;
+  if data.trim().is_empty() {
+    return Err(IoError::invalid_input("Statefile", "stdin is empty"));
+  }
+  Ok(data)
+}
+
 /// ## Download cargo image
 ///
 /// Download cargo image if it's not already downloaded and if the force pull flag is set
@@ -345,7 +420,7 @@ async fn attach_to_cargoes(
 ///
 /// ## Arguments
 ///
-/// * [host](str) The host of the daemon
+/// * [cli_conf](CliConfig) The cli config
 /// * [meta](StateMeta) The meta of the Statefile
 ///
 /// ## Return
@@ -354,8 +429,12 @@ async fn attach_to_cargoes(
 ///   * [Ok](NanocldClient) The nanocl daemon client
 ///   * [Err](IoError) An error occured
 ///
-fn gen_client(host: &str, meta: &StateMeta) -> IoResult<NanocldClient> {
-  let client = match meta.api_version.clone() {
+fn gen_client(
+  cli_conf: &CliConfig,
+  meta: &StateMeta,
+) -> IoResult<NanocldClient> {
+  let host = &cli_conf.host;
+  let mut client = match meta.api_version.clone() {
     api_version if meta.api_version.starts_with("http") => {
       let mut paths = api_version
         .split('/')
@@ -393,6 +472,7 @@ fn gen_client(host: &str, meta: &StateMeta) -> IoResult<NanocldClient> {
       NanocldClient::connect_to(url, Some(version.into()))
     }
   };
+  client.set_verbosity(cli_conf.verbose);
   Ok(client)
 }
 
@@ -547,8 +627,9 @@ where
 ///
 /// ## Arguments
 ///
-/// * [path](Option<String>) The path or url of the Statefile if empty set to current path + Statefile.yml
+/// * [path](Option<String>) The path or url of the Statefile if empty set to current path + Statefile.yml, `-` to read from stdin
 /// * [format](DisplayFormat) The format of the Statefile
+/// * [env_subst](bool) Expand `${VAR}` environment variables before parsing
 ///
 /// ## Return
 ///
@@ -556,60 +637,381 @@ where
 ///   * [Ok](StateRef) The StateRef
 ///   * [Err](IoError) An error occured
 ///
-async fn parse_state_file<T>(
+pub(crate) async fn parse_state_file<T>(
   path: &Option<String>,
   format: &DisplayFormat,
+  env_subst: bool,
 ) -> IoResult<StateRef<T>>
 where
   T: serde::Serialize + serde::de::DeserializeOwned,
 {
   if let Some(path) = path {
+    if path == "-" {
+      return read_from_stdin(env_subst);
+    }
     if let Ok(path) = std::path::Path::new(&path)
       .canonicalize()
       .map_err(|err| err.map_err_context(|| format!("Statefile {path}")))
     {
-      return read_from_file(&path, format);
+      return read_from_file(&path, format, env_subst);
     }
-    return get_from_url::<T>(path).await;
+    return get_from_url::<T>(path, env_subst).await;
   }
   if let Ok(path) = std::path::Path::new("Statefile.yaml").canonicalize() {
-    return read_from_file(&path, format);
+    return read_from_file(&path, format, env_subst);
   }
   if let Ok(path) = std::path::Path::new("Statefile").canonicalize() {
-    return read_from_file(&path, format);
+    return read_from_file(&path, format, env_subst);
   }
   let path = std::path::Path::new("Statefile.yml")
     .canonicalize()
     .map_err(|err| err.map_err_context(|| "Statefile Statefile.yml"))?;
-  read_from_file(&path, format)
+  read_from_file(&path, format, env_subst)
 }
 
-/// ## Exec state apply
+/// ## Get state elements
+///
+/// Extract the list of elements of a given key eg: `Cargoes` from a Statefile
+///
+/// ## Arguments
+///
+/// * [data](serde_json::Value) The data of the Statefile
+/// * [key](str) The key of the list
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Vec) The list of elements, empty if the key is missing
+///   * [Err](IoError) An error occured
+///
+fn get_state_elements<T>(
+  data: &serde_json::Value,
+  key: &str,
+) -> IoResult<Vec<T>>
+where
+  T: DeserializeOwned,
+{
+  match data.get(key) {
+    Some(elements) => Ok(
+      serde_json::from_value(elements.clone())
+        .map_err(|err| err.map_err_context(|| format!("Statefile {key}")))?,
+    ),
+    None => Ok(Vec::new()),
+  }
+}
+
+/// ## State element
+///
+/// An element of a Statefile with its current state in the cluster
+///
+struct StateElement {
+  /// Kind of the element (Cargo, VirtualMachine, Resource)
+  kind: &'static str,
+  /// Name of the element
+  name: String,
+  /// Current state of the element if it exists
+  current: Option<serde_json::Value>,
+  /// State of the element in the Statefile
+  wanted: serde_json::Value,
+}
+
+/// ## New state element
+///
+/// Serialize the current and the wanted state of an element to compare them
+///
+/// ## Arguments
+///
+/// * [kind](str) The kind of the element
+/// * [name](str) The name of the element
+/// * [current](Option<Serialize>) The current state of the element if it exists
+/// * [wanted](Serialize) The state of the element in the Statefile
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](StateElement) The element
+///   * [Err](IoError) An error occured
+///
+fn new_state_element<T>(
+  kind: &'static str,
+  name: &str,
+  current: Option<T>,
+  wanted: &T,
+) -> IoResult<StateElement>
+where
+  T: Serialize,
+{
+  let current = match current {
+    Some(current) => Some(
+      serde_json::to_value(current)
+        .map_err(|err| err.map_err_context(|| "Plan current state"))?,
+    ),
+    None => None,
+  };
+  let wanted = serde_json::to_value(wanted)
+    .map_err(|err| err.map_err_context(|| "Plan wanted state"))?;
+  Ok(StateElement {
+    kind,
+    name: name.to_owned(),
+    current,
+    wanted,
+  })
+}
+
+/// ## Gen state elements
+///
+/// Fetch the current state in the cluster of every element of a Statefile,
+/// concurrently up to the `--concurrency` limit
+///
+/// ## Arguments
+///
+/// * [client](NanocldClient) The client to the daemon
+/// * [data](serde_json::Value) The data of the Statefile
+/// * [concurrency](usize) The maximum number of concurrent requests
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Vec<StateElement>) The elements in the order they are applied
+///   * [Err](IoError) An error occured
+///
+async fn gen_state_elements(
+  client: &NanocldClient,
+  data: &serde_json::Value,
+  concurrency: usize,
+) -> IoResult<Vec<StateElement>> {
+  let mut elements = Vec::new();
+  let namespace = data
+    .get("Namespace")
+    .and_then(|namespace| namespace.as_str())
+    .unwrap_or("global")
+    .to_owned();
+  let resources: Vec<ResourcePartial> = get_state_elements(data, "Resources")?;
+  let inspects = resources.iter().map(|resource| async move {
+    let current = client
+      .inspect_resource(&resource.name)
+      .await
+      .ok()
+      .map(ResourcePartial::from);
+    (resource, current)
+  });
+  for (resource, current) in
+    utils::client::run_concurrent(concurrency, inspects).await
+  {
+    elements.push(new_state_element(
+      "Resource",
+      &resource.name,
+      current,
+      resource,
+    )?);
+  }
+  let cargoes: Vec<CargoConfigPartial> = get_state_elements(data, "Cargoes")?;
+  let inspects = cargoes.iter().map(|cargo| {
+    let namespace = Some(namespace.clone());
+    async move {
+      let current = client
+        .inspect_cargo(&cargo.name, namespace)
+        .await
+        .ok()
+        .map(CargoConfigPartial::from);
+      (cargo, current)
+    }
+  });
+  for (cargo, current) in
+    utils::client::run_concurrent(concurrency, inspects).await
+  {
+    elements.push(new_state_element("Cargo", &cargo.name, current, cargo)?);
+  }
+  let vms: Vec<VmConfigPartial> = get_state_elements(data, "VirtualMachines")?;
+  let inspects = vms.iter().map(|vm| {
+    let namespace = Some(namespace.clone());
+    async move {
+      let current = client
+        .inspect_vm(&vm.name, namespace)
+        .await
+        .ok()
+        .map(VmConfigPartial::from);
+      (vm, current)
+    }
+  });
+  for (vm, current) in
+    utils::client::run_concurrent(concurrency, inspects).await
+  {
+    // The daemon store the disk as a snapshot named after the vm key
+    let vm = VmConfigPartial {
+      disk: VmDiskConfig {
+        image: format!("{}.{}.{namespace}", vm.disk.image, vm.name),
+        size: Some(vm.disk.size.unwrap_or(20)),
+      },
+      host_config: Some(vm.host_config.clone().unwrap_or_default()),
+      ..vm.clone()
+    };
+    elements.push(new_state_element("VirtualMachine", &vm.name, current, &vm)?);
+  }
+  Ok(elements)
+}
+
+/// ## Gen plan
 ///
-/// Function called when running `nanocl state apply`
+/// Compare a Statefile with the current state of the cluster
+/// to know which elements would be created, updated or deleted
+///
+/// ## Arguments
+///
+/// * [client](NanocldClient) The client to the daemon
+/// * [data](serde_json::Value) The data of the Statefile
+/// * [remove](bool) Whether the Statefile is removed instead of applied
+/// * [concurrency](usize) The maximum number of concurrent requests
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](StatePlan) The plan
+///   * [Err](IoError) An error occured
+///
+async fn gen_plan(
+  client: &NanocldClient,
+  data: &serde_json::Value,
+  remove: bool,
+  concurrency: usize,
+) -> IoResult<StatePlan> {
+  let mut plan = StatePlan::default();
+  for element in gen_state_elements(client, data, concurrency).await? {
+    let mut item = StatePlanItem {
+      kind: element.kind.to_owned(),
+      name: element.name,
+      fields: Vec::new(),
+    };
+    match (element.current, remove) {
+      (None, true) => {}
+      (None, false) => plan.create.push(item),
+      (Some(_), true) => plan.delete.push(item),
+      (Some(current), false) => {
+        item.fields = utils::state::diff_fields(&current, &element.wanted);
+        if !item.fields.is_empty() {
+          plan.update.push(item);
+        }
+      }
+    }
+  }
+  Ok(plan)
+}
+
+/// ## Changed cargoes
+///
+/// List the cargoes of a Statefile that already exist in the cluster
+/// with a config different from the Statefile
+///
+/// ## Arguments
+///
+/// * [client](NanocldClient) The client to the daemon
+/// * [data](serde_json::Value) The data of the Statefile
+/// * [concurrency](usize) The maximum number of concurrent requests
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Vec<String>) The names of the changed cargoes
+///   * [Err](IoError) An error occured
+///
+async fn changed_cargoes(
+  client: &NanocldClient,
+  data: &serde_json::Value,
+  concurrency: usize,
+) -> IoResult<Vec<String>> {
+  let changed = gen_state_elements(client, data, concurrency)
+    .await?
+    .into_iter()
+    .filter(|element| element.kind == "Cargo")
+    .filter(|element| match &element.current {
+      Some(current) => {
+        !utils::state::diff_fields(current, &element.wanted).is_empty()
+      }
+      None => false,
+    })
+    .map(|element| element.name)
+    .collect();
+  Ok(changed)
+}
+
+/// ## Parse state files
+///
+/// Parse every Statefile given with `--state-location` in the given order,
+/// directories are walked for Statefiles parsed in lexicographic order
+///
+/// ## Arguments
+///
+/// * [locations](Vec<String>) The paths, urls or directories of the Statefiles, the current Statefile is used when empty
+/// * [format](DisplayFormat) The format of the Statefiles
+/// * [env_subst](bool) Expand `${VAR}` environment variables before parsing
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Vec<(String, StateRef)>) The StateRefs with their location
+///   * [Err](IoError) An error occured
+///
+async fn parse_state_files<T>(
+  locations: &[String],
+  format: &DisplayFormat,
+  env_subst: bool,
+) -> IoResult<Vec<(String, StateRef<T>)>>
+where
+  T: serde::Serialize + serde::de::DeserializeOwned,
+{
+  if locations.is_empty() {
+    let state_ref = parse_state_file(&None, format, env_subst).await?;
+    return Ok(vec![("Statefile".to_owned(), state_ref)]);
+  }
+  let mut states = Vec::new();
+  for location in locations {
+    let path = std::path::Path::new(location);
+    if location != "-" && path.is_dir() {
+      for file in utils::state::list_state_files(path)? {
+        let state_ref = read_from_file(&file, format, env_subst)
+          .map_err(|err| err.map_err_context(|| file.display()))?;
+        states.push((file.display().to_string(), state_ref));
+      }
+      continue;
+    }
+    let state_ref =
+      parse_state_file(&Some(location.clone()), format, env_subst).await?;
+    states.push((location.clone(), state_ref));
+  }
+  Ok(states)
+}
+
+/// ## Prepare state apply
+///
+/// Inject the data of a Statefile and hook its cargoes before applying it,
+/// the namespace of the Statefile is created unless `--dry-run` is set
 ///
 /// ## Arguments
 ///
 /// * [cli_conf](CliConfig) The cli config
-/// * [opts](StateApplyOpts) The state apply options
+/// * [args](Vec<String>) The additional arguments to pass to the Statefile
+/// * [dry_run](bool) Whether the Statefile is only compared to the cluster
+/// * [location](String) The path or url of the Statefile
+/// * [state_ref](StateRef) The parsed Statefile
 ///
 /// ## Return
 ///
 /// * [Result](Result) The result of the operation
-///   * [Ok](()) The operation was successful
+///   * [Ok](StatePrepared) The Statefile ready to be applied
 ///   * [Err](IoError) An error occured
 ///
-async fn exec_state_apply(
+async fn prepare_state_apply(
   cli_conf: &CliConfig,
-  opts: &StateApplyOpts,
-) -> IoResult<()> {
-  let host = &cli_conf.host;
-  let format = cli_conf.user_config.display_format.clone();
-  let state_ref = parse_state_file(&opts.state_location, &format).await?;
-  let client = gen_client(host, &state_ref.meta)?;
-  let args = parse_build_args(&state_ref.data, opts.args.clone())?;
+  args: &[String],
+  dry_run: bool,
+  location: String,
+  state_ref: StateRef<serde_yaml::Value>,
+) -> IoResult<StatePrepared<serde_yaml::Value>> {
+  let client = gen_client(cli_conf, &state_ref.meta)?;
+  let args = parse_build_args(&state_ref.data, args.to_vec())?;
   let mut namespace = String::from("global");
   let mut cargoes = Vec::new();
+  let depends_on;
   let data = match state_ref.meta.kind.as_str() {
     "Deployment" | "Cargo" => {
       namespace = match state_ref.data.get("Namespace") {
@@ -618,9 +1020,13 @@ async fn exec_state_apply(
         None => "global".to_owned(),
       };
       namespace = inject_namespace(&namespace, &args)?;
-      let _ = client.create_namespace(&namespace).await;
+      if !dry_run {
+        let _ = client.create_namespace(&namespace).await;
+      }
       let mut yaml: serde_yaml::Value =
         inject_data(&state_ref.format, &state_ref.raw, &args, &client).await?;
+      // Taken before the cargoes are parsed which would drop it
+      depends_on = utils::state::take_depends_on(&mut yaml)?;
       let current_cargoes: Vec<CargoConfigPartial> = match yaml.get("Cargoes") {
         Some(cargoes) => serde_yaml::from_value(cargoes.clone())
           .map_err(|err| err.map_err_context(|| "Unable to convert to yaml"))?,
@@ -632,48 +1038,490 @@ async fn exec_state_apply(
         .map_err(|err| err.map_err_context(|| "Unable to convert to yaml"))?;
       yaml
     }
-    _ => inject_data(&state_ref.format, &state_ref.raw, &args, &client).await?,
+    _ => {
+      let mut yaml: serde_yaml::Value =
+        inject_data(&state_ref.format, &state_ref.raw, &args, &client).await?;
+      depends_on = utils::state::take_depends_on(&mut yaml)?;
+      yaml
+    }
+  };
+  for cargo in &cargoes {
+    validate_image(&cargo.name, &cargo.container)?;
+    validate_replication(&cargo.name, &cargo.replication)?;
+  }
+  let layers = utils::state::dependency_layers(&depends_on)?;
+  Ok(StatePrepared {
+    location,
+    format: state_ref.format,
+    meta: state_ref.meta,
+    namespace,
+    cargoes,
+    depends_on,
+    layers,
+    data,
+  })
+}
+
+/// ## StatePrune
+///
+/// Elements of a namespace that are not in the applied Statefiles
+///
+struct StatePrune {
+  /// Client of the Statefiles of the namespace
+  client: NanocldClient,
+  /// Namespace of the Statefiles
+  namespace: String,
+  /// Elements to delete
+  items: Vec<StatePlanItem>,
+}
+
+/// ## Gen prunes
+///
+/// List the elements to delete with `--prune` for each namespace
+/// of the Deployment, Cargo and VirtualMachine Statefiles:
+/// the cargoes and virtual machines of the namespace that are not in the Statefiles,
+/// and the resources targeting the namespace that are not in the Statefiles.
+/// With a label only the cargoes and virtual machines having it are pruned.
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+/// * [prepared](Vec<StatePrepared>) The Statefiles to apply
+/// * [label](Option<(String, String)>) The `--prune-label` option
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Vec<StatePrune>) The elements to delete of each namespace
+///   * [Err](IoError) An error occured
+///
+async fn gen_prunes(
+  cli_conf: &CliConfig,
+  prepared: &[StatePrepared<serde_yaml::Value>],
+  label: &Option<(String, String)>,
+) -> IoResult<Vec<StatePrune>> {
+  let wanted_resources = prepared
+    .iter()
+    .flat_map(|state| utils::state::state_names(&state.data, "Resources"))
+    .collect::<HashSet<_>>();
+  let mut scopes: Vec<(String, &StateMeta)> = Vec::new();
+  let mut wanted: HashMap<String, HashSet<String>> = HashMap::new();
+  for state in prepared {
+    let namespace = match state.meta.kind.as_str() {
+      "Deployment" | "Cargo" => state.namespace.clone(),
+      "VirtualMachine" => state
+        .data
+        .get("Namespace")
+        .and_then(|namespace| namespace.as_str())
+        .unwrap_or("global")
+        .to_owned(),
+      _ => continue,
+    };
+    if !scopes.iter().any(|(scope, _)| scope == &namespace) {
+      scopes.push((namespace.clone(), &state.meta));
+    }
+    let names = wanted.entry(namespace).or_default();
+    for key in ["Cargoes", "VirtualMachines"] {
+      names.extend(utils::state::state_names(&state.data, key));
+    }
+  }
+  let mut prunes = Vec::new();
+  let mut pruned_resources = HashSet::new();
+  for (namespace, meta) in scopes {
+    let client = gen_client(cli_conf, meta)?;
+    let names = &wanted[&namespace];
+    // A namespace that doesn't exist yet has nothing to prune
+    let Ok(inspect) = client.inspect_namespace(&namespace).await else {
+      continue;
+    };
+    let vms = client.list_vm(Some(namespace.clone())).await?;
+    let resources = match label {
+      Some(_) => Vec::new(),
+      None => client.list_resource(None).await?,
+    };
+    let mut items = Vec::new();
+    for resource in namespace_resources(&inspect, &vms, &resources) {
+      if !wanted_resources.contains(&resource.name)
+        && pruned_resources.insert(resource.name.clone())
+      {
+        items.push(StatePlanItem {
+          kind: "Resource".to_owned(),
+          name: resource.name.clone(),
+          fields: Vec::new(),
+        });
+      }
+    }
+    for cargo in &inspect.cargoes {
+      if !names.contains(&cargo.name)
+        && utils::state::has_label(&cargo.config.container.labels, label)
+      {
+        items.push(StatePlanItem {
+          kind: "Cargo".to_owned(),
+          name: cargo.name.clone(),
+          fields: Vec::new(),
+        });
+      }
+    }
+    for vm in &vms {
+      if !names.contains(&vm.name)
+        && utils::state::has_label(&vm.config.labels, label)
+      {
+        items.push(StatePlanItem {
+          kind: "VirtualMachine".to_owned(),
+          name: vm.name.clone(),
+          fields: Vec::new(),
+        });
+      }
+    }
+    if !items.is_empty() {
+      prunes.push(StatePrune {
+        client,
+        namespace,
+        items,
+      });
+    }
+  }
+  Ok(prunes)
+}
+
+/// ## Exec prunes
+///
+/// Delete the elements listed by [gen_prunes](gen_prunes),
+/// resources first as they target the cargoes and virtual machines.
+/// Every element is attempted even if a previous one failed.
+///
+/// ## Arguments
+///
+/// * [prunes](Vec<StatePrune>) The elements to delete of each namespace
+/// * [summary](StateApplySummary) The summary counting the pruned elements
+/// * [quiet](bool) Only count the pruned elements without printing them
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) Every element was deleted
+///   * [Err](IoError) Some elements failed to be deleted
+///
+async fn exec_prunes(
+  prunes: &[StatePrune],
+  summary: &mut StateApplySummary,
+  quiet: bool,
+) -> IoResult<()> {
+  let mut failed = 0;
+  let total = prunes.iter().map(|prune| prune.items.len()).sum::<usize>();
+  for prune in prunes {
+    let client = &prune.client;
+    let namespace = Some(prune.namespace.clone());
+    for item in &prune.items {
+      let res = match item.kind.as_str() {
+        "Resource" => client.delete_resource(&item.name).await,
+        "Cargo" => {
+          let query = CargoDeleteQuery {
+            namespace: namespace.clone(),
+            force: Some(true),
+          };
+          client.delete_cargo(&item.name, &query).await
+        }
+        _ => client.delete_vm(&item.name, namespace.clone()).await,
+      };
+      match res {
+        Ok(_) => {
+          summary.pruned += 1;
+          if !quiet {
+            println!("Pruned {} {}", item.kind, item.name);
+          }
+        }
+        Err(err) => {
+          eprintln!("Failed to prune {} {}: {err}", item.kind, item.name);
+          summary.failed += 1;
+          failed += 1;
+        }
+      }
+    }
+  }
+  if failed == 0 {
+    return Ok(());
+  }
+  Err(IoError::new(
+    "State prune",
+    std::io::Error::new(
+      std::io::ErrorKind::Other,
+      format!("{failed} of {total} elements failed to be pruned"),
+    ),
+  ))
+}
+
+/// ## Wait dependencies
+///
+/// Wait for the cargoes and virtual machines of a layer that others
+/// depend on to be running, and healthy for the cargoes with a healthcheck,
+/// before the next layer is applied
+///
+/// ## Arguments
+///
+/// * [client](NanocldClient) The client to the daemon
+/// * [state](StatePrepared) The Statefile being applied
+/// * [layer](Vec<String>) The names of the applied layer
+/// * [timeout](Duration) The maximum time to wait for each dependency
+/// * [quiet](bool) Hide the spinners
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) Every dependency is ready
+///   * [Err](IoError) A dependency is not ready before the timeout
+///
+async fn wait_dependencies(
+  client: &NanocldClient,
+  state: &StatePrepared<serde_yaml::Value>,
+  layer: &[String],
+  timeout: Duration,
+  quiet: bool,
+) -> IoResult<()> {
+  let dependencies =
+    state.depends_on.values().flatten().collect::<HashSet<_>>();
+  let namespace = state
+    .data
+    .get("Namespace")
+    .and_then(|namespace| namespace.as_str())
+    .unwrap_or(&state.namespace);
+  let multiprogress = MultiProgress::new();
+  if quiet {
+    multiprogress.set_draw_target(ProgressDrawTarget::hidden());
+  }
+  let waits =
+    layer
+      .iter()
+      .filter(|name| dependencies.contains(name))
+      .map(|name| {
+        let pg = utils::state::new_spinner(&multiprogress, name);
+        let namespace = Some(namespace.to_owned());
+        async move {
+          match state.cargoes.iter().any(|cargo| &cargo.name == name) {
+            true => {
+              wait_cargo_running(client, name, namespace, timeout, true, pg)
+                .await
+            }
+            false => {
+              wait_vm_running(client, name, namespace, timeout, pg).await
+            }
+          }
+        }
+      });
+  for res in futures::future::join_all(waits).await {
+    res?;
+  }
+  Ok(())
+}
+
+/// ## Exec state apply
+///
+/// Function called when running `nanocl state apply`,
+/// with `--dry-run` it only print what would be created or updated.
+/// With `--reload` the existing cargoes whose config changed are restarted.
+/// With `--prune` the elements missing from the Statefiles are deleted
+/// once they are applied.
+/// With `--quiet` or the global `--quiet` flag only errors and a summary line
+/// counting the created, updated and unchanged elements are printed.
+/// The cargoes and virtual machines declaring a `DependsOn` are applied
+/// after their dependencies, with `--wait` once they are running and healthy.
+/// Every Statefile is parsed and checked for duplicated elements
+/// and dependency cycles before any of them is applied
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+/// * [opts](StateApplyOpts) The state apply options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+async fn exec_state_apply(
+  cli_conf: &CliConfig,
+  opts: &StateApplyOpts,
+) -> IoResult<()> {
+  let format = cli_conf.user_config.display_format.clone();
+  let states =
+    parse_state_files(&opts.state_location, &format, !opts.no_env_subst)
+      .await?;
+  utils::state::check_duplicates(&states)?;
+  let mut prepared = Vec::new();
+  for (location, state_ref) in states {
+    prepared.push(
+      prepare_state_apply(
+        cli_conf,
+        &opts.args,
+        opts.dry_run,
+        location,
+        state_ref,
+      )
+      .await?,
+    );
+  }
+  if opts.dry_run {
+    let mut plan = StatePlan::default();
+    for state in &prepared {
+      let client = gen_client(cli_conf, &state.meta)?;
+      let data = serde_json::to_value(&state.data)
+        .map_err(|err| err.map_err_context(|| "Unable to convert to json"))?;
+      let state_plan =
+        gen_plan(&client, &data, false, cli_conf.concurrency).await?;
+      plan.create.extend(state_plan.create);
+      plan.update.extend(state_plan.update);
+      plan.delete.extend(state_plan.delete);
+    }
+    if opts.prune {
+      for prune in gen_prunes(cli_conf, &prepared, &opts.prune_label).await? {
+        plan.delete.extend(prune.items);
+      }
+    }
+    let format = cli_conf.get_display_format(&None);
+    return utils::print::display_format(&format, plan);
+  }
+  let prunes = match opts.prune {
+    true => gen_prunes(cli_conf, &prepared, &opts.prune_label).await?,
+    false => Vec::new(),
   };
   if !opts.skip_confirm {
-    utils::print::display_format(&state_ref.format, &data)?;
+    for state in &prepared {
+      if prepared.len() > 1 {
+        println!("{}:", state.location);
+      }
+      utils::print::display_format(&state.format, &state.data)?;
+    }
+    for prune in &prunes {
+      println!("Prune from namespace {}:", prune.namespace);
+      for item in &prune.items {
+        println!("  {} {}", item.kind, item.name);
+      }
+    }
     utils::dialog::confirm("Are you sure to apply this state ?")
       .map_err(|err| err.map_err_context(|| "StateApply"))?;
   }
-  for cargo in &cargoes {
-    let is_missing = client
-      .inspect_cargo_image(&cargo.container.image.clone().unwrap_or_default())
-      .await
-      .is_err();
-    // Download cargoes images
-    if is_missing || opts.force_pull {
-      if let Err(err) = download_cargo_image(&client, cargo).await {
-        eprintln!("{err}");
-        if is_missing {
-          return Err(err);
+  let quiet = opts.quiet || cli_conf.quiet;
+  let mut summary = StateApplySummary::default();
+  let mut follows = Vec::new();
+  for state in prepared {
+    let client = gen_client(cli_conf, &state.meta)?;
+    for cargo in &state.cargoes {
+      let is_missing = client
+        .inspect_cargo_image(&cargo.container.image.clone().unwrap_or_default())
+        .await
+        .is_err();
+      // Download cargoes images
+      if is_missing || opts.force_pull {
+        if let Err(err) = download_cargo_image(&client, cargo).await {
+          eprintln!("{err}");
+          if is_missing {
+            return Err(err);
+          }
+        }
+      }
+    }
+    let data = serde_json::to_value(&state.data).map_err(|err| {
+      err.map_err_context(|| "Unable to create json payload for the daemon")
+    })?;
+    let changed = match opts.reload {
+      true => changed_cargoes(&client, &data, cli_conf.concurrency).await?,
+      false => Vec::new(),
+    };
+    // The daemon report every applied element as a success,
+    // the missing ones are listed beforehand to count them as created
+    let created = match quiet {
+      true => gen_plan(&client, &data, false, cli_conf.concurrency)
+        .await?
+        .create
+        .into_iter()
+        .map(|item| match item.kind.as_str() {
+          "Resource" => item.name,
+          _ => format!("{}.{}", item.name, state.namespace),
+        })
+        .collect::<HashSet<_>>(),
+      false => HashSet::new(),
+    };
+    // Without dependencies the whole Statefile is applied at once,
+    // otherwise each layer is applied after the ones it depends on
+    let last = state.layers.len().saturating_sub(1);
+    let payloads = match state.layers.is_empty() {
+      true => vec![data],
+      false => state
+        .layers
+        .iter()
+        .enumerate()
+        .map(|(index, layer)| {
+          let layer =
+            utils::state::layer_data(&state.data, layer, index == last);
+          serde_json::to_value(layer).map_err(|err| {
+            err.map_err_context(|| {
+              "Unable to create json payload for the daemon"
+            })
+          })
+        })
+        .collect::<Result<Vec<_>, _>>()?,
+    };
+    for (index, payload) in payloads.iter().enumerate() {
+      let mut stream = client.apply_state(payload).await?;
+      let multiprogress = MultiProgress::new();
+      multiprogress.set_move_cursor(false);
+      let mut layers: HashMap<String, ProgressBar> = HashMap::new();
+      while let Some(res) = stream.next().await {
+        let res = res?;
+        if quiet {
+          utils::state::record_apply(&mut summary, &res, &created);
+          continue;
         }
+        utils::state::update_progress(
+          &multiprogress,
+          &mut layers,
+          &res.key,
+          &res,
+        );
+      }
+      if opts.wait && index < last {
+        wait_dependencies(
+          &client,
+          &state,
+          &state.layers[index],
+          opts.wait_timeout,
+          quiet,
+        )
+        .await?;
+      }
+    }
+    for name in changed {
+      client
+        .restart_cargo(&name, Some(state.namespace.clone()))
+        .await?;
+      summary.restarted += 1;
+      if !quiet {
+        println!("Restarted cargo {name}");
       }
     }
+    if opts.follow {
+      follows.push((client, state.cargoes, state.namespace));
+    }
   }
-  let data = serde_json::to_value(&data).map_err(|err| {
-    err.map_err_context(|| "Unable to create json payload for the daemon")
-  })?;
-  let mut stream = client.apply_state(&data).await?;
-  let multiprogress = MultiProgress::new();
-  multiprogress.set_move_cursor(false);
-  let mut layers: HashMap<String, ProgressBar> = HashMap::new();
-  while let Some(res) = stream.next().await {
-    let res = res?;
-    utils::state::update_progress(&multiprogress, &mut layers, &res.key, &res);
+  let pruned = exec_prunes(&prunes, &mut summary, quiet).await;
+  if quiet {
+    println!("{summary}");
   }
-  if opts.follow {
-    attach_to_cargoes(&client, cargoes, &namespace).await?;
+  pruned?;
+  let attaches = follows.iter().map(|(client, cargoes, namespace)| {
+    attach_to_cargoes(client, cargoes.clone(), namespace)
+  });
+  for res in futures::future::join_all(attaches).await {
+    res?;
   }
   Ok(())
 }
 
 /// ## Exec state remove
 ///
-/// Function called when running `nanocl state rm`
+/// Function called when running `nanocl state rm`,
+/// with `--dry-run` it only print what would be deleted
 ///
 /// ## Arguments
 ///
@@ -690,13 +1538,18 @@ async fn exec_state_remove(
   cli_conf: &CliConfig,
   opts: &StateRemoveOpts,
 ) -> IoResult<()> {
-  let host = &cli_conf.host;
   let format = cli_conf.user_config.display_format.clone();
-  let state_ref = parse_state_file(&opts.state_location, &format).await?;
-  let client = gen_client(host, &state_ref.meta)?;
+  let state_ref =
+    parse_state_file(&opts.state_location, &format, !opts.no_env_subst).await?;
+  let client = gen_client(cli_conf, &state_ref.meta)?;
   let args = parse_build_args(&state_ref.data, opts.args.clone())?;
   let data: serde_json::Value =
     inject_data(&state_ref.format, &state_ref.raw, &args, &client).await?;
+  if opts.dry_run {
+    let plan = gen_plan(&client, &data, true, cli_conf.concurrency).await?;
+    let format = cli_conf.get_display_format(&None);
+    return utils::print::display_format(&format, plan);
+  }
   if !opts.skip_confirm {
     utils::print::display_format(&state_ref.format, &data)?;
     utils::dialog::confirm("Are you sure to remove this state ?")
@@ -713,6 +1566,227 @@ async fn exec_state_remove(
   Ok(())
 }
 
+/// ## Exec state diff
+///
+/// Function called when running `nanocl state diff`
+/// Print a unified diff of the fields that differ between the elements
+/// of the Statefiles and their current state in the cluster.
+/// It fails when there are differences so drifts can be detected in scripts
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+/// * [opts](StateDiffOpts) The state diff options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The cluster is in sync with the Statefiles
+///   * [Err](IoError) Some elements differ or an error occured
+///
+async fn exec_state_diff(
+  cli_conf: &CliConfig,
+  opts: &StateDiffOpts,
+) -> IoResult<()> {
+  let format = cli_conf.user_config.display_format.clone();
+  let states =
+    parse_state_files(&opts.state_location, &format, !opts.no_env_subst)
+      .await?;
+  utils::state::check_duplicates(&states)?;
+  let mut changed = 0;
+  for (location, state_ref) in states {
+    let state =
+      prepare_state_apply(cli_conf, &opts.args, true, location, state_ref)
+        .await?;
+    let client = gen_client(cli_conf, &state.meta)?;
+    let data = serde_json::to_value(&state.data)
+      .map_err(|err| err.map_err_context(|| "Unable to convert to json"))?;
+    for element in
+      gen_state_elements(&client, &data, cli_conf.concurrency).await?
+    {
+      let lines = utils::state::diff_lines(
+        &format!("{} {}", element.kind, element.name),
+        element.current.as_ref(),
+        &element.wanted,
+      );
+      if lines.is_empty() {
+        continue;
+      }
+      changed += 1;
+      utils::print::print_diff(lines);
+    }
+  }
+  if changed == 0 {
+    return Ok(());
+  }
+  Err(IoError::new(
+    "State diff",
+    std::io::Error::new(
+      std::io::ErrorKind::Other,
+      format!("{changed} elements differ from the cluster"),
+    ),
+  ))
+}
+
+/// ## Stream element logs
+///
+/// Print the logs of a cargo or a virtual machine of a Statefile
+/// with each line prefixed by its name in the given color
+///
+/// ## Arguments
+///
+/// * [client](NanocldClient) The client to the daemon
+/// * [kind](str) The kind of the element `Cargo` or `VirtualMachine`
+/// * [name](str) The name of the element
+/// * [query](CargoLogQuery) The logs query
+/// * [color](console::Color) The color of the prefix
+///
+async fn stream_element_logs(
+  client: NanocldClient,
+  kind: &str,
+  name: String,
+  query: CargoLogQuery,
+  color: console::Color,
+) {
+  let stream = match kind {
+    "Cargo" => client.logs_cargo(&name, &query).await,
+    _ => client.logs_vm(&name, &query).await,
+  };
+  let stream = match stream {
+    Ok(stream) => stream,
+    Err(err) => {
+      eprintln!("Cannot get logs of {name}: {err}");
+      return;
+    }
+  };
+  let prefix = format!("{} ", console::style(format!("[{name}]")).fg(color));
+  utils::print::print_prefixed_logs(stream, &prefix).await;
+}
+
+/// ## Exec state logs
+///
+/// Function called when running `nanocl state logs`
+/// Multiplex the logs of every cargo and virtual machine of a Statefile,
+/// elements that don't exist yet are skipped with a warning
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+/// * [opts](StateLogsOpts) The state logs options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+async fn exec_state_logs(
+  cli_conf: &CliConfig,
+  opts: &StateLogsOpts,
+) -> IoResult<()> {
+  let format = cli_conf.user_config.display_format.clone();
+  let state_ref =
+    parse_state_file(&opts.state_location, &format, !opts.no_env_subst).await?;
+  let client = gen_client(cli_conf, &state_ref.meta)?;
+  let args = parse_build_args(&state_ref.data, opts.args.clone())?;
+  let data: serde_json::Value =
+    inject_data(&state_ref.format, &state_ref.raw, &args, &client).await?;
+  let namespace = data
+    .get("Namespace")
+    .and_then(|namespace| namespace.as_str())
+    .unwrap_or("global")
+    .to_owned();
+  let cargoes: Vec<CargoConfigPartial> = get_state_elements(&data, "Cargoes")?;
+  let vms: Vec<VmConfigPartial> = get_state_elements(&data, "VirtualMachines")?;
+  let mut elements = Vec::new();
+  for cargo in cargoes {
+    match client
+      .inspect_cargo(&cargo.name, Some(namespace.clone()))
+      .await
+    {
+      Ok(_) => elements.push(("Cargo", cargo.name)),
+      Err(_) => eprintln!("Cargo {} doesn't exist yet, skipping", cargo.name),
+    }
+  }
+  for vm in vms {
+    match client.inspect_vm(&vm.name, Some(namespace.clone())).await {
+      Ok(_) => elements.push(("VirtualMachine", vm.name)),
+      Err(_) => eprintln!("Vm {} doesn't exist yet, skipping", vm.name),
+    }
+  }
+  let futures = elements
+    .into_iter()
+    .zip(utils::print::PREFIX_COLORS.iter().cycle())
+    .map(|((kind, name), color)| {
+      let query = CargoLogQuery {
+        namespace: Some(namespace.clone()),
+        tail: opts.tail.clone(),
+        follow: Some(opts.follow),
+        ..Default::default()
+      };
+      rt::spawn(stream_element_logs(
+        client.clone(),
+        kind,
+        name,
+        query,
+        *color,
+      ))
+    })
+    .collect::<Vec<_>>();
+  futures::future::join_all(futures).await;
+  Ok(())
+}
+
+/// ## Exec state template
+///
+/// Function called when running `nanocl state template`
+/// Print a Statefile with its `${VAR}` variables substituted without applying it.
+/// A variable is taken from `--set`, then the `--values` files,
+/// then the environment and finally its `${VAR:-default}`.
+/// `${{ }}` templates are left to be rendered by `state apply`
+///
+/// ## Arguments
+///
+/// * [opts](StateTemplateOpts) The state template options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+fn exec_state_template(opts: &StateTemplateOpts) -> IoResult<()> {
+  let raw = match opts.file.as_str() {
+    "-" => read_stdin()?,
+    file => fs::read_to_string(file)
+      .map_err(|err| err.map_err_context(|| format!("Statefile {file}")))?,
+  };
+  let mut values = HashMap::new();
+  for file in &opts.values {
+    let content = fs::read_to_string(file)
+      .map_err(|err| err.map_err_context(|| format!("Values {file}")))?;
+    values.extend(utils::state::read_values(&content)?);
+  }
+  values.extend(opts.set.iter().cloned());
+  let rendered = utils::state::expand_vars(&raw, |name| {
+    values
+      .get(name)
+      .cloned()
+      .or_else(|| std::env::var(name).ok())
+  })?;
+  let ext = match std::path::Path::new(&opts.file)
+    .extension()
+    .and_then(|ext| ext.to_str())
+  {
+    Some(ext @ ("yaml" | "yml" | "json" | "toml")) => ext,
+    _ => utils::state::detect_ext(&rendered),
+  };
+  // Make sure the rendered Statefile is valid before printing it
+  utils::state::get_state_ref::<serde_yaml::Value>(ext, &rendered)?;
+  print!("{rendered}");
+  Ok(())
+}
+
 /// ## Exec state
 ///
 /// Function called when running `nanocl state` with correct arguments
@@ -732,5 +1806,8 @@ pub async fn exec_state(cli_conf: &CliConfig, args: &StateArg) -> IoResult<()> {
   match &args.command {
     StateCommand::Apply(opts) => exec_state_apply(cli_conf, opts).await,
     StateCommand::Remove(opts) => exec_state_remove(cli_conf, opts).await,
+    StateCommand::Logs(opts) => exec_state_logs(cli_conf, opts).await,
+    StateCommand::Diff(opts) => exec_state_diff(cli_conf, opts).await,
+    StateCommand::Template(opts) => exec_state_template(opts),
   }
 }
@@ -1,18 +1,78 @@
-use nanocl_utils::io_error::IoResult;
-use nanocld_client::NanocldClient;
+use std::collections::BTreeMap;
+
+use nanocl_utils::io_error::{IoError, IoResult};
+use nanocld_client::stubs::system::DiskUsageItem;
 
 use crate::config::CliConfig;
 use crate::models::{
-  ProcessOpts, ProcessRow, SystemArg, SystemHttpArg, SystemHttpCommand,
-  SystemCommand,
+  ProcessOpts, ProcessRow, ProcessFormat, SystemArg, SystemHttpArg,
+  SystemHttpCommand, SystemCommand, DiskUsageRow, DiskUsageOutput,
 };
 use crate::utils;
-use crate::utils::print::print_table;
+use crate::utils::print::{print_table, TreeNode};
+
+/// ## Process tree
+///
+/// Group processes under their namespace then under the cargo or the vm
+/// owning them, everything is sorted by name.
+/// The node is only shown when processes run on different nodes
+///
+/// ## Arguments
+///
+/// * [rows](Vec<ProcessRow>) The processes
+///
+/// ## Return
+///
+/// * [Vec](Vec<TreeNode>) A root node per namespace
+///
+fn process_tree(rows: Vec<ProcessRow>) -> Vec<TreeNode> {
+  let many_nodes = rows.iter().any(|row| row.node != rows[0].node);
+  let mut namespaces: BTreeMap<String, BTreeMap<(String, String), Vec<_>>> =
+    BTreeMap::new();
+  for row in rows {
+    namespaces
+      .entry(row.namespace.clone())
+      .or_default()
+      .entry((row.kind.clone(), row.owner.clone()))
+      .or_default()
+      .push(row);
+  }
+  namespaces
+    .into_iter()
+    .map(|(namespace, owners)| {
+      let owners = owners
+        .into_iter()
+        .map(|((kind, owner), mut rows)| {
+          rows.sort_by(|a, b| a.name.cmp(&b.name));
+          let instances = rows
+            .into_iter()
+            .map(|row| {
+              let mut label = format!("{}  {}", row.name, row.status);
+              if !row.ip_address.is_empty() {
+                label = format!("{label}  {}", row.ip_address);
+              }
+              if many_nodes {
+                label = format!("{label}  on {}", row.node);
+              }
+              TreeNode::new(label, Vec::new())
+            })
+            .collect();
+          TreeNode::new(format!("{kind} {owner}"), instances)
+        })
+        .collect();
+      TreeNode::new(namespace, owners)
+    })
+    .collect()
+}
 
 /// ## Exec process
 ///
 /// Function that execute when running `nanocl ps`
 /// Will print the list of existing instances of cargoes and virtual machines
+/// filtered by namespace and name glob pattern when given,
+/// with `--all-namespaces` they are sorted by namespace and name,
+/// then paged with `--limit` and `--offset`.
+/// With `--format tree` they are grouped by namespace and cargo or vm
 ///
 /// ## Arguments
 ///
@@ -31,12 +91,46 @@ pub async fn exec_process(
 ) -> IoResult<()> {
   let client = &cli_conf.client;
   let opts = args.clone().into();
+  let matcher = match &args.pattern {
+    Some(pattern) => Some(
+      globset::Glob::new(pattern)
+        .map_err(|err| {
+          IoError::invalid_input(
+            "Pattern",
+            format!("{pattern}: {err}").as_str(),
+          )
+        })?
+        .compile_matcher(),
+    ),
+    None => None,
+  };
   let items = client.process(Some(opts)).await?;
-  let rows = items
+  let mut rows = items
     .into_iter()
     .map(ProcessRow::from)
+    .filter(|row| match &args.namespace {
+      Some(namespace) => &row.namespace == namespace,
+      None => true,
+    })
+    .filter(|row| match &matcher {
+      Some(matcher) => matcher.is_match(&row.name),
+      None => true,
+    })
     .collect::<Vec<ProcessRow>>();
-  print_table(rows);
+  if args.all_namespaces {
+    rows.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+  }
+  let (rows, footer) = utils::print::paginate(rows, &args.page);
+  match args.format {
+    // Structured outputs keep the flat rows as they have the namespace
+    ProcessFormat::Tree if cli_conf.structured_output().is_none() => {
+      utils::print::print_tree(&process_tree(rows));
+    }
+    _ => print_table(cli_conf, rows)?,
+  }
+  if let Some(footer) = footer {
+    eprintln!("{footer}");
+  }
   Ok(())
 }
 
@@ -47,7 +141,7 @@ pub async fn exec_process(
 ///
 /// ## Arguments
 ///
-/// * [client](NanocldClient) The nanocl daemon client
+/// * [cli_conf](CliConfig) The cli config
 /// * [opts](SystemHttpArg) The system http options
 ///
 /// ## Return
@@ -57,18 +151,71 @@ pub async fn exec_process(
 ///   * [Err](nanocl_utils::io_error::IoError) An error occured
 ///
 pub async fn exec_http(
-  client: &NanocldClient,
+  cli_conf: &CliConfig,
   opts: &SystemHttpArg,
 ) -> IoResult<()> {
+  let client = &cli_conf.client;
   match &opts.command {
     SystemHttpCommand::Logs(opts) => {
       let logs = client.list_http_metric(Some(opts.clone().into())).await?;
-      utils::print::print_yml(logs)?;
+      let display = cli_conf.get_display_format(&None);
+      utils::print::display_format(&display, logs)?;
     }
   }
   Ok(())
 }
 
+/// ## Exec df
+///
+/// Function that execute when running `nanocl system df`
+/// Will print the disk usage of cargo images, vm images and volumes
+/// with human readable sizes, or in bytes when an output format is given
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+pub async fn exec_df(cli_conf: &CliConfig) -> IoResult<()> {
+  let client = &cli_conf.client;
+  let usage = client.disk_usage().await?;
+  let total = usage
+    .cargo_images
+    .iter()
+    .chain(usage.vm_images.iter())
+    .chain(usage.volumes.iter())
+    .map(|item| item.size)
+    .sum::<i64>();
+  if let Some(format) = cli_conf.structured_output() {
+    let output = DiskUsageOutput {
+      total,
+      cargo_images: usage.cargo_images,
+      vm_images: usage.vm_images,
+      volumes: usage.volumes,
+    };
+    return utils::print::display_format(format, output);
+  }
+  let mut rows = Vec::new();
+  for (kind, items) in [
+    ("cargo image", &usage.cargo_images),
+    ("vm image", &usage.vm_images),
+    ("volume", &usage.volumes),
+  ] {
+    rows.extend(items.iter().map(|item| DiskUsageRow::new(kind, item)));
+  }
+  let total = DiskUsageItem {
+    name: String::default(),
+    size: total,
+  };
+  rows.push(DiskUsageRow::new("total", &total));
+  print_table(cli_conf, rows)
+}
+
 /// ## Exec system
 ///
 /// Function that execute when running `nanocl system`
@@ -88,8 +235,8 @@ pub async fn exec_system(
   cli_conf: &CliConfig,
   args: &SystemArg,
 ) -> IoResult<()> {
-  let client = &cli_conf.client;
   match &args.command {
-    SystemCommand::Http(opts) => exec_http(client, opts).await,
+    SystemCommand::Http(opts) => exec_http(cli_conf, opts).await,
+    SystemCommand::Df => exec_df(cli_conf).await,
   }
 }
@@ -1,27 +1,47 @@
 use nanocl_utils::io_error::IoResult;
-use nanocld_client::NanocldClient;
+use nanocld_client::stubs::system::Version;
 
+use crate::utils;
+use crate::models::VersionArg;
 use crate::{version, config::CliConfig};
 
-/// ## Print version
+/// ## Compatibility
 ///
-/// Print version of nanocli and nanocld
+/// Compare the version of nanocli with the version of nanocld,
+/// a different patch version is a warning while a different major
+/// or minor version is a mismatch
 ///
 /// ## Arguments
 ///
-/// * [client](NanocldClient) The nanocl daemon client
+/// * [server](str) The version of nanocld
 ///
 /// ## Return
 ///
-/// * [Result](Result) The result of the operation
-///   * [Ok](()) The operation was successful
-///   * [Err](nanocl_utils::io_error::IoError) An error occured
+/// * [str](str) `match`, `warning` or `mismatch`
 ///
-async fn print_version(client: &NanocldClient) -> IoResult<()> {
-  println!("=== [nanocli] ===");
-  version::print_version();
-  let daemon_version = client.get_version().await?;
-  println!("=== [nanocld] ===");
+fn compatibility(server: &str) -> &'static str {
+  if server == version::VERSION {
+    return "match";
+  }
+  fn major_minor(version: &str) -> Vec<&str> {
+    let release = version.split('-').next().unwrap_or_default();
+    release.split('.').take(2).collect()
+  }
+  match major_minor(server) == major_minor(version::VERSION) {
+    true => "warning",
+    false => "mismatch",
+  }
+}
+
+/// ## Print daemon version
+///
+/// Print version of nanocld and its compatibility with nanocli
+///
+/// ## Arguments
+///
+/// * [daemon_version](Version) The version of nanocld
+///
+fn print_daemon_version(daemon_version: &Version) {
   println!(
     "Arch: {}\nChannel: {}\nVersion: {}\nCommit ID: {}",
     daemon_version.arch,
@@ -29,16 +49,20 @@ async fn print_version(client: &NanocldClient) -> IoResult<()> {
     daemon_version.version,
     daemon_version.commit_id
   );
-  Ok(())
+  println!("Compatibility: {}", compatibility(&daemon_version.version));
 }
 
 /// ## Exec version
 ///
 /// Function that execute when running `nanocl version`
+/// Print version of nanocli and nanocld with their compatibility,
+/// when nanocld is unreachable it's noted instead of failing
+/// unless only the server version is requested
 ///
 /// ## Arguments
 ///
 /// * [cli_conf](CliConfig) The cli config
+/// * [args](VersionArg) The version arguments
 ///
 /// ## Return
 ///
@@ -46,8 +70,25 @@ async fn print_version(client: &NanocldClient) -> IoResult<()> {
 ///   * [Ok](()) The operation was successful
 ///   * [Err](IoError) An error occured
 ///
-pub async fn exec_version(cli_conf: &CliConfig) -> IoResult<()> {
+pub async fn exec_version(
+  cli_conf: &CliConfig,
+  args: &VersionArg,
+) -> IoResult<()> {
+  if !args.server {
+    println!("=== [nanocli] ===");
+    version::print_version();
+  }
+  if args.client {
+    return Ok(());
+  }
   let client = &cli_conf.client;
-  print_version(client).await?;
+  let res =
+    utils::client::with_timeout(cli_conf.timeout, client.get_version()).await;
+  println!("=== [nanocld] ===");
+  match res {
+    Ok(daemon_version) => print_daemon_version(&daemon_version),
+    Err(err) if !args.server => println!("Status: unreachable ({err})"),
+    Err(err) => return Err(err),
+  }
   Ok(())
 }
@@ -1,32 +1,56 @@
-use std::thread;
-use std::io::{Read, Write};
-use std::os::fd::AsRawFd;
+use std::rc::Rc;
+use std::collections::HashMap;
+use std::cell::RefCell;
+use std::io::{SeekFrom, Write};
 use std::time::Duration;
+use std::os::unix::process::CommandExt;
 
-use ntex::rt;
 use ntex::ws;
 use ntex::time;
-use ntex::util::Bytes;
-use futures::channel::mpsc;
-use futures::{SinkExt, StreamExt};
-use termios::{TCSANOW, tcsetattr, Termios, ICANON, ECHO};
+use futures::{Stream, StreamExt};
+use ring::digest;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_util::{codec, io};
+use indicatif::{MultiProgress, ProgressBar};
+use dialoguer::console::{style, Term};
+use bollard_next::service::EndpointSettings;
 
-use nanocl_utils::io_error::{IoResult, FromIo};
-use nanocld_client::stubs::cargo::{OutputLog, OutputKind};
+use nanocl_utils::io_error::{FromIo, IoError, IoResult};
+use nanocld_client::NanocldClient;
+use nanocld_client::stubs::cargo::{OutputKind, CargoLogQuery};
+use nanocld_client::stubs::vm::VmInspect;
+use nanocld_client::stubs::node::Node;
+use nanocld_client::stubs::vm_config::{VmConfigPartial, VmConfigUpdate};
 
 use crate::utils;
 use crate::config::CliConfig;
+use crate::utils::record::Recorder;
+use crate::utils::progress::TransferProgress;
 use crate::models::{
-  VmArg, VmCommand, VmCreateOpts, VmRow, VmRunOpts, VmPatchOpts, VmListOpts,
-  VmInspectOpts,
+  VmArg, VmCommand, VmCreateOpts, VmRow, VmRunOpts, VmPatchOpts, VmResizeOpts,
+  VmListOpts, VmInspectOpts, VmListState, VmListSort, VmLogsOpts, VmStartOpts,
+  VmRemoveOpts, VmSshOpts, VmExportOpts, VmImportOpts, VmMigrateOpts,
+  DisplayFormat,
 };
 
-use super::vm_image::exec_vm_image;
+use super::node::connect_node;
+use super::vm_image::{
+  exec_vm_image, exec_vm_image_clone, import_vm_image_from_url, verify_sha256,
+};
+
+/// Entry of a vm archive holding the vm config
+const ARCHIVE_CONFIG: &str = "vm.json";
+/// Entry of a vm archive holding the disk as a standalone qcow2 image
+const ARCHIVE_DISK: &str = "disk.qcow2";
+/// Entry of a vm archive holding the sha256 digest of the disk
+const ARCHIVE_DIGEST: &str = "disk.sha256";
 
 /// ## Exec vm create
 ///
 /// Function executed when running `nanocl vm create`
-/// It will create a new virtual machine but not start it
+/// It will create a new virtual machine but not start it.
+/// With `--from-image-url` the vm image is imported first
+/// and removed if the virtual machine cannot be created.
 ///
 /// ## Arguments
 ///
@@ -46,16 +70,204 @@ pub async fn exec_vm_create(
   options: &VmCreateOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
-  let vm = options.clone().into();
-  let vm = client.create_vm(&vm, args.namespace.clone()).await?;
-  println!("{}", &vm.key);
+  let mut vm: VmConfigPartial = options.clone().into();
+  vm.name =
+    utils::naming::resolve_name("Vm", &options.name, options.normalize)?;
+  let mut imported = None;
+  if let Some(url) = &options.from_image_url {
+    let (image, is_new) =
+      import_vm_image_from_url(client, url, &options.sha256).await?;
+    if is_new {
+      imported = Some(image.clone());
+    }
+    vm.disk.image = image;
+  }
+  let namespace = cli_conf.resolve_namespace(&args.namespace);
+  let res = utils::client::retry_create(cli_conf, |key| {
+    let namespace = namespace.clone();
+    let vm = &vm;
+    async move { client.create_vm_idempotent(vm, namespace, &key).await }
+  })
+  .await;
+  let (vm, existed) = match res {
+    Ok(created) => created,
+    Err(err) => {
+      if let Some(image) = imported {
+        let _ = client.delete_vm_image(&image).await;
+      }
+      return Err(err);
+    }
+  };
+  if !cli_conf.quiet {
+    match existed {
+      true => eprintln!("Vm {} already existed (idempotent)", vm.name),
+      false => eprintln!("Vm {} created", vm.name),
+    }
+    println!("{}", &vm.key);
+  }
   Ok(())
 }
 
+/// ## List vm rows
+///
+/// List the virtual machines of the namespace, or of every namespace
+/// with `--all-namespaces`, as table rows
+/// filtered by state and sorted by name, memory or cpu.
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [args](VmArg) The command arguments
+/// * [opts](VmListOpts) The command options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Vec<VmRow>) The rows of the virtual machines
+///   * [Err](IoError) An error occured
+///
+async fn list_vm_rows(
+  cli_conf: &CliConfig,
+  args: &VmArg,
+  opts: &VmListOpts,
+) -> IoResult<Vec<VmRow>> {
+  let client = &cli_conf.client;
+  let mut items = utils::client::list_in_namespaces(
+    cli_conf,
+    &args.namespace,
+    opts.all_namespaces,
+    |namespace| client.list_vm(namespace),
+  )
+  .await?
+  .into_iter()
+  .filter(|vm| match opts.state {
+    Some(VmListState::Running) => vm.running_instances > 0,
+    Some(VmListState::Stopped) => vm.running_instances == 0,
+    None => true,
+  })
+  .collect::<Vec<_>>();
+  if opts.all_namespaces {
+    items.sort_by(|a, b| {
+      (&a.namespace_name, &a.name).cmp(&(&b.namespace_name, &b.name))
+    });
+  }
+  if let Some(sort) = &opts.sort {
+    // sort_by is stable so vms with equal values keep the daemon order
+    items.sort_by(|a, b| {
+      let ordering = match sort {
+        VmListSort::Name => a.name.cmp(&b.name),
+        VmListSort::Memory => a
+          .config
+          .host_config
+          .memory
+          .cmp(&b.config.host_config.memory),
+        VmListSort::Cpu => {
+          a.config.host_config.cpu.cmp(&b.config.host_config.cpu)
+        }
+      };
+      match opts.reverse {
+        true => ordering.reverse(),
+        false => ordering,
+      }
+    });
+  }
+  Ok(
+    items
+      .into_iter()
+      .map(|vm| VmRow {
+        raw: opts.raw,
+        ..VmRow::from(vm)
+      })
+      .collect(),
+  )
+}
+
+/// ## Watch vm ls
+///
+/// Redraw the virtual machine table in place every interval until Ctrl-C
+/// that restore the cursor. Rows whose instances or config version changed
+/// since the previous refresh, or that just appeared, are highlighted.
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [args](VmArg) The command arguments
+/// * [opts](VmListOpts) The command options
+/// * [interval](Duration) The refresh interval
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+async fn watch_vm_ls(
+  cli_conf: &CliConfig,
+  args: &VmArg,
+  opts: &VmListOpts,
+  interval: Duration,
+) -> IoResult<()> {
+  let term = Term::stdout();
+  term
+    .clear_screen()
+    .map_err(|err| err.map_err_context(|| "Vm ls"))?;
+  term
+    .hide_cursor()
+    .map_err(|err| err.map_err_context(|| "Vm ls"))?;
+  // Vms are keyed by namespace and name since names are only unique
+  // in a namespace and `--all-namespaces` lists vms of every namespace
+  let mut previous: Option<HashMap<(String, String), (String, String)>> = None;
+  loop {
+    let res = async {
+      let rows = list_vm_rows(cli_conf, args, opts).await?;
+      let (rows, footer) = utils::print::paginate(rows, &opts.page);
+      let states = rows
+        .iter()
+        .map(|row| {
+          let state = (row.instances.clone(), row.config_version.clone());
+          ((row.namespace.clone(), row.name.clone()), state)
+        })
+        .collect::<HashMap<_, _>>();
+      let highlighted = rows
+        .iter()
+        .map(|row| {
+          let key = (row.namespace.clone(), row.name.clone());
+          match &previous {
+            Some(previous) => previous.get(&key) != states.get(&key),
+            None => false,
+          }
+        })
+        .collect::<Vec<_>>();
+      let table = utils::print::render_table_without(cli_conf, rows, &[])?;
+      let table = utils::print::highlight_rows(&table, &highlighted);
+      let header = format!(
+        "Every {interval:?}: nanocl vm ls    {}",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+      );
+      let lines = std::iter::once(header.as_str())
+        .chain(std::iter::once(""))
+        .chain(table.lines())
+        .chain(footer.as_deref());
+      utils::print::redraw(&term, lines)?;
+      previous = Some(states);
+      Ok::<_, IoError>(())
+    }
+    .await;
+    if let Err(err) = res {
+      let _ = term.show_cursor();
+      return Err(err);
+    }
+    time::sleep(interval).await;
+  }
+}
+
 /// ## Exec vm ls
 ///
 /// Function executed when running `nanocl vm ls`
 /// It will list existing virtual machine and output them on stdout as a table.
+/// Virtual machines can be filtered by state and sorted by name, memory or cpu,
+/// then paged with `--limit` and `--offset`.
+/// With `--watch` the table is refreshed on an interval.
 ///
 /// ## Arguments
 ///
@@ -74,9 +286,11 @@ pub async fn exec_vm_ls(
   args: &VmArg,
   opts: &VmListOpts,
 ) -> IoResult<()> {
-  let client = &cli_conf.client;
-  let items = client.list_vm(args.namespace.clone()).await?;
-  let rows = items.into_iter().map(VmRow::from).collect::<Vec<VmRow>>();
+  if let Some(interval) = opts.watch {
+    return watch_vm_ls(cli_conf, args, opts, interval).await;
+  }
+  let rows = list_vm_rows(cli_conf, args, opts).await?;
+  let (rows, footer) = utils::print::paginate(rows, &opts.page);
   match opts.quiet {
     true => {
       for row in rows {
@@ -84,22 +298,28 @@ pub async fn exec_vm_ls(
       }
     }
     false => {
-      utils::print::print_table(rows);
+      utils::print::print_table(cli_conf, rows)?;
     }
   }
+  if let Some(footer) = footer {
+    eprintln!("{footer}");
+  }
   Ok(())
 }
 
 /// ## Exec vm rm
 ///
 /// Function executed when running `nanocl vm rm`
-/// It will remove a virtual machine from the system
+/// It will remove virtual machines from the system.
+/// Virtual machines are removed concurrently up to the `--concurrency` limit,
+/// every virtual machine is attempted even if another one failed,
+/// with `--ignore-not-found` the ones that don't exist are not failures.
 ///
 /// ## Arguments
 ///
 /// * [cli_conf](CliConfig) The cli configuration
 /// * [args](VmArg) The command arguments
-/// * [names](Vec<String>) The list of virtual machine names to remove
+/// * [opts](VmRemoveOpts) The command options
 ///
 /// ## Return
 ///
@@ -110,13 +330,29 @@ pub async fn exec_vm_ls(
 pub async fn exec_vm_rm(
   cli_conf: &CliConfig,
   args: &VmArg,
-  names: &[String],
+  opts: &VmRemoveOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
-  for name in names {
-    client.delete_vm(name, args.namespace.clone()).await?;
+  let namespace = cli_conf.resolve_namespace(&args.namespace);
+  let deletes = opts.names.iter().map(|name| {
+    let namespace = namespace.clone();
+    async move { (name, client.delete_vm(name, namespace).await) }
+  });
+  let mut failed = 0;
+  for (name, res) in
+    utils::client::run_concurrent(cli_conf.concurrency, deletes).await
+  {
+    match res {
+      Ok(_) => {}
+      Err(err)
+        if opts.ignore_not_found && utils::client::is_not_found(&err) => {}
+      Err(err) => {
+        eprintln!("Failed to remove vm {name}: {err}");
+        failed += 1;
+      }
+    }
   }
-  Ok(())
+  check_vm_failures("remove", failed, opts.names.len())
 }
 
 /// ## Exec vm inspect
@@ -144,27 +380,125 @@ pub async fn exec_vm_inspect(
   opts: &VmInspectOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
-  let vm = client
-    .inspect_vm(&opts.name, args.namespace.clone())
-    .await?;
-  let display = opts
-    .display
-    .clone()
-    .unwrap_or(cli_conf.user_config.display_format.clone());
-  utils::print::display_format(&display, vm)?;
+  let vm = utils::client::with_timeout(
+    cli_conf.timeout,
+    client.inspect_vm(&opts.name, cli_conf.resolve_namespace(&args.namespace)),
+  )
+  .await?;
+  if let Some(template) = &opts.template {
+    println!("{}", utils::template::render_template(template, vm)?);
+    return Ok(());
+  }
+  let display = cli_conf.get_display_format(&opts.display);
+  utils::print::display_format_prefixed(&display, "VM", vm)?;
   Ok(())
 }
 
+/// ## Wait vm running
+///
+/// Poll a virtual machine until at least one of its instances is running
+/// or the timeout elapsed
+///
+/// ## Arguments
+///
+/// * [client](NanocldClient) The client to the daemon
+/// * [name](str) The name of the virtual machine
+/// * [namespace](Option<String>) The namespace of the virtual machine
+/// * [timeout](Duration) The maximum time to wait
+/// * [pg](ProgressBar) The spinner to update
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The virtual machine is running
+///   * [Err](IoError) The timeout elapsed with the last observed state
+///
+pub(crate) async fn wait_vm_running(
+  client: &NanocldClient,
+  name: &str,
+  namespace: Option<String>,
+  timeout: Duration,
+  pg: ProgressBar,
+) -> IoResult<()> {
+  /// How often the vm state is polled
+  const POLL_INTERVAL: Duration = Duration::from_millis(500);
+  let start = std::time::Instant::now();
+  loop {
+    let state = match client.inspect_vm(name, namespace.clone()).await {
+      Ok(vm) if vm.instance_running > 0 => {
+        pg.finish_with_message(format!("{name} running"));
+        return Ok(());
+      }
+      Ok(vm) => format!(
+        "{}/{} instances running",
+        vm.instance_running, vm.instance_total
+      ),
+      Err(err) => err.to_string(),
+    };
+    if start.elapsed() >= timeout {
+      pg.abandon_with_message(format!("{name} {state}"));
+      return Err(IoError::new(
+        format!("Vm {name}"),
+        std::io::Error::new(
+          std::io::ErrorKind::TimedOut,
+          format!(
+            "not running after {}s, last state: {state}",
+            timeout.as_secs()
+          ),
+        ),
+      ));
+    }
+    pg.set_message(format!("{name} {state}"));
+    time::sleep(POLL_INTERVAL).await;
+  }
+}
+
+/// ## Check vm failures
+///
+/// Summarize the failures of an operation done on multiple virtual machines
+///
+/// ## Arguments
+///
+/// * [action](str) The action that was done eg: start, stop
+/// * [failed](usize) The number of virtual machines that failed
+/// * [total](usize) The number of virtual machines targeted
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) Every virtual machine succeeded
+///   * [Err](IoError) At least one virtual machine failed
+///
+fn check_vm_failures(
+  action: &str,
+  failed: usize,
+  total: usize,
+) -> IoResult<()> {
+  if failed == 0 {
+    return Ok(());
+  }
+  Err(IoError::new(
+    format!("Vm {action}"),
+    std::io::Error::new(
+      std::io::ErrorKind::Other,
+      format!("{failed} of {total} VMs failed to {action}"),
+    ),
+  ))
+}
+
 /// ## Exec vm start
 ///
 /// Function executed when running `nanocl vm start`
-/// It will start a virtual machine that was previously created or stopped
+/// It will start a virtual machine that was previously created or stopped.
+/// If the `wait` option is set, it will wait for the virtual machines
+/// to be running concurrently.
+/// Every virtual machine is started even if some of them failed.
 ///
 /// ## Arguments
 ///
 /// * [cli_conf](CliConfig) The cli configuration
 /// * [args](VmArg) The command arguments
-/// * [names](Vec<String>) The list of virtual machine names to start
+/// * [opts](VmStartOpts) The command options
 ///
 /// ## Return
 ///
@@ -175,21 +509,50 @@ pub async fn exec_vm_inspect(
 pub async fn exec_vm_start(
   cli_conf: &CliConfig,
   args: &VmArg,
-  names: &[String],
+  opts: &VmStartOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
-  for name in names {
-    if let Err(err) = client.start_vm(name, args.namespace.clone()).await {
+  let mut failed = 0;
+  let mut started = Vec::new();
+  for name in &opts.names {
+    if let Err(err) = client
+      .start_vm(name, cli_conf.resolve_namespace(&args.namespace))
+      .await
+    {
       eprintln!("Failed to start vm {}: {}", name, err);
+      failed += 1;
+      continue;
     }
+    started.push(name);
   }
-  Ok(())
+  if opts.wait {
+    let timeout = opts.wait_timeout;
+    let multiprogress = MultiProgress::new();
+    let waits = started.into_iter().map(|name| {
+      let pg = utils::state::new_spinner(&multiprogress, name);
+      wait_vm_running(
+        client,
+        name,
+        cli_conf.resolve_namespace(&args.namespace),
+        timeout,
+        pg,
+      )
+    });
+    for res in futures::future::join_all(waits).await {
+      if let Err(err) = res {
+        eprintln!("{err}");
+        failed += 1;
+      }
+    }
+  }
+  check_vm_failures("start", failed, opts.names.len())
 }
 
 /// ## Exec vm stop
 ///
 /// Function executed when running `nanocl vm stop`
-/// It will stop a virtual machine that was previously started
+/// It will stop a virtual machine that was previously started.
+/// Every virtual machine is stopped even if some of them failed.
 ///
 /// ## Arguments
 ///
@@ -209,12 +572,17 @@ pub async fn exec_vm_stop(
   names: &[String],
 ) -> IoResult<()> {
   let client = &cli_conf.client;
+  let mut failed = 0;
   for name in names {
-    if let Err(err) = client.stop_vm(name, args.namespace.clone()).await {
+    if let Err(err) = client
+      .stop_vm(name, cli_conf.resolve_namespace(&args.namespace))
+      .await
+    {
       eprintln!("Failed to stop vm {}: {}", name, err);
+      failed += 1;
     }
   }
-  Ok(())
+  check_vm_failures("stop", failed, names.len())
 }
 
 /// ## Exec vm run
@@ -241,11 +609,18 @@ pub async fn exec_vm_run(
   options: &VmRunOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
-  let vm = options.clone().into();
-  let vm = client.create_vm(&vm, args.namespace.clone()).await?;
-  client.start_vm(&vm.name, args.namespace.clone()).await?;
+  let mut vm: VmConfigPartial = options.clone().into();
+  vm.name =
+    utils::naming::resolve_name("Vm", &options.name, options.normalize)?;
+  let vm = client
+    .create_vm(&vm, cli_conf.resolve_namespace(&args.namespace))
+    .await?;
+  client
+    .start_vm(&vm.name, cli_conf.resolve_namespace(&args.namespace))
+    .await?;
   if options.attach {
-    exec_vm_attach(cli_conf, args, &options.name).await?;
+    exec_vm_attach(cli_conf, args, &vm.name, false, None, None, Duration::ZERO)
+      .await?;
   }
   Ok(())
 }
@@ -254,6 +629,7 @@ pub async fn exec_vm_run(
 ///
 /// Function executed when running `nanocl vm patch`
 /// It will patch a virtual machine with the provided options
+/// or with the file given with `--from-file`
 ///
 /// ## Arguments
 ///
@@ -273,128 +649,1138 @@ pub async fn exec_vm_patch(
   options: &VmPatchOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
-  let vm = options.clone().into();
+  let vm = match &options.from_file {
+    Some(file) => {
+      read_vm_patch_file(cli_conf, args, &options.name, file).await?
+    }
+    None => options.clone().into(),
+  };
   client
-    .patch_vm(&options.name, &vm, args.namespace.clone())
+    .patch_vm(
+      &options.name,
+      &vm,
+      cli_conf.resolve_namespace(&args.namespace),
+    )
     .await?;
   Ok(())
 }
 
-/// ## Exec vm attach
+/// ## Read vm patch file
 ///
-/// Function executed when running `nanocl vm attach`
-/// It will attach to a virtual machine console
+/// Parse a yaml, json or toml file describing the fields to patch
+/// and merge it over the current config of the virtual machine.
+/// Fields that are not part of a vm config are rejected
 ///
 /// ## Arguments
 ///
 /// * [cli_conf](CliConfig) The cli configuration
 /// * [args](VmArg) The command arguments
-/// * [name](&str) The name of the virtual machine to attach to
+/// * [name](str) The name of the virtual machine
+/// * [file](str) The path to the file
 ///
 /// ## Return
 ///
 /// * [Result](Result) The result of the operation
-///   * [Ok](()) The operation was successful
+///   * [Ok](VmConfigUpdate) The merged config
 ///   * [Err](IoError) An error occured
 ///
-pub async fn exec_vm_attach(
+async fn read_vm_patch_file(
   cli_conf: &CliConfig,
   args: &VmArg,
   name: &str,
+  file: &str,
+) -> IoResult<VmConfigUpdate> {
+  let context = format!("Vm patch {file}");
+  let data = std::fs::read_to_string(file)
+    .map_err(|err| err.map_err_context(|| &context))?;
+  let ext = std::path::Path::new(file)
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .unwrap_or_else(|| utils::state::detect_ext(&data));
+  let format = match ext {
+    "json" => DisplayFormat::Json,
+    "toml" => DisplayFormat::Toml,
+    _ => DisplayFormat::Yaml,
+  };
+  let patch: serde_json::Value = utils::state::serialize_ext(&format, &data)?;
+  let vm = cli_conf
+    .client
+    .inspect_vm(name, cli_conf.resolve_namespace(&args.namespace))
+    .await?;
+  let vm = VmConfigUpdate::from(VmConfigPartial::from(vm));
+  let mut config = serde_json::to_value(&vm)
+    .map_err(|err| err.map_err_context(|| &context))?;
+  utils::state::merge_values(&mut config, patch.clone());
+  let config: VmConfigUpdate = serde_json::from_value(config)
+    .map_err(|err| err.map_err_context(|| &context))?;
+  let parsed = serde_json::to_value(&config)
+    .map_err(|err| err.map_err_context(|| &context))?;
+  let unknown = utils::state::unknown_fields(&patch, &parsed);
+  if !unknown.is_empty() {
+    return Err(IoError::invalid_input(
+      context.as_str(),
+      format!(
+        "unknown field {}, see `nanocl vm inspect {name}` for the available fields",
+        unknown.join(", ")
+      )
+      .as_str(),
+    ));
+  }
+  Ok(config)
+}
+
+/// Minimum memory of a virtual machine in MB
+const MIN_VM_MEMORY: u64 = 128;
+
+/// ## Exec vm resize
+///
+/// Function executed when running `nanocl vm resize`
+/// It will only patch the cpu and memory of a virtual machine,
+/// a running virtual machine needs a restart to apply them
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [args](VmArg) The command arguments
+/// * [options](VmResizeOpts) The command options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+async fn exec_vm_resize(
+  cli_conf: &CliConfig,
+  args: &VmArg,
+  options: &VmResizeOpts,
 ) -> IoResult<()> {
   let client = &cli_conf.client;
-  /// How often heartbeat pings are sent
-  const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
-  let conn = client.attach_vm(name, args.namespace.clone()).await?;
-  let (mut tx, mut rx) = mpsc::unbounded();
-  // start heartbeat task
-  let sink = conn.sink();
-  rt::spawn(async move {
-    loop {
-      time::sleep(HEARTBEAT_INTERVAL).await;
-      if sink.send(ws::Message::Ping(Bytes::new())).await.is_err() {
-        return;
+  let memory = match &options.memory {
+    Some(memory) => {
+      let memory = utils::math::parse_size(memory)? / 1024 / 1024;
+      if memory < MIN_VM_MEMORY {
+        return Err(IoError::invalid_input(
+          "Memory",
+          format!("must be at least {MIN_VM_MEMORY}M").as_str(),
+        ));
       }
+      Some(memory)
     }
-  });
-  // // Get the current terminal settings
-  let mut termios = Termios::from_fd(std::io::stdin().as_raw_fd())?;
-  // Save a copy of the original terminal settings
-  let original_termios = termios;
-  // Disable canonical mode and echo
-  termios.c_lflag &= !(ICANON | ECHO);
-  // Redirect the output of the console to the TTY device
-  let mut stderr = std::io::stderr();
-  let mut stdout = std::io::stdout();
-  // let mut tty_writer = std::io::BufWriter::new(tty_file);
-  // std::io::copy(&mut stdout, &mut tty_writer)?;
-  // Apply the new terminal settings
-  tcsetattr(std::io::stdin().as_raw_fd(), TCSANOW, &termios)?;
-  // start console read loop
-  thread::spawn(move || loop {
-    let mut input = [0; 1];
-    if std::io::stdin().read(&mut input).is_err() {
-      println!("Unable to read stdin");
-      return;
+    None => None,
+  };
+  let vm = client
+    .inspect_vm(&options.name, cli_conf.resolve_namespace(&args.namespace))
+    .await?;
+  let mut host_config = vm.config.host_config;
+  if let Some(cpu) = options.cpu {
+    host_config.cpu = cpu;
+  }
+  if let Some(memory) = memory {
+    host_config.memory = memory;
+  }
+  let config = VmConfigUpdate {
+    host_config: Some(host_config),
+    ..Default::default()
+  };
+  client
+    .patch_vm(
+      &options.name,
+      &config,
+      cli_conf.resolve_namespace(&args.namespace),
+    )
+    .await?;
+  if vm.instance_running > 0 {
+    eprintln!(
+      "Warning: vm {} is running, restart it to apply the new resources",
+      options.name
+    );
+  }
+  Ok(())
+}
+
+/// ## Stop vm for disk
+///
+/// Ensure a virtual machine is stopped before touching its disk.
+/// A running virtual machine is only stopped when `force` is set.
+///
+/// ## Arguments
+///
+/// * [client](NanocldClient) The client of the node the vm is on
+/// * [namespace](Option<String>) The namespace of the virtual machine
+/// * [name](str) The name of the virtual machine
+/// * [force](bool) Stop the virtual machine if it's running
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](bool) True if the virtual machine was stopped
+///   * [Err](IoError) An error occured
+///
+async fn stop_vm_for_disk(
+  client: &NanocldClient,
+  namespace: Option<String>,
+  name: &str,
+  force: bool,
+) -> IoResult<bool> {
+  let vm = client.inspect_vm(name, namespace.clone()).await?;
+  if vm.instance_running == 0 {
+    return Ok(false);
+  }
+  if !force {
+    return Err(IoError::invalid_input(
+      "Vm disk",
+      format!("vm {name} is running, stop it first or use --force").as_str(),
+    ));
+  }
+  eprintln!("Warning: stopping vm {name}, it will be started again once done");
+  client.stop_vm(name, namespace).await?;
+  Ok(true)
+}
+
+/// Number of phases of `nanocl vm migrate`
+const MIGRATE_PHASES: usize = 6;
+
+/// ## VmMigration
+///
+/// Progress of a `nanocl vm migrate` used to roll it back when a phase fail
+///
+struct VmMigration<'a> {
+  /// Client of the node the vm is moved from
+  source: &'a NanocldClient,
+  /// Client of the node the vm is moved to
+  target: NanocldClient,
+  /// Namespace of the vm
+  namespace: Option<String>,
+  /// Config of the vm to recreate
+  config: VmConfigPartial,
+  /// Image holding the disk of the vm on the source node
+  disk: String,
+  /// Base image holding the disk of the vm on both nodes
+  image: String,
+  /// The vm was running before the migration
+  was_running: bool,
+  /// The disk was saved as a base image on the source node
+  saved: bool,
+  /// The disk was imported on the target node
+  transferred: bool,
+  /// The vm was removed from the source node
+  removed: bool,
+  /// The vm was created on the target node
+  created: bool,
+}
+
+impl VmMigration<'_> {
+  /// ## Rollback
+  ///
+  /// Undo the phases done so far, the vm is recreated on the source node
+  /// from its saved disk when it was already removed.
+  /// Failures are printed as warnings so every step is attempted.
+  ///
+  async fn rollback(&self) {
+    let name = &self.config.name;
+    let namespace = self.namespace.clone();
+    eprintln!("Rolling back the migration of vm {name}");
+    if self.created {
+      let res = self.target.delete_vm(name, namespace.clone()).await;
+      if let Err(err) = res {
+        eprintln!("Warning: unable to remove vm {name} from the node: {err}");
+      }
     }
-    let s = std::str::from_utf8(&input).unwrap();
-    // send text to server
-    if futures::executor::block_on(tx.send(ws::Message::Text(s.into())))
-      .is_err()
-    {
-      return;
+    if self.transferred {
+      if let Err(err) = self.target.delete_vm_image(&self.image).await {
+        eprintln!(
+          "Warning: unable to remove image {} from the node: {err}",
+          self.image
+        );
+      }
     }
-  });
-  // read console commands
-  let sink = conn.sink();
-  rt::spawn(async move {
-    while let Some(msg) = rx.next().await {
-      if sink.send(msg).await.is_err() {
+    if self.removed {
+      let res = self.source.create_vm(&self.config, namespace.clone()).await;
+      if let Err(err) = res {
+        eprintln!("Warning: unable to recreate vm {name}: {err}");
+        eprintln!("Its disk is kept as the image {}", self.image);
         return;
       }
+    } else if self.saved {
+      if let Err(err) = self.source.delete_vm_image(&self.image).await {
+        eprintln!("Warning: unable to remove image {}: {err}", self.image);
+      }
     }
-  });
-  // run ws dispatcher
-  let sink = conn.sink();
-  let mut rx = conn.seal().receiver();
-  while let Some(frame) = rx.next().await {
-    match frame {
-      Ok(ws::Frame::Binary(text)) => {
-        let output =
-          serde_json::from_slice::<OutputLog>(&text).map_err(|err| {
-            err.map_err_context(|| "Unable to serialize output")
-          })?;
-        match &output.kind {
-          OutputKind::StdOut => {
-            stdout.write_all(output.data.as_bytes())?;
-            stdout.flush()?;
-          }
-          OutputKind::StdErr => {
-            stderr.write_all(output.data.as_bytes())?;
-            stdout.flush()?;
-          }
-          OutputKind::Console => {
-            stdout.write_all(output.data.as_bytes())?;
-            stdout.flush()?;
-          }
-          _ => {}
-        }
+    if self.was_running {
+      if let Err(err) = self.source.start_vm(name, namespace).await {
+        eprintln!("Warning: unable to start vm {name}: {err}");
       }
-      Ok(ws::Frame::Ping(msg)) => {
-        sink
-          .send(ws::Message::Pong(msg))
-          .await
-          .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    }
+  }
+
+  /// ## Run
+  ///
+  /// Run the phases of the migration after the vm was stopped
+  ///
+  /// ## Arguments
+  ///
+  /// * [cli_conf](CliConfig) The cli configuration
+  /// * [node](str) The name of the target node
+  ///
+  /// ## Return
+  ///
+  /// * [Result](Result) The result of the operation
+  ///   * [Ok](()) The vm runs on the target node
+  ///   * [Err](IoError) A phase failed
+  ///
+  async fn run(&mut self, cli_conf: &CliConfig, node: &str) -> IoResult<()> {
+    let name = self.config.name.clone();
+    print_migrate_phase(cli_conf, 2, "Saving the disk");
+    exec_vm_image_clone(self.source, &self.disk, &self.image).await?;
+    self.saved = true;
+    print_migrate_phase(
+      cli_conf,
+      3,
+      &format!("Transferring the disk to {node}"),
+    );
+    let (size, stream) = self.source.export_vm_image(&self.image).await?;
+    let mut progress = TransferProgress::new(&self.image, size);
+    let stream = stream.inspect(move |chunk| {
+      if let Ok(chunk) = chunk {
+        progress.inc(chunk.len() as u64);
       }
-      Err(_) => break,
-      _ => (),
+    });
+    self.transferred = true;
+    self
+      .target
+      .import_vm_image(&self.image, Box::pin(stream))
+      .await?;
+    print_migrate_phase(cli_conf, 4, "Removing the vm from the current node");
+    self.source.delete_vm(&name, self.namespace.clone()).await?;
+    self.removed = true;
+    print_migrate_phase(cli_conf, 5, &format!("Creating the vm on {node}"));
+    self
+      .target
+      .create_vm(&self.config, self.namespace.clone())
+      .await?;
+    self.created = true;
+    if self.was_running {
+      print_migrate_phase(cli_conf, 6, &format!("Starting the vm on {node}"));
+      self.target.start_vm(&name, self.namespace.clone()).await?;
     }
+    Ok(())
+  }
+}
+
+/// ## Print migrate phase
+///
+/// Print the phase of a `nanocl vm migrate` on stderr unless `--quiet` is set
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [phase](usize) The number of the phase
+/// * [msg](str) The description of the phase
+///
+fn print_migrate_phase(cli_conf: &CliConfig, phase: usize, msg: &str) {
+  if !cli_conf.quiet {
+    eprintln!("[{phase}/{MIGRATE_PHASES}] {msg}");
+  }
+}
+
+/// ## Exec vm migrate
+///
+/// Function executed when running `nanocl vm migrate`
+/// It will move a virtual machine from the node the cli is connected to
+/// to another node of the cluster:
+/// its disk is saved as a base image, transferred to the node,
+/// the virtual machine is removed and recreated on the node
+/// then started again if it was running.
+/// When a phase fail the previous ones are rolled back
+/// so the virtual machine is kept on the current node.
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [args](VmArg) The command arguments
+/// * [opts](VmMigrateOpts) The command options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+async fn exec_vm_migrate(
+  cli_conf: &CliConfig,
+  args: &VmArg,
+  opts: &VmMigrateOpts,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  let namespace = cli_conf.resolve_namespace(&args.namespace);
+  let nodes = client.list_node().await?;
+  let node = nodes
+    .iter()
+    .find(|node| node.name == opts.to_node)
+    .ok_or_else(|| {
+      IoError::not_fount(
+        format!("Node {}", opts.to_node),
+        "doesn't exist, see `nanocl node ls`".to_owned(),
+      )
+    })?;
+  let info = client.info().await?;
+  if node.name == info.config.hostname {
+    return Err(IoError::invalid_input(
+      "Vm migrate".to_owned(),
+      format!("vm {} is already on node {}", opts.name, node.name),
+    ));
+  }
+  if node.unschedulable {
+    return Err(IoError::invalid_input(
+      "Vm migrate".to_owned(),
+      format!("node {} is cordoned, see `nanocl node uncordon`", node.name),
+    ));
+  }
+  migrate_vm(cli_conf, client, namespace, &opts.name, node, opts.force).await?;
+  if !cli_conf.quiet {
+    println!("{}", node.name);
   }
-  // Restore the original terminal settings
-  tcsetattr(std::io::stdin().as_raw_fd(), TCSANOW, &original_termios)?;
   Ok(())
 }
 
+/// ## Migrate vm
+///
+/// Move a virtual machine to a node like `nanocl vm migrate`,
+/// also used by `nanocl node drain` to move the vms of the drained node
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [source](NanocldClient) The client of the node the vm is on
+/// * [namespace](Option<String>) The namespace of the vm
+/// * [name](str) The name of the vm
+/// * [node](Node) The node to move the vm to
+/// * [force](bool) Stop the vm if it's running
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The vm runs on the node
+///   * [Err](IoError) A phase failed and the previous ones were rolled back
+///
+pub(crate) async fn migrate_vm(
+  cli_conf: &CliConfig,
+  source: &NanocldClient,
+  namespace: Option<String>,
+  name: &str,
+  node: &Node,
+  force: bool,
+) -> IoResult<()> {
+  let target = connect_node(node)?;
+  target.ping().await?;
+  let vm = source.inspect_vm(name, namespace.clone()).await?;
+  print_migrate_phase(cli_conf, 1, "Stopping the vm");
+  let was_running =
+    stop_vm_for_disk(source, namespace.clone(), name, force).await?;
+  let suffix = uuid::Uuid::new_v4().simple().to_string();
+  let image = format!("{}-{}", vm.name, &suffix[..12]);
+  let disk = vm.config.disk.image.clone();
+  let mut config: VmConfigPartial = vm.into();
+  config.disk.image = image.clone();
+  let mut migration = VmMigration {
+    source,
+    target,
+    namespace,
+    config,
+    disk,
+    image,
+    was_running,
+    saved: false,
+    transferred: false,
+    removed: false,
+    created: false,
+  };
+  if let Err(err) = migration.run(cli_conf, &node.name).await {
+    migration.rollback().await;
+    return Err(err);
+  }
+  // The saved disk is only needed to roll back
+  if let Err(err) = source.delete_vm_image(&migration.image).await {
+    eprintln!("Warning: unable to remove image {}: {err}", migration.image);
+  }
+  Ok(())
+}
+
+/// ## Exec vm snapshot
+///
+/// Function executed when running `nanocl vm snapshot`
+/// It will save the disk of a virtual machine as a vm image
+/// listed by `nanocl vm image ls`
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [args](VmArg) The command arguments
+/// * [name](str) The name of the virtual machine
+/// * [snapshot_name](str) The name of the snapshot
+/// * [force](bool) Stop the virtual machine if it's running
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+pub async fn exec_vm_snapshot(
+  cli_conf: &CliConfig,
+  args: &VmArg,
+  name: &str,
+  snapshot_name: &str,
+  force: bool,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  let stopped = stop_vm_for_disk(
+    client,
+    cli_conf.resolve_namespace(&args.namespace),
+    name,
+    force,
+  )
+  .await?;
+  let res = client
+    .snapshot_vm(
+      name,
+      snapshot_name,
+      cli_conf.resolve_namespace(&args.namespace),
+    )
+    .await;
+  if stopped {
+    client
+      .start_vm(name, cli_conf.resolve_namespace(&args.namespace))
+      .await?;
+  }
+  let image = res?;
+  if !cli_conf.quiet {
+    println!("{}", image.name);
+  }
+  Ok(())
+}
+
+/// ## Exec vm restore
+///
+/// Function executed when running `nanocl vm restore`
+/// It will overwrite the disk of a virtual machine with a snapshot
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [args](VmArg) The command arguments
+/// * [name](str) The name of the virtual machine
+/// * [snapshot_name](str) The name of the snapshot
+/// * [force](bool) Stop the virtual machine if it's running
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+pub async fn exec_vm_restore(
+  cli_conf: &CliConfig,
+  args: &VmArg,
+  name: &str,
+  snapshot_name: &str,
+  force: bool,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  let stopped = stop_vm_for_disk(
+    client,
+    cli_conf.resolve_namespace(&args.namespace),
+    name,
+    force,
+  )
+  .await?;
+  let res = client
+    .restore_vm(
+      name,
+      snapshot_name,
+      cli_conf.resolve_namespace(&args.namespace),
+    )
+    .await;
+  if stopped {
+    client
+      .start_vm(name, cli_conf.resolve_namespace(&args.namespace))
+      .await?;
+  }
+  res?;
+  Ok(())
+}
+
+/// ## Exec vm clone
+///
+/// Function executed when running `nanocl vm clone`
+/// It will create a new virtual machine from the definition of an existing one
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [args](VmArg) The command arguments
+/// * [name](str) The name of the virtual machine to clone
+/// * [new_name](str) The name of the new virtual machine
+/// * [namespace_to](Option<String>) The namespace of the new virtual machine
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+pub async fn exec_vm_clone(
+  cli_conf: &CliConfig,
+  args: &VmArg,
+  name: &str,
+  new_name: &str,
+  namespace_to: &Option<String>,
+) -> IoResult<()> {
+  utils::naming::validate_name("Vm", new_name)?;
+  let client = &cli_conf.client;
+  let namespace = namespace_to
+    .clone()
+    .or(cli_conf.resolve_namespace(&args.namespace));
+  if client.inspect_vm(new_name, namespace.clone()).await.is_ok() {
+    return Err(IoError::new(
+      "Vm clone",
+      std::io::Error::new(
+        std::io::ErrorKind::AlreadyExists,
+        format!(
+          "vm {new_name} already exists in namespace {}",
+          namespace.unwrap_or("global".into())
+        ),
+      ),
+    ));
+  }
+  let vm = client
+    .inspect_vm(name, cli_conf.resolve_namespace(&args.namespace))
+    .await?;
+  let key = vm.key.clone();
+  let mut config: VmConfigPartial = vm.into();
+  // The disk of a vm is a snapshot of a base image named `{image}.{key}`
+  // so we create the new vm from the same base image
+  let suffix = format!(".{key}");
+  if let Some(image) = config.disk.image.strip_suffix(&suffix) {
+    config.disk.image = image.to_owned();
+  }
+  if config.hostname.as_deref() == Some(name) {
+    config.hostname = Some(new_name.to_owned());
+  }
+  config.name = new_name.to_owned();
+  config.mac_address = None;
+  let vm = client.create_vm(&config, namespace).await?;
+  if !cli_conf.quiet {
+    println!("{}", &vm.key);
+  }
+  Ok(())
+}
+
+/// ## Write vm archive
+///
+/// Write the config of a vm and its disk streamed from the daemon
+/// in a tar archive, followed by the digest of the disk
+///
+/// ## Arguments
+///
+/// * [path](str) The path of the archive
+/// * [config](VmConfigPartial) The config of the vm
+/// * [size](u64) The size of the disk
+/// * [disk](Stream) The content of the disk
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The archive was written
+///   * [Err](IoError) An error occured
+///
+async fn write_vm_archive(
+  path: &str,
+  config: &VmConfigPartial,
+  size: u64,
+  disk: impl Stream<Item = Result<ntex::util::Bytes, std::io::Error>>,
+) -> IoResult<()> {
+  if size == 0 {
+    return Err(IoError::invalid_data(
+      "Vm export",
+      "the daemon did not send the size of the disk",
+    ));
+  }
+  let config = serde_json::to_vec_pretty(config)
+    .map_err(|err| err.map_err_context(|| "Vm config"))?;
+  let file = tokio::fs::File::create(path)
+    .await
+    .map_err(|err| err.map_err_context(|| path))?;
+  let mut writer = tokio::io::BufWriter::new(file);
+  utils::archive::write_entry(&mut writer, ARCHIVE_CONFIG, &config).await?;
+  utils::archive::write_header(&mut writer, ARCHIVE_DISK, size).await?;
+  let mut progress = TransferProgress::new(ARCHIVE_DISK, size);
+  let mut hasher = digest::Context::new(&digest::SHA256);
+  let mut written = 0;
+  let mut disk = Box::pin(disk);
+  while let Some(chunk) = disk.next().await {
+    let chunk = chunk.map_err(|err| err.map_err_context(|| "Vm disk"))?;
+    writer
+      .write_all(&chunk)
+      .await
+      .map_err(|err| err.map_err_context(|| path))?;
+    hasher.update(&chunk);
+    progress.inc(chunk.len() as u64);
+    written += chunk.len() as u64;
+  }
+  if written != size {
+    return Err(IoError::invalid_data(
+      "Vm disk".to_owned(),
+      format!("stream ended after {written} of {size} bytes"),
+    ));
+  }
+  utils::archive::write_padding(&mut writer, size).await?;
+  let checksum = utils::hash::to_hex(&hasher.finish());
+  utils::archive::write_entry(&mut writer, ARCHIVE_DIGEST, checksum.as_bytes())
+    .await?;
+  utils::archive::write_end(&mut writer).await?;
+  writer
+    .flush()
+    .await
+    .map_err(|err| err.map_err_context(|| path))?;
+  Ok(())
+}
+
+/// ## Exec vm export
+///
+/// Function executed when running `nanocl vm export`
+/// It will write the config of a virtual machine and its disk
+/// in a tar archive that can be imported in another cluster.
+/// The disk is streamed to the archive and the archive is removed on error.
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [args](VmArg) The command arguments
+/// * [opts](VmExportOpts) The command options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+async fn exec_vm_export(
+  cli_conf: &CliConfig,
+  args: &VmArg,
+  opts: &VmExportOpts,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  let vm = client
+    .inspect_vm(&opts.name, cli_conf.resolve_namespace(&args.namespace))
+    .await?;
+  let disk = vm.config.disk.image.clone();
+  let mut config: VmConfigPartial = vm.into();
+  config.mac_address = None;
+  let (size, stream) = client.export_vm_image(&disk).await?;
+  let res = write_vm_archive(&opts.output, &config, size, stream).await;
+  if res.is_err() {
+    let _ = tokio::fs::remove_file(&opts.output).await;
+  }
+  res
+}
+
+/// ## Expect archive entry
+///
+/// Read the header of the next entry of a vm archive
+/// and check it's the expected one
+///
+/// ## Arguments
+///
+/// * [file](tokio::fs::File) The archive
+/// * [expected](str) The path of the expected entry
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](u64) The size of the entry
+///   * [Err](IoError) The archive is not a vm archive
+///
+async fn expect_archive_entry(
+  file: &mut tokio::fs::File,
+  expected: &str,
+) -> IoResult<u64> {
+  match utils::archive::read_header(file).await? {
+    Some((path, size)) if path == expected => Ok(size),
+    Some((path, _)) => Err(IoError::invalid_data(
+      "Vm archive".to_owned(),
+      format!("expected entry {expected} got {path}"),
+    )),
+    None => Err(IoError::invalid_data(
+      "Vm archive".to_owned(),
+      format!("entry {expected} is missing"),
+    )),
+  }
+}
+
+/// ## Exec vm import
+///
+/// Function executed when running `nanocl vm import`
+/// It will create a virtual machine from an archive made by `nanocl vm export`.
+/// The whole archive structure is validated before anything is imported,
+/// the disk is streamed to the daemon and removed if its digest mismatch.
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [args](VmArg) The command arguments
+/// * [opts](VmImportOpts) The command options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+async fn exec_vm_import(
+  cli_conf: &CliConfig,
+  args: &VmArg,
+  opts: &VmImportOpts,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  let path = opts.file.as_str();
+  let mut file = tokio::fs::File::open(path)
+    .await
+    .map_err(|err| err.map_err_context(|| path))?;
+  let size = expect_archive_entry(&mut file, ARCHIVE_CONFIG).await?;
+  let config =
+    utils::archive::read_entry(&mut file, ARCHIVE_CONFIG, size, 1_000_000)
+      .await?;
+  let mut config = serde_json::from_slice::<VmConfigPartial>(&config)
+    .map_err(|err| err.map_err_context(|| ARCHIVE_CONFIG))?;
+  let disk_size = expect_archive_entry(&mut file, ARCHIVE_DISK).await?;
+  let disk_offset = file
+    .stream_position()
+    .await
+    .map_err(|err| err.map_err_context(|| path))?;
+  let disk_end = disk_size + utils::archive::padding(disk_size);
+  file
+    .seek(SeekFrom::Current(disk_end as i64))
+    .await
+    .map_err(|err| err.map_err_context(|| path))?;
+  let size = expect_archive_entry(&mut file, ARCHIVE_DIGEST).await?;
+  let checksum =
+    utils::archive::read_entry(&mut file, ARCHIVE_DIGEST, size, 128).await?;
+  let checksum = String::from_utf8(checksum).map_err(|_| {
+    IoError::invalid_data(ARCHIVE_DIGEST, "is not a valid sha256 digest")
+  })?;
+  if checksum.len() != 64 || !checksum.chars().all(|c| c.is_ascii_hexdigit()) {
+    return Err(IoError::invalid_data(
+      ARCHIVE_DIGEST,
+      "is not a valid sha256 digest",
+    ));
+  }
+  if utils::archive::read_header(&mut file).await?.is_some() {
+    return Err(IoError::invalid_data(
+      "Vm archive",
+      "unexpected entry after the disk digest",
+    ));
+  }
+  if let Some(name) = &opts.name {
+    if config.hostname.as_deref() == Some(config.name.as_str()) {
+      config.hostname = Some(name.to_owned());
+    }
+    config.name = name.to_owned();
+  }
+  utils::naming::validate_name("Vm", &config.name)?;
+  let namespace = cli_conf.resolve_namespace(&args.namespace);
+  if client
+    .inspect_vm(&config.name, namespace.clone())
+    .await
+    .is_ok()
+  {
+    return Err(IoError::new(
+      "Vm import",
+      std::io::Error::new(
+        std::io::ErrorKind::AlreadyExists,
+        format!(
+          "vm {} already exists in namespace {}",
+          config.name,
+          namespace.unwrap_or("global".into())
+        ),
+      ),
+    ));
+  }
+  // The image is named after the digest of the disk
+  // so it's not imported again when it already exists
+  let image = format!("{}-{}", config.name, &checksum[..12]);
+  let images = client.list_vm_image().await?;
+  if !images.iter().any(|item| item.name == image) {
+    file
+      .seek(SeekFrom::Start(disk_offset))
+      .await
+      .map_err(|err| err.map_err_context(|| path))?;
+    let mut progress = TransferProgress::new(&image, disk_size);
+    let hasher = Rc::new(RefCell::new(digest::Context::new(&digest::SHA256)));
+    let stream_hasher = hasher.clone();
+    let reader =
+      io::InspectReader::new(file.take(disk_size), move |chunk: &[u8]| {
+        progress.inc(chunk.len() as u64);
+        stream_hasher.borrow_mut().update(chunk);
+      });
+    let stream =
+      codec::FramedRead::new(reader, codec::BytesCodec::new()).map(|r| {
+        let r = r?;
+        Ok::<_, std::io::Error>(ntex::util::Bytes::from_iter(
+          r.freeze().to_vec(),
+        ))
+      });
+    client.import_vm_image(&image, Box::pin(stream)).await?;
+    verify_sha256(client, &image, &Some(checksum), hasher).await?;
+  }
+  config.disk.image = image;
+  let vm = client.create_vm(&config, namespace).await?;
+  if !cli_conf.quiet {
+    println!("{}", &vm.key);
+  }
+  Ok(())
+}
+
+/// ## Exec vm logs
+///
+/// Function executed when running `nanocl vm logs`
+/// It will print the console, stdout and stderr logs of a virtual machine.
+/// Console and stdout logs are written to stdout and stderr logs to stderr
+/// so the output can be piped.
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [args](VmArg) The command arguments
+/// * [opts](VmLogsOpts) The command options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+pub async fn exec_vm_logs(
+  cli_conf: &CliConfig,
+  args: &VmArg,
+  opts: &VmLogsOpts,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  let since = opts
+    .since
+    .map(|since| chrono::Utc::now().timestamp() - since.as_secs() as i64);
+  let query = CargoLogQuery {
+    namespace: cli_conf.resolve_namespace(&args.namespace),
+    tail: opts.tail.map(|tail| tail.to_string()),
+    since,
+    until: None,
+    follow: Some(opts.follow),
+    timestamps: None,
+    stderr: None,
+    stdout: None,
+  };
+  let mut stream = client.logs_vm(&opts.name, &query).await?;
+  let mut stderr = std::io::stderr();
+  let mut stdout = std::io::stdout();
+  while let Some(log) = stream.next().await {
+    let log = match log {
+      Ok(log) => log,
+      Err(e) => {
+        eprintln!("Error: {e}");
+        break;
+      }
+    };
+    match log.kind {
+      OutputKind::StdErr => {
+        stderr.write_all(log.data.as_bytes())?;
+        stderr.flush()?;
+      }
+      OutputKind::StdOut | OutputKind::Console => {
+        stdout.write_all(log.data.as_bytes())?;
+        stdout.flush()?;
+      }
+      OutputKind::StdIn => {}
+    }
+  }
+  Ok(())
+}
+
+/// ## Exec vm attach
+///
+/// Function executed when running `nanocl vm attach`
+/// It will attach to a virtual machine console.
+/// If the connection is dropped it will try to reconnect
+/// with an exponential backoff unless `no_reconnect` is set,
+/// after too many failed attempts the error of the last one is returned.
+/// With a recorder the console output is also written to its file
+/// across reconnections.
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [args](VmArg) The command arguments
+/// * [name](&str) The name of the virtual machine to attach to
+/// * [no_reconnect](bool) Do not reconnect when the connection is dropped
+/// * [recorder](Option<Recorder>) Where the console output is recorded
+/// * [heartbeat](Option<Duration>) The interval between heartbeat pings
+/// * [idle_timeout](Duration) How long without data before disconnecting,
+///   zero to disable it
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+pub async fn exec_vm_attach(
+  cli_conf: &CliConfig,
+  args: &VmArg,
+  name: &str,
+  no_reconnect: bool,
+  mut recorder: Option<Recorder>,
+  heartbeat: Option<Duration>,
+  idle_timeout: Duration,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  /// Delay before the first reconnection attempt
+  const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(500);
+  /// Maximum delay between two reconnection attempts
+  const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+  /// Number of failed reconnection attempts before giving up
+  const RECONNECT_MAX_ATTEMPTS: usize = 10;
+  let mut conn = client
+    .attach_vm(name, cli_conf.resolve_namespace(&args.namespace))
+    .await?;
+  // Restore the original terminal settings once dropped
+  let _raw_mode = utils::tty::RawModeGuard::new()?;
+  // send console input to the current connection
+  let current_sink: Rc<RefCell<Option<ws::WsSink>>> = Default::default();
+  utils::tty::forward_stdin(current_sink.clone());
+  let res = 'attach: loop {
+    match utils::tty::dispatch(
+      conn,
+      &current_sink,
+      recorder.as_mut(),
+      heartbeat,
+      idle_timeout,
+    )
+    .await
+    {
+      Ok(true) if !no_reconnect => {}
+      Ok(_) => break Ok(()),
+      Err(err) => break Err(err),
+    }
+    let mut delay = RECONNECT_BASE_DELAY;
+    let mut attempts = 0;
+    conn = loop {
+      eprintln!("{}", style("reconnecting...").dim().for_stderr());
+      time::sleep(delay).await;
+      match client
+        .attach_vm(name, cli_conf.resolve_namespace(&args.namespace))
+        .await
+      {
+        Ok(conn) => break conn,
+        Err(err) => {
+          attempts += 1;
+          if attempts >= RECONNECT_MAX_ATTEMPTS {
+            break 'attach Err(err.into());
+          }
+          delay = std::cmp::min(delay * 2, RECONNECT_MAX_DELAY);
+        }
+      }
+    };
+  };
+  res
+}
+
+/// ## Vm address
+///
+/// Find the ip address of a vm on the network of its namespace,
+/// or on the first network where it has one
+///
+/// ## Arguments
+///
+/// * [vm](VmInspect) The inspected vm
+///
+/// ## Return
+///
+/// * [Option](Option<String>) The ip address of the vm if it has one
+///
+fn vm_address(vm: &VmInspect) -> Option<String> {
+  let networks = vm
+    .instances
+    .iter()
+    .filter_map(|instance| instance.network_settings.as_ref())
+    .filter_map(|settings| settings.networks.as_ref())
+    .collect::<Vec<_>>();
+  let address = |network: &EndpointSettings| {
+    network
+      .ip_address
+      .clone()
+      .filter(|ip_address| !ip_address.is_empty())
+  };
+  networks
+    .iter()
+    .find_map(|networks| networks.get(&vm.namespace_name).and_then(address))
+    .or_else(|| {
+      networks
+        .iter()
+        .flat_map(|networks| networks.values())
+        .find_map(address)
+    })
+}
+
+/// ## Exec vm ssh
+///
+/// Function executed when running `nanocl vm ssh`
+/// Replace the current process with the system `ssh` binary
+/// connected to the ip address of the vm
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli configuration
+/// * [args](VmArg) The command arguments
+/// * [opts](VmSshOpts) The command options
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+async fn exec_vm_ssh(
+  cli_conf: &CliConfig,
+  args: &VmArg,
+  opts: &VmSshOpts,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  let vm = client
+    .inspect_vm(&opts.name, cli_conf.resolve_namespace(&args.namespace))
+    .await?;
+  let Some(address) = vm_address(&vm) else {
+    return Err(IoError::not_fount(
+      format!("Vm {}", opts.name).as_str(),
+      "has no reachable address yet, make sure it is running",
+    ));
+  };
+  let destination = match opts.user.as_ref().or(vm.config.user.as_ref()) {
+    Some(user) => format!("{user}@{address}"),
+    None => address,
+  };
+  let err = std::process::Command::new("ssh")
+    .args(["-p", &opts.port.to_string()])
+    .args(["-o", "StrictHostKeyChecking=accept-new"])
+    .arg(destination)
+    .args(&opts.args)
+    .exec();
+  // exec only return when the process couldn't be replaced
+  if err.kind() == std::io::ErrorKind::NotFound {
+    return Err(IoError::not_fount("ssh", "binary is not in the PATH"));
+  }
+  Err(IoError::new("ssh", err))
+}
+
 /// ## Exec vm
 ///
 /// Function executed when running `nanocl vm`
@@ -412,17 +1798,60 @@ pub async fn exec_vm_attach(
 ///   * [Err](IoError) An error occured
 ///
 pub async fn exec_vm(cli_conf: &CliConfig, args: &VmArg) -> IoResult<()> {
-  let client = &cli_conf.client;
+  cli_conf.print_namespace(&args.namespace);
   match &args.command {
-    VmCommand::Image(args) => exec_vm_image(client, args).await,
+    VmCommand::Image(args) => exec_vm_image(cli_conf, args).await,
     VmCommand::Create(options) => exec_vm_create(cli_conf, args, options).await,
     VmCommand::List(opts) => exec_vm_ls(cli_conf, args, opts).await,
-    VmCommand::Remove(opts) => exec_vm_rm(cli_conf, args, &opts.names).await,
+    VmCommand::Remove(opts) => exec_vm_rm(cli_conf, args, opts).await,
     VmCommand::Inspect(opts) => exec_vm_inspect(cli_conf, args, opts).await,
-    VmCommand::Start(opts) => exec_vm_start(cli_conf, args, &opts.names).await,
+    VmCommand::Start(opts) => exec_vm_start(cli_conf, args, opts).await,
     VmCommand::Stop(opts) => exec_vm_stop(cli_conf, args, &opts.names).await,
     VmCommand::Run(options) => exec_vm_run(cli_conf, args, options).await,
     VmCommand::Patch(options) => exec_vm_patch(cli_conf, args, options).await,
-    VmCommand::Attach { name } => exec_vm_attach(cli_conf, args, name).await,
+    VmCommand::Resize(options) => exec_vm_resize(cli_conf, args, options).await,
+    VmCommand::Logs(opts) => exec_vm_logs(cli_conf, args, opts).await,
+    VmCommand::Ssh(opts) => exec_vm_ssh(cli_conf, args, opts).await,
+    VmCommand::Export(opts) => exec_vm_export(cli_conf, args, opts).await,
+    VmCommand::Import(opts) => exec_vm_import(cli_conf, args, opts).await,
+    VmCommand::Migrate(opts) => exec_vm_migrate(cli_conf, args, opts).await,
+    VmCommand::Attach {
+      name,
+      no_reconnect,
+      record,
+      record_format,
+      heartbeat,
+      timeout,
+    } => {
+      let recorder = record
+        .as_deref()
+        .map(|path| Recorder::create(path, record_format, name))
+        .transpose()?;
+      exec_vm_attach(
+        cli_conf,
+        args,
+        name,
+        *no_reconnect,
+        recorder,
+        *heartbeat,
+        *timeout,
+      )
+      .await
+    }
+    VmCommand::Clone {
+      name,
+      new_name,
+      namespace_to,
+    } => exec_vm_clone(cli_conf, args, name, new_name, namespace_to).await,
+    VmCommand::Snapshot {
+      name,
+      snapshot_name,
+      force,
+    } => exec_vm_snapshot(cli_conf, args, name, snapshot_name, *force).await,
+    VmCommand::Restore {
+      name,
+      snapshot_name,
+      force,
+    } => exec_vm_restore(cli_conf, args, name, snapshot_name, *force).await,
   }
 }
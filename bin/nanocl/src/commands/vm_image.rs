@@ -1,24 +1,148 @@
+use std::rc::Rc;
+use std::time::Duration;
 use std::path::Path;
+use std::cell::RefCell;
+use std::collections::HashMap;
 
-use tokio_util::codec;
-use futures::StreamExt;
+use tokio_util::{codec, io};
+use futures::{Stream, StreamExt};
+use ring::digest;
 use indicatif::{ProgressBar, ProgressStyle};
 
-use nanocl_utils::io_error::{IoResult, FromIo};
+use nanocl_utils::io_error::{IoError, IoResult, FromIo};
 use nanocld_client::NanocldClient;
-use nanocld_client::stubs::vm_image::VmImageCloneStream;
+use nanocld_client::stubs::vm_image::{VmImage, VmImageCloneStream};
 
+use crate::utils;
+use crate::config::CliConfig;
 use crate::utils::print::print_table;
-use crate::utils::math::calculate_percentage;
+use crate::utils::progress::TransferProgress;
+use crate::utils::{humanize, upload};
+use crate::utils::upload::UploadState;
 
 use crate::models::{
   VmImageArg, VmImageCreateOpts, VmImageCommand, VmImageRow, VmImageResizeOpts,
   VmImageListOpts,
 };
 
+/// ## Verify sha256
+///
+/// Compare the digest of an imported vm image with the expected one,
+/// the vm image is removed on mismatch.
+/// When no digest is expected the computed one is printed on stderr
+///
+/// ## Arguments
+///
+/// * [client](NanocldClient) The nanocl daemon client
+/// * [name](str) The name of the imported vm image
+/// * [expected](Option<String>) The expected sha256 digest
+/// * [hasher](digest::Context) The digest of the streamed bytes
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The digest match or no digest is expected
+///   * [Err](nanocl_utils::io_error::IoError) The digest mismatch
+///
+pub(crate) async fn verify_sha256(
+  client: &NanocldClient,
+  name: &str,
+  expected: &Option<String>,
+  hasher: Rc<RefCell<digest::Context>>,
+) -> IoResult<()> {
+  let hasher = hasher.borrow().clone();
+  let computed = utils::hash::to_hex(&hasher.finish());
+  let Some(expected) = expected else {
+    eprintln!("{name} sha256:{computed}");
+    return Ok(());
+  };
+  let expected = expected.trim_start_matches("sha256:").to_lowercase();
+  if expected == computed {
+    return Ok(());
+  }
+  client.delete_vm_image(name).await?;
+  Err(IoError::invalid_data(
+    format!("Vm image {name}").as_str(),
+    format!("sha256 mismatch expected {expected} got {computed}").as_str(),
+  ))
+}
+
+/// ## Decompressed stream
+///
+/// Decompress the vm image read by the given reader if it is compressed
+/// and turn it into a stream of bytes that can be sent to the daemon
+///
+/// ## Arguments
+///
+/// * [name](str) The path or url of the vm image
+/// * [reader](tokio::io::AsyncBufRead) The reader of the vm image
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Stream) The stream of the decompressed vm image
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+async fn decompressed_stream<R>(
+  name: &str,
+  reader: R,
+) -> IoResult<impl Stream<Item = Result<ntex::util::Bytes, std::io::Error>>>
+where
+  R: tokio::io::AsyncBufRead + Unpin + 'static,
+{
+  let reader = utils::compression::decompress(name, reader).await?;
+  let stream =
+    codec::FramedRead::new(reader, codec::BytesCodec::new()).map(|r| {
+      let r = r?;
+      Ok(ntex::util::Bytes::from_iter(r.freeze().to_vec()))
+    });
+  Ok(stream)
+}
+
+/// ## Resume offset
+///
+/// Find where an interrupted upload of the same file can resume,
+/// the lowest of the bytes saved in the `.part` file
+/// and of the bytes the daemon received,
+/// since the client may have sent bytes that never reached it.
+/// Daemons without resume support start the upload over
+///
+/// ## Arguments
+///
+/// * [client](NanocldClient) The nanocl daemon client
+/// * [fp](Path) The path of the uploaded file
+/// * [state](UploadState) The state of the new upload
+///
+/// ## Return
+///
+/// * [u64](u64) The number of bytes to skip
+///
+async fn resume_offset(
+  client: &NanocldClient,
+  fp: &Path,
+  state: &UploadState,
+) -> u64 {
+  let Some(saved) = upload::read_state(fp) else {
+    return 0;
+  };
+  if !saved.same_upload(state) {
+    return 0;
+  }
+  match client.inspect_vm_image_import(&state.name).await {
+    Ok(status) => status.offset.min(saved.sent),
+    Err(_) => 0,
+  }
+}
+
 /// ## Exec vm image create
 ///
-/// Function that execute when running `nanocl vm image create`
+/// Function that execute when running `nanocl vm image create`,
+/// the digest of the file is computed while it's streamed to the daemon.
+/// Compressed files are decompressed on the fly,
+/// the progress and the digest are computed on the compressed bytes.
+/// The upload is tracked in a `.part` file next to the image
+/// so an interrupted upload resumes where the daemon stopped,
+/// the whole file is still read to verify its digest
 ///
 /// ## Arguments
 ///
@@ -42,40 +166,202 @@ async fn exec_vm_image_create(
   let file = tokio::fs::File::open(&fp)
     .await
     .map_err(|err| err.map_err_context(|| file_path.to_string()))?;
-  // Get file size
-  let file_size = file
+  let metadata = file
     .metadata()
     .await
-    .map_err(|err| err.map_err_context(|| file_path.to_string()))?
-    .len();
-  let mut sent: u64 = 0;
-  let pg = ProgressBar::new(100);
-  let style = ProgressStyle::with_template(
-    "[{elapsed_precise}] [{bar:20.cyan/blue}] {pos:>7}% {msg}",
-  )
-  .unwrap()
-  .progress_chars("=> ");
-  pg.set_style(style);
-  let byte_stream =
-    codec::FramedRead::new(file, codec::BytesCodec::new()).map(move |r| {
-      let r = r?;
-      sent += r.len() as u64;
-      let percent = calculate_percentage(sent, file_size);
-      pg.set_position(percent);
-      let bytes = ntex::util::Bytes::from_iter(r.freeze().to_vec());
-      Ok::<ntex::util::Bytes, std::io::Error>(bytes)
-    });
-  client.import_vm_image(&options.name, byte_stream).await?;
-  Ok(())
+    .map_err(|err| err.map_err_context(|| file_path.to_string()))?;
+  let mut state = UploadState::new(&options.name, &metadata);
+  let offset = resume_offset(client, &fp, &state).await;
+  if offset > 0 {
+    eprintln!(
+      "Resuming import of {} after {}",
+      options.name,
+      humanize::format_bytes(offset)
+    );
+  }
+  state.sent = offset;
+  // Without a writable state the upload simply can't be resumed
+  let _ = upload::write_state(&fp, &state);
+  let mut progress = TransferProgress::new(&options.name, metadata.len());
+  let hasher = Rc::new(RefCell::new(digest::Context::new(&digest::SHA256)));
+  let stream_hasher = hasher.clone();
+  let reader = io::InspectReader::new(file, move |chunk: &[u8]| {
+    progress.inc(chunk.len() as u64);
+    stream_hasher.borrow_mut().update(chunk);
+  });
+  let reader = tokio::io::BufReader::new(reader);
+  let byte_stream = decompressed_stream(&file_path, reader).await?;
+  let mut skip = offset;
+  let mut saved = offset;
+  let mut throttle = options.bwlimit.map(upload::Throttle::new);
+  let state_path = fp.clone();
+  let byte_stream = byte_stream.filter_map(move |res| {
+    let res = match res {
+      Ok(bytes) if skip >= bytes.len() as u64 => {
+        skip -= bytes.len() as u64;
+        None
+      }
+      Ok(bytes) => {
+        let bytes = bytes.slice(skip as usize..);
+        skip = 0;
+        state.sent += bytes.len() as u64;
+        if state.sent - saved >= upload::SAVE_INTERVAL {
+          saved = state.sent;
+          let _ = upload::write_state(&state_path, &state);
+        }
+        Some(Ok(bytes))
+      }
+      Err(err) => Some(Err(err)),
+    };
+    let delay = match (&res, throttle.as_mut()) {
+      (Some(Ok(bytes)), Some(throttle)) => throttle.delay(bytes.len() as u64),
+      _ => Duration::ZERO,
+    };
+    async move {
+      if !delay.is_zero() {
+        ntex::time::sleep(delay).await;
+      }
+      res
+    }
+  });
+  let byte_stream = Box::pin(byte_stream);
+  if offset > 0 {
+    client
+      .resume_vm_image_import(&options.name, offset, byte_stream)
+      .await?;
+  } else {
+    client.import_vm_image(&options.name, byte_stream).await?;
+  }
+  upload::remove_state(&fp);
+  verify_sha256(client, &options.name, &options.sha256, hasher).await
+}
+
+/// ## Import vm image from url
+///
+/// Download a vm image and stream it to the daemon without storing it locally.
+/// The image is named after the hash of the url,
+/// so it is not downloaded again when it already exists.
+/// The digest is computed while the image is streamed and checked once imported.
+/// Compressed images are decompressed on the fly.
+///
+/// ## Arguments
+///
+/// * [client](NanocldClient) The nanocl daemon client
+/// * [url](str) The url of the vm image
+/// * [sha256](Option<String>) The expected sha256 digest of the vm image
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok]((String, bool)) The name of the vm image and true if it was imported
+///   * [Err](nanocl_utils::io_error::IoError) An error occured
+///
+pub(crate) async fn import_vm_image_from_url(
+  client: &NanocldClient,
+  url: &str,
+  sha256: &Option<String>,
+) -> IoResult<(String, bool)> {
+  let hash = utils::hash::calculate_SHA256(url);
+  let name = format!("url-{}", &hash[..12]);
+  let images = client.list_vm_image().await?;
+  if images.iter().any(|image| image.name == name) {
+    return Ok((name, false));
+  }
+  let http = ntex::http::client::Client::build()
+    .disable_timeout()
+    .finish();
+  let mut res = http
+    .get(url)
+    .send()
+    .await
+    .map_err(|err| err.map_err_context(|| format!("Vm image {url}")))?;
+  if res.status().is_redirection() {
+    let location = res
+      .headers()
+      .get("location")
+      .ok_or_else(|| IoError::invalid_data("Location", "is not specified"))?
+      .to_str()
+      .map_err(|err| IoError::invalid_data("Location", &format!("{err}")))?
+      .to_owned();
+    res = http
+      .get(location)
+      .send()
+      .await
+      .map_err(|err| err.map_err_context(|| format!("Vm image {url}")))?;
+  }
+  if !res.status().is_success() {
+    return Err(IoError::invalid_data(
+      format!("Vm image {url}").as_str(),
+      format!("download failed with status {}", res.status()).as_str(),
+    ));
+  }
+  let file_size = res
+    .headers()
+    .get("content-length")
+    .and_then(|value| value.to_str().ok())
+    .and_then(|value| value.parse::<u64>().ok())
+    .unwrap_or_default();
+  let mut progress = TransferProgress::new(&name, file_size);
+  let hasher = Rc::new(RefCell::new(digest::Context::new(&digest::SHA256)));
+  let stream_hasher = hasher.clone();
+  let stream = res.map(move |r| {
+    let r = r.map_err(|err| {
+      std::io::Error::new(std::io::ErrorKind::Other, err.to_string())
+    })?;
+    progress.inc(r.len() as u64);
+    stream_hasher.borrow_mut().update(&r);
+    Ok::<_, std::io::Error>(std::io::Cursor::new(r.to_vec()))
+  });
+  let reader = io::StreamReader::new(Box::pin(stream));
+  let byte_stream = decompressed_stream(url, reader).await?;
+  client.import_vm_image(&name, byte_stream).await?;
+  verify_sha256(client, &name, sha256, hasher).await?;
+  Ok((name, true))
+}
+
+/// ## Image chain
+///
+/// List the names of an image and of its parents up to its base image
+///
+/// ## Arguments
+///
+/// * [images](HashMap<&str, &VmImage>) The images by name
+/// * [name](str) The name of the image
+///
+/// ## Return
+///
+/// * [Vec](Vec<&str>) The chain starting by the image and ending by its base
+///
+fn image_chain<'a>(
+  images: &HashMap<&'a str, &'a VmImage>,
+  name: &'a str,
+) -> Vec<&'a str> {
+  let mut chain = vec![name];
+  let mut current = name;
+  while let Some(parent) = images
+    .get(current)
+    .and_then(|image| image.parent.as_deref())
+  {
+    // Guard against a parent cycle or a parent missing from the list
+    if chain.contains(&parent) || !images.contains_key(parent) {
+      break;
+    }
+    chain.push(parent);
+    current = parent;
+  }
+  chain
 }
 
 /// ## Exec vm image ls
 ///
 /// Function that execute when running `nanocl vm image ls`
+/// The shared column show the base image of images sharing its data,
+/// the table is followed by the total size of the images
+/// if their shared data was copied and their unique size on disk
 ///
 /// ## Arguments
 ///
-/// * [client](NanocldClient) The nanocl daemon client
+/// * [cli_conf](CliConfig) The cli config
 /// * [opts](VmImageListOpts) The vm image list options
 ///
 /// ## Return
@@ -85,23 +371,54 @@ async fn exec_vm_image_create(
 ///   * [Err](nanocl_utils::io_error::IoError) An error occured
 ///
 async fn exec_vm_image_ls(
-  client: &NanocldClient,
+  cli_conf: &CliConfig,
   opts: &VmImageListOpts,
 ) -> IoResult<()> {
+  let client = &cli_conf.client;
   let items = client.list_vm_image().await?;
+  if opts.quiet {
+    for item in items {
+      println!("{}", item.name);
+    }
+    return Ok(());
+  }
+  let images = items
+    .iter()
+    .map(|item| (item.name.as_str(), item))
+    .collect::<HashMap<_, _>>();
+  let chains = items
+    .iter()
+    .map(|item| image_chain(&images, &item.name))
+    .collect::<Vec<_>>();
+  let mut family_sizes = HashMap::<&str, usize>::new();
+  for chain in &chains {
+    *family_sizes.entry(chain[chain.len() - 1]).or_default() += 1;
+  }
+  let unique_size = items.iter().map(|item| item.size_actual).sum::<i64>();
+  let total_size = chains
+    .iter()
+    .flatten()
+    .map(|name| images[name].size_actual)
+    .sum::<i64>();
   let rows = items
-    .into_iter()
-    .map(VmImageRow::from)
-    .collect::<Vec<VmImageRow>>();
-  match opts.quiet {
-    true => {
-      for row in rows {
-        println!("{}", row.name);
+    .iter()
+    .zip(chains.iter())
+    .map(|(item, chain)| {
+      let base = chain[chain.len() - 1];
+      let mut row = VmImageRow::from(item.clone());
+      if family_sizes[&base] > 1 {
+        row.shared = base.to_owned();
       }
-    }
-    false => {
-      print_table(rows);
-    }
+      row
+    })
+    .collect::<Vec<VmImageRow>>();
+  print_table(cli_conf, rows)?;
+  if cli_conf.structured_output().is_none() {
+    println!(
+      "Total size: {}, unique size: {}",
+      humanize::format_bytes(u64::try_from(total_size).unwrap_or_default()),
+      humanize::format_bytes(u64::try_from(unique_size).unwrap_or_default())
+    );
   }
   Ok(())
 }
@@ -147,7 +464,7 @@ async fn exec_vm_image_rm(
 ///   * [Ok](()) The operation was successful
 ///   * [Err](nanocl_utils::io_error::IoError) An error occured
 ///
-async fn exec_vm_image_clone(
+pub(crate) async fn exec_vm_image_clone(
   client: &NanocldClient,
   name: &str,
   clone_name: &str,
@@ -204,7 +521,7 @@ async fn exec_vm_resize(
 ///
 /// ## Arguments
 ///
-/// * [client](NanocldClient) The nanocl daemon client
+/// * [cli_conf](CliConfig) The cli config
 /// * [args](VmImageArg) The vm image arguments
 ///
 /// ## Return
@@ -214,14 +531,15 @@ async fn exec_vm_resize(
 ///   * [Err](nanocl_utils::io_error::IoError) An error occured
 ///
 pub async fn exec_vm_image(
-  client: &NanocldClient,
+  cli_conf: &CliConfig,
   args: &VmImageArg,
 ) -> IoResult<()> {
+  let client = &cli_conf.client;
   match &args.command {
     VmImageCommand::Create(options) => {
       exec_vm_image_create(client, options).await
     }
-    VmImageCommand::List(opts) => exec_vm_image_ls(client, opts).await,
+    VmImageCommand::List(opts) => exec_vm_image_ls(cli_conf, opts).await,
     VmImageCommand::Remove { names } => exec_vm_image_rm(client, names).await,
     VmImageCommand::Clone { name, clone_name } => {
       exec_vm_image_clone(client, name, clone_name).await
@@ -1,13 +1,18 @@
 use std::fs;
+use std::collections::HashMap;
+use std::time::Duration;
+use clap::ValueEnum;
 use nanocld_client::NanocldClient;
+use nanocl_utils::io_error::{IoError, IoResult};
 use serde::{Serialize, Deserialize};
 
-use crate::models::{DisplayFormat, Context};
+use crate::models::{DisplayFormat, Context, TableStyle};
 
 /// ## CliConfig
 ///
 /// This struct is used to store the user configuration
 /// It is stored in the user's home directory in a file located at `.nanocl/conf.yml`
+/// unless another path is set with `--config` or `NANOCL_CONFIG`
 ///
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
@@ -16,6 +21,9 @@ pub struct UserConfig {
   pub current_context: String,
   #[serde(default)]
   pub display_format: DisplayFormat,
+  /// Default namespace of each context set with `nanocl context use --namespace`
+  #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+  pub namespaces: HashMap<String, String>,
 }
 
 /// ## Default current context
@@ -35,6 +43,7 @@ impl Default for UserConfig {
     Self {
       current_context: default_current_context(),
       display_format: DisplayFormat::Yaml,
+      namespaces: HashMap::new(),
     }
   }
 }
@@ -42,19 +51,16 @@ impl Default for UserConfig {
 /// ## CliConfig implementations
 ///
 impl UserConfig {
-  /// ## New
+  /// ## Read
   ///
-  /// This function is used to create a new CliConfig struct
-  /// It will read the configuration file located in the user's home directory
-  /// If no configuration file is found, it will return the default configuration
+  /// This function is used to read the user configuration from a file
+  /// If the file can't be read or parsed, it will return the default configuration
   ///
-  pub fn new() -> Self {
-    // Get user config path
-    let home_path = match std::env::var("HOME") {
-      Ok(home) => home,
-      Err(_) => return UserConfig::default(),
-    };
-    let path = format!("{}/.nanocl/conf.yml", home_path);
+  /// ## Arguments
+  ///
+  /// * [path](str) The path to the configuration file
+  ///
+  pub fn read(path: &str) -> Self {
     let s = match fs::read_to_string(path) {
       Ok(s) => s,
       Err(_) => return UserConfig::default(),
@@ -66,6 +72,114 @@ impl UserConfig {
   }
 }
 
+/// ## Resolve config path
+///
+/// Resolve the path of the user configuration, the first one set win:
+/// 1. The global `--config` flag
+/// 2. The `NANOCL_CONFIG` environment variable
+/// 3. `$HOME/.nanocl/conf.yml`
+///
+/// An explicit path must exist, it's an error rather than
+/// silently using the default configuration.
+/// Without an explicit path nor `$HOME` there is no path
+/// and the default configuration is used.
+///
+/// ## Arguments
+///
+/// * [config](Option<String>) The global `--config` flag
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Option<String>) The path of the user configuration if any
+///   * [Err](IoError) The explicit path doesn't exist
+///
+pub fn resolve_config_path(
+  config: &Option<String>,
+) -> IoResult<Option<String>> {
+  let explicit = config.clone().or_else(|| {
+    std::env::var("NANOCL_CONFIG")
+      .ok()
+      .filter(|path| !path.trim().is_empty())
+  });
+  match explicit {
+    Some(path) if std::path::Path::new(&path).is_file() => Ok(Some(path)),
+    Some(path) => Err(IoError::not_fount(
+      format!("Config {path}"),
+      "doesn't exist".to_owned(),
+    )),
+    None => Ok(
+      std::env::var("HOME")
+        .ok()
+        .map(|home| format!("{home}/.nanocl/conf.yml")),
+    ),
+  }
+}
+
+/// ## Resolve display format
+///
+/// Resolve the display format from its sources, the first one set win:
+/// 1. The `--display` option of the command
+/// 2. The global `--output` flag
+/// 3. The `NANOCL_OUTPUT` environment variable
+/// 4. The `DisplayFormat` of the current context
+/// 5. The `DisplayFormat` of the user configuration, `Yaml` by default
+///
+/// ## Arguments
+///
+/// * [command](Option<DisplayFormat>) The display format of the command
+/// * [global](Option<DisplayFormat>) The global `--output` flag
+/// * [env](Option<DisplayFormat>) The `NANOCL_OUTPUT` environment variable
+/// * [context](Option<DisplayFormat>) The display format of the context
+/// * [user](DisplayFormat) The display format of the user configuration
+///
+/// ## Return
+///
+/// * [DisplayFormat](DisplayFormat) The display format to use
+///
+pub fn resolve_display_format(
+  command: &Option<DisplayFormat>,
+  global: &Option<DisplayFormat>,
+  env: &Option<DisplayFormat>,
+  context: &Option<DisplayFormat>,
+  user: &DisplayFormat,
+) -> DisplayFormat {
+  // `wide` only apply to tables so the next source is used
+  let not_wide =
+    |format: &DisplayFormat| !matches!(format, DisplayFormat::Wide);
+  [command, global, env, context]
+    .into_iter()
+    .find_map(|format| format.clone().filter(not_wide))
+    .or_else(|| Some(user.clone()).filter(not_wide))
+    .unwrap_or_default()
+}
+
+/// ## Resolve env output
+///
+/// Read the display format from the `NANOCL_OUTPUT` environment variable
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Option<DisplayFormat>) The display format if the variable is set
+///   * [Err](IoError) The `NANOCL_OUTPUT` environment variable is invalid
+///
+pub fn resolve_env_output() -> IoResult<Option<DisplayFormat>> {
+  match std::env::var("NANOCL_OUTPUT") {
+    Ok(output) if !output.trim().is_empty() => {
+      let format =
+        DisplayFormat::from_str(output.trim(), true).map_err(|_| {
+          IoError::invalid_input(
+            "NANOCL_OUTPUT".to_owned(),
+            format!("Expected yaml, toml, json, env or wide got {output}"),
+          )
+        })?;
+      Ok(Some(format))
+    }
+    _ => Ok(None),
+  }
+}
+
 /// ## Cli Config
 ///
 /// A new `CliConfig` is created for each command.
@@ -81,4 +195,192 @@ pub struct CliConfig {
   pub context: Context,
   /// User configuration
   pub user_config: UserConfig,
+  /// Path of the user configuration set by `--config` or `NANOCL_CONFIG`,
+  /// none without `$HOME`
+  pub config_path: Option<String>,
+  /// Output format set by the global `--output` flag
+  pub output: Option<DisplayFormat>,
+  /// Output format set by the `NANOCL_OUTPUT` environment variable
+  pub env_output: Option<DisplayFormat>,
+  /// Timeout of requests to the daemon, zero means no timeout
+  pub timeout: Duration,
+  /// Table style set by the global `--table-style` flag
+  pub table_style: Option<TableStyle>,
+  /// Suppress confirmations set by the global `--quiet` flag
+  pub quiet: bool,
+  /// Print full table values set by the global `--no-truncate` flag
+  pub no_truncate: bool,
+  /// Print full ids in tables set by the global `--no-trunc` flag
+  pub no_trunc: bool,
+  /// Maximum number of concurrent requests set by the global `--concurrency` flag
+  pub concurrency: usize,
+  /// Verbosity set by the number of global `--verbose` flags
+  pub verbose: u8,
+  /// Default namespace of the current context
+  pub namespace: Option<String>,
+}
+
+/// ## Cli Config implementations
+///
+impl CliConfig {
+  /// ## Get display format
+  ///
+  /// Resolve the display format to use for a command
+  /// from every source of the configuration,
+  /// see [resolve_display_format](resolve_display_format) for the precedence.
+  ///
+  /// ## Arguments
+  ///
+  /// * [display](Option<DisplayFormat>) The display format of the command
+  ///
+  /// ## Return
+  ///
+  /// * [DisplayFormat](DisplayFormat) The display format to use
+  ///
+  pub fn get_display_format(
+    &self,
+    display: &Option<DisplayFormat>,
+  ) -> DisplayFormat {
+    resolve_display_format(
+      display,
+      &self.output,
+      &self.env_output,
+      &self.context.display_format,
+      &self.user_config.display_format,
+    )
+  }
+
+  /// ## Structured output
+  ///
+  /// The format set by the global `--output` flag to print tables
+  /// as structured data, none when tables are printed including with `wide`
+  ///
+  /// ## Return
+  ///
+  /// * [Option](Option<DisplayFormat>) The format to print tables with
+  ///
+  pub fn structured_output(&self) -> Option<&DisplayFormat> {
+    self
+      .output
+      .as_ref()
+      .filter(|format| !matches!(format, DisplayFormat::Wide))
+  }
+
+  /// ## Is wide
+  ///
+  /// Check if tables are printed with every optional column
+  /// with the global `--output wide` flag
+  ///
+  /// ## Return
+  ///
+  /// * [bool](bool) True with `--output wide`
+  ///
+  pub fn is_wide(&self) -> bool {
+    matches!(self.output, Some(DisplayFormat::Wide))
+  }
+
+  /// ## Resolve namespace
+  ///
+  /// Resolve the namespace to use for a command.
+  /// The `--namespace` option take precedence over the default namespace
+  /// of the current context, when none are set the daemon use `global`.
+  ///
+  /// ## Arguments
+  ///
+  /// * [namespace](Option<String>) The namespace of the command
+  ///
+  /// ## Return
+  ///
+  /// * [Option](Option<String>) The namespace to use
+  ///
+  pub fn resolve_namespace(
+    &self,
+    namespace: &Option<String>,
+  ) -> Option<String> {
+    namespace.clone().or_else(|| self.namespace.clone())
+  }
+
+  /// ## Explain error
+  ///
+  /// Turn the error of a daemon that couldn't be reached
+  /// into an actionable message with the resolved host,
+  /// see [explain_unreachable](crate::utils::error::explain_unreachable)
+  ///
+  /// ## Arguments
+  ///
+  /// * [err](IoError) The error of the command
+  ///
+  /// ## Return
+  ///
+  /// * [IoError](IoError) The explained error
+  ///
+  pub fn explain_error(&self, err: IoError) -> IoError {
+    let target = self
+      .client
+      .unix_socket
+      .as_deref()
+      .unwrap_or(&self.client.url);
+    crate::utils::error::explain_unreachable(&self.host, target, err)
+  }
+
+  /// ## Print namespace
+  ///
+  /// Print the resolved namespace on stderr in verbose mode
+  ///
+  /// ## Arguments
+  ///
+  /// * [namespace](Option<String>) The namespace of the command
+  ///
+  pub fn print_namespace(&self, namespace: &Option<String>) {
+    if self.verbose == 0 {
+      return;
+    }
+    let namespace = self
+      .resolve_namespace(namespace)
+      .unwrap_or_else(|| "global".to_owned());
+    eprintln!("Using namespace {namespace}");
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn display_format_precedence() {
+    use DisplayFormat::*;
+    let resolve = |command, global, env, context| {
+      resolve_display_format(&command, &global, &env, &context, &Toml)
+    };
+    let format = resolve(Some(Json), Some(Yaml), Some(Yaml), Some(Yaml));
+    assert!(matches!(format, Json));
+    let format = resolve(None, Some(Json), Some(Yaml), Some(Yaml));
+    assert!(matches!(format, Json));
+    let format = resolve(None, None, Some(Json), Some(Yaml));
+    assert!(matches!(format, Json));
+    let format = resolve(None, None, None, Some(Json));
+    assert!(matches!(format, Json));
+    let format = resolve(None, None, None, None);
+    assert!(matches!(format, Toml));
+    assert!(matches!(UserConfig::default().display_format, Yaml));
+    let format = resolve(None, Some(Wide), Some(Json), None);
+    assert!(matches!(format, Json));
+    let format =
+      resolve_display_format(&None, &Some(Wide), &None, &None, &Wide);
+    assert!(matches!(format, Yaml));
+  }
+
+  #[test]
+  fn config_path() {
+    let path = std::env::temp_dir().join("nanocl-config-path.yml");
+    std::fs::write(&path, "CurrentContext: default\n").unwrap();
+    let path = path.to_string_lossy().to_string();
+    assert_eq!(
+      resolve_config_path(&Some(path.clone())).unwrap(),
+      Some(path)
+    );
+    let missing = Some("/tmp/nanocl-config-missing.yml".to_owned());
+    let err = resolve_config_path(&missing).unwrap_err();
+    assert_eq!(err.inner.kind(), std::io::ErrorKind::NotFound);
+  }
 }
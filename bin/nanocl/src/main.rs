@@ -1,8 +1,8 @@
 use clap::Parser;
 use dotenv::dotenv;
 
-use nanocl_utils::io_error::IoResult;
-use nanocld_client::NanocldClient;
+use nanocl_utils::io_error::{IoError, IoResult};
+use nanocld_client::{NanocldClient, SslConfig};
 
 mod utils;
 mod config;
@@ -10,7 +10,7 @@ mod models;
 mod version;
 mod commands;
 
-use config::{UserConfig, CliConfig};
+use config::{UserConfig, CliConfig, resolve_env_output, resolve_config_path};
 use models::{Cli, Command, Context};
 
 /// ## Create cli config
@@ -29,23 +29,35 @@ use models::{Cli, Command, Context};
 ///
 fn create_cli_config(cli_args: &Cli) -> IoResult<CliConfig> {
   Context::ensure()?;
-  let user_conf = UserConfig::new();
+  let config_path = resolve_config_path(&cli_args.config)?;
+  let user_conf = config_path
+    .as_deref()
+    .map(UserConfig::read)
+    .unwrap_or_default();
   let mut context = Context::new();
-  if user_conf.current_context != "default" {
+  // The `--context` flag select a context without persisting it
+  if let Some(name) = &cli_args.context {
+    if name != "default" {
+      context = Context::read_by_name(name).map_err(|_| {
+        IoError::not_fount(
+          format!("Context {name}").as_str(),
+          "doesn't exist, see `nanocl context ls`",
+        )
+      })?;
+    }
+  } else if user_conf.current_context != "default" {
     match Context::read_by_name(&user_conf.current_context) {
       Err(_) => {
-        Context::r#use("default")?;
+        Context::r#use(config_path.as_deref(), "default", None)?;
       }
       Ok(cur_context) => {
         context = cur_context;
       }
     }
   }
+  let endpoint = context.endpoints.get("Nanocl").unwrap().clone();
   #[allow(unused)]
-  let mut host = cli_args
-    .host
-    .clone()
-    .unwrap_or(context.endpoints.get("Nanocl").unwrap().host.clone());
+  let mut host = cli_args.host.clone().unwrap_or(endpoint.host.clone());
   #[cfg(any(feature = "dev", feature = "test"))]
   {
     if context.name == "default" {
@@ -55,13 +67,55 @@ fn create_cli_config(cli_args: &Cli) -> IoResult<CliConfig> {
         .unwrap_or("http://localhost:8585".into());
     }
   }
+  // Tls paths of the context are only used with the host of the context
+  let (cert, key, ca) = match &cli_args.host {
+    Some(_) => (
+      cli_args.cert.clone(),
+      cli_args.key.clone(),
+      cli_args.ca.clone(),
+    ),
+    None => (
+      cli_args.cert.clone().or(endpoint.cert),
+      cli_args.key.clone().or(endpoint.key),
+      cli_args.ca.clone().or(endpoint.ca),
+    ),
+  };
+  let ssl = match (&cert, &key, &ca) {
+    (None, None, None) => None,
+    _ if !host.starts_with("https://") => {
+      return Err(IoError::invalid_input(
+        "Host",
+        "--cert, --key and --ca can only be used with an https:// host",
+      ))
+    }
+    (cert, key, ca) => Some(SslConfig {
+      cert: cert.clone(),
+      key: key.clone(),
+      ca: ca.clone(),
+    }),
+  };
+  let namespace = user_conf.namespaces.get(&context.name).cloned();
   let url = Box::leak(host.clone().into_boxed_str());
-  let client = NanocldClient::connect_to(url, None);
+  let mut client = NanocldClient::connect_to_with_ssl(url, None, ssl)?;
+  client.set_verbosity(cli_args.verbose);
+  utils::print::init_color(cli_args.no_color);
+  utils::print::init_json(cli_args.pretty, cli_args.compact);
   Ok(CliConfig {
     host,
     client,
     context,
     user_config: user_conf,
+    config_path,
+    output: cli_args.output.clone(),
+    env_output: resolve_env_output()?,
+    timeout: utils::client::resolve_timeout(cli_args.timeout)?,
+    table_style: cli_args.table_style.clone(),
+    quiet: cli_args.quiet,
+    no_truncate: cli_args.no_truncate,
+    no_trunc: cli_args.no_trunc,
+    concurrency: cli_args.concurrency.into(),
+    verbose: cli_args.verbose,
+    namespace,
   })
 }
 
@@ -81,23 +135,29 @@ fn create_cli_config(cli_args: &Cli) -> IoResult<CliConfig> {
 ///
 async fn execute_arg(cli_args: &Cli) -> IoResult<()> {
   let cli_conf = create_cli_config(cli_args)?;
-  match &cli_args.command {
+  let res = match &cli_args.command {
     Command::Namespace(args) => commands::exec_namespace(&cli_conf, args).await,
     Command::Resource(args) => commands::exec_resource(&cli_conf, args).await,
     Command::Cargo(args) => commands::exec_cargo(&cli_conf, args).await,
-    Command::Events => commands::exec_events(&cli_conf).await,
+    Command::Events(opts) => commands::exec_events(&cli_conf, opts).await,
     Command::State(args) => commands::exec_state(&cli_conf, args).await,
-    Command::Version => commands::exec_version(&cli_conf).await,
+    Command::Version(args) => commands::exec_version(&cli_conf, args).await,
     Command::Vm(args) => commands::exec_vm(&cli_conf, args).await,
     Command::Ps(args) => commands::exec_process(&cli_conf, args).await,
-    Command::Install(args) => commands::exec_install(args).await,
+    Command::Install(args) => commands::exec_install(&cli_conf, args).await,
     Command::Uninstall(args) => commands::exec_uninstall(args).await,
     Command::Upgrade(args) => commands::exec_upgrade(&cli_conf, args).await,
     Command::System(args) => commands::exec_system(&cli_conf, args).await,
     Command::Node(args) => commands::exec_node(&cli_conf, args).await,
     Command::Context(args) => commands::exec_context(&cli_conf, args).await,
-    Command::Info => commands::exec_info(&cli_conf).await,
-  }
+    Command::Info(opts) => commands::exec_info(&cli_conf, opts).await,
+    Command::Schema(opts) => commands::exec_schema(opts),
+    Command::Completion { shell, dynamic } => {
+      commands::exec_completion(shell, *dynamic)
+    }
+    Command::Complete(opts) => commands::exec_complete(&cli_conf, opts).await,
+  };
+  res.map_err(|err| cli_conf.explain_error(err))
 }
 
 /// ## Main
@@ -111,6 +171,7 @@ async fn main() -> std::io::Result<()> {
   let args = Cli::parse();
   dotenv().ok();
   ctrlc::set_handler(move || {
+    utils::tty::restore_saved_mode();
     let term = dialoguer::console::Term::stdout();
     let _ = term.show_cursor();
     let _ = term.clear_last_lines(0);
@@ -118,8 +179,7 @@ async fn main() -> std::io::Result<()> {
   })
   .expect("Error setting Ctrl-C handler");
   if let Err(err) = execute_arg(&args).await {
-    eprintln!("{err}");
-    err.exit();
+    utils::error::exit_error(&args.error_format, &err);
   }
   Ok(())
 }
@@ -128,6 +188,8 @@ async fn main() -> std::io::Result<()> {
 mod tests {
   use super::*;
 
+  use std::time::Duration;
+
   use nanocld_client::NanocldClient;
 
   /// Test version command
@@ -135,6 +197,21 @@ mod tests {
   async fn version() {
     let args = Cli::parse_from(["nanocl", "version"]);
     assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "version", "--server"]);
+    assert!(execute_arg(&args).await.is_ok());
+    assert!(
+      Cli::try_parse_from(["nanocl", "version", "--client", "--server"])
+        .is_err()
+    );
+    let unreachable = "http://localhost:1";
+    let args = Cli::parse_from(["nanocl", "-H", unreachable, "version"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args =
+      Cli::parse_from(["nanocl", "-H", unreachable, "version", "--client"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args =
+      Cli::parse_from(["nanocl", "-H", unreachable, "version", "--server"]);
+    assert!(execute_arg(&args).await.is_err());
   }
 
   /// Test Namespace commands
@@ -156,6 +233,47 @@ mod tests {
     let args =
       Cli::parse_from(["nanocl", "namespace", "rm", "-y", NAMESPACE_NAME]);
     assert!(execute_arg(&args).await.is_ok());
+    // Inspecting a removed namespace should fail with not found
+    let args =
+      Cli::parse_from(["nanocl", "namespace", "inspect", NAMESPACE_NAME]);
+    let err = execute_arg(&args).await.unwrap_err();
+    assert_eq!(err.inner.kind(), std::io::ErrorKind::NotFound);
+  }
+
+  /// Test namespace rm --cascade
+  #[ntex::test]
+  async fn namespace_rm_cascade() {
+    const NAMESPACE_NAME: &str = "cli-rm-cascade";
+    const IMAGE_NAME: &str = "nexthat/nanocl-get-started:latest";
+    let args =
+      Cli::parse_from(["nanocl", "namespace", "create", NAMESPACE_NAME]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "--namespace",
+      NAMESPACE_NAME,
+      "create",
+      "cli-rm-cascade-1",
+      IMAGE_NAME,
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    // A non empty namespace is only removed with --cascade
+    let args =
+      Cli::parse_from(["nanocl", "namespace", "rm", "--yes", NAMESPACE_NAME]);
+    assert!(execute_arg(&args).await.is_err());
+    let args = Cli::parse_from([
+      "nanocl",
+      "namespace",
+      "rm",
+      "--yes",
+      "--cascade",
+      NAMESPACE_NAME,
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args =
+      Cli::parse_from(["nanocl", "namespace", "inspect", NAMESPACE_NAME]);
+    assert!(execute_arg(&args).await.is_err());
   }
 
   /// Test Cargo image commands
@@ -198,21 +316,99 @@ mod tests {
     let args =
       Cli::parse_from(["nanocl", "cargo", "create", CARGO_NAME, IMAGE_NAME]);
     assert!(execute_arg(&args).await.is_ok());
+    // A cargo without image is rejected before reaching the daemon
+    let args = Cli::parse_from(["nanocl", "cargo", "create", "no-image", ""]);
+    assert!(execute_arg(&args).await.is_err());
+    // An invalid name is rejected unless normalized
+    let args =
+      Cli::parse_from(["nanocl", "cargo", "create", "Cli_Test", IMAGE_NAME]);
+    assert!(execute_arg(&args).await.is_err());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "create",
+      "--normalize",
+      "Cli_Normalize",
+      IMAGE_NAME,
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args =
+      Cli::parse_from(["nanocl", "cargo", "rm", "-y", "cli-normalize"]);
+    assert!(execute_arg(&args).await.is_ok());
     // Try to list cargoes
     let args = Cli::parse_from(["nanocl", "cargo", "ls"]);
     assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "ls",
+      "--sort",
+      "replicas",
+      "--reverse",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    assert!(
+      Cli::try_parse_from(["nanocl", "cargo", "ls", "--reverse"]).is_err()
+    );
     // Try to start a cargo
     let args = Cli::parse_from(["nanocl", "cargo", "start", CARGO_NAME]);
     assert!(execute_arg(&args).await.is_ok());
     // Try to inspect a cargo
     let args = Cli::parse_from(["nanocl", "cargo", "inspect", CARGO_NAME]);
     assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "inspect",
+      CARGO_NAME,
+      "--display",
+      "env",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "-o", "env", "cargo", "ls"]);
+    assert!(execute_arg(&args).await.is_ok());
     // Try to patch a cargo
     let args = Cli::parse_from([
       "nanocl", "cargo", "patch", CARGO_NAME, "--image", IMAGE_NAME, "--env",
       "TEST=1",
     ]);
     assert!(execute_arg(&args).await.is_ok());
+    // Patch a cargo with a JSON Merge Patch
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "patch",
+      CARGO_NAME,
+      "--patch",
+      r#"{"Container":{"Env":["TEST=2"],"Labels":null}}"#,
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "patch",
+      CARGO_NAME,
+      "--patch",
+      r#"{"Container":{"Imagee":"busybox"}}"#,
+    ]);
+    assert!(execute_arg(&args).await.is_err());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "patch",
+      CARGO_NAME,
+      "--patch",
+      r#"{"Container":{"Env":"TEST=3"}}"#,
+    ]);
+    assert!(execute_arg(&args).await.is_err());
+    let args =
+      Cli::parse_from(["nanocl", "cargo", "patch", CARGO_NAME, "--patch", "{"]);
+    assert!(execute_arg(&args).await.is_err());
+    assert!(Cli::try_parse_from([
+      "nanocl", "cargo", "patch", CARGO_NAME, "--patch", "{}", "--image",
+      IMAGE_NAME,
+    ])
+    .is_err());
 
     let args = Cli::parse_from(["nanocl", "cargo", "history", CARGO_NAME]);
     assert!(execute_arg(&args).await.is_ok());
@@ -233,6 +429,99 @@ mod tests {
       &history.key.to_string(),
     ]);
     assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "inspect",
+      CARGO_NAME,
+      "--revision",
+      &history.key.to_string(),
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args =
+      Cli::parse_from(["nanocl", "cargo", "inspect", CARGO_NAME, "--history"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "inspect",
+      CARGO_NAME,
+      "--revision",
+      "unknown",
+    ]);
+    assert!(execute_arg(&args).await.is_err());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "inspect",
+      CARGO_NAME,
+      "--template",
+      "{{.Name}} {{.Config.Container.Image}}",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "inspect",
+      CARGO_NAME,
+      "--template",
+      "{{.Config.Unknown}}",
+    ]);
+    assert!(execute_arg(&args).await.is_err());
+    // Try to show the logs of each replica
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "scale",
+      CARGO_NAME,
+      "--replicas",
+      "2",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "logs",
+      CARGO_NAME,
+      "--timestamps",
+      "-t",
+      "10",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    // Try to show the resource usage of each replica
+    let args =
+      Cli::parse_from(["nanocl", "cargo", "inspect", CARGO_NAME, "--stats"]);
+    assert!(execute_arg(&args).await.is_ok());
+    assert!(Cli::try_parse_from([
+      "nanocl", "cargo", "inspect", CARGO_NAME, "--follow",
+    ])
+    .is_err());
+
+    // Try to rename a cargo and rename it back
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "rename",
+      CARGO_NAME,
+      "cli-test-renamed",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "cargo", "inspect", CARGO_NAME]);
+    assert!(execute_arg(&args).await.is_err());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "rename",
+      "cli-test-renamed",
+      CARGO_NAME,
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args =
+      Cli::parse_from(["nanocl", "cargo", "rename", CARGO_NAME, CARGO_NAME]);
+    assert!(execute_arg(&args).await.is_err());
+    let args =
+      Cli::parse_from(["nanocl", "cargo", "rename", CARGO_NAME, "Invalid"]);
+    assert!(execute_arg(&args).await.is_err());
 
     // Try to stop a cargo
     let args = Cli::parse_from(["nanocl", "cargo", "stop", CARGO_NAME]);
@@ -263,6 +552,24 @@ mod tests {
       "../../examples/deploy_example.yml",
     ]);
     assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "-yqs",
+      "../../examples/deploy_example.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    assert!(Cli::try_parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "-q",
+      "--dry-run",
+      "-s",
+      "../../examples/deploy_example.yml",
+    ])
+    .is_err());
     // Create a new resource
     let args = Cli::parse_from([
       "nanocl",
@@ -275,10 +582,35 @@ mod tests {
     // List resources
     let args = Cli::parse_from(["nanocl", "resource", "ls"]);
     assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "resource",
+      "ls",
+      "--kind",
+      "ProxyRule",
+      "--name",
+      "resource-*",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args =
+      Cli::parse_from(["nanocl", "resource", "ls", "--kind", "Unknown"]);
+    assert!(execute_arg(&args).await.is_ok());
     // Inspect resource
     let args =
       Cli::parse_from(["nanocl", "resource", "inspect", "resource-example"]);
     assert!(execute_arg(&args).await.is_ok());
+    // ProxyRule is validated by its controller and has no schema
+    let args = Cli::parse_from([
+      "nanocl",
+      "resource",
+      "inspect",
+      "--kind-schema",
+      "ProxyRule",
+    ]);
+    assert!(execute_arg(&args).await.is_err());
+    let args =
+      Cli::parse_from(["nanocl", "resource", "inspect", "--schema", "Unknown"]);
+    assert!(execute_arg(&args).await.is_err());
 
     // History
     let args =
@@ -302,11 +634,52 @@ mod tests {
       &history.key.to_string(),
     ]);
     assert!(execute_arg(&args).await.is_ok());
+    // Rollback
+    let args = Cli::parse_from([
+      "nanocl",
+      "resource",
+      "rollback",
+      "-y",
+      "--to",
+      &history.key.to_string(),
+      "resource-example",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "resource",
+      "rollback",
+      "-y",
+      "--to",
+      "unknown-revision",
+      "resource-example",
+    ]);
+    assert!(execute_arg(&args).await.is_err());
 
     // Remove resource
     let args =
       Cli::parse_from(["nanocl", "resource", "rm", "-y", "resource-example"]);
     assert!(execute_arg(&args).await.is_ok());
+    // Apply and remove resources from a file
+    for _ in 0..2 {
+      let args = Cli::parse_from([
+        "nanocl",
+        "resource",
+        "apply",
+        "-yf",
+        "../../examples/resource_ssl_example.yml",
+      ]);
+      assert!(execute_arg(&args).await.is_ok());
+    }
+    let args = Cli::parse_from([
+      "nanocl",
+      "resource",
+      "rm",
+      "-yf",
+      "../../examples/resource_ssl_example.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    assert!(execute_arg(&args).await.is_err());
     let args = Cli::parse_from([
       "nanocl",
       "state",
@@ -317,97 +690,530 @@ mod tests {
     assert!(execute_arg(&args).await.is_ok());
   }
 
-  /// Test cargo exec command
   #[ntex::test]
-  async fn cargo_exec() {
+  async fn cargo_create_replicas() {
+    const IMAGE_NAME: &str = "nexthat/nanocl-get-started:latest";
+    let cases = [("3", true), ("auto", true), ("-1", false), ("two", false)];
+    for (replicas, valid) in cases {
+      let args = Cli::try_parse_from([
+        "nanocl",
+        "cargo",
+        "create",
+        "cli-replicas",
+        IMAGE_NAME,
+        "--replicas",
+        replicas,
+      ]);
+      assert_eq!(args.is_ok(), valid, "{replicas}");
+    }
     let args = Cli::parse_from([
       "nanocl",
       "cargo",
-      "--namespace",
-      "system",
-      "exec",
-      "nstore",
-      "--",
-      "echo",
-      "hello",
+      "create",
+      "cli-replicas",
+      IMAGE_NAME,
+      "--replicas",
+      "2",
     ]);
     assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "cargo", "rm", "-y", "cli-replicas"]);
+    assert!(execute_arg(&args).await.is_ok());
   }
 
   #[ntex::test]
-  async fn state() {
+  async fn cargo_hostname_conflict() {
+    const IMAGE_NAME: &str = "nexthat/nanocl-get-started:latest";
     let args = Cli::parse_from([
       "nanocl",
-      "state",
-      "apply",
-      "-ys",
-      "../../examples/deploy_example.yml",
+      "cargo",
+      "create",
+      "--hostname",
+      "cli-host",
+      "cli-host-1",
+      IMAGE_NAME,
     ]);
     assert!(execute_arg(&args).await.is_ok());
-
     let args = Cli::parse_from([
       "nanocl",
-      "state",
-      "apply",
-      "-ys",
-      "../../examples/deploy_example.toml",
+      "cargo",
+      "create",
+      "--hostname",
+      "cli-host",
+      "cli-host-2",
+      IMAGE_NAME,
     ]);
-    assert!(execute_arg(&args).await.is_ok());
-
+    assert!(execute_arg(&args).await.is_err());
     let args = Cli::parse_from([
       "nanocl",
-      "state",
-      "apply",
-      "-pys",
-      "../../examples/deploy_example.yml",
+      "cargo",
+      "create",
+      "--force",
+      "--hostname",
+      "cli-host",
+      "cli-host-2",
+      IMAGE_NAME,
     ]);
     assert!(execute_arg(&args).await.is_ok());
-
     let args = Cli::parse_from([
       "nanocl",
-      "state",
-      "apply",
-      "-ys",
-      "../../examples/cargo_example.yml",
+      "cargo",
+      "rm",
+      "-y",
+      "cli-host-1",
+      "cli-host-2",
     ]);
     assert!(execute_arg(&args).await.is_ok());
+  }
 
+  /// Test cargo rm --all command
+  #[ntex::test]
+  async fn cargo_rm_all() {
+    const NAMESPACE_NAME: &str = "cli-rm-all";
+    const IMAGE_NAME: &str = "nexthat/nanocl-get-started:latest";
+    let args =
+      Cli::parse_from(["nanocl", "namespace", "create", NAMESPACE_NAME]);
+    assert!(execute_arg(&args).await.is_ok());
+    for name in ["cli-rm-all-1", "cli-rm-all-2"] {
+      let args = Cli::parse_from([
+        "nanocl",
+        "cargo",
+        "--namespace",
+        NAMESPACE_NAME,
+        "create",
+        name,
+        IMAGE_NAME,
+      ]);
+      assert!(execute_arg(&args).await.is_ok());
+    }
+    assert!(Cli::try_parse_from(["nanocl", "cargo", "rm", "-y"]).is_err());
     let args = Cli::parse_from([
       "nanocl",
-      "state",
-      "apply",
-      "-ys",
-      "../../examples/cargo_example.yml",
+      "cargo",
+      "--namespace",
+      NAMESPACE_NAME,
+      "rm",
+      "--yes",
+      "--all",
     ]);
     assert!(execute_arg(&args).await.is_ok());
+    let args =
+      Cli::parse_from(["nanocl", "cargo", "rm", "-y", "cli-rm-missing"]);
+    assert!(execute_arg(&args).await.is_err());
+    let args =
+      Cli::parse_from(["nanocl", "namespace", "rm", "-y", NAMESPACE_NAME]);
+    assert!(execute_arg(&args).await.is_ok());
+  }
 
+  /// Test cargo ls optional columns
+  #[ntex::test]
+  async fn cargo_ls_columns() {
     let args = Cli::parse_from([
       "nanocl",
-      "state",
-      "rm",
-      "-ys",
-      "../../examples/cargo_example.yml",
+      "cargo",
+      "--namespace",
+      "system",
+      "ls",
+      "--show-dns",
+      "--show-image",
+      "--show-replicas",
     ]);
     assert!(execute_arg(&args).await.is_ok());
-
     let args = Cli::parse_from([
       "nanocl",
-      "state",
-      "rm",
-      "-ys",
-      "../../examples/deploy_example.yml",
+      "-o",
+      "json",
+      "cargo",
+      "--namespace",
+      "system",
+      "ls",
+      "--show-dns",
     ]);
     assert!(execute_arg(&args).await.is_ok());
-  }
-
-  #[ntex::test]
-  async fn info() {
-    let args = Cli::parse_from(["nanocl", "info"]);
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "--namespace",
+      "system",
+      "ls",
+      "--show-health",
+    ]);
     assert!(execute_arg(&args).await.is_ok());
+    assert!(Cli::try_parse_from([
+      "nanocl",
+      "cargo",
+      "ls",
+      "--show-health",
+      "--status-summary",
+    ])
+    .is_err());
   }
 
+  /// Test cargo ls --status-summary
   #[ntex::test]
-  async fn cargo_run() {
+  async fn cargo_ls_status_summary() {
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "--namespace",
+      "system",
+      "ls",
+      "--status-summary",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "-o",
+      "json",
+      "cargo",
+      "ls",
+      "--status-summary",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    assert!(Cli::try_parse_from([
+      "nanocl",
+      "cargo",
+      "ls",
+      "--status-summary",
+      "--quiet",
+    ])
+    .is_err());
+  }
+
+  /// Test cargo restart command
+  #[ntex::test]
+  async fn cargo_restart() {
+    const NAMESPACE_NAME: &str = "cli-restart";
+    const CARGO_NAME: &str = "cli-restart-1";
+    const IMAGE_NAME: &str = "nexthat/nanocl-get-started:latest";
+    assert!(Cli::try_parse_from(["nanocl", "cargo", "restart"]).is_err());
+    assert!(Cli::try_parse_from([
+      "nanocl",
+      "cargo",
+      "restart",
+      "--wait-timeout",
+      "10s",
+      CARGO_NAME,
+    ])
+    .is_err());
+    let args =
+      Cli::parse_from(["nanocl", "namespace", "create", NAMESPACE_NAME]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "--namespace",
+      NAMESPACE_NAME,
+      "run",
+      CARGO_NAME,
+      IMAGE_NAME,
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "--namespace",
+      NAMESPACE_NAME,
+      "restart",
+      "--wait",
+      "--all",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "--namespace",
+      NAMESPACE_NAME,
+      "restart",
+      CARGO_NAME,
+      "cli-restart-missing",
+    ]);
+    assert!(execute_arg(&args).await.is_err());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "--namespace",
+      NAMESPACE_NAME,
+      "rm",
+      "-y",
+      "--all",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args =
+      Cli::parse_from(["nanocl", "namespace", "rm", "-y", NAMESPACE_NAME]);
+    assert!(execute_arg(&args).await.is_ok());
+  }
+
+  /// Test cargo exec command
+  #[ntex::test]
+  async fn cargo_exec() {
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "--namespace",
+      "system",
+      "exec",
+      "nstore",
+      "--",
+      "echo",
+      "hello",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "--namespace",
+      "system",
+      "exec",
+      "-t",
+      "--replica",
+      "0",
+      "nstore",
+      "--",
+      "echo",
+      "hello",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+  }
+
+  #[ntex::test]
+  async fn state() {
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "-ys",
+      "../../examples/deploy_example.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "-ys",
+      "../../examples/deploy_example.toml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "-pys",
+      "../../examples/deploy_example.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "-ys",
+      "../../examples/cargo_example.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "-ys",
+      "../../examples/cargo_example.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "--dry-run",
+      "-s",
+      "../../examples/cargo_example.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "--reload",
+      "-ys",
+      "../../examples/cargo_example.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    assert!(Cli::try_parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "--reload",
+      "--dry-run",
+      "-s",
+      "../../examples/cargo_example.yml",
+    ])
+    .is_err());
+
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "logs",
+      "-t",
+      "10",
+      "-s",
+      "../../examples/cargo_example.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "--wait",
+      "-ys",
+      "../../examples/depends_on.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "rm",
+      "-ys",
+      "../../examples/depends_on.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    assert!(Cli::try_parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "--wait-timeout",
+      "10s",
+      "-s",
+      "../../examples/depends_on.yml",
+    ])
+    .is_err());
+
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "--dry-run",
+      "-s",
+      "../../examples/cargo_example.yml",
+      "-s",
+      "../../examples/cargo_autoremove.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "--dry-run",
+      "--prune",
+      "--prune-label",
+      "app=example",
+      "-s",
+      "../../examples/cargo_example.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+
+    // --prune-label needs --prune and a key=value label
+    assert!(Cli::try_parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "--prune-label",
+      "app=example",
+      "-s",
+      "../../examples/cargo_example.yml",
+    ])
+    .is_err());
+    assert!(Cli::try_parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "--prune",
+      "--prune-label",
+      "app",
+      "-s",
+      "../../examples/cargo_example.yml",
+    ])
+    .is_err());
+
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "apply",
+      "--dry-run",
+      "-s",
+      "../../examples/cargo_example.yml",
+      "-s",
+      "../../examples/cargo_example.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_err());
+
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "rm",
+      "--dry-run",
+      "-s",
+      "../../examples/cargo_example.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "rm",
+      "-ys",
+      "../../examples/cargo_example.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+
+    // The removed cargoes differ from the Statefile
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "diff",
+      "-s",
+      "../../examples/cargo_example.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_err());
+
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "rm",
+      "-ys",
+      "../../examples/deploy_example.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+  }
+
+  #[ntex::test]
+  async fn info() {
+    let args = Cli::parse_from(["nanocl", "info"]);
+    assert!(matches!(
+      Cli::parse_from(["nanocl", "info", "--watch"]).command,
+      Command::Info(models::InfoOpts { watch: Some(watch) })
+        if watch == Duration::from_secs(2)
+    ));
+    assert!(matches!(
+      Cli::parse_from(["nanocl", "info", "--watch", "500ms"]).command,
+      Command::Info(models::InfoOpts { watch: Some(watch) })
+        if watch == Duration::from_millis(500)
+    ));
+    assert!(Cli::try_parse_from(["nanocl", "info", "--watch", "0"]).is_err());
+    assert!(Cli::try_parse_from(["nanocl", "info", "--watch", "5"]).is_err());
+    assert!(execute_arg(&args).await.is_ok());
+    for format in ["json", "yaml", "toml", "env"] {
+      let args = Cli::parse_from(["nanocl", "-o", format, "info"]);
+      assert!(execute_arg(&args).await.is_ok());
+    }
+  }
+
+  #[ntex::test]
+  async fn cargo_run() {
     let args = Cli::parse_from([
       "nanocl",
       "cargo",
@@ -426,9 +1232,636 @@ mod tests {
     assert!(execute_arg(&args).await.is_ok());
   }
 
+  #[ntex::test]
+  async fn completion() {
+    let args = Cli::parse_from(["nanocl", "completion", "bash"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "completion", "zsh"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "completion", "--dynamic", "bash"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "completion", "--dynamic", "fish"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args =
+      Cli::parse_from(["nanocl", "completion", "--dynamic", "powershell"]);
+    assert!(execute_arg(&args).await.is_err());
+    let args = Cli::parse_from(["nanocl", "complete", "cargo", "rm", "-y", ""]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "complete", "cargo", "-n", ""]);
+    assert!(execute_arg(&args).await.is_ok());
+  }
+
+  #[ntex::test]
+  async fn output() {
+    let args = Cli::parse_from(["nanocl", "-o", "json", "ps"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "-o", "toml", "namespace", "ls"]);
+    assert!(execute_arg(&args).await.is_ok());
+  }
+
+  #[ntex::test]
+  async fn process() {
+    let args = Cli::parse_from(["nanocl", "ps"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "ps", "-n", "system", "nstore*"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "ps", "[invalid"]);
+    assert!(execute_arg(&args).await.is_err());
+    let args = Cli::parse_from(["nanocl", "ps", "-A"]);
+    assert!(execute_arg(&args).await.is_ok());
+    assert!(
+      Cli::try_parse_from(["nanocl", "ps", "-A", "-n", "system"]).is_err()
+    );
+    let args = Cli::parse_from(["nanocl", "ps", "-A", "--format", "tree"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args =
+      Cli::parse_from(["nanocl", "-o", "json", "ps", "--format", "tree"]);
+    assert!(execute_arg(&args).await.is_ok());
+    assert!(Cli::try_parse_from(["nanocl", "ps", "--format", "list"]).is_err());
+    let args = Cli::parse_from(["nanocl", "cargo", "ls", "-A"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "vm", "ls", "--all-namespaces"]);
+    assert!(execute_arg(&args).await.is_ok());
+  }
+
+  #[ntex::test]
+  async fn vm_ls_raw() {
+    let args = Cli::parse_from(["nanocl", "vm", "ls", "--raw"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "-o", "json", "vm", "ls"]);
+    assert!(execute_arg(&args).await.is_ok());
+  }
+
+  #[ntex::test]
+  async fn output_wide() {
+    for command in [
+      vec!["cargo", "ls"],
+      vec!["vm", "ls"],
+      vec!["ps"],
+      vec!["resource", "ls"],
+      vec!["namespace", "inspect", "system"],
+    ] {
+      let args = ["nanocl", "-o", "wide"].into_iter().chain(command);
+      let args = Cli::parse_from(args);
+      assert!(execute_arg(&args).await.is_ok());
+    }
+  }
+
+  #[ntex::test]
+  async fn list_pagination() {
+    let args = Cli::parse_from(["nanocl", "ps", "--limit", "1"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl", "cargo", "ls", "-A", "--limit", "1", "--offset", "1",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "vm", "ls", "--offset", "100"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "resource", "ls", "--limit", "0"]);
+    assert!(execute_arg(&args).await.is_ok());
+    assert!(Cli::try_parse_from(["nanocl", "ps", "--limit", "-1"]).is_err());
+    assert!(Cli::try_parse_from(["nanocl", "vm", "ls", "--offset"]).is_err());
+  }
+
+  #[ntex::test]
+  async fn vm_image_ls() {
+    let args = Cli::parse_from(["nanocl", "vm", "image", "ls"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "-o", "json", "vm", "image", "ls"]);
+    assert!(execute_arg(&args).await.is_ok());
+  }
+
+  #[ntex::test]
+  async fn vm_image_create_bwlimit() {
+    assert!(Cli::try_parse_from([
+      "nanocl",
+      "vm",
+      "image",
+      "create",
+      "--bwlimit",
+      "1048576",
+      "img",
+      "img.qcow2",
+    ])
+    .is_ok());
+    assert!(Cli::try_parse_from([
+      "nanocl",
+      "vm",
+      "image",
+      "create",
+      "--bwlimit",
+      "1M",
+      "img",
+      "img.qcow2",
+    ])
+    .is_err());
+  }
+
+  #[ntex::test]
+  async fn vm_ssh() {
+    assert!(Cli::try_parse_from([
+      "nanocl", "vm", "ssh", "my-vm", "--user", "ubuntu", "-p", "2222", "--",
+      "uptime",
+    ])
+    .is_ok());
+    let args = Cli::parse_from(["nanocl", "vm", "ssh", "missing-vm"]);
+    assert!(execute_arg(&args).await.is_err());
+  }
+
+  #[ntex::test]
+  async fn vm_stop_missing() {
+    let args =
+      Cli::parse_from(["nanocl", "vm", "stop", "missing-a", "missing-b"]);
+    assert!(execute_arg(&args).await.is_err());
+    let args = Cli::parse_from(["nanocl", "vm", "rm", "missing-a"]);
+    assert!(execute_arg(&args).await.is_err());
+    let args = Cli::parse_from([
+      "nanocl",
+      "vm",
+      "rm",
+      "--ignore-not-found",
+      "missing-a",
+      "missing-b",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from([
+      "nanocl",
+      "cargo",
+      "rm",
+      "-y",
+      "--ignore-not-found",
+      "missing-a",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+  }
+
+  #[test]
+  fn vm_ls_watch() {
+    assert!(Cli::try_parse_from(["nanocl", "vm", "ls", "--watch"]).is_ok());
+    assert!(Cli::try_parse_from(["nanocl", "vm", "ls", "-w", "5s"]).is_ok());
+    assert!(Cli::try_parse_from(["nanocl", "vm", "ls", "-w", "0"]).is_err());
+    assert!(Cli::try_parse_from(["nanocl", "vm", "ls", "-w", "-q"]).is_err());
+  }
+
+  #[test]
+  fn vm_create_from_image_url() {
+    assert!(Cli::try_parse_from(["nanocl", "vm", "create", "my-vm"]).is_err());
+    let args = Cli::try_parse_from([
+      "nanocl",
+      "vm",
+      "create",
+      "my-vm",
+      "--from-image-url",
+      "https://cloud-images.ubuntu.com/minimal/releases/jammy/release/ubuntu-22.04-minimal-cloudimg-amd64.img",
+    ]);
+    assert!(args.is_ok());
+    let args = Cli::try_parse_from([
+      "nanocl", "vm", "create", "my-vm", "my-image", "--sha256", "abc",
+    ]);
+    assert!(args.is_err());
+    let args = Cli::try_parse_from([
+      "nanocl",
+      "vm",
+      "create",
+      "my-vm",
+      "--from-image-url",
+      "https://example.com/image.img",
+      "--checksum",
+      "abc",
+    ]);
+    assert!(args.is_ok());
+  }
+
+  #[ntex::test]
+  async fn vm_export_import() {
+    assert!(Cli::try_parse_from(["nanocl", "vm", "export", "my-vm"]).is_err());
+    assert!(Cli::try_parse_from(["nanocl", "vm", "import", "my-vm"]).is_err());
+    let output = "/tmp/nanocl-missing-vm-export.tar";
+    let args =
+      Cli::parse_from(["nanocl", "vm", "export", "missing-vm", "-o", output]);
+    assert!(execute_arg(&args).await.is_err());
+    assert!(!std::path::Path::new(output).exists());
+    let args = Cli::parse_from([
+      "nanocl",
+      "vm",
+      "import",
+      "-f",
+      "/tmp/nanocl-missing-vm-import.tar",
+    ]);
+    assert!(execute_arg(&args).await.is_err());
+  }
+
+  #[ntex::test]
+  async fn vm_patch_from_file() {
+    assert!(Cli::try_parse_from([
+      "nanocl",
+      "vm",
+      "patch",
+      "my-vm",
+      "--from-file",
+      "patch.yml",
+      "--cpu",
+      "2",
+    ])
+    .is_err());
+    let path = std::env::temp_dir().join("cli-vm-patch.yml");
+    std::fs::write(&path, "HostConfig:\n  Cpus: 2\n").unwrap();
+    let path = path.to_string_lossy().to_string();
+    let args =
+      Cli::parse_from(["nanocl", "vm", "patch", "my-vm", "--from-file", &path]);
+    assert!(execute_arg(&args).await.is_err());
+    let args = Cli::parse_from([
+      "nanocl",
+      "vm",
+      "patch",
+      "my-vm",
+      "--from-file",
+      "cli-vm-patch-missing.yml",
+    ]);
+    assert!(execute_arg(&args).await.is_err());
+  }
+
+  #[ntex::test]
+  async fn vm_migrate_invalid() {
+    assert!(Cli::try_parse_from(["nanocl", "vm", "migrate", "my-vm"]).is_err());
+    let args = Cli::parse_from([
+      "nanocl",
+      "vm",
+      "migrate",
+      "my-vm",
+      "--to-node",
+      "cli-missing-node",
+    ]);
+    let err = execute_arg(&args).await.unwrap_err();
+    assert_eq!(err.inner.kind(), std::io::ErrorKind::NotFound);
+  }
+
+  #[test]
+  fn vm_attach_record() {
+    let args = Cli::parse_from([
+      "nanocl",
+      "vm",
+      "console",
+      "my-vm",
+      "--record",
+      "install.cast",
+    ]);
+    assert!(matches!(
+      args.command,
+      Command::Vm(models::VmArg {
+        command: models::VmCommand::Attach {
+          record: Some(_),
+          ..
+        },
+        ..
+      })
+    ));
+    assert!(Cli::try_parse_from([
+      "nanocl",
+      "vm",
+      "attach",
+      "my-vm",
+      "--record-format",
+      "cast",
+    ])
+    .is_err());
+  }
+
+  #[test]
+  fn vm_attach_timeouts() {
+    let args = Cli::parse_from([
+      "nanocl",
+      "vm",
+      "attach",
+      "my-vm",
+      "--heartbeat",
+      "10s",
+      "--timeout",
+      "1m",
+    ]);
+    assert!(matches!(
+      args.command,
+      Command::Vm(models::VmArg {
+        command: models::VmCommand::Attach {
+          heartbeat: Some(heartbeat),
+          timeout,
+          ..
+        },
+        ..
+      }) if heartbeat.as_secs() == 10 && timeout.as_secs() == 60
+    ));
+    let args = Cli::parse_from(["nanocl", "vm", "attach", "my-vm"]);
+    assert!(matches!(
+      args.command,
+      Command::Vm(models::VmArg {
+        command: models::VmCommand::Attach {
+          heartbeat: None,
+          timeout,
+          ..
+        },
+        ..
+      }) if timeout.is_zero()
+    ));
+    assert!(Cli::try_parse_from([
+      "nanocl",
+      "vm",
+      "attach",
+      "my-vm",
+      "--heartbeat",
+      "0",
+    ])
+    .is_err());
+  }
+
+  #[ntex::test]
+  async fn vm_resize_invalid() {
+    assert!(Cli::try_parse_from(["nanocl", "vm", "resize", "my-vm"]).is_err());
+    let args =
+      Cli::parse_from(["nanocl", "vm", "resize", "my-vm", "--memory", "64M"]);
+    assert!(execute_arg(&args).await.is_err());
+  }
+
+  #[ntex::test]
+  async fn quiet() {
+    const NAMESPACE_NAME: &str = "cli-quiet";
+    let args =
+      Cli::parse_from(["nanocl", "-q", "namespace", "create", NAMESPACE_NAME]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args =
+      Cli::parse_from(["nanocl", "namespace", "rm", "-y", NAMESPACE_NAME]);
+    assert!(execute_arg(&args).await.is_ok());
+  }
+
+  #[ntex::test]
+  async fn error_format() {
+    let args = Cli::parse_from(["nanocl", "--error-format", "json", "version"]);
+    assert!(execute_arg(&args).await.is_ok());
+  }
+
+  #[ntex::test]
+  async fn table_style() {
+    for style in ["unicode", "ascii", "markdown"] {
+      let args =
+        Cli::parse_from(["nanocl", "--table-style", style, "namespace", "ls"]);
+      assert!(execute_arg(&args).await.is_ok());
+    }
+    let args = Cli::parse_from(["nanocl", "--no-truncate", "cargo", "ls"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args =
+      Cli::parse_from(["nanocl", "--no-trunc", "cargo", "image", "ls"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "--no-trunc", "ps"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "--no-color", "namespace", "ls"]);
+    assert!(execute_arg(&args).await.is_ok());
+  }
+
+  #[ntex::test]
+  async fn timeout() {
+    let args = Cli::parse_from(["nanocl", "--timeout", "10s", "cargo", "ls"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "--timeout", "0", "vm", "ls"]);
+    assert!(execute_arg(&args).await.is_ok());
+    assert!(
+      Cli::try_parse_from(["nanocl", "--timeout", "10", "cargo", "ls"])
+        .is_err()
+    );
+  }
+
+  #[ntex::test]
+  async fn state_template() {
+    let dir = "/tmp/nanocl-state-template";
+    std::fs::create_dir_all(dir).unwrap();
+    let template = format!("{dir}/Statefile.yml");
+    let values = format!("{dir}/values.yml");
+    std::fs::write(
+      &template,
+      "ApiVersion: v0.8\nKind: Cargo\nCargoes:\n- Name: ${NAME}\n  \
+      Container:\n    Image: nginx:${TAG:-latest}\n",
+    )
+    .unwrap();
+    std::fs::write(&values, "NAME: from-values\nTAG: 1.25\n").unwrap();
+    let args = Cli::parse_from([
+      "nanocl",
+      "state",
+      "template",
+      "-f",
+      &template,
+      "--values",
+      &values,
+      "--set",
+      "NAME=from-set",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    // NAME has no default
+    let args =
+      Cli::parse_from(["nanocl", "state", "template", "-f", &template]);
+    assert!(execute_arg(&args).await.is_err());
+    assert!(Cli::try_parse_from([
+      "nanocl", "state", "template", "-f", &template, "--set", "NAME",
+    ])
+    .is_err());
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[ntex::test]
+  async fn json_compact() {
+    let args =
+      Cli::parse_from(["nanocl", "-o", "json", "--compact", "cargo", "ls"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args =
+      Cli::parse_from(["nanocl", "-o", "json", "--pretty", "cargo", "ls"]);
+    assert!(execute_arg(&args).await.is_ok());
+    assert!(Cli::try_parse_from([
+      "nanocl",
+      "--pretty",
+      "--compact",
+      "cargo",
+      "ls"
+    ])
+    .is_err());
+  }
+
+  #[ntex::test]
+  async fn concurrency() {
+    assert_eq!(Cli::parse_from(["nanocl", "cargo", "ls"]).concurrency, 8);
+    let args =
+      Cli::parse_from(["nanocl", "--concurrency", "2", "cargo", "ls", "-A"]);
+    assert_eq!(args.concurrency, 2);
+    assert!(execute_arg(&args).await.is_ok());
+    assert!(Cli::try_parse_from([
+      "nanocl",
+      "--concurrency",
+      "0",
+      "cargo",
+      "ls"
+    ])
+    .is_err());
+  }
+
+  #[ntex::test]
+  async fn context() {
+    let args = Cli::parse_from(["nanocl", "context", "ls"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "context", "current"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "context", "export", "default"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let path = std::env::temp_dir().join("cli-context-import.yml");
+    let mut context = Context::new();
+    context.name = "cli-context-import".into();
+    context.endpoints.get_mut("Nanocl").unwrap().ca = Some("/ca.pem".into());
+    std::fs::write(&path, serde_yaml::to_string(&context).unwrap()).unwrap();
+    let path = path.to_string_lossy().to_string();
+    let args = Cli::parse_from(["nanocl", "context", "import", "-f", &path]);
+    assert!(execute_arg(&args).await.is_ok());
+    let imported = Context::read_by_name("cli-context-import").unwrap();
+    assert_eq!(
+      serde_yaml::to_string(&imported).unwrap(),
+      serde_yaml::to_string(&context).unwrap()
+    );
+    let args = Cli::parse_from(["nanocl", "context", "import", "-f", &path]);
+    assert!(execute_arg(&args).await.is_err());
+    let args = Cli::parse_from([
+      "nanocl",
+      "context",
+      "import",
+      "-f",
+      &path,
+      "--overwrite",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+  }
+
+  #[ntex::test]
+  async fn context_flag() {
+    let args = Cli::parse_from(["nanocl", "--context", "default", "info"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "--context", "cli-missing", "info"]);
+    let err = execute_arg(&args).await.unwrap_err();
+    assert_eq!(err.inner.kind(), std::io::ErrorKind::NotFound);
+  }
+
+  #[ntex::test]
+  async fn config_flag() {
+    let args = Cli::parse_from([
+      "nanocl",
+      "--config",
+      "/tmp/cli-config-missing.yml",
+      "context",
+      "ls",
+    ]);
+    let err = execute_arg(&args).await.unwrap_err();
+    assert_eq!(err.inner.kind(), std::io::ErrorKind::NotFound);
+    let path = std::env::temp_dir().join("cli-config.yml");
+    std::fs::write(&path, "CurrentContext: default\n").unwrap();
+    let path = path.to_string_lossy().to_string();
+    let args = Cli::parse_from([
+      "nanocl",
+      "--config",
+      &path,
+      "context",
+      "use",
+      "default",
+      "--namespace",
+      "cli-config",
+    ]);
+    assert!(execute_arg(&args).await.is_ok());
+    // The explicit config is the one written back
+    let config = std::fs::read_to_string(&path).unwrap();
+    assert!(config.contains("cli-config"));
+  }
+
+  #[ntex::test]
+  async fn verbose_requests() {
+    let args = Cli::parse_from(["nanocl", "-vv", "cargo", "ls"]);
+    let cli_conf = create_cli_config(&args).unwrap();
+    assert_eq!(cli_conf.verbose, 2);
+    assert_eq!(cli_conf.client.verbosity, 2);
+    assert!(execute_arg(&args).await.is_ok());
+  }
+
+  #[ntex::test]
+  async fn context_namespace() {
+    let args = Cli::parse_from(["nanocl", "-v", "cargo", "ls"]);
+    let mut cli_conf = create_cli_config(&args).unwrap();
+    assert_eq!(cli_conf.verbose, 1);
+    cli_conf.namespace = Some("system".into());
+    assert_eq!(cli_conf.resolve_namespace(&None), Some("system".into()));
+    assert_eq!(
+      cli_conf.resolve_namespace(&Some("global".into())),
+      Some("global".into())
+    );
+    let Command::Cargo(cargo_args) = &args.command else {
+      panic!("Expect a cargo command");
+    };
+    assert!(commands::exec_cargo(&cli_conf, cargo_args).await.is_ok());
+  }
+
+  #[ntex::test]
+  async fn system_df() {
+    let args = Cli::parse_from(["nanocl", "system", "df"]);
+    assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "-o", "json", "system", "df"]);
+    assert!(execute_arg(&args).await.is_ok());
+  }
+
+  #[ntex::test]
+  async fn events_time_bounds() {
+    assert!(Cli::try_parse_from(["nanocl", "events", "--since", "10"]).is_err());
+    assert!(
+      Cli::try_parse_from(["nanocl", "events", "--since", "yesterday"])
+        .is_err()
+    );
+    // Events are received live so past and relative bounds filter nothing
+    assert!(
+      Cli::try_parse_from(["nanocl", "events", "--since", "10m"]).is_err()
+    );
+    assert!(Cli::try_parse_from([
+      "nanocl",
+      "events",
+      "--until",
+      "2023-09-01T00:00:00Z"
+    ])
+    .is_err());
+    let until =
+      (chrono::Utc::now() + chrono::Duration::seconds(1)).to_rfc3339();
+    let args = Cli::parse_from(["nanocl", "events", "--until", &until]);
+    assert!(execute_arg(&args).await.is_ok());
+    let since = (chrono::Utc::now() + chrono::Duration::hours(2)).to_rfc3339();
+    let until = (chrono::Utc::now() + chrono::Duration::hours(1)).to_rfc3339();
+    let args = Cli::parse_from([
+      "nanocl", "events", "--since", &since, "--until", &until,
+    ]);
+    assert!(execute_arg(&args).await.is_err());
+  }
+
+  #[ntex::test]
+  async fn schema() {
+    for kind in ["cargo", "vm", "resource"] {
+      let args = Cli::parse_from(["nanocl", "schema", kind]);
+      assert!(execute_arg(&args).await.is_ok());
+    }
+    assert!(Cli::try_parse_from(["nanocl", "schema", "namespace"]).is_err());
+  }
+
   #[ntex::test]
   async fn node_list() {
     let args = Cli::parse_from(["nanocl", "node", "ls"]);
     assert!(execute_arg(&args).await.is_ok());
+    let args = Cli::parse_from(["nanocl", "node", "ls", "-q"]);
+    assert!(execute_arg(&args).await.is_ok());
+  }
+
+  #[ntex::test]
+  async fn node_cordon() {
+    let args = Cli::parse_from(["nanocl", "node", "cordon", "unknown-node"]);
+    assert!(execute_arg(&args).await.is_err());
+    let args = Cli::parse_from(["nanocl", "node", "uncordon", "unknown-node"]);
+    assert!(execute_arg(&args).await.is_err());
+    let args =
+      Cli::parse_from(["nanocl", "node", "drain", "unknown-node", "-y"]);
+    assert!(execute_arg(&args).await.is_err());
+    assert!(Cli::try_parse_from(["nanocl", "node", "drain"]).is_err());
   }
 }
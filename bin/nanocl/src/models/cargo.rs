@@ -1,14 +1,18 @@
 use tabled::Tabled;
 use chrono::TimeZone;
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
 
-use bollard_next::exec::CreateExecOptions;
+use bollard_next::container::Stats;
 use nanocld_client::stubs::cargo::CargoSummary;
 use nanocld_client::stubs::cargo_config::{
-  CargoConfigUpdate, Config as ContainerConfig, CargoConfigPartial, HostConfig,
+  CargoConfig, CargoConfigUpdate, Config as ContainerConfig,
+  CargoConfigPartial, HostConfig, ReplicationMode, ReplicationStatic,
+  ReplicationPercentage,
 };
 
-use super::{cargo_image::CargoImageArg, DisplayFormat};
+use super::{cargo_image::CargoImageArg, DisplayFormat, PaginationOpts};
+use super::humanize::format_bytes;
 
 /// ## CargoRemoveOpts
 ///
@@ -17,12 +21,19 @@ use super::{cargo_image::CargoImageArg, DisplayFormat};
 #[derive(Debug, Parser)]
 pub struct CargoRemoveOpts {
   /// Skip confirmation
-  #[clap(short = 'y')]
+  #[clap(long = "yes", short = 'y')]
   pub skip_confirm: bool,
   /// Force delete
   #[clap(short = 'f')]
   pub force: bool,
+  /// Delete every cargo of the namespace
+  #[clap(long, conflicts_with = "names")]
+  pub all: bool,
+  /// Treat cargoes that don't exist as deleted
+  #[clap(long)]
+  pub ignore_not_found: bool,
   /// List of cargo names to delete
+  #[clap(required_unless_present = "all")]
   pub names: Vec<String>,
 }
 
@@ -42,6 +53,43 @@ pub struct CargoCreateOpts {
   /// Environment variables of the cargo
   #[clap(short, long = "env")]
   pub(crate) env: Option<Vec<String>>,
+  /// Hostname of the cargo, used to reach it from its namespace
+  #[clap(long)]
+  pub hostname: Option<String>,
+  /// Create the cargo even if another one of the namespace use the same hostname
+  #[clap(long)]
+  pub force: bool,
+  /// Replication of the cargo, a number of replicas or `auto`
+  #[clap(long, value_parser = parse_replicas)]
+  pub replicas: Option<ReplicationMode>,
+  /// Lowercase the name and replace its invalid characters by `-`
+  #[clap(long)]
+  pub normalize: bool,
+}
+
+/// ## Parse replicas
+///
+/// Parse the `--replicas` option of `nanocl cargo create`
+/// a number is a static replication and `auto` let the daemon decide
+///
+/// ## Arguments
+///
+/// * [value](str) The value to parse
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](ReplicationMode) The replication of the cargo
+///   * [Err](String) The value is neither a number nor `auto`
+///
+fn parse_replicas(value: &str) -> Result<ReplicationMode, String> {
+  if value.eq_ignore_ascii_case("auto") {
+    return Ok(ReplicationMode::Auto);
+  }
+  let number = value.parse::<usize>().map_err(|_| {
+    format!("invalid replicas `{value}`, expected a number or `auto`")
+  })?;
+  Ok(ReplicationMode::Static(ReplicationStatic { number }))
 }
 
 /// Convert CargoCreateOpts to CargoConfigPartial
@@ -51,6 +99,7 @@ impl From<CargoCreateOpts> for CargoConfigPartial {
       name: val.name,
       container: ContainerConfig {
         image: Some(val.image),
+        hostname: val.hostname,
         // network: val.network,
         // volumes: val.volumes,
         env: val.env,
@@ -60,7 +109,7 @@ impl From<CargoCreateOpts> for CargoConfigPartial {
         }),
         ..Default::default()
       },
-      ..Default::default()
+      replication: val.replicas,
     }
   }
 }
@@ -83,6 +132,9 @@ pub struct CargoRunOpts {
   pub env: Option<Vec<String>>,
   #[clap(long = "rm", default_value = "false")]
   pub auto_remove: bool,
+  /// Lowercase the name and replace its invalid characters by `-`
+  #[clap(long)]
+  pub normalize: bool,
   /// Command to execute
   pub command: Vec<String>,
 }
@@ -136,7 +188,22 @@ pub struct CargoStopOpts {
 ///
 #[derive(Debug, Parser)]
 pub struct CargoRestartOpts {
-  // List of cargo to stop
+  /// Wait for every instance of the cargoes to be running
+  #[clap(long)]
+  pub wait: bool,
+  /// Maximum time to wait for the cargoes to be running like `60s` or `5m`
+  #[clap(
+    long,
+    default_value = "60s",
+    value_parser = super::duration::parse_duration,
+    requires = "wait"
+  )]
+  pub wait_timeout: std::time::Duration,
+  /// Restart every cargo of the namespace
+  #[clap(long, conflicts_with = "names")]
+  pub all: bool,
+  /// List of cargo to restart
+  #[clap(required_unless_present = "all")]
   pub names: Vec<String>,
 }
 
@@ -149,6 +216,22 @@ pub struct CargoInspectOpts {
   /// Display format
   #[clap(long)]
   pub display: Option<DisplayFormat>,
+  /// Show the config of a previous revision by its key
+  #[clap(long, conflicts_with = "history")]
+  pub revision: Option<String>,
+  /// List the previous revisions of the cargo config
+  #[clap(long)]
+  pub history: bool,
+  /// Render the cargo with a Go-like template
+  /// e.g. '{{.Name}} {{.Config.Container.Image}}'
+  #[clap(long, conflicts_with_all = ["display", "history", "revision"])]
+  pub template: Option<String>,
+  /// Show the CPU, memory and network usage of every instance of the cargo
+  #[clap(long, conflicts_with_all = ["history", "revision", "template"])]
+  pub stats: bool,
+  /// Keep refreshing the usage until Ctrl-C instead of showing it once
+  #[clap(long, short, requires = "stats")]
+  pub follow: bool,
   /// Name of cargo to inspect
   pub(crate) name: String,
 }
@@ -173,6 +256,21 @@ pub struct CargoPatchOpts {
   /// New volumes of cargo
   #[clap(short, long = "volume")]
   pub(crate) volumes: Option<Vec<String>>,
+  /// JSON Merge Patch (RFC 7396) applied to the current config,
+  /// `null` removes a field and arrays like `Env` are replaced,
+  /// e.g. '{"Container":{"Image":"nginx:1.25"}}'
+  #[clap(
+    long,
+    conflicts_with_all = ["new_name", "image", "env", "volumes", "patch_file"]
+  )]
+  pub(crate) patch: Option<String>,
+  /// Read the JSON Merge Patch from this file, use `-` to read it from stdin
+  #[clap(
+    long,
+    value_name = "FILE",
+    conflicts_with_all = ["new_name", "image", "env", "volumes"]
+  )]
+  pub(crate) patch_file: Option<String>,
 }
 
 /// Convert CargoPatchOpts to CargoConfigUpdate
@@ -196,6 +294,15 @@ impl From<CargoPatchOpts> for CargoConfigUpdate {
 ///
 #[derive(Debug, Clone, Parser)]
 pub struct CargoExecOpts {
+  /// Keep stdin open and forward it to the command
+  #[clap(long, short)]
+  pub interactive: bool,
+  /// Allocate a pseudo-TTY
+  #[clap(long, short)]
+  pub tty: bool,
+  /// Replica where the command is executed when the cargo has many
+  #[clap(long)]
+  pub replica: Option<usize>,
   /// Name of cargo to execute command
   pub name: String,
   /// Command to execute
@@ -203,18 +310,6 @@ pub struct CargoExecOpts {
   pub command: Vec<String>,
 }
 
-/// Convert CargoExecOpts to CreateExecOptions
-impl From<CargoExecOpts> for CreateExecOptions {
-  fn from(val: CargoExecOpts) -> Self {
-    CreateExecOptions {
-      cmd: Some(val.command),
-      attach_stderr: Some(true),
-      attach_stdout: Some(true),
-      ..Default::default()
-    }
-  }
-}
-
 /// ## CargoHistoryOpts
 ///
 /// `nanocl cargo history` available options
@@ -254,7 +349,7 @@ pub struct CargoLogsOpts {
   /// If integer only return last n logs, if "all" returns all logs
   #[clap(short = 't')]
   pub tail: Option<String>,
-  /// Bool, if set include timestamp to ever log line
+  /// Prefix every log line with its timestamp
   #[clap(long = "timestamps")]
   pub timestamps: bool,
   /// Bool, if set open the log as stream
@@ -271,6 +366,103 @@ pub struct CargoListOpts {
   /// Only show cargo names
   #[clap(long, short)]
   pub quiet: bool,
+  /// Show the image of the cargoes
+  #[clap(long)]
+  pub show_image: bool,
+  /// Show the replication of the cargoes
+  #[clap(long)]
+  pub show_replicas: bool,
+  /// Show the hostname and domain name of the cargoes
+  #[clap(long)]
+  pub show_dns: bool,
+  /// Show the health of the cargoes with a healthcheck in their config,
+  /// read from their instances
+  #[clap(long)]
+  pub show_health: bool,
+  /// List in every namespace, the `--namespace` option is ignored
+  #[clap(long, short = 'A')]
+  pub all_namespaces: bool,
+  /// Print how many cargoes are running, stopped or failed
+  /// and their replicas instead of a row per cargo
+  #[clap(
+    long,
+    conflicts_with_all = [
+      "quiet", "show_image", "show_replicas", "show_dns", "show_health"
+    ]
+  )]
+  pub status_summary: bool,
+  /// Sort cargoes by the given column
+  #[clap(long, value_enum)]
+  pub sort: Option<CargoListSort>,
+  /// Reverse the sort order
+  #[clap(long, requires = "sort")]
+  pub reverse: bool,
+  #[clap(flatten)]
+  pub page: PaginationOpts,
+}
+
+/// ## CargoListSort
+///
+/// `nanocl cargo list --sort` available values
+///
+#[derive(Clone, Debug, ValueEnum)]
+pub enum CargoListSort {
+  Name,
+  Created,
+  /// Number of desired replicas
+  Replicas,
+  Image,
+}
+
+/// ## CargoRenameOpts
+///
+/// `nanocl cargo rename` available options
+///
+#[derive(Clone, Debug, Parser)]
+pub struct CargoRenameOpts {
+  /// Name of the cargo to rename
+  pub name: String,
+  /// New name of the cargo
+  pub new_name: String,
+}
+
+/// ## CargoScaleOpts
+///
+/// `nanocl cargo scale` available options
+///
+#[derive(Clone, Debug, Parser)]
+#[clap(group(
+  ArgGroup::new("replication")
+    .required(true)
+    .args(["replicas", "auto", "percentage"]),
+))]
+pub struct CargoScaleOpts {
+  /// Name of the cargo to scale
+  pub name: String,
+  /// Number of replicas
+  #[clap(long)]
+  pub replicas: Option<usize>,
+  /// Let the daemon decide the number of replicas
+  #[clap(long)]
+  pub auto: bool,
+  /// Run replicas on a percentage of the available nodes
+  #[clap(long)]
+  pub percentage: Option<f64>,
+}
+
+/// Convert CargoScaleOpts to ReplicationMode
+impl From<&CargoScaleOpts> for ReplicationMode {
+  fn from(opts: &CargoScaleOpts) -> Self {
+    match (opts.replicas, opts.percentage) {
+      (Some(number), _) => {
+        ReplicationMode::Static(ReplicationStatic { number })
+      }
+      (_, Some(percentage)) => {
+        ReplicationMode::Percentage(ReplicationPercentage { percentage })
+      }
+      _ => ReplicationMode::Auto,
+    }
+  }
 }
 
 /// ## CargoCommand
@@ -310,6 +502,10 @@ pub enum CargoCommand {
   Logs(CargoLogsOpts),
   /// Run a cargo
   Run(CargoRunOpts),
+  /// Change the replication of a cargo
+  Scale(CargoScaleOpts),
+  /// Rename a cargo keeping its config and replication
+  Rename(CargoRenameOpts),
 }
 
 /// ## CargoArg
@@ -330,16 +526,32 @@ pub struct CargoArg {
 ///
 /// A row of the cargo table
 ///
-#[derive(Tabled)]
+#[derive(Tabled, Serialize)]
+#[serde(rename_all = "PascalCase")]
 pub struct CargoRow {
   /// Name of the cargo
   pub(crate) name: String,
   /// Name of the namespace
   pub(crate) namespace: String,
-  /// Image of the cargo
-  pub(crate) image: String,
+  /// Image of the cargo, only set with `--show-image`
+  #[tabled(display_with = "display_optional")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) image: Option<String>,
+  /// Hostname and domain name of the cargo, only set with `--show-dns`
+  #[tabled(display_with = "display_optional")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) dns: Option<String>,
   /// Number of running instances
   pub(crate) instances: String,
+  /// `healthy`, `unhealthy`, `starting` or `none` when no instance report it,
+  /// `-` without healthcheck, only set with `--show-health`
+  #[tabled(display_with = "display_optional")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) health: Option<String>,
+  /// Replication of the cargo, only set with `--show-replicas`
+  #[tabled(display_with = "display_optional")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) replicas: Option<String>,
   /// Config version of the cargo
   pub(crate) config_version: String,
   /// When the cargo was created
@@ -348,6 +560,126 @@ pub struct CargoRow {
   pub(crate) updated_at: String,
 }
 
+/// ## CargoStatusSummary
+///
+/// Output of `nanocl cargo ls --status-summary`
+///
+#[derive(Debug, Default, Tabled, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CargoStatusSummary {
+  /// Number of cargoes
+  pub(crate) cargoes: usize,
+  /// Number of cargoes running their desired replicas
+  pub(crate) running: usize,
+  /// Number of cargoes without running instances
+  pub(crate) stopped: usize,
+  /// Number of cargoes with a failed instance or missing replicas
+  pub(crate) failed: usize,
+  /// Total number of desired replicas
+  pub(crate) desired_replicas: usize,
+  /// Total number of running replicas
+  pub(crate) running_replicas: usize,
+}
+
+/// ## Display optional
+///
+/// Display an optional column of a table
+///
+fn display_optional(value: &Option<String>) -> String {
+  value.clone().unwrap_or_default()
+}
+
+/// ## Display replication
+///
+/// Summarize the replication of a cargo eg: `3 by node`
+///
+/// ## Arguments
+///
+/// * [replication](Option<ReplicationMode>) The replication of the cargo
+///
+/// ## Return
+///
+/// * [String](String) The summary
+///
+pub fn display_replication(replication: &Option<ReplicationMode>) -> String {
+  match replication {
+    None => "-".to_owned(),
+    Some(ReplicationMode::Auto) => "auto".to_owned(),
+    Some(ReplicationMode::Unique) => "unique".to_owned(),
+    Some(ReplicationMode::UniqueByNode) => "unique by node".to_owned(),
+    Some(ReplicationMode::UniqueByNodeGroups { groups }) => {
+      format!("unique by groups {}", groups.join(","))
+    }
+    Some(ReplicationMode::UniqueByNodeNames { names }) => {
+      format!("unique by nodes {}", names.join(","))
+    }
+    Some(ReplicationMode::Static(ReplicationStatic { number })) => {
+      number.to_string()
+    }
+    Some(ReplicationMode::StaticByNodes(ReplicationStatic { number })) => {
+      format!("{number} by node")
+    }
+    Some(ReplicationMode::StaticByNodeGroups { groups, number }) => {
+      format!("{number} by groups {}", groups.join(","))
+    }
+    Some(ReplicationMode::StaticByNodeNames { names, number }) => {
+      format!("{number} by nodes {}", names.join(","))
+    }
+    Some(ReplicationMode::Percentage(ReplicationPercentage { percentage })) => {
+      format!("{percentage}% of nodes")
+    }
+  }
+}
+
+/// ## Desired replicas
+///
+/// Number of instances a cargo should run according to its replication.
+/// Replications depending on the nodes can't be known by the client,
+/// the instances created by the daemon are used instead.
+///
+/// ## Arguments
+///
+/// * [replication](Option<ReplicationMode>) The replication of the cargo
+/// * [instance_total](usize) The number of instances of the cargo
+///
+/// ## Return
+///
+/// * [usize](usize) The number of desired instances
+///
+pub fn desired_replicas(
+  replication: &Option<ReplicationMode>,
+  instance_total: usize,
+) -> usize {
+  match replication {
+    None | Some(ReplicationMode::Auto) | Some(ReplicationMode::Unique) => 1,
+    Some(ReplicationMode::Static(ReplicationStatic { number })) => *number,
+    Some(_) => instance_total,
+  }
+}
+
+/// ## Display dns
+///
+/// Display the hostname and the domain name of a cargo eg: `web.example.com`
+///
+/// ## Arguments
+///
+/// * [container](ContainerConfig) The container config of the cargo
+///
+/// ## Return
+///
+/// * [String](String) The hostname and the domain name
+///
+pub fn display_dns(container: &ContainerConfig) -> String {
+  match (&container.hostname, &container.domainname) {
+    (Some(hostname), Some(domainname)) if !domainname.is_empty() => {
+      format!("{hostname}.{domainname}")
+    }
+    (Some(hostname), _) => hostname.clone(),
+    (None, Some(domainname)) => domainname.clone(),
+    (None, None) => "-".to_owned(),
+  }
+}
+
 /// Convert CargoSummary to CargoRow
 impl From<CargoSummary> for CargoRow {
   fn from(cargo: CargoSummary) -> Self {
@@ -365,7 +697,10 @@ impl From<CargoSummary> for CargoRow {
     Self {
       name: cargo.name,
       namespace: cargo.namespace_name,
-      image: cargo.config.container.image.unwrap_or_default(),
+      image: None,
+      dns: None,
+      health: None,
+      replicas: None,
       config_version: cargo.config.version,
       instances: format!("{}/{}", cargo.instance_running, cargo.instance_total),
       created_at: format!("{created_at}"),
@@ -373,3 +708,99 @@ impl From<CargoSummary> for CargoRow {
     }
   }
 }
+
+/// ## CargoRevisionRow
+///
+/// A row of the cargo revision table
+///
+#[derive(Tabled, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CargoRevisionRow {
+  /// Key of the config
+  pub(crate) key: String,
+  /// Version of the config
+  pub(crate) version: String,
+  /// Image of the config
+  pub(crate) image: String,
+  /// When the config was created
+  pub(crate) created_at: String,
+}
+
+/// Convert CargoConfig to CargoRevisionRow
+impl From<CargoConfig> for CargoRevisionRow {
+  fn from(config: CargoConfig) -> Self {
+    let binding = chrono::Local::now();
+    let tz = binding.offset();
+    // Convert the created_at to the current timezone
+    let created_at = tz
+      .timestamp_opt(config.created_at.timestamp(), 0)
+      .unwrap()
+      .format("%Y-%m-%d %H:%M:%S");
+    Self {
+      key: config.key.to_string(),
+      version: config.version,
+      image: config.container.image.unwrap_or_default(),
+      created_at: format!("{created_at}"),
+    }
+  }
+}
+
+/// ## CargoStatsRow
+///
+/// A row of the cargo stats table
+///
+#[derive(Clone, Tabled, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CargoStatsRow {
+  /// Name of the instance usually `name` or `number-name`
+  pub(crate) instance: String,
+  /// Percentage of the host CPU used by the instance
+  pub(crate) cpu: String,
+  /// Memory used by the instance over its limit
+  pub(crate) memory: String,
+  /// Percentage of the memory limit used by the instance
+  pub(crate) memory_percent: String,
+  /// Bytes received and sent over every network of the instance
+  pub(crate) net_io: String,
+}
+
+/// Convert the instance name and its Stats to CargoStatsRow
+impl From<(String, Stats)> for CargoStatsRow {
+  fn from((instance, stats): (String, Stats)) -> Self {
+    let cpu_delta = stats
+      .cpu_stats
+      .cpu_usage
+      .total_usage
+      .saturating_sub(stats.precpu_stats.cpu_usage.total_usage);
+    let system_delta = stats
+      .cpu_stats
+      .system_cpu_usage
+      .unwrap_or_default()
+      .saturating_sub(stats.precpu_stats.system_cpu_usage.unwrap_or_default());
+    let cpus = stats.cpu_stats.online_cpus.unwrap_or(1);
+    let cpu = match system_delta {
+      0 => 0.0,
+      _ => cpu_delta as f64 / system_delta as f64 * cpus as f64 * 100.0,
+    };
+    let usage = stats.memory_stats.usage.unwrap_or_default();
+    let limit = stats.memory_stats.limit.unwrap_or_default();
+    let memory_percent = match limit {
+      0 => 0.0,
+      _ => usage as f64 / limit as f64 * 100.0,
+    };
+    let (rx, tx) = stats
+      .networks
+      .unwrap_or_default()
+      .values()
+      .fold((0, 0), |(rx, tx), network| {
+        (rx + network.rx_bytes, tx + network.tx_bytes)
+      });
+    Self {
+      instance,
+      cpu: format!("{cpu:.2}%"),
+      memory: format!("{} / {}", format_bytes(usage), format_bytes(limit)),
+      memory_percent: format!("{memory_percent:.2}%"),
+      net_io: format!("{} / {}", format_bytes(rx), format_bytes(tx)),
+    }
+  }
+}
@@ -2,8 +2,11 @@ use chrono::NaiveDateTime;
 use nanocld_client::stubs::cargo_image::ListCargoImagesOptions;
 use tabled::Tabled;
 use clap::{Parser, Subcommand};
+use serde::Serialize;
 use bollard_next::models::ImageSummary;
 
+use super::humanize::format_bytes;
+
 /// ## CargoImageRemoveOpts
 ///
 /// `nanocl cargo image remove` available options
@@ -117,7 +120,8 @@ pub struct CargoImageArg {
 ///
 /// A row of the cargo image table
 ///
-#[derive(Tabled)]
+#[derive(Tabled, Serialize)]
+#[serde(rename_all = "PascalCase")]
 pub struct CargoImageRow {
   /// Image ID
   pub(crate) id: String,
@@ -131,26 +135,6 @@ pub struct CargoImageRow {
   pub(crate) created: String,
 }
 
-/// ## Convert size
-///
-/// Convert size in bytes to human readable format
-///
-/// ## Arguments
-///
-/// - [size](i64) size in bytes
-///
-/// ## Return
-///
-/// - [String](String) human readable size
-///
-fn convert_size(size: i64) -> String {
-  if size >= 1_000_000_000 {
-    format!("{} GB", size / 1024 / 1024 / 1024)
-  } else {
-    format!("{} MB", size / 1024 / 1024)
-  }
-}
-
 /// Convert ImageSummary to CargoImageRow
 impl From<ImageSummary> for CargoImageRow {
   fn from(value: ImageSummary) -> Self {
@@ -161,7 +145,6 @@ impl From<ImageSummary> for CargoImageRow {
       .to_owned();
     let vals: Vec<_> = binding.split(':').collect();
     let id = value.id.replace("sha256:", "");
-    let id = id[0..12].to_owned();
     let created = NaiveDateTime::from_timestamp_opt(value.created, 0).unwrap();
     let created = created.format("%Y-%m-%d %H:%M:%S").to_string();
 
@@ -169,7 +152,7 @@ impl From<ImageSummary> for CargoImageRow {
       id,
       repositories: vals.first().unwrap_or(&"<none>").to_string(),
       tag: vals.get(1).unwrap_or(&"<none>").to_string(),
-      size: convert_size(value.size),
+      size: format_bytes(u64::try_from(value.size).unwrap_or_default()),
       created,
     }
   }
@@ -0,0 +1,14 @@
+use clap::Parser;
+
+/// ## CompleteOpts
+///
+/// `nanocl complete` available options
+/// Called by the shell hook printed by `nanocl completion --dynamic`
+///
+#[derive(Debug, Parser)]
+pub struct CompleteOpts {
+  /// Words of the command line after `nanocl`, the last one is the word
+  /// being completed
+  #[clap(trailing_var_arg = true, allow_hyphen_values = true)]
+  pub words: Vec<String>,
+}
@@ -4,6 +4,8 @@ use tabled::Tabled;
 use clap::{Parser, Subcommand};
 use serde::{Serialize, Deserialize};
 
+use super::DisplayFormat;
+
 /// ## ContextArg
 ///
 /// `nanocl context` available arguments
@@ -23,26 +25,54 @@ pub enum ContextCommand {
   /// List contexts
   #[clap(alias = "ls")]
   List,
+  /// Show current context
+  Current,
   /// Set current context
   Use {
     /// Context name
     name: String,
+    /// Default namespace of the context, an empty value unset it
+    #[clap(long)]
+    namespace: Option<String>,
   },
   /// Create a new context from a file
   From {
     /// Path to context file
     path: String,
   },
+  /// Print a context as yaml to share it
+  Export {
+    /// Context name
+    name: String,
+  },
+  /// Add a context exported with `nanocl context export`
+  Import {
+    /// Path to the exported context file
+    #[clap(long, short = 'f')]
+    file: String,
+    /// Replace the context if one with the same name exists
+    #[clap(long)]
+    overwrite: bool,
+  },
 }
 
 /// ## ContextEndpoint
 ///
 /// A context endpoint definition
 ///
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct ContextEndpoint {
   pub host: String,
+  /// Path to the client certificate to use with an https:// host
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub cert: Option<String>,
+  /// Path to the client private key to use with an https:// host
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub key: Option<String>,
+  /// Path to the certificate authority to use with an https:// host
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ca: Option<String>,
 }
 
 /// ## ContextMetaData
@@ -65,6 +95,9 @@ pub struct Context {
   pub name: String,
   pub meta_data: ContextMetaData,
   pub endpoints: HashMap<String, ContextEndpoint>,
+  /// Default display format of the context
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub display_format: Option<DisplayFormat>,
 }
 
 /// Default value for a Context
@@ -82,10 +115,12 @@ impl std::default::Default for Context {
           ContextEndpoint {
             host: std::env::var("NANOCL_HOST")
               .unwrap_or("unix:///run/nanocl/nanocl.sock".into()),
+            ..Default::default()
           },
         );
         map
       },
+      display_format: None,
     }
   }
 }
@@ -94,7 +129,8 @@ impl std::default::Default for Context {
 ///
 /// A row of the context table
 ///
-#[derive(Clone, Debug, Tabled)]
+#[derive(Clone, Debug, Tabled, Serialize)]
+#[serde(rename_all = "PascalCase")]
 pub struct ContextRow {
   /// Name of the context
   pub name: String,
@@ -117,7 +153,7 @@ impl From<Context> for ContextRow {
       name: context.name,
       description: context.meta_data.description,
       endpoint: endpoint.host.clone(),
-      current: "⨯".into(),
+      current: "".into(),
     }
   }
 }
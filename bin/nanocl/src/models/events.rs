@@ -0,0 +1,68 @@
+use clap::{Parser, ValueEnum};
+use chrono::{DateTime, Utc};
+
+/// ## EventsFormat
+///
+/// `nanocl events` available output formats
+///
+#[derive(Default, Clone, Debug, ValueEnum)]
+pub enum EventsFormat {
+  /// Human readable output using the display format
+  #[default]
+  Human,
+  /// One compact json object per line
+  Jsonl,
+}
+
+/// ## EventsOpts
+///
+/// `nanocl events` available options
+///
+#[derive(Debug, Parser)]
+pub struct EventsOpts {
+  /// Output format of the events
+  #[clap(long, value_enum, default_value_t)]
+  pub format: EventsFormat,
+  /// Exit when the event stream ends instead of resubscribing
+  #[clap(long)]
+  pub no_reconnect: bool,
+  /// Only show events received after this time,
+  /// a RFC3339 timestamp in the future
+  #[clap(long, value_parser = parse_time_bound)]
+  pub since: Option<DateTime<Utc>>,
+  /// Stop when this time is reached,
+  /// a RFC3339 timestamp in the future
+  #[clap(long, value_parser = parse_time_bound)]
+  pub until: Option<DateTime<Utc>>,
+}
+
+/// ## Parse time bound
+///
+/// Parse a RFC3339 timestamp bounding the live event stream.
+/// Events carry no timestamp and are compared to the time they are received,
+/// so a time in the past or a relative duration before now would never
+/// filter anything and is rejected.
+///
+/// ## Arguments
+///
+/// * [value](str) The value to parse
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](DateTime<Utc>) The parsed time
+///   * [Err](String) The value isn't a timestamp in the future
+///
+pub fn parse_time_bound(value: &str) -> Result<DateTime<Utc>, String> {
+  let time = DateTime::parse_from_rfc3339(value)
+    .map_err(|_| {
+      format!("invalid time `{value}` expected a RFC3339 timestamp")
+    })?
+    .with_timezone(&Utc);
+  if time <= Utc::now() {
+    return Err(format!(
+      "time `{value}` is in the past, events are only received live"
+    ));
+  }
+  Ok(time)
+}
@@ -0,0 +1,69 @@
+use clap::Parser;
+use serde::Serialize;
+
+/// ## InfoOpts
+///
+/// `nanocl info` available options
+///
+#[derive(Debug, Parser)]
+pub struct InfoOpts {
+  /// Refresh the information at the given interval like `5s` until Ctrl-C
+  #[clap(
+    long,
+    short,
+    value_name = "INTERVAL",
+    num_args = 0..=1,
+    default_missing_value = "2s",
+    value_parser = super::duration::parse_interval,
+  )]
+  pub watch: Option<std::time::Duration>,
+}
+
+/// ## InfoOutput
+///
+/// Information of the host printed by `nanocl info` with `--output`.
+/// The fields are serialized with the same names and types across releases
+/// so they can be scraped by monitoring tools, unknown values are `null`.
+///
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct InfoOutput {
+  /// Version of the daemon
+  pub version: String,
+  /// Commit of the daemon
+  pub commit_id: String,
+  /// Release channel of the daemon
+  pub channel: String,
+  /// Hostname of the node the cli is connected to
+  pub hostname: String,
+  /// Address advertised to the other nodes
+  pub advertise_addr: String,
+  /// Gateway address of the host
+  pub host_gateway: String,
+  /// Number of nodes of the cluster
+  pub nodes: usize,
+  /// Number of namespaces
+  pub namespaces: usize,
+  /// Number of cargoes in every namespace
+  pub cargoes: i64,
+  /// Architecture of the host
+  pub arch: Option<String>,
+  /// Operating system of the host
+  pub os: Option<String>,
+  /// Kernel version of the host
+  pub kernel: Option<String>,
+  /// Number of cpus of the host
+  pub cpus: Option<i64>,
+  /// Total memory of the host in bytes
+  pub memory: Option<i64>,
+  /// Version of docker
+  pub docker_version: Option<String>,
+  /// Storage driver of docker
+  pub storage_driver: Option<String>,
+  /// Number of containers
+  pub containers: Option<i64>,
+  /// Number of running containers
+  pub containers_running: Option<i64>,
+  /// Number of images
+  pub images: Option<i64>,
+}
@@ -1,5 +1,6 @@
 use serde::{Serialize, Deserialize};
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 
 mod namespace;
 mod cargo;
@@ -15,6 +16,15 @@ mod uninstall;
 mod upgrade;
 mod node;
 mod context;
+mod events;
+mod info;
+mod schema;
+mod completion;
+// Shared with the build script which can't reach the utils
+#[path = "../utils/duration.rs"]
+pub mod duration;
+#[path = "../utils/humanize.rs"]
+pub mod humanize;
 
 pub use system::*;
 pub use context::*;
@@ -30,14 +40,81 @@ pub use install::*;
 pub use uninstall::*;
 pub use upgrade::*;
 pub use node::*;
+pub use events::*;
+pub use info::*;
+pub use schema::*;
+pub use completion::*;
 
 /// A self-sufficient hybrid-cloud manager
 #[derive(Debug, Parser)]
 #[clap(about, version, name = "nanocl")]
 pub struct Cli {
   /// Nanocld host default: unix://run/nanocl/nanocl.sock
+  /// supported schemes: unix://, tcp://, http://, https://
   #[clap(long, short = 'H')]
   pub host: Option<String>,
+  /// Path of the user config file, take precedence over the `NANOCL_CONFIG`
+  /// env variable (default: $HOME/.nanocl/conf.yml)
+  #[clap(long)]
+  pub config: Option<String>,
+  /// Context to use for this command only, `--host` take precedence over it
+  #[clap(long)]
+  pub context: Option<String>,
+  /// Path to the client certificate to use with an https:// host
+  #[clap(long, requires = "key")]
+  pub cert: Option<String>,
+  /// Path to the client private key to use with an https:// host
+  #[clap(long, requires = "cert")]
+  pub key: Option<String>,
+  /// Path to the certificate authority to use with an https:// host
+  #[clap(long)]
+  pub ca: Option<String>,
+  /// Output format, the `--display` option of a command take precedence
+  /// over it and it take precedence over the `NANOCL_OUTPUT` env variable
+  #[clap(long, short = 'o')]
+  pub output: Option<DisplayFormat>,
+  /// Indent json output, default when stdout is a terminal
+  #[clap(long, conflicts_with = "compact")]
+  pub pretty: bool,
+  /// Print json output on a single line, default when stdout is piped
+  #[clap(long)]
+  pub compact: bool,
+  /// Timeout of requests to the daemon like `30s` or `2m`, 0 to disable
+  /// (default: 30s)
+  #[clap(long, value_parser = duration::parse_duration)]
+  pub timeout: Option<std::time::Duration>,
+  /// Disable colors, also disabled when the `NO_COLOR` env variable is set
+  #[clap(long)]
+  pub no_color: bool,
+  /// Draw tables with borders, unicode fallback to ascii without an UTF-8 locale
+  #[clap(long)]
+  pub table_style: Option<TableStyle>,
+  /// Do not print confirmations like the key of a created cargo or vm
+  #[clap(long, short = 'q')]
+  pub quiet: bool,
+  /// Print full values in tables instead of truncating them to the terminal width
+  #[clap(long)]
+  pub no_truncate: bool,
+  /// Print full ids like uuids and image ids in tables
+  /// instead of shortening them, other values are still truncated
+  #[clap(long)]
+  pub no_trunc: bool,
+  /// Maximum number of concurrent requests of commands acting on many
+  /// elements like `--all-namespaces`, bulk `rm` or `state apply`
+  #[clap(
+    long,
+    default_value_t = 8,
+    value_parser = clap::value_parser!(u16).range(1..),
+  )]
+  pub concurrency: u16,
+  /// Print details like the namespace a command is using and each request
+  /// with its response status, repeat it (-vv) to print the json bodies
+  /// with their secrets redacted
+  #[clap(long, short = 'v', action = clap::ArgAction::Count)]
+  pub verbose: u8,
+  /// Format of the error printed on stderr when a command fails
+  #[clap(long, value_enum, default_value_t)]
+  pub error_format: ErrorFormat,
   /// Commands
   #[clap(subcommand)]
   pub command: Command,
@@ -60,15 +137,15 @@ pub enum Command {
   /// Manage nodes (experimental)
   Node(NodeArg),
   /// Watch daemon events
-  Events,
+  Events(EventsOpts),
   /// Define, Run, or Remove Cargo or Virtual Machines
   State(StateArg),
   /// Manage contexts
   Context(ContextArg),
   /// Show nanocl host information
-  Info,
+  Info(InfoOpts),
   /// Show nanocl version information
-  Version,
+  Version(VersionArg),
   /// Install nanocl components
   Install(InstallOpts),
   /// Uninstall nanocl components
@@ -79,12 +156,22 @@ pub enum Command {
   Ps(ProcessOpts),
   /// Manage system
   System(SystemArg),
-  // TODO: shell completion
-  // Completion {
-  //   /// Shell to generate completion for
-  //   #[clap(arg_enum)]
-  //   shell: Shell,
-  // },
+  /// Print the json schema of a config for editor integration
+  #[clap(hide = true)]
+  Schema(SchemaOpts),
+  /// Generate shell completion script
+  Completion {
+    /// Shell to generate completion for
+    #[clap(value_enum)]
+    shell: Shell,
+    /// Also complete namespace, cargo, vm, resource and node names
+    /// by asking the daemon
+    #[clap(long)]
+    dynamic: bool,
+  },
+  /// Print the completion candidates of a command line
+  #[clap(hide = true)]
+  Complete(CompleteOpts),
 }
 
 /// ## DisplayFormat
@@ -98,6 +185,53 @@ pub enum DisplayFormat {
   Yaml,
   Toml,
   Json,
+  /// `KEY=value` lines to source in a shell, nested keys are joined
+  /// with underscores in uppercase and array items are indexed from 0
+  Env,
+  /// Tables with every optional column, other outputs are not affected
+  Wide,
+}
+
+/// ## PaginationOpts
+///
+/// `--limit` and `--offset` options of the list commands,
+/// applied after the rows are filtered and sorted
+///
+#[derive(Clone, Debug, Default, Args)]
+pub struct PaginationOpts {
+  /// Show at most this number of rows
+  #[clap(long)]
+  pub limit: Option<usize>,
+  /// Skip this number of rows before showing them
+  #[clap(long, default_value_t = 0)]
+  pub offset: usize,
+}
+
+/// ## TableStyle
+///
+/// `nanocl` available table styles, tables are drawn without borders by default
+///
+#[derive(Clone, Debug, ValueEnum)]
+pub enum TableStyle {
+  /// Box-drawing characters
+  Unicode,
+  /// Ascii characters only
+  Ascii,
+  /// Pipe-delimited markdown table
+  Markdown,
+}
+
+/// ## ErrorFormat
+///
+/// `nanocl` available error formats, `human` by default
+///
+#[derive(Default, Clone, Debug, ValueEnum)]
+pub enum ErrorFormat {
+  /// Human readable message
+  #[default]
+  Human,
+  /// `{"error": {...}, "code": N}` with a stable exit code
+  Json,
 }
 
 /// Convert DisplayFormat to String
@@ -107,6 +241,8 @@ impl ToString for DisplayFormat {
       Self::Yaml => "yaml",
       Self::Toml => "toml",
       Self::Json => "json",
+      Self::Env => "env",
+      Self::Wide => "wide",
     }
     .to_string()
   }
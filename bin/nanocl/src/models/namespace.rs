@@ -1,7 +1,8 @@
 use tabled::Tabled;
 use clap::{Parser, Subcommand};
+use serde::Serialize;
 
-use nanocld_client::stubs::namespace::NamespaceSummary;
+use nanocld_client::stubs::namespace::{NamespaceSummary, NamespaceInspect};
 
 /// ## NamespaceCommand
 ///
@@ -39,8 +40,11 @@ pub struct NamespaceListOpts {
 #[derive(Debug, Parser)]
 pub struct NamespaceDeleteOpts {
   /// skip confirmation
-  #[clap(short = 'y')]
+  #[clap(long = "yes", short = 'y')]
   pub skip_confirm: bool,
+  /// remove the cargoes, virtual machines and resources of the namespaces
+  #[clap(long)]
+  pub cascade: bool,
   /// list of namespace names to delete
   pub names: Vec<String>,
 }
@@ -70,7 +74,8 @@ pub struct NamespaceOpts {
 ///
 /// A row of the namespace table
 ///
-#[derive(Tabled)]
+#[derive(Tabled, Serialize)]
+#[serde(rename_all = "PascalCase")]
 pub struct NamespaceRow {
   /// Name of the namespace
   pub(crate) name: String,
@@ -82,6 +87,40 @@ pub struct NamespaceRow {
   pub(crate) gateway: String,
 }
 
+/// ## NamespaceUsage
+///
+/// Aggregated usage of a namespace
+///
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct NamespaceUsage {
+  /// Number of cargoes
+  pub(crate) cargoes: usize,
+  /// Number of virtual machines
+  pub(crate) vms: usize,
+  /// Number of resources targeting the cargoes or virtual machines
+  pub(crate) resources: usize,
+  /// Total number of instances
+  pub(crate) instances: usize,
+  /// Total allocated memory in MB
+  pub(crate) memory: u64,
+  /// Total allocated cpus
+  pub(crate) cpus: f64,
+}
+
+/// ## NamespaceInspectOutput
+///
+/// Output of `nanocl namespace inspect`
+///
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct NamespaceInspectOutput {
+  #[serde(flatten)]
+  pub(crate) namespace: NamespaceInspect,
+  /// Aggregated usage of the namespace
+  pub(crate) usage: NamespaceUsage,
+}
+
 /// Convert a NamespaceSummary to a NamespaceRow
 impl From<NamespaceSummary> for NamespaceRow {
   fn from(item: NamespaceSummary) -> Self {
@@ -1,5 +1,6 @@
 use tabled::Tabled;
 use clap::{Parser, Subcommand};
+use serde::Serialize;
 use nanocld_client::stubs::node::Node;
 
 /// ## NodeArg
@@ -21,25 +22,102 @@ pub struct NodeArg {
 pub enum NodeCommand {
   /// List nodes
   #[clap(alias = "ls")]
-  List,
+  List(NodeListOpts),
+  /// Mark a node unschedulable, no new workloads are created on it
+  Cordon(NodeCordonOpts),
+  /// Mark a node schedulable again
+  Uncordon(NodeCordonOpts),
+  /// Cordon a node and move its vms and cargo instances to other nodes
+  Drain(NodeDrainOpts),
+}
+
+/// ## NodeListOpts
+///
+/// `nanocl node list` available options
+///
+#[derive(Debug, Parser)]
+pub struct NodeListOpts {
+  /// Only show node names
+  #[clap(long, short)]
+  pub quiet: bool,
+}
+
+/// ## NodeCordonOpts
+///
+/// `nanocl node cordon` and `nanocl node uncordon` available options
+///
+#[derive(Debug, Parser)]
+pub struct NodeCordonOpts {
+  /// Name of the node
+  pub name: String,
+}
+
+/// ## NodeDrainOpts
+///
+/// `nanocl node drain` available options
+///
+#[derive(Debug, Parser)]
+pub struct NodeDrainOpts {
+  /// Name of the node
+  pub name: String,
+  /// Move every workload to this node instead of spreading them
+  /// over the schedulable nodes
+  #[clap(long)]
+  pub to_node: Option<String>,
+  /// Skip confirmation
+  #[clap(long = "yes", short = 'y')]
+  pub skip_confirm: bool,
 }
 
 /// ## NodeRow
 ///
 /// A row of the node table
 ///
-#[derive(Debug, Tabled)]
+#[derive(Debug, Tabled, Serialize)]
+#[serde(rename_all = "PascalCase")]
 pub struct NodeRow {
   pub name: String,
   pub ip_address: String,
+  /// `local` for the node the cli is connected to, `peer` otherwise
+  pub role: String,
+  /// `reachable` or `UNREACHABLE` when the node didn't answer a ping
+  pub status: String,
+  /// `enabled` or `cordoned` when no new workloads are created on the node
+  pub scheduling: String,
 }
 
-/// Convert a Node to a NodeRow
-impl From<Node> for NodeRow {
-  fn from(node: Node) -> Self {
+impl NodeRow {
+  /// Create a node row from a node, its role and whether it's reachable
+  pub fn new(node: Node, role: &str, reachable: bool) -> Self {
     Self {
       name: node.name,
       ip_address: node.ip_address,
+      role: role.to_owned(),
+      status: match reachable {
+        true => "reachable".to_owned(),
+        false => "UNREACHABLE".to_owned(),
+      },
+      scheduling: match node.unschedulable {
+        true => "cordoned".to_owned(),
+        false => "enabled".to_owned(),
+      },
     }
   }
 }
+
+/// ## NodeDrainRow
+///
+/// A workload moved off a node by `nanocl node drain`
+///
+#[derive(Debug, Tabled, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct NodeDrainRow {
+  /// `vm` or `cargo`
+  pub kind: String,
+  pub namespace: String,
+  pub name: String,
+  /// Number of instances moved, always 1 for a vm
+  pub instances: usize,
+  /// Node the workload was moved to
+  pub to_node: String,
+}
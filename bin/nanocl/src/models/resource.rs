@@ -1,10 +1,11 @@
 use tabled::Tabled;
 use chrono::TimeZone;
 use clap::{Parser, Subcommand};
+use serde::Serialize;
 
-use nanocld_client::stubs::resource::Resource;
+use nanocld_client::stubs::resource::{Resource, ResourceConfig};
 
-use super::DisplayFormat;
+use super::{DisplayFormat, PaginationOpts};
 
 /// ## ResourceCommand
 ///
@@ -12,6 +13,8 @@ use super::DisplayFormat;
 ///
 #[derive(Debug, Subcommand)]
 pub enum ResourceCommand {
+  /// Create or update the resources declared in a file
+  Apply(ResourceApplyOpts),
   /// Remove existing resource
   #[clap(alias("rm"))]
   Remove(ResourceRemoveOpts),
@@ -24,6 +27,8 @@ pub enum ResourceCommand {
   History(ResourceHistoryOpts),
   /// Revert a resource to a specific history
   Revert(ResourceRevertOpts),
+  /// Show the changes and revert a resource to a previous revision
+  Rollback(ResourceRollbackOpts),
 }
 
 /// ## ResourceListOpts
@@ -35,6 +40,14 @@ pub struct ResourceListOpts {
   /// Show only resource names
   #[clap(long, short)]
   pub quiet: bool,
+  /// Show only resources of the given kind (e.g: `ProxyRule`)
+  #[clap(long)]
+  pub kind: Option<String>,
+  /// Show only resources whose name match this glob pattern (e.g: `web-*`)
+  #[clap(long)]
+  pub name: Option<String>,
+  #[clap(flatten)]
+  pub page: PaginationOpts,
 }
 
 /// ## ResourceArg
@@ -52,7 +65,8 @@ pub struct ResourceArg {
 ///
 /// A row of the resource table
 ///
-#[derive(Debug, Tabled)]
+#[derive(Debug, Tabled, Serialize)]
+#[serde(rename_all = "PascalCase")]
 pub struct ResourceRow {
   pub name: String,
   pub kind: String,
@@ -86,6 +100,64 @@ impl From<Resource> for ResourceRow {
   }
 }
 
+/// ## ResourceRevisionRow
+///
+/// A row of the resource revision table
+///
+#[derive(Debug, Tabled, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ResourceRevisionRow {
+  /// Key of the config
+  pub key: String,
+  /// Version of the config
+  pub version: String,
+  /// `*` for the config currently used by the resource
+  pub current: String,
+  /// When the config was created
+  pub created_at: String,
+}
+
+impl ResourceRevisionRow {
+  /// ## New
+  ///
+  /// Create a row of a revision of a resource
+  ///
+  /// ## Arguments
+  ///
+  /// * [config](ResourceConfig) The config of the revision
+  /// * [current](bool) True if the resource currently uses this config
+  ///
+  pub fn new(config: ResourceConfig, current: bool) -> Self {
+    let binding = chrono::Local::now();
+    let tz = binding.offset();
+    // Convert the created_at to the current timezone
+    let created_at = tz
+      .timestamp_opt(config.created_at.timestamp(), 0)
+      .unwrap()
+      .format("%Y-%m-%d %H:%M:%S");
+    Self {
+      key: config.key.to_string(),
+      version: config.version,
+      current: if current { "*" } else { "" }.to_owned(),
+      created_at: format!("{created_at}"),
+    }
+  }
+}
+
+/// ## ResourceApplyOpts
+///
+/// `nanocl resource apply` available options
+///
+#[derive(Debug, Parser)]
+pub struct ResourceApplyOpts {
+  /// Skip confirmation
+  #[clap(short = 'y')]
+  pub skip_confirm: bool,
+  /// Path or Url to a file declaring `Resources` like a Statefile, use `-` to read it from stdin
+  #[clap(long, short = 'f')]
+  pub file: String,
+}
+
 /// ## ResourceRemoveOpts
 ///
 /// `nanocl resource remove` available options
@@ -95,7 +167,11 @@ pub struct ResourceRemoveOpts {
   /// Skip confirmation
   #[clap(short = 'y')]
   pub skip_confirm: bool,
+  /// Remove the resources declared in this file instead of the given names
+  #[clap(long, short = 'f', conflicts_with = "names")]
+  pub file: Option<String>,
   /// The names of the resources to delete
+  #[clap(required_unless_present = "file")]
   pub names: Vec<String>,
 }
 
@@ -108,7 +184,10 @@ pub struct ResourceInspectOpts {
   /// Display format
   #[clap(long)]
   pub display: Option<DisplayFormat>,
-  /// The name of the resource to inspect
+  /// Print the JSON schema of the resource kind given as name
+  #[clap(long, alias = "schema")]
+  pub kind_schema: bool,
+  /// The name of the resource to inspect, or of the kind with `--kind-schema`
   pub name: String,
 }
 
@@ -133,3 +212,19 @@ pub struct ResourceRevertOpts {
   /// The key of the history to revert to
   pub key: String,
 }
+
+/// ## ResourceRollbackOpts
+///
+/// `nanocl resource rollback` available options
+///
+#[derive(Debug, Parser)]
+pub struct ResourceRollbackOpts {
+  /// Skip confirmation
+  #[clap(short = 'y')]
+  pub skip_confirm: bool,
+  /// The key or the version of the revision to roll back to
+  #[clap(long)]
+  pub to: String,
+  /// The name of the resource to roll back
+  pub name: String,
+}
@@ -0,0 +1,26 @@
+use clap::{Parser, ValueEnum};
+
+/// ## SchemaKind
+///
+/// Config types a json schema can be exported for
+///
+#[derive(Clone, Debug, ValueEnum)]
+pub enum SchemaKind {
+  /// The config of a cargo in a Statefile or `nanocl cargo patch --from-file`
+  Cargo,
+  /// The config of a virtual machine
+  Vm,
+  /// The config of a resource
+  Resource,
+}
+
+/// ## SchemaOpts
+///
+/// `nanocl schema` available options
+///
+#[derive(Debug, Parser)]
+pub struct SchemaOpts {
+  /// Kind of config to export the json schema for
+  #[clap(value_enum)]
+  pub kind: SchemaKind,
+}
@@ -1,5 +1,8 @@
+use std::collections::BTreeMap;
+
 use clap::{Parser, Subcommand};
 use nanocld_client::stubs::state::StateMeta;
+use nanocld_client::stubs::cargo_config::CargoConfigPartial;
 use serde::{Serialize, Deserialize};
 
 use super::DisplayFormat;
@@ -35,9 +38,10 @@ pub struct StateBuildArg {
 ///
 #[derive(Debug, Parser)]
 pub struct StateApplyOpts {
-  /// Path or Url to the Statefile
+  /// Path or Url to the Statefile, use `-` to read it from stdin.
+  /// Can be repeated and directories are walked for yml, yaml, json and toml files
   #[clap(long, short = 's')]
-  pub state_location: Option<String>,
+  pub state_location: Vec<String>,
   /// Force pulling images even if they exist
   #[clap(long, short = 'p')]
   pub force_pull: bool,
@@ -47,28 +51,149 @@ pub struct StateApplyOpts {
   /// Skip the confirmation prompt
   #[clap(long = "yes", short = 'y')]
   pub skip_confirm: bool,
+  /// Print what would change without applying anything
+  #[clap(long)]
+  pub dry_run: bool,
+  /// Restart the existing cargoes whose config changed
+  /// so their running containers use the new config
+  #[clap(long, conflicts_with = "dry_run")]
+  pub reload: bool,
+  /// Do not expand `${VAR}` environment variables in the Statefile
+  #[clap(long)]
+  pub no_env_subst: bool,
+  /// Delete the cargoes and virtual machines of the namespaces of the Statefiles
+  /// and the resources targeting them that are not in the Statefiles
+  #[clap(long)]
+  pub prune: bool,
+  /// Only prune the cargoes and virtual machines with this label like `app=web`,
+  /// resources have no labels so they are not pruned with it
+  #[clap(long, requires = "prune", value_parser = parse_key_value)]
+  pub prune_label: Option<(String, String)>,
+  /// Print a single summary line instead of the progress of each element,
+  /// errors are still printed. Also enabled by the global `--quiet` flag
+  #[clap(long, short = 'q', conflicts_with = "dry_run")]
+  pub quiet: bool,
+  /// Wait for the cargoes and virtual machines others depend on
+  /// to be running and healthy before applying their dependents
+  #[clap(long, conflicts_with = "dry_run")]
+  pub wait: bool,
+  /// Maximum time to wait for each dependency like `60s` or `5m`
+  #[clap(
+    long,
+    default_value = "60s",
+    value_parser = super::duration::parse_duration,
+    requires = "wait"
+  )]
+  pub wait_timeout: std::time::Duration,
   /// Additional arguments to pass to the file
   #[clap(last = true, raw = true)]
   pub args: Vec<String>,
 }
 
+/// ## Parse key value
+///
+/// Parse a label or a variable given as `key=value`
+///
+/// ## Arguments
+///
+/// * [value](str) The pair to parse
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok]((String, String)) The key and the value
+///   * [Err](String) The value is not a `key=value` pair
+///
+fn parse_key_value(value: &str) -> Result<(String, String), String> {
+  match value.split_once('=') {
+    Some((key, value)) if !key.is_empty() => {
+      Ok((key.to_owned(), value.to_owned()))
+    }
+    _ => Err(format!("invalid `{value}`, expected `key=value`")),
+  }
+}
+
 /// ## StateRemoveOpts
 ///
 /// `nanocl state rm` available options
 ///
 #[derive(Debug, Parser)]
 pub struct StateRemoveOpts {
-  /// Path or Url to the Statefile
+  /// Path or Url to the Statefile, use `-` to read it from stdin
   #[clap(long, short = 's')]
   pub state_location: Option<String>,
   /// Skip the confirmation prompt
   #[clap(long = "yes", short = 'y')]
   pub skip_confirm: bool,
+  /// Print what would change without applying anything
+  #[clap(long)]
+  pub dry_run: bool,
+  /// Do not expand `${VAR}` environment variables in the Statefile
+  #[clap(long)]
+  pub no_env_subst: bool,
   /// Additional arguments to pass to the file
   #[clap(last = true, raw = true)]
   pub args: Vec<String>,
 }
 
+/// ## StateLogsOpts
+///
+/// `nanocl state logs` available options
+///
+#[derive(Debug, Parser)]
+pub struct StateLogsOpts {
+  /// Path or Url to the Statefile, use `-` to read it from stdin
+  #[clap(long, short = 's')]
+  pub state_location: Option<String>,
+  /// If integer only return last n logs of each element, if "all" returns all logs
+  #[clap(long, short = 't')]
+  pub tail: Option<String>,
+  /// Follow the logs output
+  #[clap(long, short = 'f')]
+  pub follow: bool,
+  /// Do not expand `${VAR}` environment variables in the Statefile
+  #[clap(long)]
+  pub no_env_subst: bool,
+  /// Additional arguments to pass to the file
+  #[clap(last = true, raw = true)]
+  pub args: Vec<String>,
+}
+
+/// ## StateDiffOpts
+///
+/// `nanocl state diff` available options
+///
+#[derive(Debug, Parser)]
+pub struct StateDiffOpts {
+  /// Path or Url to the Statefile, use `-` to read it from stdin.
+  /// Can be repeated and directories are walked for yml, yaml, json and toml files
+  #[clap(long, short = 's')]
+  pub state_location: Vec<String>,
+  /// Do not expand `${VAR}` environment variables in the Statefile
+  #[clap(long)]
+  pub no_env_subst: bool,
+  /// Additional arguments to pass to the file
+  #[clap(last = true, raw = true)]
+  pub args: Vec<String>,
+}
+
+/// ## StateTemplateOpts
+///
+/// `nanocl state template` available options
+///
+#[derive(Debug, Parser)]
+pub struct StateTemplateOpts {
+  /// Path to the Statefile to render, use `-` to read it from stdin
+  #[clap(long, short = 'f')]
+  pub file: String,
+  /// Set a `${VAR}` variable like `TAG=1.25`, can be repeated
+  #[clap(long = "set", value_parser = parse_key_value)]
+  pub set: Vec<(String, String)>,
+  /// Yaml file of variables, can be repeated and the last one wins
+  #[clap(long)]
+  pub values: Vec<String>,
+}
+
 /// ## StateCommand
 ///
 /// `nanocl state` available commands
@@ -80,6 +205,14 @@ pub enum StateCommand {
   /// Remove elements from a Statefile
   #[clap(alias("rm"))]
   Remove(StateRemoveOpts),
+  /// Show the logs of the cargoes and virtual machines of a Statefile
+  Logs(StateLogsOpts),
+  /// Show the fields that differ between a Statefile and the cluster,
+  /// exit with 1 when there are differences
+  Diff(StateDiffOpts),
+  /// Print a Statefile with its `${VAR}` variables substituted
+  /// from `--set`, `--values` and the environment, without applying it
+  Template(StateTemplateOpts),
 }
 
 /// ## StateArg
@@ -110,3 +243,100 @@ where
   /// Data of the Statefile (serialized)
   pub data: T,
 }
+
+/// ## StatePrepared
+///
+/// A Statefile with its data injected ready to be applied
+///
+#[derive(Clone, Debug)]
+pub struct StatePrepared<T> {
+  /// Path or Url of the Statefile
+  pub location: String,
+  /// Format of the Statefile
+  pub format: DisplayFormat,
+  /// Metadata of the Statefile
+  pub meta: StateMeta,
+  /// Namespace of the cargoes of the Statefile
+  pub namespace: String,
+  /// Cargoes of the Statefile with their hooks applied
+  pub cargoes: Vec<CargoConfigPartial>,
+  /// Dependencies of the cargoes and virtual machines by name
+  pub depends_on: BTreeMap<String, Vec<String>>,
+  /// Names of the cargoes and virtual machines to apply one after the other,
+  /// empty when nothing declare dependencies
+  pub layers: Vec<Vec<String>>,
+  /// Data of the Statefile with `Args`, `Envs`... injected
+  pub data: T,
+}
+
+/// ## StatePlanItem
+///
+/// An element of a Statefile that would be affected by `--dry-run`
+///
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StatePlanItem {
+  /// Kind of the element (Cargo, VirtualMachine, Resource)
+  pub kind: String,
+  /// Name of the element
+  pub name: String,
+  /// Fields that differ from the current state when updated
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub fields: Vec<String>,
+}
+
+/// ## StateApplySummary
+///
+/// What `nanocl state apply --quiet` did, printed as a single line
+///
+#[derive(Debug, Clone, Default)]
+pub struct StateApplySummary {
+  /// Elements created
+  pub created: usize,
+  /// Existing elements applied again
+  pub updated: usize,
+  /// Elements left as they were
+  pub unchanged: usize,
+  /// Elements that failed to be applied or pruned
+  pub failed: usize,
+  /// Cargoes restarted with `--reload`
+  pub restarted: usize,
+  /// Elements deleted with `--prune`
+  pub pruned: usize,
+}
+
+impl std::fmt::Display for StateApplySummary {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "created {}, updated {}, unchanged {}",
+      self.created, self.updated, self.unchanged
+    )?;
+    let optionals = [
+      ("failed", self.failed),
+      ("restarted", self.restarted),
+      ("pruned", self.pruned),
+    ];
+    for (label, count) in optionals {
+      if count > 0 {
+        write!(f, ", {label} {count}")?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// ## StatePlan
+///
+/// What `nanocl state apply` or `nanocl state rm` would do with `--dry-run`
+///
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StatePlan {
+  /// Elements that would be created
+  pub create: Vec<StatePlanItem>,
+  /// Elements that would be updated
+  pub update: Vec<StatePlanItem>,
+  /// Elements that would be deleted
+  pub delete: Vec<StatePlanItem>,
+}
@@ -1,11 +1,15 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
 use tabled::Tabled;
 use chrono::TimeZone;
 
-use nanocld_client::stubs::system::ProccessQuery;
+use nanocld_client::stubs::system::{ProccessQuery, DiskUsageItem};
 use nanocld_client::stubs::node::NodeContainerSummary;
 use nanocld_client::stubs::http_metric::HttpMetricListQuery;
 
+use super::PaginationOpts;
+use super::humanize::format_bytes;
+
 /// ## SystemArg
 ///
 /// `nanocl system` available arguments
@@ -25,6 +29,8 @@ pub struct SystemArg {
 pub enum SystemCommand {
   /// System HTTP metrics information
   Http(SystemHttpArg),
+  /// Show disk usage of cargo images, vm images and volumes
+  Df,
 }
 
 /// ## SystemHttpArg
@@ -76,7 +82,8 @@ impl From<SystemHttpLogsOpts> for HttpMetricListQuery {
 
 /// ## ProcessOpts
 ///
-/// `nanocl ps` available options
+/// `nanocl ps` available options,
+/// running `nanocl ps` without arguments list every process of the current node
 ///
 #[derive(Clone, Debug, Parser)]
 pub struct ProcessOpts {
@@ -92,6 +99,30 @@ pub struct ProcessOpts {
   /// Show all containers running for the given namespace
   #[clap(long, short)]
   pub namespace: Option<String>,
+  /// Show the containers of every namespace sorted by namespace and name
+  #[clap(long, short = 'A', conflicts_with = "namespace")]
+  pub all_namespaces: bool,
+  /// Only show processes whose name match this glob pattern (e.g: `web-*`)
+  pub pattern: Option<String>,
+  /// Render the processes as a flat table or as a tree grouping them
+  /// by namespace then by cargo or vm
+  #[clap(long, value_enum, default_value_t)]
+  pub format: ProcessFormat,
+  #[clap(flatten)]
+  pub page: PaginationOpts,
+}
+
+/// ## ProcessFormat
+///
+/// `nanocl ps` available formats, `table` by default
+///
+#[derive(Default, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ProcessFormat {
+  /// One row per process
+  #[default]
+  Table,
+  /// Processes grouped under their namespace and their cargo or vm
+  Tree,
 }
 
 /// Convert ProcessOpts to ProccessQuery
@@ -110,24 +141,29 @@ impl From<ProcessOpts> for ProccessQuery {
 ///
 /// A row for the process table
 ///
-#[derive(Tabled)]
+#[derive(Tabled, Serialize)]
+#[serde(rename_all = "PascalCase")]
 pub struct ProcessRow {
   /// Node name
-  node: String,
+  pub(crate) node: String,
   /// Name of the instance of the cargo or the vm
-  name: String,
+  pub(crate) name: String,
   /// Namespace of the cargo or the vm
-  namespace: String,
+  pub(crate) namespace: String,
   /// Kind of instance cargo or vm
-  kind: String,
+  pub(crate) kind: String,
   /// Image used by the cargo or the vm
-  image: String,
+  pub(crate) image: String,
   /// Status of the cargo or the vm
-  status: String,
+  pub(crate) status: String,
   /// IP address of the cargo or the vm
-  ip_address: String,
+  pub(crate) ip_address: String,
   /// When the cargo or the vm was created
-  created: String,
+  pub(crate) created: String,
+  /// Name of the cargo or the vm owning the instance
+  #[tabled(skip)]
+  #[serde(skip)]
+  pub(crate) owner: String,
 }
 
 /// Convert NodeContainerSummary to ProcessRow
@@ -154,6 +190,15 @@ impl From<NodeContainerSummary> for ProcessRow {
     if let Some(network) = networks.get(namespace) {
       ipaddr = network.ip_address.clone().unwrap_or_default();
     }
+    // Replicas are named `N-name` so their owner is found by its key
+    // `name.namespace` in the labels
+    let labels = container.labels.unwrap_or_default();
+    let owner = labels
+      .get("io.nanocl.c")
+      .or_else(|| labels.get("io.nanocl.v"))
+      .and_then(|key| key.strip_suffix(&format!(".{namespace}")))
+      .unwrap_or(name)
+      .to_owned();
     let binding = chrono::Local::now();
     let tz = binding.offset();
     // Convert the created_at and updated_at to the current timezone
@@ -170,6 +215,49 @@ impl From<NodeContainerSummary> for ProcessRow {
       status: container.status.unwrap_or_default(),
       ip_address: ipaddr,
       created: format!("{created_at}"),
+      owner,
+    }
+  }
+}
+
+/// ## DiskUsageRow
+///
+/// A row of the disk usage table
+///
+#[derive(Tabled, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DiskUsageRow {
+  /// Kind of the item cargo image, vm image or volume
+  pub(crate) kind: String,
+  /// Name of the item
+  pub(crate) name: String,
+  /// Human readable size of the item
+  pub(crate) size: String,
+}
+
+impl DiskUsageRow {
+  pub(crate) fn new(kind: &str, item: &DiskUsageItem) -> Self {
+    Self {
+      kind: kind.to_owned(),
+      name: item.name.clone(),
+      size: format_bytes(u64::try_from(item.size).unwrap_or_default()),
     }
   }
 }
+
+/// ## DiskUsageOutput
+///
+/// Disk usage with the total size in bytes
+///
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DiskUsageOutput {
+  /// Total size in bytes
+  pub(crate) total: i64,
+  /// Container images used by cargoes
+  pub(crate) cargo_images: Vec<DiskUsageItem>,
+  /// Images used by virtual machines
+  pub(crate) vm_images: Vec<DiskUsageItem>,
+  /// Volumes
+  pub(crate) volumes: Vec<DiskUsageItem>,
+}
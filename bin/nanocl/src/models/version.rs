@@ -6,4 +6,11 @@ use clap::Parser;
 ///
 #[derive(Debug, Parser)]
 #[clap(name = "nanocl-version")]
-pub struct VersionArg {}
+pub struct VersionArg {
+  /// Only print the client version without contacting the daemon
+  #[clap(long, conflicts_with = "server")]
+  pub client: bool,
+  /// Only print the daemon version and its compatibility with the client
+  #[clap(long)]
+  pub server: bool,
+}
@@ -1,13 +1,16 @@
 use tabled::Tabled;
 use chrono::TimeZone;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
 
 use nanocld_client::stubs::vm::VmSummary;
 use nanocld_client::stubs::vm_config::{
   VmConfigPartial, VmDiskConfig, VmHostConfig, VmConfigUpdate,
 };
 
-use super::{VmImageArg, DisplayFormat};
+use super::{VmImageArg, DisplayFormat, PaginationOpts};
+use super::duration::{parse_duration, parse_interval};
+use super::humanize::{format_bytes, format_count};
 
 /// ## VmCommands
 ///
@@ -26,20 +29,81 @@ pub enum VmCommand {
   List(VmListOpts),
   /// Remove vms
   #[clap(alias = "rm")]
-  Remove(VmNamesOpts),
+  Remove(VmRemoveOpts),
   /// Inspect a vm
   Inspect(VmInspectOpts),
   /// Start a vm
-  Start(VmNamesOpts),
+  Start(VmStartOpts),
   /// Stop a vm
   Stop(VmNamesOpts),
   /// Attach to a vm
+  #[clap(alias = "console")]
   Attach {
     /// Name of the vm
     name: String,
+    /// Do not try to reconnect when the connection is dropped
+    #[clap(long)]
+    no_reconnect: bool,
+    /// Write the console output to this file while displaying it
+    #[clap(long, value_name = "FILE")]
+    record: Option<String>,
+    /// Format of the recording, `cast` by default for a `.cast` file
+    #[clap(long, value_enum, requires = "record")]
+    record_format: Option<VmRecordFormat>,
+    /// Interval between heartbeat pings like `5s`,
+    /// defaults to `NANOCL_HEARTBEAT_INTERVAL` or `5s`
+    #[clap(long, value_parser = parse_interval)]
+    heartbeat: Option<std::time::Duration>,
+    /// Disconnect when no data is received for this long like `30s`,
+    /// the replies to heartbeats count so it should be longer than them.
+    /// `0` disables it
+    #[clap(long, default_value = "0", value_parser = parse_duration)]
+    timeout: std::time::Duration,
   },
   /// Patch a vm
   Patch(VmPatchOpts),
+  /// Change the cpu or memory of a vm
+  Resize(VmResizeOpts),
+  /// Show logs of a vm
+  Logs(VmLogsOpts),
+  /// Connect to a vm with ssh
+  Ssh(VmSshOpts),
+  /// Export a vm definition and its disk to an archive
+  Export(VmExportOpts),
+  /// Create a vm from an archive made by `nanocl vm export`
+  Import(VmImportOpts),
+  /// Clone a vm definition under a new name
+  Clone {
+    /// Name of the vm to clone
+    name: String,
+    /// Name of the new vm
+    new_name: String,
+    /// Namespace where to create the new vm, default to the vm namespace
+    #[clap(long)]
+    namespace_to: Option<String>,
+  },
+  /// Move a vm to another node (experimental)
+  Migrate(VmMigrateOpts),
+  /// Save the disk of a vm as a vm image
+  Snapshot {
+    /// Name of the vm
+    name: String,
+    /// Name of the snapshot
+    snapshot_name: String,
+    /// Stop the vm if it's running and start it again once done
+    #[clap(long)]
+    force: bool,
+  },
+  /// Restore the disk of a vm from a snapshot
+  Restore {
+    /// Name of the vm
+    name: String,
+    /// Name of the snapshot
+    snapshot_name: String,
+    /// Stop the vm if it's running and start it again once done
+    #[clap(long)]
+    force: bool,
+  },
 }
 
 /// ## VmNamesOpts
@@ -52,6 +116,40 @@ pub struct VmNamesOpts {
   pub names: Vec<String>,
 }
 
+/// ## VmRemoveOpts
+///
+/// `nanocl vm rm` available options
+///
+#[derive(Debug, Parser)]
+pub struct VmRemoveOpts {
+  /// Treat vms that don't exist as removed
+  #[clap(long)]
+  pub ignore_not_found: bool,
+  /// Names of the vm
+  pub names: Vec<String>,
+}
+
+/// ## VmStartOpts
+///
+/// `nanocl vm start` available options
+///
+#[derive(Debug, Parser)]
+pub struct VmStartOpts {
+  /// Names of the vm
+  pub names: Vec<String>,
+  /// Wait for the vms to be running
+  #[clap(long)]
+  pub wait: bool,
+  /// Maximum time to wait for the vms to be running like `60s` or `5m`
+  #[clap(
+    long,
+    default_value = "60s",
+    value_parser = parse_duration,
+    requires = "wait"
+  )]
+  pub wait_timeout: std::time::Duration,
+}
+
 /// ## VmInspectOpts
 ///
 /// `nanocl vm inspect` available options
@@ -61,6 +159,9 @@ pub struct VmInspectOpts {
   /// Display format
   #[clap(long)]
   pub display: Option<DisplayFormat>,
+  /// Render the vm with a template like '{{.Name}} {{.Config.Disk.Image}}'
+  #[clap(long, conflicts_with = "display")]
+  pub template: Option<String>,
   /// Name of the vm
   pub name: String,
 }
@@ -74,6 +175,152 @@ pub struct VmListOpts {
   /// Show only vms name
   #[clap(long, short)]
   pub quiet: bool,
+  /// Show only vms in the given state
+  #[clap(long, value_enum)]
+  pub state: Option<VmListState>,
+  /// Sort vms by the given column
+  #[clap(long, value_enum)]
+  pub sort: Option<VmListSort>,
+  /// Reverse the sort order
+  #[clap(long, requires = "sort")]
+  pub reverse: bool,
+  /// Show the cpu and memory as exact integers, memory in MB,
+  /// instead of human units like `2.0 GiB`
+  #[clap(long)]
+  pub raw: bool,
+  /// List in every namespace, the `--namespace` option is ignored
+  #[clap(long, short = 'A')]
+  pub all_namespaces: bool,
+  /// Refresh the list at the given interval like `5s` until Ctrl-C,
+  /// vms whose instances changed since the last refresh are highlighted
+  #[clap(
+    long,
+    short,
+    value_name = "INTERVAL",
+    num_args = 0..=1,
+    default_missing_value = "2s",
+    value_parser = parse_interval,
+    conflicts_with = "quiet"
+  )]
+  pub watch: Option<std::time::Duration>,
+  #[clap(flatten)]
+  pub page: PaginationOpts,
+}
+
+/// ## VmListState
+///
+/// `nanocl vm list --state` available values
+///
+#[derive(Clone, Debug, ValueEnum)]
+pub enum VmListState {
+  /// At least one instance of the vm is running
+  Running,
+  /// No instance of the vm is running
+  Stopped,
+}
+
+/// ## VmRecordFormat
+///
+/// `nanocl vm attach --record-format` available values
+///
+#[derive(Clone, Debug, PartialEq, ValueEnum)]
+pub enum VmRecordFormat {
+  /// The output as printed on the terminal
+  Raw,
+  /// An asciinema v2 cast with the timing of each output
+  Cast,
+}
+
+/// ## VmListSort
+///
+/// `nanocl vm list --sort` available values
+///
+#[derive(Clone, Debug, ValueEnum)]
+pub enum VmListSort {
+  Name,
+  Memory,
+  Cpu,
+}
+
+/// ## VmLogsOpts
+///
+/// `nanocl vm logs` available options
+///
+#[derive(Debug, Parser)]
+pub struct VmLogsOpts {
+  /// Name of the vm
+  pub name: String,
+  /// Follow the logs output
+  #[clap(long, short)]
+  pub follow: bool,
+  /// Number of lines to show from the end of the logs
+  #[clap(long, short)]
+  pub tail: Option<usize>,
+  /// Only show logs newer than a relative duration like 30s, 10m, 2h or 1d
+  #[clap(long, short, value_parser = parse_duration)]
+  pub since: Option<std::time::Duration>,
+}
+
+/// ## VmSshOpts
+///
+/// `nanocl vm ssh` available options
+///
+#[derive(Debug, Parser)]
+pub struct VmSshOpts {
+  /// User to login as, default to the user of the vm config
+  #[clap(long, short)]
+  pub user: Option<String>,
+  /// Port of the ssh server of the vm
+  #[clap(long, short, default_value = "22")]
+  pub port: u16,
+  /// Name of the vm
+  pub name: String,
+  /// Extra arguments given to ssh after the address like a command to run
+  #[clap(last = true)]
+  pub args: Vec<String>,
+}
+
+/// ## VmExportOpts
+///
+/// `nanocl vm export` available options
+///
+#[derive(Debug, Parser)]
+pub struct VmExportOpts {
+  /// Path of the archive to write
+  #[clap(long, short)]
+  pub output: String,
+  /// Name of the vm
+  pub name: String,
+}
+
+/// ## VmMigrateOpts
+///
+/// `nanocl vm migrate` available options
+///
+#[derive(Debug, Parser)]
+pub struct VmMigrateOpts {
+  /// Name of the vm
+  pub name: String,
+  /// Name of the node to move the vm to, see `nanocl node ls`
+  #[clap(long)]
+  pub to_node: String,
+  /// Stop the vm if it's running, it's started again on the node
+  #[clap(long)]
+  pub force: bool,
+}
+
+/// ## VmImportOpts
+///
+/// `nanocl vm import` available options
+///
+#[derive(Debug, Parser)]
+pub struct VmImportOpts {
+  /// Path of the archive made by `nanocl vm export`
+  #[clap(long, short)]
+  pub file: String,
+  /// Name of the imported vm, default to the name of the exported vm
+  #[clap(long)]
+  pub name: Option<String>,
 }
 
 /// ## VmPatchOpts
@@ -84,6 +331,15 @@ pub struct VmListOpts {
 pub struct VmPatchOpts {
   /// Name of the vm
   pub name: String,
+  /// Path to a yaml, json or toml file merged over the current config of the vm
+  #[clap(
+    long,
+    conflicts_with_all = [
+      "user", "password", "ssh_key", "hostname", "cpu", "memory", "kvm",
+      "net_iface",
+    ],
+  )]
+  pub from_file: Option<String>,
   /// Default user of the VM
   #[clap(long)]
   pub user: Option<String>,
@@ -131,6 +387,22 @@ impl From<VmPatchOpts> for VmConfigUpdate {
   }
 }
 
+/// ## VmResizeOpts
+///
+/// `nanocl vm resize` available options
+///
+#[derive(Clone, Debug, Parser)]
+pub struct VmResizeOpts {
+  /// Name of the vm
+  pub name: String,
+  /// Number of cpu of the vm
+  #[clap(long, required_unless_present = "memory")]
+  pub cpu: Option<u64>,
+  /// Memory of the vm eg: 2G, 512M
+  #[clap(long)]
+  pub memory: Option<String>,
+}
+
 /// ## VmRunOpts
 ///
 /// `nanocl vm run` available options
@@ -167,6 +439,9 @@ pub struct VmRunOpts {
   /// Attach to the vm
   #[clap(short, long)]
   pub attach: bool,
+  /// Lowercase the name and replace its invalid characters by `-`
+  #[clap(long)]
+  pub normalize: bool,
   /// Name of the vm
   pub name: String,
   /// Name of the vm image
@@ -228,10 +503,25 @@ pub struct VmCreateOpts {
   /// Enable KVM
   #[clap(long)]
   pub kvm: bool,
+  /// Download and import the vm image from this url before creating the vm
+  #[clap(long, conflicts_with = "image")]
+  pub from_image_url: Option<String>,
+  /// Expected sha256 digest of the image downloaded with `--from-image-url`
+  #[clap(
+    long,
+    alias = "checksum",
+    requires = "from_image_url",
+    conflicts_with = "image"
+  )]
+  pub sha256: Option<String>,
+  /// Lowercase the name and replace its invalid characters by `-`
+  #[clap(long)]
+  pub normalize: bool,
   /// Name of the vm
   pub name: String,
   /// Name of the vm image
-  pub image: String,
+  #[clap(required_unless_present = "from_image_url")]
+  pub image: Option<String>,
 }
 
 /// Convert VmCreateOpts to VmConfigPartial
@@ -251,7 +541,7 @@ impl From<VmCreateOpts> for VmConfigPartial {
         ..Default::default()
       }),
       disk: VmDiskConfig {
-        image: val.image,
+        image: val.image.unwrap_or_default(),
         ..Default::default()
       },
       ..Default::default()
@@ -263,7 +553,8 @@ impl From<VmCreateOpts> for VmConfigPartial {
 ///
 /// A row for the vm table
 ///
-#[derive(Tabled)]
+#[derive(Tabled, Serialize)]
+#[serde(rename_all = "PascalCase")]
 pub struct VmRow {
   /// Name of the vm
   pub(crate) name: String,
@@ -271,6 +562,12 @@ pub struct VmRow {
   pub(crate) namespace: String,
   /// Disk of the vm
   pub(crate) disk: String,
+  /// Number of cpu
+  #[tabled(display_with("Self::display_cpu", self))]
+  pub(crate) cpu: u64,
+  /// Memory in MB
+  #[tabled(display_with("Self::display_memory", self))]
+  pub(crate) memory: u64,
   /// Number of instances
   pub(crate) instances: String,
   /// Config version
@@ -279,6 +576,29 @@ pub struct VmRow {
   pub(crate) created_at: String,
   /// When the vm was last updated
   pub(crate) updated_at: String,
+  /// Show the cpu and memory as exact integers in the table,
+  /// structured outputs always have them
+  #[tabled(skip)]
+  #[serde(skip)]
+  pub(crate) raw: bool,
+}
+
+impl VmRow {
+  /// Display the cpu like `4 vCPU` unless raw
+  fn display_cpu(&self) -> String {
+    match self.raw {
+      true => self.cpu.to_string(),
+      false => format_count(self.cpu, "vCPU"),
+    }
+  }
+
+  /// Display the memory like `2.0 GiB` unless raw
+  fn display_memory(&self) -> String {
+    match self.raw {
+      true => self.memory.to_string(),
+      false => format_bytes(self.memory.saturating_mul(1024 * 1024)),
+    }
+  }
 }
 
 /// Convert VmSummary to VmRow
@@ -300,10 +620,13 @@ impl From<VmSummary> for VmRow {
       name: vm.name,
       namespace: vm.namespace_name,
       disk: vm.config.disk.image,
+      cpu: vm.config.host_config.cpu,
+      memory: vm.config.host_config.memory,
       config_version: vm.config.version,
       instances: format!("{}/{}", vm.running_instances, vm.instances),
       created_at: format!("{created_at}"),
       updated_at: format!("{updated_at}"),
+      raw: false,
     }
   }
 }
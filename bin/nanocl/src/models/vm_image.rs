@@ -1,9 +1,12 @@
 use tabled::Tabled;
 use chrono::TimeZone;
 use clap::{Parser, Subcommand};
+use serde::Serialize;
 
 use nanocld_client::stubs::vm_image::{VmImage, VmImageResizePayload};
 
+use super::humanize::format_bytes;
+
 /// ## VmImageCommand
 ///
 /// `nanocl vm image` available commands
@@ -38,9 +41,17 @@ pub enum VmImageCommand {
 ///
 #[derive(Debug, Parser)]
 pub struct VmImageCreateOpts {
+  /// Expected sha256 digest of the VM image, it is removed on mismatch.
+  /// For compressed images it is the digest of the compressed file
+  #[clap(long, alias = "checksum")]
+  pub sha256: Option<String>,
+  /// Limit the upload bandwidth in bytes per second
+  #[clap(long)]
+  pub bwlimit: Option<u64>,
   /// Name of the VM image
   pub name: String,
-  /// Path or url to the VM image
+  /// Path or url to the VM image, `.gz`, `.xz` and `.zst` images
+  /// are decompressed while they are imported
   pub file_path: String,
 }
 
@@ -95,7 +106,8 @@ pub struct VmImageArg {
 ///
 /// A row for the vm image table
 ///
-#[derive(Tabled)]
+#[derive(Tabled, Serialize)]
+#[serde(rename_all = "PascalCase")]
 pub struct VmImageRow {
   /// Name of the VM image
   pub name: String,
@@ -105,17 +117,13 @@ pub struct VmImageRow {
   pub format: String,
   /// Size of the VM image
   pub size: String,
+  /// Base image whose data is shared with other images of the list
+  pub shared: String,
   /// When the VM image was created
   pub created_at: String,
-}
-
-/// Convert size to human readable format
-fn convert_size(size: i64) -> String {
-  if size >= 1_000_000_000 {
-    format!("{} GB", size / 1024 / 1024 / 1024)
-  } else {
-    format!("{} MB", size / 1024 / 1024)
-  }
+  /// Image this one is based on, only in the serialized output
+  #[tabled(skip)]
+  pub parent: Option<String>,
 }
 
 /// Convert VmImage to VmImageRow
@@ -129,15 +137,19 @@ impl From<VmImage> for VmImageRow {
       .timestamp_opt(item.created_at.timestamp(), 0)
       .unwrap()
       .format("%Y-%m-%d %H:%M:%S");
-    let size_virtual = convert_size(item.size_virtual);
-    let size_actual = convert_size(item.size_actual);
+    let size_virtual =
+      format_bytes(u64::try_from(item.size_virtual).unwrap_or_default());
+    let size_actual =
+      format_bytes(u64::try_from(item.size_actual).unwrap_or_default());
     let size = format!("{} / {}", size_actual, size_virtual);
     Self {
       name: item.name.to_owned(),
       kind: item.kind,
       format: item.format,
       size,
+      shared: String::default(),
       created_at: format!("{created_at}"),
+      parent: item.parent,
     }
   }
 }
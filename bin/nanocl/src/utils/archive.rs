@@ -0,0 +1,296 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use nanocl_utils::io_error::{IoError, IoResult, FromIo};
+
+/// Size of a block of a tar archive
+pub const BLOCK_SIZE: u64 = 512;
+
+/// ## Padding
+///
+/// Number of bytes needed after an entry of the given size
+/// to align the next header on a block
+///
+/// ## Arguments
+///
+/// * [size](u64) The size of the entry
+///
+/// ## Return
+///
+/// * [u64](u64) The number of padding bytes
+///
+pub fn padding(size: u64) -> u64 {
+  (BLOCK_SIZE - size % BLOCK_SIZE) % BLOCK_SIZE
+}
+
+/// ## Write header
+///
+/// Write the header of a regular file entry of a tar archive,
+/// the content of the entry must be written after it followed by its
+/// [padding](padding)
+///
+/// ## Arguments
+///
+/// * [writer](AsyncWrite) The writer of the archive
+/// * [path](str) The path of the entry
+/// * [size](u64) The size of the content of the entry
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The header was written
+///   * [Err](IoError) An error occured
+///
+pub async fn write_header<W>(
+  writer: &mut W,
+  path: &str,
+  size: u64,
+) -> IoResult<()>
+where
+  W: AsyncWrite + Unpin,
+{
+  let mut header = tar::Header::new_gnu();
+  header
+    .set_path(path)
+    .map_err(|err| err.map_err_context(|| format!("Archive entry {path}")))?;
+  header.set_size(size);
+  header.set_mode(0o644);
+  header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+  header.set_entry_type(tar::EntryType::Regular);
+  header.set_cksum();
+  writer
+    .write_all(header.as_bytes())
+    .await
+    .map_err(|err| err.map_err_context(|| format!("Archive entry {path}")))?;
+  Ok(())
+}
+
+/// ## Write padding
+///
+/// Write the zeros aligning the end of an entry on a block
+///
+/// ## Arguments
+///
+/// * [writer](AsyncWrite) The writer of the archive
+/// * [size](u64) The size of the content of the entry
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The padding was written
+///   * [Err](IoError) An error occured
+///
+pub async fn write_padding<W>(writer: &mut W, size: u64) -> IoResult<()>
+where
+  W: AsyncWrite + Unpin,
+{
+  let zeros = vec![0; padding(size) as usize];
+  writer
+    .write_all(&zeros)
+    .await
+    .map_err(|err| err.map_err_context(|| "Archive padding"))?;
+  Ok(())
+}
+
+/// ## Write entry
+///
+/// Write a small entry of a tar archive from memory
+///
+/// ## Arguments
+///
+/// * [writer](AsyncWrite) The writer of the archive
+/// * [path](str) The path of the entry
+/// * [content](Vec<u8>) The content of the entry
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The entry was written
+///   * [Err](IoError) An error occured
+///
+pub async fn write_entry<W>(
+  writer: &mut W,
+  path: &str,
+  content: &[u8],
+) -> IoResult<()>
+where
+  W: AsyncWrite + Unpin,
+{
+  write_header(writer, path, content.len() as u64).await?;
+  writer
+    .write_all(content)
+    .await
+    .map_err(|err| err.map_err_context(|| format!("Archive entry {path}")))?;
+  write_padding(writer, content.len() as u64).await
+}
+
+/// ## Write end
+///
+/// Write the two empty blocks ending a tar archive
+///
+/// ## Arguments
+///
+/// * [writer](AsyncWrite) The writer of the archive
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The end of the archive was written
+///   * [Err](IoError) An error occured
+///
+pub async fn write_end<W>(writer: &mut W) -> IoResult<()>
+where
+  W: AsyncWrite + Unpin,
+{
+  writer
+    .write_all(&[0; 2 * BLOCK_SIZE as usize])
+    .await
+    .map_err(|err| err.map_err_context(|| "Archive end"))?;
+  Ok(())
+}
+
+/// ## Read header
+///
+/// Read the header of the next entry of a tar archive.
+/// The checksum of the header is verified so a corrupted archive is refused.
+///
+/// ## Arguments
+///
+/// * [reader](AsyncRead) The reader of the archive
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Option<(String, u64)>) The path and the size of the entry, None at the end of the archive
+///   * [Err](IoError) The archive is truncated or corrupted
+///
+pub async fn read_header<R>(reader: &mut R) -> IoResult<Option<(String, u64)>>
+where
+  R: AsyncRead + Unpin,
+{
+  let mut block = [0; BLOCK_SIZE as usize];
+  reader
+    .read_exact(&mut block)
+    .await
+    .map_err(|err| err.map_err_context(|| "Archive is truncated"))?;
+  if block.iter().all(|byte| *byte == 0) {
+    return Ok(None);
+  }
+  let corrupted = |msg: &str| IoError::invalid_data("Archive", msg);
+  let header = tar::Header::from_byte_slice(&block);
+  let checksum = header
+    .cksum()
+    .map_err(|_| corrupted("header checksum is invalid"))?;
+  let computed = block
+    .iter()
+    .enumerate()
+    .map(|(index, byte)| match index {
+      148..=155 => u32::from(b' '),
+      _ => u32::from(*byte),
+    })
+    .sum::<u32>();
+  if checksum != computed {
+    return Err(corrupted("header checksum mismatch"));
+  }
+  if !header.entry_type().is_file() {
+    return Err(corrupted("only regular files are expected"));
+  }
+  let path = header
+    .path()
+    .map_err(|_| corrupted("entry path is invalid"))?
+    .display()
+    .to_string();
+  let size = header
+    .entry_size()
+    .map_err(|_| corrupted("entry size is invalid"))?;
+  Ok(Some((path, size)))
+}
+
+/// ## Read entry
+///
+/// Read the content of a small entry of a tar archive in memory
+/// and skip its padding
+///
+/// ## Arguments
+///
+/// * [reader](AsyncRead) The reader of the archive
+/// * [path](str) The path of the entry
+/// * [size](u64) The size of the entry
+/// * [max_size](u64) The maximum size accepted for this entry
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Vec<u8>) The content of the entry
+///   * [Err](IoError) The entry is too large or the archive is truncated
+///
+pub async fn read_entry<R>(
+  reader: &mut R,
+  path: &str,
+  size: u64,
+  max_size: u64,
+) -> IoResult<Vec<u8>>
+where
+  R: AsyncRead + Unpin,
+{
+  if size > max_size {
+    return Err(IoError::invalid_data(
+      format!("Archive entry {path}"),
+      format!("is larger than {max_size} bytes"),
+    ));
+  }
+  let mut content = vec![0; (size + padding(size)) as usize];
+  reader
+    .read_exact(&mut content)
+    .await
+    .map_err(|err| err.map_err_context(|| "Archive is truncated"))?;
+  content.truncate(size as usize);
+  Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn padding_is_block_aligned() {
+    assert_eq!(padding(0), 0);
+    assert_eq!(padding(1), 511);
+    assert_eq!(padding(512), 0);
+    assert_eq!(padding(513), 511);
+  }
+
+  #[ntex::test]
+  async fn write_then_read() {
+    let mut archive = Vec::new();
+    write_entry(&mut archive, "vm.json", b"{}").await.unwrap();
+    write_entry(&mut archive, "disk.sha256", b"abc")
+      .await
+      .unwrap();
+    write_end(&mut archive).await.unwrap();
+    assert_eq!(archive.len() as u64 % BLOCK_SIZE, 0);
+    // The archive can be read by other tar implementations
+    let mut entries = tar::Archive::new(archive.as_slice());
+    assert_eq!(entries.entries().unwrap().count(), 2);
+    let mut reader = archive.as_slice();
+    let (path, size) = read_header(&mut reader).await.unwrap().unwrap();
+    assert_eq!((path.as_str(), size), ("vm.json", 2));
+    let content = read_entry(&mut reader, &path, size, 1024).await.unwrap();
+    assert_eq!(content, b"{}");
+    let (path, size) = read_header(&mut reader).await.unwrap().unwrap();
+    assert!(read_entry(&mut reader, &path, size, 2).await.is_err());
+  }
+
+  #[ntex::test]
+  async fn refuse_corrupted_and_truncated() {
+    let mut archive = Vec::new();
+    write_entry(&mut archive, "vm.json", b"{}").await.unwrap();
+    let mut corrupted = archive.clone();
+    corrupted[0] = b'x';
+    assert!(read_header(&mut corrupted.as_slice()).await.is_err());
+    let truncated = &archive[..100];
+    assert!(read_header(&mut &truncated[..]).await.is_err());
+    let mut reader = &archive[..BLOCK_SIZE as usize + 1];
+    let (path, size) = read_header(&mut reader).await.unwrap().unwrap();
+    assert!(read_entry(&mut reader, &path, size, 1024).await.is_err());
+  }
+}
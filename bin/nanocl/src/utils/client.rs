@@ -0,0 +1,273 @@
+use std::future::Future;
+use std::time::Duration;
+
+use ntex::http;
+use futures::StreamExt;
+
+use nanocl_utils::io_error::{IoError, IoResult};
+use nanocl_utils::http_client_error::HttpClientError;
+
+use crate::config::CliConfig;
+
+/// Default timeout of requests to the daemon
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// ## With timeout
+///
+/// Wait for a client future to complete within a deadline.
+/// A zero timeout means no timeout, it should be used for streaming calls
+/// like `attach` that are expected to run for a long time.
+///
+/// ## Arguments
+///
+/// * [timeout](Duration) The deadline of the future
+/// * [fut](Future) The client future to wait for
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](T) The output of the future
+///   * [Err](IoError) The future failed or the deadline elapsed
+///
+pub async fn with_timeout<F, T, E>(timeout: Duration, fut: F) -> IoResult<T>
+where
+  F: Future<Output = Result<T, E>>,
+  E: Into<IoError>,
+{
+  if timeout.is_zero() {
+    return fut.await.map_err(Into::into);
+  }
+  match ntex::time::timeout(timeout, fut).await {
+    Ok(res) => res.map_err(Into::into),
+    Err(_) => Err(timeout_error(timeout)),
+  }
+}
+
+/// ## Timeout error
+///
+/// The error of a request the daemon did not answer in time
+///
+/// ## Arguments
+///
+/// * [timeout](Duration) The elapsed deadline
+///
+/// ## Return
+///
+/// * [IoError](IoError) The timeout error
+///
+fn timeout_error(timeout: Duration) -> IoError {
+  IoError::new(
+    "Timeout",
+    std::io::Error::new(
+      std::io::ErrorKind::TimedOut,
+      format!("Daemon did not respond within {timeout:?}"),
+    ),
+  )
+}
+
+/// ## Retry create
+///
+/// Call a create function of the client with an idempotency key
+/// and retry it with the same key when the request timed out
+/// or the connection failed. The daemon may have created the item before
+/// the failure, in this case the retry return it instead of a conflict.
+/// Errors answered by the daemon are not retried.
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+/// * [create](Fn) The create function called with the idempotency key
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok]((T, bool)) The created item and true if it already existed
+///   * [Err](IoError) The item could not be created
+///
+pub async fn retry_create<F, Fut, T>(
+  cli_conf: &CliConfig,
+  create: F,
+) -> IoResult<(T, bool)>
+where
+  F: Fn(String) -> Fut,
+  Fut: Future<Output = Result<(T, bool), HttpClientError>>,
+{
+  /// How many times a create request is sent at most
+  const CREATE_ATTEMPTS: u32 = 3;
+  /// Delay before the first retry, doubled for the next ones
+  const RETRY_DELAY: Duration = Duration::from_millis(500);
+  let idempotency_key = uuid::Uuid::new_v4().to_string();
+  let mut attempt = 1;
+  loop {
+    let fut = create(idempotency_key.clone());
+    let res = match cli_conf.timeout.is_zero() {
+      true => Some(fut.await),
+      false => ntex::time::timeout(cli_conf.timeout, fut).await.ok(),
+    };
+    let err = match res {
+      Some(Ok(created)) => return Ok(created),
+      // The daemon answered so a retry would get the same answer
+      Some(Err(HttpClientError::HttpError(err))) => return Err(err.into()),
+      Some(Err(err)) => err.into(),
+      None => timeout_error(cli_conf.timeout),
+    };
+    if attempt >= CREATE_ATTEMPTS {
+      return Err(err);
+    }
+    if cli_conf.verbose > 0 {
+      eprintln!("{err}, retrying ({attempt}/{CREATE_ATTEMPTS})");
+    }
+    ntex::time::sleep(RETRY_DELAY * 2u32.pow(attempt - 1)).await;
+    attempt += 1;
+  }
+}
+
+/// ## Is not found
+///
+/// Check if a request failed because the element doesn't exist
+///
+/// ## Arguments
+///
+/// * [err](HttpClientError) The error of the request
+///
+/// ## Return
+///
+/// * [bool](bool) True if the daemon answered with a 404
+///
+pub fn is_not_found(err: &HttpClientError) -> bool {
+  matches!(
+    err,
+    HttpClientError::HttpError(err) if err.status == http::StatusCode::NOT_FOUND
+  )
+}
+
+/// ## Resolve timeout
+///
+/// Resolve the timeout of requests to the daemon.
+/// The `--timeout` flag take precedence over the `NANOCL_TIMEOUT` environment variable
+/// which is parsed with [parse_duration](crate::utils::duration::parse_duration)
+///
+/// ## Arguments
+///
+/// * [timeout](Option<Duration>) The timeout set by the `--timeout` flag
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Duration) The timeout to use
+///   * [Err](IoError) The `NANOCL_TIMEOUT` environment variable is invalid
+///
+pub fn resolve_timeout(timeout: Option<Duration>) -> IoResult<Duration> {
+  if let Some(timeout) = timeout {
+    return Ok(timeout);
+  }
+  match std::env::var("NANOCL_TIMEOUT") {
+    Ok(timeout) => crate::utils::duration::parse_duration(timeout.trim())
+      .map_err(|err| IoError::invalid_input("NANOCL_TIMEOUT".to_owned(), err)),
+    Err(_) => Ok(DEFAULT_TIMEOUT),
+  }
+}
+
+/// ## Run concurrent
+///
+/// Run futures with at most `concurrency` of them in flight at once,
+/// to avoid opening a connection to the daemon for each element
+/// of commands acting on many of them
+///
+/// ## Arguments
+///
+/// * [concurrency](usize) The maximum number of futures in flight
+/// * [futures](IntoIterator) The futures to run
+///
+/// ## Return
+///
+/// * [Vec](Vec<T>) The outputs of the futures in the order they were given
+///
+pub async fn run_concurrent<I, Fut, T>(concurrency: usize, futures: I) -> Vec<T>
+where
+  I: IntoIterator<Item = Fut>,
+  Fut: Future<Output = T>,
+{
+  futures::stream::iter(futures)
+    .buffered(concurrency.max(1))
+    .collect()
+    .await
+}
+
+/// ## List in namespaces
+///
+/// Call a list function of the client in the namespace of the command,
+/// or concurrently in every namespace with `--all-namespaces`
+/// up to the `--concurrency` limit and merge the results
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config
+/// * [namespace](Option<String>) The namespace of the command
+/// * [all_namespaces](bool) Whether to list in every namespace
+/// * [list](Fn) The list function called with each namespace
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Vec<T>) The items of every namespace
+///   * [Err](IoError) A list call failed
+///
+pub async fn list_in_namespaces<F, Fut, T, E>(
+  cli_conf: &CliConfig,
+  namespace: &Option<String>,
+  all_namespaces: bool,
+  list: F,
+) -> IoResult<Vec<T>>
+where
+  F: Fn(Option<String>) -> Fut,
+  Fut: Future<Output = Result<Vec<T>, E>>,
+  E: Into<IoError>,
+{
+  if !all_namespaces {
+    let namespace = cli_conf.resolve_namespace(namespace);
+    return with_timeout(cli_conf.timeout, list(namespace)).await;
+  }
+  let namespaces =
+    with_timeout(cli_conf.timeout, cli_conf.client.list_namespace()).await?;
+  let lists = namespaces.into_iter().map(|namespace| {
+    with_timeout(cli_conf.timeout, list(Some(namespace.name)))
+  });
+  let items = run_concurrent(cli_conf.concurrency, lists)
+    .await
+    .into_iter()
+    .collect::<IoResult<Vec<_>>>()?
+    .into_iter()
+    .flatten()
+    .collect();
+  Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::rc::Rc;
+  use std::cell::Cell;
+
+  use super::*;
+
+  #[ntex::test]
+  async fn run_concurrent_limit() {
+    let in_flight = Rc::new(Cell::new(0));
+    let max_in_flight = Rc::new(Cell::new(0));
+    let futures = (0..20).map(|index| {
+      let in_flight = in_flight.clone();
+      let max_in_flight = max_in_flight.clone();
+      async move {
+        in_flight.set(in_flight.get() + 1);
+        max_in_flight.set(max_in_flight.get().max(in_flight.get()));
+        ntex::time::sleep(Duration::from_millis(20 - index)).await;
+        in_flight.set(in_flight.get() - 1);
+        index
+      }
+    });
+    let outputs = run_concurrent(4, futures).await;
+    assert_eq!(outputs, (0..20).collect::<Vec<_>>());
+    assert_eq!(max_in_flight.get(), 4);
+    assert!(run_concurrent(0, [async { 1 }]).await == vec![1]);
+  }
+}
@@ -0,0 +1,301 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use clap::{Arg, Command, CommandFactory};
+
+use crate::models::Cli;
+
+/// How long the candidates fetched from the daemon are reused
+pub const CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// Subcommands whose name argument is a new name that can't be completed
+const NEW_NAME_COMMANDS: [&str; 2] = ["create", "run"];
+
+/// ## CompleteKind
+///
+/// Kind of the names a word can be completed with
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompleteKind {
+  Namespace,
+  Cargo,
+  Vm,
+  Resource,
+  Node,
+}
+
+impl CompleteKind {
+  /// ## As str
+  ///
+  /// The name of the kind used in the cache keys
+  ///
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Namespace => "namespace",
+      Self::Cargo => "cargo",
+      Self::Vm => "vm",
+      Self::Resource => "resource",
+      Self::Node => "node",
+    }
+  }
+
+  /// ## Is namespaced
+  ///
+  /// True if the names of this kind depend on the namespace
+  ///
+  pub fn is_namespaced(&self) -> bool {
+    matches!(self, Self::Cargo | Self::Vm)
+  }
+}
+
+/// ## CompleteTarget
+///
+/// What the last word of a command line should be completed with
+///
+#[derive(Debug, PartialEq, Eq)]
+pub struct CompleteTarget {
+  /// Kind of names to complete
+  pub kind: CompleteKind,
+  /// Namespace given with `--namespace` on the command line
+  pub namespace: Option<String>,
+}
+
+/// ## Find arg
+///
+/// Find the option matching a flag in the walked commands,
+/// the deepest command first so globals are found too
+///
+/// ## Arguments
+///
+/// * [cmds](Vec<&Command>) The commands walked so far
+/// * [flag](str) The flag without its leading dashes
+///
+/// ## Return
+///
+/// * [Option](Option<&Arg>) The matching option if any
+///
+fn find_arg<'a>(cmds: &[&'a Command], flag: &str) -> Option<&'a Arg> {
+  cmds.iter().rev().find_map(|cmd| {
+    cmd.get_arguments().find(|arg| {
+      if flag.chars().count() == 1 {
+        arg.get_short().map(String::from).as_deref() == Some(flag)
+      } else {
+        arg.get_long() == Some(flag)
+      }
+    })
+  })
+}
+
+/// ## Complete target
+///
+/// Walk the [Cli](Cli) definition with the words of a command line
+/// to find what its last word should be completed with.
+/// Only existing names are completed: the value of `--namespace`
+/// and the name arguments of the cargo, vm, namespace, resource
+/// and node subcommands, except the ones creating a new name.
+///
+/// ## Arguments
+///
+/// * [words](Vec<String>) The words after `nanocl`, the last one being completed
+///
+/// ## Return
+///
+/// * [Option](Option<CompleteTarget>) What to complete the word with if anything
+///
+pub fn complete_target(words: &[String]) -> Option<CompleteTarget> {
+  let (current, words) = words.split_last()?;
+  if current.starts_with('-') {
+    return None;
+  }
+  let cli = Cli::command();
+  let mut cmds = vec![&cli];
+  let mut namespace = None;
+  let mut positionals = 0;
+  let mut value_of: Option<&Arg> = None;
+  for word in words {
+    if let Some(arg) = value_of.take() {
+      if arg.get_id() == "namespace" {
+        namespace = Some(word.clone());
+      }
+      continue;
+    }
+    if word == "--" {
+      return None;
+    }
+    if let Some(flag) = word.strip_prefix('-') {
+      let flag = flag.strip_prefix('-').unwrap_or(flag);
+      let (flag, value) = match flag.split_once('=') {
+        Some((flag, value)) => (flag, Some(value)),
+        None => (flag, None),
+      };
+      let Some(arg) = find_arg(&cmds, flag) else {
+        continue;
+      };
+      match value {
+        Some(value) if arg.get_id() == "namespace" => {
+          namespace = Some(value.to_owned());
+        }
+        None if arg.get_action().takes_values() => value_of = Some(arg),
+        _ => {}
+      }
+      continue;
+    }
+    match cmds.last().and_then(|cmd| cmd.find_subcommand(word)) {
+      Some(sub) => {
+        cmds.push(sub);
+        positionals = 0;
+      }
+      None => positionals += 1,
+    }
+  }
+  if let Some(arg) = value_of {
+    return (arg.get_id() == "namespace").then_some(CompleteTarget {
+      kind: CompleteKind::Namespace,
+      namespace: None,
+    });
+  }
+  let [_, group, cmd] = cmds.as_slice() else {
+    return None;
+  };
+  let kind = match group.get_name() {
+    "namespace" => CompleteKind::Namespace,
+    "cargo" => CompleteKind::Cargo,
+    "vm" => CompleteKind::Vm,
+    "resource" => CompleteKind::Resource,
+    "node" => CompleteKind::Node,
+    _ => return None,
+  };
+  if NEW_NAME_COMMANDS.contains(&cmd.get_name()) {
+    return None;
+  }
+  let arg = cmd.get_positionals().next()?;
+  if !matches!(arg.get_id().as_str(), "name" | "names") {
+    return None;
+  }
+  let multiple = matches!(arg.get_action(), clap::ArgAction::Append);
+  if positionals > 0 && !multiple {
+    return None;
+  }
+  Some(CompleteTarget { kind, namespace })
+}
+
+/// ## Cache path
+///
+/// Path of the cache file of a key in `$XDG_CACHE_HOME/nanocl/completion`
+/// or `$HOME/.cache/nanocl/completion`
+///
+/// ## Arguments
+///
+/// * [key](str) The key of the cached candidates
+///
+/// ## Return
+///
+/// * [Option](Option<PathBuf>) The path or none without a cache directory
+///
+fn cache_path(key: &str) -> Option<PathBuf> {
+  let dir = match std::env::var("XDG_CACHE_HOME") {
+    Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+    _ => PathBuf::from(std::env::var("HOME").ok()?).join(".cache"),
+  };
+  let file = key
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+    .collect::<String>();
+  Some(dir.join("nanocl").join("completion").join(file))
+}
+
+/// ## Read cache
+///
+/// Read the candidates cached for a key less than [CACHE_TTL](CACHE_TTL) ago
+///
+/// ## Arguments
+///
+/// * [key](str) The key of the cached candidates
+///
+/// ## Return
+///
+/// * [Option](Option<Vec<String>>) The candidates or none if missing or stale
+///
+pub fn read_cache(key: &str) -> Option<Vec<String>> {
+  let path = cache_path(key)?;
+  let modified = std::fs::metadata(&path).ok()?.modified().ok()?;
+  let age = SystemTime::now().duration_since(modified).ok()?;
+  if age > CACHE_TTL {
+    return None;
+  }
+  let content = std::fs::read_to_string(path).ok()?;
+  Some(content.lines().map(ToOwned::to_owned).collect())
+}
+
+/// ## Write cache
+///
+/// Cache the candidates of a key, failures are ignored
+/// since the cache is only an optimization
+///
+/// ## Arguments
+///
+/// * [key](str) The key of the cached candidates
+/// * [names](Vec<String>) The candidates to cache
+///
+pub fn write_cache(key: &str, names: &[String]) {
+  let Some(path) = cache_path(key) else {
+    return;
+  };
+  if let Some(dir) = path.parent() {
+    if std::fs::create_dir_all(dir).is_err() {
+      return;
+    }
+  }
+  let _ = std::fs::write(path, names.join("\n"));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn target(line: &str) -> Option<CompleteTarget> {
+    let mut words = line
+      .split_whitespace()
+      .map(String::from)
+      .collect::<Vec<_>>();
+    if line.ends_with(' ') {
+      words.push(String::new());
+    }
+    complete_target(&words)
+  }
+
+  fn kind(line: &str) -> Option<CompleteKind> {
+    target(line).map(|target| target.kind)
+  }
+
+  #[test]
+  fn complete_targets() {
+    assert_eq!(kind("cargo start "), Some(CompleteKind::Cargo));
+    assert_eq!(kind("cargo inspect my"), Some(CompleteKind::Cargo));
+    assert_eq!(kind("cargo rm -y a b "), Some(CompleteKind::Cargo));
+    assert_eq!(kind("vm rm "), Some(CompleteKind::Vm));
+    assert_eq!(kind("namespace inspect "), Some(CompleteKind::Namespace));
+    assert_eq!(kind("resource rm "), Some(CompleteKind::Resource));
+    assert_eq!(kind("node cordon "), Some(CompleteKind::Node));
+    assert_eq!(kind("cargo -n "), Some(CompleteKind::Namespace));
+    assert_eq!(kind("cargo --namespace "), Some(CompleteKind::Namespace));
+    assert_eq!(kind("cargo "), None);
+    assert_eq!(kind("cargo create "), None);
+    assert_eq!(kind("cargo run "), None);
+    assert_eq!(kind("cargo inspect a "), None);
+    assert_eq!(kind("cargo logs -"), None);
+    assert_eq!(kind("cargo image rm "), None);
+    assert_eq!(kind("cargo exec a -- "), None);
+    assert_eq!(kind("version "), None);
+  }
+
+  #[test]
+  fn complete_namespace() {
+    let found = target("cargo -n dev stop ").unwrap();
+    assert_eq!(found.namespace.as_deref(), Some("dev"));
+    let found = target("cargo --namespace=dev stop ").unwrap();
+    assert_eq!(found.namespace.as_deref(), Some("dev"));
+    let found = target("cargo stop ").unwrap();
+    assert_eq!(found.namespace, None);
+  }
+}
@@ -0,0 +1,174 @@
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead};
+
+use nanocl_utils::io_error::{IoError, IoResult, FromIo};
+
+/// ## Compression
+///
+/// Compression formats of a vm image that can be decompressed on the fly
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+  Gzip,
+  Xz,
+  Zstd,
+}
+
+impl Compression {
+  /// Name of the cargo feature that compile the decoder
+  fn feature(&self) -> &'static str {
+    match self {
+      Self::Gzip => "gzip",
+      Self::Xz => "xz",
+      Self::Zstd => "zstd",
+    }
+  }
+}
+
+/// ## Detect compression
+///
+/// Detect the compression of a file from its first bytes,
+/// or from the extension of its name when they aren't conclusive
+///
+/// ## Arguments
+///
+/// * [name](str) The path or url of the file
+/// * [magic](Vec<u8>) The first bytes of the file
+///
+/// ## Return
+///
+/// * [Option](Option<Compression>) The compression if the file is compressed
+///
+pub fn detect_compression(name: &str, magic: &[u8]) -> Option<Compression> {
+  if magic.starts_with(&[0x1f, 0x8b]) {
+    return Some(Compression::Gzip);
+  }
+  if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+    return Some(Compression::Xz);
+  }
+  if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+    return Some(Compression::Zstd);
+  }
+  // A full magic number was read so the extension is not trusted
+  if magic.len() >= 6 {
+    return None;
+  }
+  let path = name.split(['?', '#']).next().unwrap_or_default();
+  match path.rsplit('.').next() {
+    Some("gz") => Some(Compression::Gzip),
+    Some("xz") => Some(Compression::Xz),
+    Some("zst") => Some(Compression::Zstd),
+    _ => None,
+  }
+}
+
+/// ## Decoder
+///
+/// Wrap a reader with the decoder of the given compression
+///
+/// ## Arguments
+///
+/// * [reader](AsyncBufRead) The reader of the compressed data
+/// * [compression](Compression) The compression of the data
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](AsyncRead) The reader of the decompressed data
+///   * [Err](IoError) The decoder isn't compiled in
+///
+fn decoder<R>(
+  reader: R,
+  compression: Compression,
+) -> IoResult<Box<dyn AsyncRead + Unpin>>
+where
+  R: AsyncBufRead + Unpin + 'static,
+{
+  match compression {
+    #[cfg(feature = "gzip")]
+    Compression::Gzip => Ok(Box::new(
+      async_compression::tokio::bufread::GzipDecoder::new(reader),
+    )),
+    #[cfg(feature = "xz")]
+    Compression::Xz => Ok(Box::new(
+      async_compression::tokio::bufread::XzDecoder::new(reader),
+    )),
+    #[cfg(feature = "zstd")]
+    Compression::Zstd => Ok(Box::new(
+      async_compression::tokio::bufread::ZstdDecoder::new(reader),
+    )),
+    #[allow(unreachable_patterns)]
+    compression => Err(IoError::invalid_data(
+      "Decompress".to_owned(),
+      format!(
+        "the image is {compression:?} compressed but nanocl was built \
+        without the `{}` feature, decompress it first",
+        compression.feature()
+      ),
+    )),
+  }
+}
+
+/// ## Decompress
+///
+/// Detect the compression of a stream and decompress it on the fly,
+/// uncompressed streams are returned as is
+///
+/// ## Arguments
+///
+/// * [name](str) The path or url of the stream, its extension is used
+///   when the first bytes don't match a known compression
+/// * [reader](AsyncBufRead) The reader of the stream
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](AsyncRead) The reader of the decompressed stream
+///   * [Err](IoError) The stream can't be read or the decoder isn't compiled in
+///
+pub async fn decompress<R>(
+  name: &str,
+  mut reader: R,
+) -> IoResult<Box<dyn AsyncRead + Unpin>>
+where
+  R: AsyncBufRead + Unpin + 'static,
+{
+  let magic = reader
+    .fill_buf()
+    .await
+    .map_err(|err| err.map_err_context(|| name.to_owned()))?;
+  match detect_compression(name, magic) {
+    None => Ok(Box::new(reader)),
+    Some(compression) => decoder(reader, compression),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn detect_compression_magic() {
+    let gzip = [0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00];
+    let xz = [0xfd, b'7', b'z', b'X', b'Z', 0x00];
+    let zstd = [0x28, 0xb5, 0x2f, 0xfd, 0x00, 0x00];
+    let qcow = [b'Q', b'F', b'I', 0xfb, 0x00, 0x00];
+    assert_eq!(detect_compression("a.img", &gzip), Some(Compression::Gzip));
+    assert_eq!(detect_compression("a.img", &xz), Some(Compression::Xz));
+    assert_eq!(detect_compression("a.img", &zstd), Some(Compression::Zstd));
+    assert_eq!(detect_compression("a.img.gz", &qcow), None);
+  }
+
+  #[test]
+  fn detect_compression_extension() {
+    assert_eq!(detect_compression("a.img.gz", &[]), Some(Compression::Gzip));
+    assert_eq!(
+      detect_compression("https://a.io/a.img.xz?dl=1", &[]),
+      Some(Compression::Xz)
+    );
+    assert_eq!(
+      detect_compression("a.zst", &[0x00]),
+      Some(Compression::Zstd)
+    );
+    assert_eq!(detect_compression("a.img", &[]), None);
+  }
+}
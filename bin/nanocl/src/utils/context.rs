@@ -136,11 +136,15 @@ impl Context {
 
   /// ## Use
   ///
-  /// Use a context
+  /// Use a context, and set its default namespace when one is given.
+  /// An empty namespace remove the default namespace of the context.
   ///
   /// ## Arguments
   ///
+  /// * [config_path](Option<&str>) The path of the user configuration,
+  ///   none without `$HOME`
   /// * [name](str) The name of the context
+  /// * [namespace](Option<&str>) The default namespace of the context
   ///
   /// ## Return
   ///
@@ -148,8 +152,12 @@ impl Context {
   ///   * [Ok](()) The operation was successful
   ///   * [Err](Err) An error occured
   ///
-  pub fn r#use(name: &str) -> std::io::Result<()> {
-    let home = std::env::var("HOME").map_err(|_| {
+  pub fn r#use(
+    config_path: Option<&str>,
+    name: &str,
+    namespace: Option<&str>,
+  ) -> std::io::Result<()> {
+    let config_path = config_path.ok_or_else(|| {
       std::io::Error::new(std::io::ErrorKind::Other, "Could not get $HOME")
     })?;
     if name != "default" {
@@ -160,16 +168,26 @@ impl Context {
         )
       })?;
     }
-    let path = format!("{home}/.nanocl/conf.yml");
-    let mut config = UserConfig::new();
+    let mut config = UserConfig::read(config_path);
     config.current_context = name.to_string();
+    match namespace {
+      None => {}
+      Some("") => {
+        config.namespaces.remove(name);
+      }
+      Some(namespace) => {
+        config
+          .namespaces
+          .insert(name.to_owned(), namespace.to_owned());
+      }
+    }
     let s = serde_yaml::to_string(&config).map_err(|err| {
       std::io::Error::new(
         std::io::ErrorKind::InvalidData,
         format!("Could not serialize config: {err}"),
       )
     })?;
-    std::fs::write(path, s)?;
+    std::fs::write(config_path, s)?;
     Ok(())
   }
 }
@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+/// ## Parse duration
+///
+/// Parse a human readable duration made of a number and a unit
+/// `ms`, `s`, `m`, `h` or `d` like `500ms`, `30s`, `5m`, `2h` or `1d`.
+/// Unitless values are rejected since they are ambiguous, except `0`.
+///
+/// ## Arguments
+///
+/// * [value](str) The value to parse
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Duration) The parsed duration
+///   * [Err](String) The value is not a valid duration
+///
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+  let expected = "expected a value like 500ms, 30s, 5m, 2h or 1d";
+  if value == "0" {
+    return Ok(Duration::ZERO);
+  }
+  let unit_index = value
+    .find(|c: char| !c.is_ascii_digit())
+    .ok_or_else(|| format!("missing unit in duration `{value}`, {expected}"))?;
+  let (amount, unit) = value.split_at(unit_index);
+  let amount = amount
+    .parse::<u64>()
+    .map_err(|_| format!("invalid duration `{value}`, {expected}"))?;
+  let factor = match unit {
+    "ms" => 1,
+    "s" => 1_000,
+    "m" => 60 * 1_000,
+    "h" => 60 * 60 * 1_000,
+    "d" => 24 * 60 * 60 * 1_000,
+    _ => return Err(format!("invalid duration `{value}`, {expected}")),
+  };
+  let millis = amount
+    .checked_mul(factor)
+    .ok_or_else(|| format!("duration `{value}` is too large"))?;
+  Ok(Duration::from_millis(millis))
+}
+
+/// ## Parse interval
+///
+/// Parse a non zero duration with [parse_duration](parse_duration),
+/// used by options refreshing at a given interval like `--watch`
+///
+/// ## Arguments
+///
+/// * [value](str) The value to parse
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Duration) The parsed interval
+///   * [Err](String) The value is not a valid interval
+///
+pub fn parse_interval(value: &str) -> Result<Duration, String> {
+  let interval = parse_duration(value)?;
+  if interval.is_zero() {
+    return Err(format!("interval `{value}` must be greater than zero"));
+  }
+  Ok(interval)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn valid_durations() {
+    let cases = [
+      ("0", Duration::ZERO),
+      ("0s", Duration::ZERO),
+      ("500ms", Duration::from_millis(500)),
+      ("30s", Duration::from_secs(30)),
+      ("5m", Duration::from_secs(5 * 60)),
+      ("2h", Duration::from_secs(2 * 60 * 60)),
+      ("1d", Duration::from_secs(24 * 60 * 60)),
+    ];
+    for (value, expected) in cases {
+      assert_eq!(parse_duration(value), Ok(expected), "{value}");
+    }
+  }
+
+  #[test]
+  fn invalid_durations() {
+    let cases = [
+      "", "30", "1.5s", "-5s", "s", "5 m", "5M", "1h30m", "10us", "2w", " 30s",
+      "30s ",
+    ];
+    for value in cases {
+      assert!(parse_duration(value).is_err(), "{value}");
+    }
+    assert!(parse_duration("30").unwrap_err().contains("missing unit"));
+    assert!(parse_duration("999999999999999999d")
+      .unwrap_err()
+      .contains("too large"));
+  }
+
+  #[test]
+  fn intervals() {
+    assert_eq!(parse_interval("2s"), Ok(Duration::from_secs(2)));
+    assert!(parse_interval("0").is_err());
+    assert!(parse_interval("0ms").is_err());
+  }
+}
@@ -0,0 +1,200 @@
+use std::io::ErrorKind;
+use std::os::unix::net::UnixStream;
+
+use nanocl_utils::io_error::IoError;
+
+use crate::models::ErrorFormat;
+
+/// ## Exit code
+///
+/// Get a stable exit code for an error based on its kind
+///
+/// ## Arguments
+///
+/// * [err](IoError) The error
+///
+/// ## Return
+///
+/// * [i32](i32) The exit code
+///
+pub fn exit_code(err: &IoError) -> i32 {
+  match err.inner.kind() {
+    ErrorKind::InvalidInput => 2,
+    ErrorKind::NotFound => 3,
+    ErrorKind::AlreadyExists => 4,
+    ErrorKind::PermissionDenied => 5,
+    ErrorKind::ConnectionRefused
+    | ErrorKind::ConnectionReset
+    | ErrorKind::ConnectionAborted
+    | ErrorKind::NotConnected => 6,
+    ErrorKind::TimedOut => 7,
+    ErrorKind::InvalidData => 8,
+    ErrorKind::Interrupted => 130,
+    _ => 1,
+  }
+}
+
+/// ## Format error
+///
+/// Format an error to be printed on stderr
+///
+/// ## Arguments
+///
+/// * [format](ErrorFormat) The error format
+/// * [err](IoError) The error
+///
+/// ## Return
+///
+/// * [String](String) The formatted error
+///
+pub fn format_error(format: &ErrorFormat, err: &IoError) -> String {
+  match format {
+    ErrorFormat::Human => err.to_string(),
+    ErrorFormat::Json => serde_json::json!({
+      "error": {
+        "kind": format!("{:?}", err.inner.kind()),
+        "context": err.context,
+        "message": err.inner.to_string(),
+      },
+      "code": exit_code(err),
+    })
+    .to_string(),
+  }
+}
+
+/// ## Exit error
+///
+/// Print an error on stderr and exit,
+/// the exit code is derived from the error kind with the json format
+///
+/// ## Arguments
+///
+/// * [format](ErrorFormat) The error format
+/// * [err](IoError) The error
+///
+pub fn exit_error(format: &ErrorFormat, err: &IoError) -> ! {
+  eprintln!("{}", format_error(format, err));
+  match format {
+    ErrorFormat::Human => err.exit(),
+    ErrorFormat::Json => std::process::exit(exit_code(err)),
+  }
+}
+
+/// ## Explain unreachable
+///
+/// Replace the raw error of a daemon that couldn't be reached
+/// by a message telling why and what to do, with the resolved host.
+/// For a unix socket it check if the socket exists and if the user
+/// is allowed to connect to it.
+/// Errors of other kinds or about another target like a peer node
+/// are returned untouched.
+///
+/// ## Arguments
+///
+/// * [host](str) The resolved host of the daemon
+/// * [target](str) The socket path or url the client connect to
+/// * [err](IoError) The error of the command
+///
+/// ## Return
+///
+/// * [IoError](IoError) The explained error
+///
+pub fn explain_unreachable(host: &str, target: &str, err: IoError) -> IoError {
+  if !matches!(
+    err.inner.kind(),
+    ErrorKind::ConnectionRefused | ErrorKind::NotConnected
+  ) || err.context.as_deref() != Some(target)
+  {
+    return err;
+  }
+  let context = format!("Daemon {host}");
+  let Some(path) = host.strip_prefix("unix://") else {
+    return IoError::new(
+      context,
+      std::io::Error::new(
+        ErrorKind::ConnectionRefused,
+        "is unreachable, check the host and that nanocld is running on it",
+      ),
+    );
+  };
+  let (kind, message) = if !std::path::Path::new(path).exists() {
+    (
+      ErrorKind::ConnectionRefused,
+      format!(
+        "is not running, the socket {path} doesn't exist. \
+        Install it with `nanocl install` \
+        or start it if it's already installed"
+      ),
+    )
+  } else {
+    match UnixStream::connect(path) {
+      Err(err) if err.kind() == ErrorKind::PermissionDenied => (
+        ErrorKind::PermissionDenied,
+        format!(
+          "refused the connection, \
+          you don't have the permission to use {path}. \
+          Add yourself to the nanocl group with \
+          `sudo usermod -aG nanocl $USER` then `newgrp nanocl`"
+        ),
+      ),
+      _ => (
+        ErrorKind::ConnectionRefused,
+        format!(
+          "is not running, nothing listen on {path}. \
+          Start it or reinstall it with `nanocl install`"
+        ),
+      ),
+    }
+  };
+  IoError::new(context, std::io::Error::new(kind, message))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn format_json_error() {
+    let err = IoError::not_fount("Cargo", "my-cargo doesn't exist");
+    assert_eq!(exit_code(&err), 3);
+    let output = format_error(&ErrorFormat::Json, &err);
+    let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(output["code"], 3);
+    assert_eq!(output["error"]["kind"], "NotFound");
+    assert_eq!(output["error"]["context"], "Cargo");
+    assert_eq!(output["error"]["message"], "my-cargo doesn't exist");
+    let output = format_error(&ErrorFormat::Human, &err);
+    assert_eq!(output, "Cargo: My-cargo doesn't exist");
+  }
+
+  #[test]
+  fn explain_unreachable_daemon() {
+    let refused = |target: &str| {
+      IoError::new(
+        target,
+        std::io::Error::new(ErrorKind::ConnectionRefused, "Connection refused"),
+      )
+    };
+    let host = "unix:///tmp/nanocl-missing.sock";
+    let err = explain_unreachable(
+      host,
+      "/tmp/nanocl-missing.sock",
+      refused("/tmp/nanocl-missing.sock"),
+    );
+    assert_eq!(
+      err.context.as_deref(),
+      Some("Daemon unix:///tmp/nanocl-missing.sock")
+    );
+    assert_eq!(exit_code(&err), 6);
+    assert!(err.inner.to_string().contains("nanocl install"));
+    let host = "http://10.0.0.1:8585";
+    let err = explain_unreachable(host, host, refused(host));
+    assert_eq!(err.context.as_deref(), Some("Daemon http://10.0.0.1:8585"));
+    assert!(err.inner.to_string().contains("is unreachable"));
+    // Errors about a peer node are left untouched
+    let err = explain_unreachable(host, host, refused("http://10.0.0.2:8081"));
+    assert_eq!(err.context.as_deref(), Some("http://10.0.0.2:8081"));
+    let err = explain_unreachable(host, host, IoError::not_fount("Cargo", "a"));
+    assert_eq!(exit_code(&err), 3);
+  }
+}
@@ -17,7 +17,23 @@ pub fn calculate_SHA256(name: &str) -> String {
   let mut context = digest::Context::new(&digest::SHA256);
   context.update(name.as_bytes());
   let hash_value: digest::Digest = context.finish();
-  hash_value
+  to_hex(&hash_value)
+}
+
+/// ## To hex
+///
+/// Format a digest as a lowercase hexadecimal string
+///
+/// ## Arguments
+///
+/// * [digest](digest::Digest) The digest to format
+///
+/// ## Return
+///
+/// * [String](String) The hexadecimal digest
+///
+pub fn to_hex(digest: &digest::Digest) -> String {
+  digest
     .as_ref()
     .iter()
     .map(|byte| format!("{:02x}", byte))
@@ -0,0 +1,65 @@
+/// Binary units used to format a number of bytes
+const BYTE_UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+/// ## Format bytes
+///
+/// Format a number of bytes with a binary unit like `12.5 MiB`,
+/// bytes under 1 KiB are kept exact like `512 B`
+///
+/// ## Arguments
+///
+/// * [bytes](u64) The number of bytes
+///
+/// ## Return
+///
+/// * [String](String) The formatted size
+///
+pub fn format_bytes(bytes: u64) -> String {
+  let mut value = bytes as f64;
+  let mut unit = 0;
+  while value >= 1024.0 && unit < BYTE_UNITS.len() - 1 {
+    value /= 1024.0;
+    unit += 1;
+  }
+  match unit {
+    0 => format!("{bytes} B"),
+    _ => format!("{value:.1} {}", BYTE_UNITS[unit]),
+  }
+}
+
+/// ## Format count
+///
+/// Format a count followed by its unit like `4 vCPU`
+///
+/// ## Arguments
+///
+/// * [count](u64) The count
+/// * [unit](str) The unit of the count
+///
+/// ## Return
+///
+/// * [String](String) The formatted count
+///
+pub fn format_count(count: u64, unit: &str) -> String {
+  format!("{count} {unit}")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn bytes() {
+    assert_eq!(format_bytes(0), "0 B");
+    assert_eq!(format_bytes(512), "512 B");
+    assert_eq!(format_bytes(1024), "1.0 KiB");
+    assert_eq!(format_bytes(1536), "1.5 KiB");
+    assert_eq!(format_bytes(2048 * 1024 * 1024), "2.0 GiB");
+    assert_eq!(format_bytes(u64::MAX), "16777216.0 TiB");
+  }
+
+  #[test]
+  fn count() {
+    assert_eq!(format_count(4, "vCPU"), "4 vCPU");
+  }
+}
@@ -1,3 +1,5 @@
+use nanocl_utils::io_error::{IoError, IoResult};
+
 /// ## Calculate Percentage
 ///
 /// Calculate the percentage of a number
@@ -14,3 +16,59 @@
 pub fn calculate_percentage(current: u64, total: u64) -> u64 {
   ((current as f64 / total as f64) * 100_f64).round() as u64
 }
+
+/// ## Parse size
+///
+/// Parse a human friendly size like `2G`, `512M`, `64K` or bare bytes
+/// using binary multiples
+///
+/// ## Arguments
+///
+/// * [size](str) The size to parse
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](u64) The size in bytes
+///   * [Err](IoError) An error occured
+///
+pub fn parse_size(size: &str) -> IoResult<u64> {
+  let size = size.trim();
+  let (value, unit) = match size.find(|c: char| !c.is_ascii_digit()) {
+    Some(index) => size.split_at(index),
+    None => (size, ""),
+  };
+  let multiplier: u64 = match unit.to_ascii_uppercase().as_str() {
+    "" | "B" => 1,
+    "K" | "KB" => 1024,
+    "M" | "MB" => 1024 * 1024,
+    "G" | "GB" => 1024 * 1024 * 1024,
+    _ => {
+      return Err(IoError::invalid_input(
+        "Size",
+        format!("{size} has an unknown unit, expected K, M or G").as_str(),
+      ))
+    }
+  };
+  let value = value.parse::<u64>().map_err(|err| {
+    IoError::invalid_input("Size", format!("{size}: {err}").as_str())
+  })?;
+  value.checked_mul(multiplier).ok_or_else(|| {
+    IoError::invalid_input("Size", format!("{size} is too large").as_str())
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_size_units() {
+    assert_eq!(parse_size("2G").unwrap(), 2 * 1024 * 1024 * 1024);
+    assert_eq!(parse_size("512M").unwrap(), 512 * 1024 * 1024);
+    assert_eq!(parse_size("64k").unwrap(), 64 * 1024);
+    assert_eq!(parse_size("4096").unwrap(), 4096);
+    assert!(parse_size("12X").is_err());
+    assert!(parse_size("G").is_err());
+  }
+}
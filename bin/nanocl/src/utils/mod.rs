@@ -6,3 +6,16 @@ pub mod installer;
 pub mod dialog;
 pub mod context;
 pub mod hash;
+pub mod client;
+pub mod progress;
+pub mod tty;
+pub mod error;
+pub mod template;
+pub mod compression;
+pub mod archive;
+pub mod naming;
+pub mod record;
+pub mod complete;
+pub mod upload;
+pub use crate::models::duration;
+pub use crate::models::humanize;
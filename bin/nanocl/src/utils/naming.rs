@@ -0,0 +1,157 @@
+use nanocl_utils::io_error::{IoError, IoResult};
+
+/// Maximum length of a DNS label
+const MAX_NAME_LEN: usize = 63;
+
+/// ## Validate name
+///
+/// Check that a cargo or vm name is a valid DNS label:
+/// lowercase alphanumeric characters and hyphens, 1 to 63 characters,
+/// without a leading or trailing hyphen.
+/// Names end up in keys and dns entries that would break otherwise.
+///
+/// ## Arguments
+///
+/// * [kind](str) The kind of the named element like `Cargo`
+/// * [name](str) The name to validate
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The name is valid
+///   * [Err](IoError) The name is invalid with the rule it breaks
+///
+pub fn validate_name(kind: &str, name: &str) -> IoResult<()> {
+  let context = format!("{kind} name {name}");
+  if name.is_empty() {
+    return Err(IoError::invalid_input(
+      format!("{kind} name"),
+      "must not be empty".to_owned(),
+    ));
+  }
+  if let Some((index, c)) = name
+    .chars()
+    .enumerate()
+    .find(|(_, c)| !c.is_ascii_lowercase() && !c.is_ascii_digit() && *c != '-')
+  {
+    return Err(IoError::invalid_input(
+      context.clone(),
+      format!(
+        "has an invalid character `{c}` at position {}, \
+        only lowercase letters, digits and `-` are allowed",
+        index + 1
+      ),
+    ));
+  }
+  if name.len() > MAX_NAME_LEN {
+    return Err(IoError::invalid_input(
+      context.clone(),
+      format!("is {} characters long, at most {MAX_NAME_LEN}", name.len()),
+    ));
+  }
+  if name.starts_with('-') || name.ends_with('-') {
+    return Err(IoError::invalid_input(
+      context,
+      "must not start or end with `-`".to_owned(),
+    ));
+  }
+  Ok(())
+}
+
+/// ## Normalize name
+///
+/// Lowercase a name and replace its invalid characters by `-`
+/// then trim the hyphens and the characters over 63
+///
+/// ## Arguments
+///
+/// * [name](str) The name to normalize
+///
+/// ## Return
+///
+/// * [String](String) The normalized name
+///
+pub fn normalize_name(name: &str) -> String {
+  let name = name
+    .chars()
+    .map(|c| match c.to_ascii_lowercase() {
+      c if c.is_ascii_lowercase() || c.is_ascii_digit() => c,
+      _ => '-',
+    })
+    .collect::<String>();
+  let name = name.trim_matches('-');
+  name
+    .chars()
+    .take(MAX_NAME_LEN)
+    .collect::<String>()
+    .trim_end_matches('-')
+    .to_owned()
+}
+
+/// ## Resolve name
+///
+/// Validate the name of a cargo or vm to create,
+/// with `--normalize` it's normalized first and the new name printed
+///
+/// ## Arguments
+///
+/// * [kind](str) The kind of the named element like `Cargo`
+/// * [name](str) The name given by the user
+/// * [normalize](bool) The `--normalize` option
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](String) The name to use
+///   * [Err](IoError) The name is invalid
+///
+pub fn resolve_name(
+  kind: &str,
+  name: &str,
+  normalize: bool,
+) -> IoResult<String> {
+  if !normalize {
+    validate_name(kind, name)?;
+    return Ok(name.to_owned());
+  }
+  let normalized = normalize_name(name);
+  validate_name(kind, &normalized)?;
+  if normalized != name {
+    eprintln!("{kind} name {name} normalized to {normalized}");
+  }
+  Ok(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn validate() {
+    assert!(validate_name("Cargo", "my-cargo-1").is_ok());
+    assert!(validate_name("Cargo", "a").is_ok());
+    assert!(validate_name("Cargo", &"a".repeat(63)).is_ok());
+    assert!(validate_name("Cargo", "").is_err());
+    assert!(validate_name("Cargo", &"a".repeat(64)).is_err());
+    assert!(validate_name("Cargo", "My-cargo").is_err());
+    assert!(validate_name("Cargo", "my_cargo").is_err());
+    assert!(validate_name("Cargo", "my.cargo").is_err());
+    assert!(validate_name("Cargo", "-cargo").is_err());
+    assert!(validate_name("Cargo", "cargo-").is_err());
+  }
+
+  #[test]
+  fn normalize() {
+    assert_eq!(normalize_name("My_Cargo.1"), "my-cargo-1");
+    assert_eq!(normalize_name("_cargo_"), "cargo");
+    assert_eq!(normalize_name("é"), "");
+    assert_eq!(normalize_name(&"a".repeat(70)), "a".repeat(63));
+    assert_eq!(
+      normalize_name(&format!("{}_b", "a".repeat(62))),
+      "a".repeat(62)
+    );
+    assert_eq!(resolve_name("Vm", "My_Vm", true).unwrap(), "my-vm");
+    assert!(resolve_name("Vm", "My_Vm", false).is_err());
+    assert!(resolve_name("Vm", "___", true).is_err());
+  }
+}
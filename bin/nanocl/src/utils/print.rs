@@ -1,32 +1,791 @@
+use std::io::IsTerminal;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use futures::{Stream, StreamExt};
 use tabled::Table;
+use tabled::builder::Builder;
+use dialoguer::console;
 use tabled::settings::object::Segment;
 use tabled::settings::{Style, Modify, Padding, Alignment};
 
 use nanocl_utils::io_error::{IoResult, FromIo, IoError};
+use nanocld_client::stubs::cargo::{OutputKind, OutputLog};
+
+use crate::config::CliConfig;
+use crate::models::{DisplayFormat, PaginationOpts, TableStyle};
 
-use crate::models::DisplayFormat;
+/// ## Prefix colors
+///
+/// Colors given in turn to the prefixes of multiplexed logs
+///
+pub const PREFIX_COLORS: [console::Color; 6] = [
+  console::Color::Cyan,
+  console::Color::Green,
+  console::Color::Yellow,
+  console::Color::Magenta,
+  console::Color::Blue,
+  console::Color::Red,
+];
+
+/// ## Min column width
+///
+/// Columns are never truncated under this width to fit the terminal
+///
+const MIN_COLUMN_WIDTH: usize = 8;
+
+/// ## Id columns
+///
+/// Headers of the columns holding identifiers like uuids,
+/// they are never truncated with the global `--no-trunc` flag
+///
+const ID_COLUMNS: [&str; 2] = ["key", "id"];
+
+/// ## Json pretty
+///
+/// Whether json is indented on many lines or printed on a single line,
+/// set once for every command by [init_json](init_json)
+///
+static JSON_PRETTY: AtomicBool = AtomicBool::new(true);
 
 /// ## Print table
 ///
 /// Print a table from an iterator of [Tabled](tabled::Tabled) elements
+/// When an output format is given the rows are serialized in this format instead.
+/// In a terminal long values are truncated with an ellipsis so rows don't wrap,
+/// unless the global `--no-truncate` flag is set.
+/// The global `--no-trunc` flag only keeps the identifiers in full.
 ///
 /// ## Arguments
 ///
+/// * [cli_conf](CliConfig) The cli config holding the global `--output` and `--table-style` flags
 /// * [iter](IntoIterator) The iterator of [Tabled](tabled::Tabled) elements
 ///
-pub fn print_table<T>(iter: impl IntoIterator<Item = T>)
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+pub fn print_table<T>(
+  cli_conf: &CliConfig,
+  iter: impl IntoIterator<Item = T>,
+) -> IoResult<()>
 where
-  T: tabled::Tabled,
+  T: tabled::Tabled + serde::Serialize,
+{
+  print_table_without(cli_conf, iter, &[])
+}
+
+/// ## Print table without
+///
+/// Print a table like [print_table](print_table) without the given columns,
+/// used for optional columns only shown when requested
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config holding the global `--output` and `--table-style` flags
+/// * [iter](IntoIterator) The iterator of [Tabled](tabled::Tabled) elements
+/// * [hidden](Vec<&str>) The headers of the columns to hide
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+pub fn print_table_without<T>(
+  cli_conf: &CliConfig,
+  iter: impl IntoIterator<Item = T>,
+  hidden: &[&str],
+) -> IoResult<()>
+where
+  T: tabled::Tabled + serde::Serialize,
+{
+  let content = render_table_without(cli_conf, iter, hidden)?;
+  print!("{content}");
+  Ok(())
+}
+
+/// ## Render table without
+///
+/// Render a table like [print_table_without](print_table_without)
+/// into a string instead of printing it
+///
+/// ## Arguments
+///
+/// * [cli_conf](CliConfig) The cli config holding the global `--output` and `--table-style` flags
+/// * [iter](IntoIterator) The iterator of [Tabled](tabled::Tabled) elements
+/// * [hidden](Vec<&str>) The headers of the columns to hide
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](String) The rendered table ending with a new line
+///   * [Err](IoError) An error occured
+///
+pub fn render_table_without<T>(
+  cli_conf: &CliConfig,
+  iter: impl IntoIterator<Item = T>,
+  hidden: &[&str],
+) -> IoResult<String>
+where
+  T: tabled::Tabled + serde::Serialize,
 {
-  let table = Table::new(iter)
-    .with(Style::empty())
-    .with(
+  if let Some(format) = cli_conf.structured_output() {
+    let rows = iter.into_iter().collect::<Vec<T>>();
+    // A toml document must be a table so rows are written as an array of tables
+    // and env keys are prefixed by `ROWS` to be valid variable names
+    if let DisplayFormat::Toml | DisplayFormat::Env = format {
+      return to_display_format(format, HashMap::from([("Rows", rows)]));
+    }
+    return to_display_format(format, rows);
+  }
+  let is_terminal = std::io::stdout().is_terminal();
+  let max_width = match (cli_conf.no_truncate, is_terminal) {
+    (false, true) => Some(console::Term::stdout().size().1 as usize),
+    _ => None,
+  };
+  let hidden = match cli_conf.is_wide() {
+    true => &[],
+    false => hidden,
+  };
+  let mut table = build_table(
+    iter,
+    hidden,
+    max_width,
+    cli_conf.no_trunc,
+    &cli_conf.table_style,
+  );
+  match &cli_conf.table_style {
+    None => table.with(Style::empty()).with(
       Modify::new(Segment::all())
         .with(Padding::new(0, 4, 0, 0))
         .with(Alignment::left()),
+    ),
+    Some(TableStyle::Unicode) if is_utf8_locale(current_locale()) => {
+      table.with(Style::modern())
+    }
+    Some(TableStyle::Unicode | TableStyle::Ascii) => table.with(Style::ascii()),
+    Some(TableStyle::Markdown) => table.with(Style::markdown()),
+  };
+  Ok(format!("{table}\n"))
+}
+
+/// ## Is border line
+///
+/// Check if a line of a rendered table is a border without any cell value
+///
+fn is_border_line(line: &str) -> bool {
+  line
+    .chars()
+    .all(|c| c.is_whitespace() || "-+|:─│┌┐└┘├┤┬┴┼".contains(c))
+}
+
+/// ## Row indexes
+///
+/// Map each line of a rendered table to the index of the row it displays,
+/// border lines and the header are mapped to `None`
+///
+/// ## Arguments
+///
+/// * [table](str) The rendered table
+///
+/// ## Return
+///
+/// * [Vec](Vec<Option<usize>>) The row index of each line
+///
+fn row_indexes(table: &str) -> Vec<Option<usize>> {
+  let mut value_lines: usize = 0;
+  table
+    .lines()
+    .map(|line| {
+      if is_border_line(line) {
+        return None;
+      }
+      value_lines += 1;
+      // The first line with values is the header
+      value_lines.checked_sub(2)
+    })
+    .collect()
+}
+
+/// ## Highlight rows
+///
+/// Highlight the rows of a rendered table, border lines and the header
+/// are skipped so it works with every table style
+///
+/// ## Arguments
+///
+/// * [table](str) The rendered table
+/// * [highlighted](Vec<bool>) Whether each row should be highlighted
+///
+/// ## Return
+///
+/// * [String](String) The table with the rows highlighted
+///
+pub fn highlight_rows(table: &str, highlighted: &[bool]) -> String {
+  table
+    .lines()
+    .zip(row_indexes(table))
+    .map(
+      |(line, index)| match index.and_then(|index| highlighted.get(index)) {
+        Some(true) => console::style(line).yellow().bold().to_string(),
+        _ => line.to_owned(),
+      },
     )
-    .to_string();
-  println!("{table}");
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// ## Column cells
+///
+/// Locate the values of a column in each line of a rendered table,
+/// they start where the header of the column starts with every table style
+///
+/// ## Arguments
+///
+/// * [table](str) The rendered table
+/// * [header](str) The header of the column
+///
+/// ## Return
+///
+/// * [Vec](Vec<Option<(usize, usize)>>) The char range of the value of each line,
+///   none for the header, the borders or when the column isn't shown
+///
+fn column_cells(table: &str, header: &str) -> Vec<Option<(usize, usize)>> {
+  let indexes = row_indexes(table);
+  let is_end = |c: Option<char>| c.map_or(true, |c| c.is_whitespace());
+  let start = table
+    .lines()
+    .zip(&indexes)
+    .find(|(line, index)| index.is_none() && !is_border_line(line))
+    .and_then(|(line, _)| {
+      line.match_indices(header).find_map(|(pos, _)| {
+        let before = line[..pos].chars().next_back();
+        let after = line[pos + header.len()..].chars().next();
+        let after_border = before.is_some_and(|c| "|│".contains(c));
+        ((is_end(before) || after_border) && is_end(after))
+          .then(|| line[..pos].chars().count())
+      })
+    });
+  let Some(start) = start else {
+    return vec![None; indexes.len()];
+  };
+  table
+    .lines()
+    .zip(indexes)
+    .map(|(line, index)| {
+      index?;
+      let len = line
+        .chars()
+        .skip(start)
+        .take_while(|c| !c.is_whitespace() && !"|│".contains(*c))
+        .count();
+      (len > 0).then_some((start, start + len))
+    })
+    .collect()
+}
+
+/// ## Color column
+///
+/// Color the values of a column of a rendered table like
+/// [highlight_rows](highlight_rows), tables are colored once rendered
+/// as the width of their cells don't account for colors
+///
+/// ## Arguments
+///
+/// * [table](str) The rendered table
+/// * [header](str) The header of the column
+/// * [color](Fn) The color of a value, none to keep it as is
+///
+/// ## Return
+///
+/// * [String](String) The table with the values of the column colored
+///
+pub fn color_column(
+  table: &str,
+  header: &str,
+  color: impl Fn(&str) -> Option<console::Color>,
+) -> String {
+  let mut colored = table
+    .lines()
+    .zip(column_cells(table, header))
+    .map(|(line, cell)| {
+      let Some((start, end)) = cell else {
+        return line.to_owned();
+      };
+      let chars = line.chars().collect::<Vec<_>>();
+      let value = chars[start..end].iter().collect::<String>();
+      let Some(color) = color(&value) else {
+        return line.to_owned();
+      };
+      format!(
+        "{}{}{}",
+        chars[..start].iter().collect::<String>(),
+        console::style(value).fg(color),
+        chars[end..].iter().collect::<String>()
+      )
+    })
+    .collect::<Vec<_>>()
+    .join("\n");
+  if table.ends_with('\n') {
+    colored.push('\n');
+  }
+  colored
+}
+
+/// ## Redraw
+///
+/// Render lines in place of the previous render,
+/// the cursor is moved back to the top left corner and each line cleared
+/// before being written to avoid the flicker of a full screen clear
+///
+/// ## Arguments
+///
+/// * [term](console::Term) The terminal to render to
+/// * [lines](IntoIterator) The lines to render
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+pub fn redraw<'a>(
+  term: &console::Term,
+  lines: impl IntoIterator<Item = &'a str>,
+) -> IoResult<()> {
+  term
+    .move_cursor_to(0, 0)
+    .map_err(|err| err.map_err_context(|| "Redraw"))?;
+  for line in lines {
+    term
+      .clear_line()
+      .map_err(|err| err.map_err_context(|| "Redraw"))?;
+    term
+      .write_line(line)
+      .map_err(|err| err.map_err_context(|| "Redraw"))?;
+  }
+  term
+    .clear_to_end_of_screen()
+    .map_err(|err| err.map_err_context(|| "Redraw"))?;
+  Ok(())
+}
+
+/// ## Print diff
+///
+/// Print the lines of a unified diff with the headers in bold,
+/// the removed lines in red and the added lines in green
+///
+/// ## Arguments
+///
+/// * [lines](Vec<String>) The lines of the diff
+///
+pub fn print_diff(lines: Vec<String>) {
+  for line in lines {
+    let line = if line.starts_with("---") || line.starts_with("+++") {
+      console::style(line).bold()
+    } else if line.starts_with('-') {
+      console::style(line).red()
+    } else {
+      console::style(line).green()
+    };
+    println!("{line}");
+  }
+}
+
+/// ## TreeNode
+///
+/// A node of a tree rendered by [render_tree](render_tree)
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+  /// Text of the node
+  pub label: String,
+  /// Nodes indented under this one
+  pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+  /// ## New
+  ///
+  /// Create a node with its children
+  ///
+  pub fn new(label: impl Into<String>, children: Vec<TreeNode>) -> Self {
+    Self {
+      label: label.into(),
+      children,
+    }
+  }
+}
+
+/// ## Render tree
+///
+/// Render root nodes without indentation and their children
+/// indented under them with branches, like the `tree` command
+///
+/// ## Arguments
+///
+/// * [roots](Vec<TreeNode>) The root nodes
+/// * [unicode](bool) Draw the branches with box-drawing characters
+///
+/// ## Return
+///
+/// * [String](String) The rendered tree, one line per node
+///
+pub fn render_tree(roots: &[TreeNode], unicode: bool) -> String {
+  let mut out = String::new();
+  for root in roots {
+    out.push_str(&root.label);
+    out.push('\n');
+    push_tree_children(&mut out, &root.children, "", unicode);
+  }
+  out
+}
+
+fn push_tree_children(
+  out: &mut String,
+  children: &[TreeNode],
+  indent: &str,
+  unicode: bool,
+) {
+  let (branch, last, pipe) = match unicode {
+    true => ("├── ", "└── ", "│   "),
+    false => ("|-- ", "`-- ", "|   "),
+  };
+  for (index, child) in children.iter().enumerate() {
+    let is_last = index == children.len() - 1;
+    out.push_str(indent);
+    out.push_str(if is_last { last } else { branch });
+    out.push_str(&child.label);
+    out.push('\n');
+    let indent = format!("{indent}{}", if is_last { "    " } else { pipe });
+    push_tree_children(out, &child.children, &indent, unicode);
+  }
+}
+
+/// ## Print tree
+///
+/// Print a tree with [render_tree](render_tree), branches are drawn
+/// with box-drawing characters when the locale supports them
+///
+/// ## Arguments
+///
+/// * [roots](Vec<TreeNode>) The root nodes
+///
+pub fn print_tree(roots: &[TreeNode]) {
+  print!("{}", render_tree(roots, is_utf8_locale(current_locale())));
+}
+
+/// ## Paginate
+///
+/// Keep the rows of the page selected by `--offset` and `--limit`
+///
+/// ## Arguments
+///
+/// * [rows](Vec) The filtered and sorted rows
+/// * [page](PaginationOpts) The pagination options
+///
+/// ## Return
+///
+/// * [Tuple](Tuple) The rows of the page and a footer like `showing 20 of 134`
+///   when some rows are not shown
+///
+pub fn paginate<T>(
+  rows: Vec<T>,
+  page: &PaginationOpts,
+) -> (Vec<T>, Option<String>) {
+  let total = rows.len();
+  let rows = rows
+    .into_iter()
+    .skip(page.offset)
+    .take(page.limit.unwrap_or(usize::MAX))
+    .collect::<Vec<T>>();
+  let footer =
+    (rows.len() < total).then(|| format!("showing {} of {total}", rows.len()));
+  (rows, footer)
+}
+
+/// ## Build table
+///
+/// Build a table without the hidden columns.
+/// With a maximum width the widest columns are shrinked to fit in it,
+/// values of the key column are elided from the middle to keep the prefix and the suffix,
+/// other values are elided from the end.
+/// With full ids the [ID_COLUMNS](ID_COLUMNS) are never shrinked.
+///
+/// ## Arguments
+///
+/// * [iter](IntoIterator) The iterator of [Tabled](tabled::Tabled) elements
+/// * [hidden](Vec<&str>) The headers of the columns to hide
+/// * [max_width](Option<usize>) The width of the terminal if values are truncated
+/// * [full_ids](bool) Keep the values of the id columns in full
+/// * [style](Option<TableStyle>) The style of the table to know the size of the borders
+///
+/// ## Return
+///
+/// * [Table](Table) The table
+///
+fn build_table<T>(
+  iter: impl IntoIterator<Item = T>,
+  hidden: &[&str],
+  max_width: Option<usize>,
+  full_ids: bool,
+  style: &Option<TableStyle>,
+) -> Table
+where
+  T: tabled::Tabled,
+{
+  let shown = T::headers()
+    .iter()
+    .map(|header| !hidden.contains(&header.as_ref()))
+    .collect::<Vec<_>>();
+  let keep = |values: Vec<String>| {
+    values
+      .into_iter()
+      .zip(&shown)
+      .filter(|(_, shown)| **shown)
+      .map(|(value, _)| value)
+      .collect::<Vec<_>>()
+  };
+  let headers = keep(T::headers().into_iter().map(|h| h.to_string()).collect());
+  let rows = iter
+    .into_iter()
+    .map(|row| keep(row.fields().into_iter().map(|f| f.to_string()).collect()))
+    .collect::<Vec<_>>();
+  let mut builder = Builder::default();
+  builder.set_header(headers.clone());
+  let Some(max_width) = max_width else {
+    for row in rows {
+      builder.push_record(row);
+    }
+    return builder.build();
+  };
+  let widths = headers
+    .iter()
+    .enumerate()
+    .map(|(index, header)| {
+      rows
+        .iter()
+        .map(|row| console::measure_text_width(&row[index]))
+        .fold(console::measure_text_width(header), usize::max)
+    })
+    .collect::<Vec<_>>();
+  // Padding or borders added around each column
+  let borders = match style {
+    None => 4 * widths.len(),
+    Some(_) => 3 * widths.len() + 1,
+  };
+  let fixed = headers
+    .iter()
+    .map(|header| {
+      full_ids && ID_COLUMNS.contains(&header.to_lowercase().as_str())
+    })
+    .collect::<Vec<_>>();
+  let widths = fit_columns(&widths, &fixed, max_width.saturating_sub(borders));
+  let ellipsis = match is_utf8_locale(current_locale()) {
+    true => "…",
+    false => "...",
+  };
+  for row in rows {
+    let row = row.into_iter().enumerate().map(|(index, field)| {
+      let width = widths[index];
+      if console::measure_text_width(&field) <= width {
+        return field;
+      }
+      match headers[index].to_lowercase().as_str() {
+        "key" => elide_middle(&field, width, ellipsis),
+        _ => console::truncate_str(&field, width, ellipsis).to_string(),
+      }
+    });
+    builder.push_record(row);
+  }
+  builder.build()
+}
+
+/// ## Fit columns
+///
+/// Shrink the widest columns until their sum fit in the given width,
+/// columns are not shrinked under [MIN_COLUMN_WIDTH](MIN_COLUMN_WIDTH)
+/// and fixed columns are not shrinked at all
+///
+/// ## Arguments
+///
+/// * [widths](Vec<usize>) The width of each column
+/// * [fixed](Vec<bool>) Whether each column keep its width
+/// * [max_width](usize) The width available for the columns
+///
+/// ## Return
+///
+/// * [Vec<usize>](Vec<usize>) The new width of each column
+///
+fn fit_columns(
+  widths: &[usize],
+  fixed: &[bool],
+  max_width: usize,
+) -> Vec<usize> {
+  let mut widths = widths.to_vec();
+  while widths.iter().sum::<usize>() > max_width {
+    let Some((widest, _)) = widths
+      .iter_mut()
+      .zip(fixed)
+      .filter(|(width, fixed)| **width > MIN_COLUMN_WIDTH && !**fixed)
+      .max_by_key(|(width, _)| **width)
+    else {
+      break;
+    };
+    *widest -= 1;
+  }
+  widths
+}
+
+/// ## Elide middle
+///
+/// Replace the middle of a value by an ellipsis so it fit in the given width
+///
+/// ## Arguments
+///
+/// * [value](str) The value to elide
+/// * [width](usize) The maximum width of the value
+/// * [ellipsis](str) The ellipsis to insert
+///
+/// ## Return
+///
+/// * [String](String) The elided value
+///
+fn elide_middle(value: &str, width: usize, ellipsis: &str) -> String {
+  let chars = value.chars().collect::<Vec<_>>();
+  if chars.len() <= width {
+    return value.to_owned();
+  }
+  let kept = width.saturating_sub(console::measure_text_width(ellipsis));
+  let tail = kept / 2;
+  let head = kept - tail;
+  let head = chars[..head].iter().collect::<String>();
+  let tail = chars[chars.len() - tail..].iter().collect::<String>();
+  format!("{head}{ellipsis}{tail}")
+}
+
+/// ## Current locale
+///
+/// Get the locale used for the characters encoding,
+/// the first set of `LC_ALL`, `LC_CTYPE` and `LANG`
+///
+/// ## Return
+///
+/// * [Option](Option) The locale if any
+///
+fn current_locale() -> Option<String> {
+  ["LC_ALL", "LC_CTYPE", "LANG"]
+    .into_iter()
+    .filter_map(|name| std::env::var(name).ok())
+    .find(|value| !value.is_empty())
+}
+
+/// ## Is utf8 locale
+///
+/// Check if a locale eg: `en_US.UTF-8` use the UTF-8 encoding
+///
+/// ## Arguments
+///
+/// * [locale](Option<String>) The locale
+///
+/// ## Return
+///
+/// * [bool](bool) True if the locale use UTF-8
+///
+fn is_utf8_locale(locale: Option<String>) -> bool {
+  match locale {
+    Some(locale) => {
+      let locale = locale.to_lowercase();
+      locale.contains("utf-8") || locale.contains("utf8")
+    }
+    None => false,
+  }
+}
+
+/// ## Init color
+///
+/// Disable colors of every output when the `--no-color` flag is given
+/// or the `NO_COLOR` env variable is set to a non empty value
+///
+/// ## Arguments
+///
+/// * [no_color](bool) The value of the `--no-color` flag
+///
+pub fn init_color(no_color: bool) {
+  let no_color_env =
+    std::env::var("NO_COLOR").is_ok_and(|value| !value.is_empty());
+  if no_color || no_color_env {
+    dialoguer::console::set_colors_enabled(false);
+    dialoguer::console::set_colors_enabled_stderr(false);
+  }
+}
+
+/// ## Resolve json pretty
+///
+/// Json is pretty printed in a terminal and compact when piped,
+/// unless the global `--pretty` or `--compact` flag is given
+///
+/// ## Arguments
+///
+/// * [pretty](bool) The value of the `--pretty` flag
+/// * [compact](bool) The value of the `--compact` flag
+/// * [is_terminal](bool) True if stdout is a terminal
+///
+/// ## Return
+///
+/// * [bool](bool) True if json must be pretty printed
+///
+fn resolve_json_pretty(pretty: bool, compact: bool, is_terminal: bool) -> bool {
+  match (pretty, compact) {
+    (true, _) => true,
+    (_, true) => false,
+    _ => is_terminal,
+  }
+}
+
+/// ## Init json
+///
+/// Choose how json is printed by every command,
+/// see [resolve_json_pretty](resolve_json_pretty)
+///
+/// ## Arguments
+///
+/// * [pretty](bool) The value of the `--pretty` flag
+/// * [compact](bool) The value of the `--compact` flag
+///
+pub fn init_json(pretty: bool, compact: bool) {
+  let pretty =
+    resolve_json_pretty(pretty, compact, std::io::stdout().is_terminal());
+  JSON_PRETTY.store(pretty, Ordering::Relaxed);
+}
+
+/// ## To json
+///
+/// Serialize data to json, pretty or compact as chosen by [init_json](init_json)
+///
+/// ## Arguments
+///
+/// * [data](serde::Serialize) The serializable data
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](String) The json
+///   * [Err](IoError) An error occured
+///
+pub fn to_json<T>(data: &T) -> IoResult<String>
+where
+  T: serde::Serialize + ?Sized,
+{
+  let json = match JSON_PRETTY.load(Ordering::Relaxed) {
+    true => serde_json::to_string_pretty(data),
+    false => serde_json::to_string(data),
+  };
+  let json = json.map_err(|err| err.map_err_context(|| "Print json"))?;
+  Ok(json)
 }
 
 /// ## Print yml
@@ -71,9 +830,8 @@ pub fn print_json<T>(data: T) -> IoResult<()>
 where
   T: serde::Serialize,
 {
-  let json = serde_json::to_string_pretty(&data)
-    .map_err(|err| err.map_err_context(|| "Print json"))?;
-  print!("{json}");
+  let json = to_json(&data)?;
+  println!("{json}");
   Ok(())
 }
 
@@ -95,16 +853,42 @@ pub fn print_toml<T>(data: T) -> IoResult<()>
 where
   T: serde::Serialize,
 {
-  let toml = toml::to_string(&data).map_err(|err| {
-    IoError::new(
-      "Print toml",
-      std::io::Error::new(std::io::ErrorKind::InvalidData, err),
-    )
-  })?;
+  let toml = to_toml(&data)?;
   print!("{toml}");
   Ok(())
 }
 
+/// ## To toml
+///
+/// Serialize data to toml
+/// Not every structure is expressible in toml (null values, non table root...)
+/// in this case an error suggesting another format is returned
+///
+/// ## Arguments
+///
+/// * [data](serde::Serialize) The serializable data
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](String) The toml document
+///   * [Err](IoError) The data can't be expressed in toml
+///
+fn to_toml<T>(data: &T) -> IoResult<String>
+where
+  T: serde::Serialize,
+{
+  toml::to_string(data).map_err(|err| {
+    IoError::invalid_data(
+      "Print toml".to_owned(),
+      format!(
+        "This structure can't be expressed in toml ({}), try with `-o yaml`",
+        err.to_string().trim()
+      ),
+    )
+  })
+}
+
 /// ## Display format
 ///
 /// Display data in a specific format
@@ -121,12 +905,487 @@ where
 ///   * [Err](IoError) An error occured
 ///
 pub fn display_format<T>(format: &DisplayFormat, data: T) -> IoResult<()>
+where
+  T: serde::Serialize,
+{
+  display_format_prefixed(format, "", data)
+}
+
+/// ## Display format prefixed
+///
+/// Display data in a specific format like [display_format](display_format),
+/// keys of the env format start with the given prefix like `CARGO`
+///
+/// ## Arguments
+///
+/// * [format](DisplayFormat) The format to display the data
+/// * [prefix](str) The prefix of the env keys
+/// * [data](serde::Serialize) The serializable data
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The operation was successful
+///   * [Err](IoError) An error occured
+///
+pub fn display_format_prefixed<T>(
+  format: &DisplayFormat,
+  prefix: &str,
+  data: T,
+) -> IoResult<()>
 where
   T: serde::Serialize,
 {
   match format {
-    DisplayFormat::Yaml => print_yml(data),
+    DisplayFormat::Yaml | DisplayFormat::Wide => print_yml(data),
     DisplayFormat::Toml => print_toml(data),
     DisplayFormat::Json => print_json(data),
+    DisplayFormat::Env => {
+      print!("{}", to_env(prefix, &data)?);
+      Ok(())
+    }
+  }
+}
+
+/// ## To display format
+///
+/// Serialize data in the given format instead of printing it
+///
+/// ## Arguments
+///
+/// * [format](DisplayFormat) The format to use
+/// * [data](serde::Serialize) The serializable data
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](String) The serialized data
+///   * [Err](IoError) An error occured
+///
+pub fn to_display_format<T>(format: &DisplayFormat, data: T) -> IoResult<String>
+where
+  T: serde::Serialize,
+{
+  let content = match format {
+    DisplayFormat::Yaml => serde_yaml::to_string(&data)
+      .map_err(|err| err.map_err_context(|| "Print yaml"))?,
+    DisplayFormat::Toml => to_toml(&data)?,
+    DisplayFormat::Json => format!("{}\n", to_json(&data)?),
+    DisplayFormat::Env => to_env("", &data)?,
+    // `wide` only apply to tables
+    DisplayFormat::Wide => to_display_format(&DisplayFormat::Yaml, data)?,
+  };
+  Ok(content)
+}
+
+/// ## Env key
+///
+/// Convert a key to an uppercase env variable name segment,
+/// `NamespaceName` become `NAMESPACE_NAME` and characters
+/// not allowed in a variable name like `.` or `-` become `_`
+///
+/// ## Arguments
+///
+/// * [key](str) The key to convert
+///
+/// ## Return
+///
+/// * [String](String) The env key segment
+///
+fn env_key(key: &str) -> String {
+  let chars = key.chars().collect::<Vec<char>>();
+  let mut env_key = String::new();
+  for (index, c) in chars.iter().enumerate() {
+    if !c.is_ascii_alphanumeric() {
+      env_key.push('_');
+      continue;
+    }
+    if c.is_ascii_uppercase() && index > 0 {
+      let prev = chars[index - 1];
+      let next_lower = chars
+        .get(index + 1)
+        .map(|next| next.is_ascii_lowercase())
+        .unwrap_or(false);
+      if prev.is_ascii_lowercase()
+        || prev.is_ascii_digit()
+        || (prev.is_ascii_uppercase() && next_lower)
+      {
+        env_key.push('_');
+      }
+    }
+    env_key.push(c.to_ascii_uppercase());
+  }
+  env_key
+}
+
+/// ## Env value
+///
+/// Quote a value for a shell when it contains characters
+/// with a special meaning, single quotes are escaped
+///
+/// ## Arguments
+///
+/// * [value](str) The value to quote
+///
+/// ## Return
+///
+/// * [String](String) The value safe to `eval`
+///
+fn env_value(value: &str) -> String {
+  let is_safe = !value.is_empty()
+    && value
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || "_-.,:/@%+=".contains(c));
+  match is_safe {
+    true => value.to_owned(),
+    false => format!("'{}'", value.replace('\'', "'\\''")),
+  }
+}
+
+/// ## Flatten env
+///
+/// Push a `KEY=value` line for each scalar of a json value,
+/// null values are skipped
+///
+/// ## Arguments
+///
+/// * [key](str) The key of the value
+/// * [value](serde_json::Value) The value to flatten
+/// * [lines](Vec<String>) The lines to push to
+///
+fn flatten_env(key: &str, value: &serde_json::Value, lines: &mut Vec<String>) {
+  let join = |segment: String| match key.is_empty() {
+    // A variable name can't start with a digit
+    true if segment.starts_with(|c: char| c.is_ascii_digit()) => {
+      format!("_{segment}")
+    }
+    true => segment,
+    false => format!("{key}_{segment}"),
+  };
+  match value {
+    serde_json::Value::Null => {}
+    serde_json::Value::Object(map) => {
+      for (name, value) in map {
+        flatten_env(&join(env_key(name)), value, lines);
+      }
+    }
+    serde_json::Value::Array(items) => {
+      for (index, value) in items.iter().enumerate() {
+        flatten_env(&join(index.to_string()), value, lines);
+      }
+    }
+    serde_json::Value::String(value) => {
+      lines.push(format!("{key}={}", env_value(value)));
+    }
+    value => lines.push(format!("{key}={value}")),
+  }
+}
+
+/// ## To env
+///
+/// Serialize data to `KEY=value` lines that can be sourced in a shell.
+/// Nested keys are joined with underscores in uppercase,
+/// array items are indexed like `PREFIX_ITEMS_0` and map keys
+/// are converted like struct fields
+///
+/// ## Arguments
+///
+/// * [prefix](str) The prefix of every key, can be empty
+/// * [data](serde::Serialize) The serializable data
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](String) The env lines each ending with a new line
+///   * [Err](IoError) An error occured
+///
+pub fn to_env<T>(prefix: &str, data: &T) -> IoResult<String>
+where
+  T: serde::Serialize,
+{
+  let value = serde_json::to_value(data)
+    .map_err(|err| err.map_err_context(|| "Print env"))?;
+  let mut lines = Vec::new();
+  flatten_env(prefix, &value, &mut lines);
+  Ok(lines.iter().map(|line| format!("{line}\n")).collect())
+}
+
+/// ## Prefix lines
+///
+/// Prefix every complete line of a chunk of logs,
+/// an incomplete last line is kept in `pending` until the next chunk
+///
+/// ## Arguments
+///
+/// * [prefix](str) The prefix of each line
+/// * [pending](String) The incomplete line of the previous chunk
+/// * [data](str) The chunk of logs
+///
+/// ## Return
+///
+/// * [Vec](Vec<String>) The prefixed complete lines without line ending
+///
+pub fn prefix_lines(
+  prefix: &str,
+  pending: &mut String,
+  data: &str,
+) -> Vec<String> {
+  pending.push_str(data);
+  let Some(end) = pending.rfind('\n') else {
+    return Vec::new();
+  };
+  let lines = pending[..end]
+    .split('\n')
+    .map(|line| format!("{prefix}{}", line.trim_end_matches('\r')))
+    .collect();
+  pending.drain(..=end);
+  lines
+}
+
+/// ## Print prefixed logs
+///
+/// Print a stream of logs with each line prefixed,
+/// stderr lines are printed on stderr and the others on stdout
+///
+/// ## Arguments
+///
+/// * [stream](Stream) The stream of logs
+/// * [prefix](str) The prefix of each line
+///
+pub async fn print_prefixed_logs<S, E>(mut stream: S, prefix: &str)
+where
+  S: Stream<Item = Result<OutputLog, E>> + Unpin,
+  E: std::fmt::Display,
+{
+  let mut stdout_pending = String::new();
+  let mut stderr_pending = String::new();
+  while let Some(output) = stream.next().await {
+    let output = match output {
+      Ok(output) => output,
+      Err(err) => {
+        eprintln!("{prefix}Error: {err}");
+        break;
+      }
+    };
+    match output.kind {
+      OutputKind::StdErr => {
+        prefix_lines(prefix, &mut stderr_pending, &output.data)
+          .into_iter()
+          .for_each(|line| eprintln!("{line}"));
+      }
+      OutputKind::StdOut | OutputKind::Console => {
+        prefix_lines(prefix, &mut stdout_pending, &output.data)
+          .into_iter()
+          .for_each(|line| println!("{line}"));
+      }
+      OutputKind::StdIn => {}
+    }
+  }
+  if !stdout_pending.is_empty() {
+    println!("{prefix}{stdout_pending}");
+  }
+  if !stderr_pending.is_empty() {
+    eprintln!("{prefix}{stderr_pending}");
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn prefix_lines_keep_pending() {
+    let mut pending = String::new();
+    assert!(prefix_lines("[a] ", &mut pending, "hel").is_empty());
+    assert_eq!(
+      prefix_lines("[a] ", &mut pending, "lo\r\nwor"),
+      vec!["[a] hello"]
+    );
+    assert_eq!(pending, "wor");
+    assert_eq!(
+      prefix_lines("[a] ", &mut pending, "ld\n\nbye\n"),
+      vec!["[a] world", "[a] ", "[a] bye"]
+    );
+    assert!(pending.is_empty());
+  }
+
+  #[test]
+  fn render_trees() {
+    let roots = vec![
+      TreeNode::new(
+        "global",
+        vec![
+          TreeNode::new("cargo web", vec![TreeNode::new("web", vec![])]),
+          TreeNode::new(
+            "cargo db",
+            vec![TreeNode::new("db", vec![]), TreeNode::new("1-db", vec![])],
+          ),
+        ],
+      ),
+      TreeNode::new("dev", vec![]),
+    ];
+    assert_eq!(
+      render_tree(&roots, false),
+      "global\n|-- cargo web\n|   `-- web\n`-- cargo db\n    |-- db\n    `-- 1-db\ndev\n"
+    );
+    assert_eq!(
+      render_tree(&roots[..1], true).lines().nth(2),
+      Some("│   └── web")
+    );
+  }
+
+  #[test]
+  fn paginate_rows() {
+    let rows = (0..10).collect::<Vec<usize>>();
+    let page = PaginationOpts::default();
+    assert_eq!(paginate(rows.clone(), &page), (rows.clone(), None));
+    let page = PaginationOpts {
+      limit: Some(3),
+      offset: 2,
+    };
+    assert_eq!(
+      paginate(rows.clone(), &page),
+      (vec![2, 3, 4], Some("showing 3 of 10".to_owned()))
+    );
+    let page = PaginationOpts {
+      limit: Some(20),
+      offset: 8,
+    };
+    assert_eq!(
+      paginate(rows.clone(), &page),
+      (vec![8, 9], Some("showing 2 of 10".to_owned()))
+    );
+    let page = PaginationOpts {
+      limit: Some(20),
+      offset: 0,
+    };
+    assert_eq!(paginate(rows.clone(), &page), (rows, None));
+  }
+
+  #[test]
+  fn env_format() {
+    assert_eq!(env_key("NamespaceName"), "NAMESPACE_NAME");
+    assert_eq!(env_key("HTTPPort"), "HTTP_PORT");
+    assert_eq!(env_key("Ipv4Address"), "IPV4_ADDRESS");
+    assert_eq!(env_key("com.docker-label"), "COM_DOCKER_LABEL");
+    let data = serde_json::json!({
+      "Name": "web",
+      "Config": {
+        "Container": {
+          "Image": "nginx:latest",
+          "Env": ["A=1", "B=it's"],
+          "Labels": { "app.name": "web" },
+        },
+        "Replicas": 2,
+        "Secrets": null,
+      },
+    });
+    let env = to_env("CARGO", &data).unwrap();
+    let mut lines = env.lines().collect::<Vec<_>>();
+    lines.sort();
+    assert_eq!(
+      lines,
+      vec![
+        "CARGO_CONFIG_CONTAINER_ENV_0=A=1",
+        "CARGO_CONFIG_CONTAINER_ENV_1='B=it'\\''s'",
+        "CARGO_CONFIG_CONTAINER_IMAGE=nginx:latest",
+        "CARGO_CONFIG_CONTAINER_LABELS_APP_NAME=web",
+        "CARGO_CONFIG_REPLICAS=2",
+        "CARGO_NAME=web",
+      ]
+    );
+    let env = to_env("", &serde_json::json!([{ "Name": "" }])).unwrap();
+    assert_eq!(env, "_0_NAME=''\n");
+  }
+
+  #[test]
+  fn row_indexes_skip_borders() {
+    let table = "+---+\n| NAME |\n+---+\n| a |\n+---+\n| b |\n+---+";
+    assert_eq!(
+      row_indexes(table),
+      vec![None, None, None, Some(0), None, Some(1), None]
+    );
+    let table = "┌───┐\n│ NAME │\n├───┤\n│ a │\n└───┘";
+    assert_eq!(row_indexes(table), vec![None, None, None, Some(0), None]);
+    let table = "| NAME | STATE |\n|------|:------|\n| a | 1/1 |";
+    assert_eq!(row_indexes(table), vec![None, None, Some(0)]);
+    let table = "NAME    STATE\na       1/1\nb       0/1";
+    assert_eq!(row_indexes(table), vec![None, Some(0), Some(1)]);
+  }
+
+  #[test]
+  fn column_cells_follow_header() {
+    let table = "name    health     image\na       healthy    nginx\nb       -          nginx\n";
+    assert_eq!(
+      column_cells(table, "health"),
+      vec![None, Some((8, 15)), Some((8, 9))]
+    );
+    let table = "+---+\n| name | health |\n+---+\n| a    | starting |\n+---+";
+    assert_eq!(
+      column_cells(table, "health"),
+      vec![None, None, None, Some((9, 17)), None]
+    );
+    assert_eq!(column_cells(table, "dns"), vec![None; 5]);
+  }
+
+  #[test]
+  fn fit_columns_shrink_widest() {
+    let fixed = [false; 3];
+    assert_eq!(fit_columns(&[10, 40, 20], &fixed, 100), vec![10, 40, 20]);
+    assert_eq!(fit_columns(&[10, 40, 20], &fixed, 50), vec![10, 20, 20]);
+    assert_eq!(fit_columns(&[10, 40, 20], &fixed, 10), vec![8, 8, 8]);
+    let fixed = [false, true, false];
+    assert_eq!(fit_columns(&[10, 40, 20], &fixed, 60), vec![10, 40, 10]);
+    assert_eq!(fit_columns(&[10, 40, 20], &fixed, 10), vec![8, 40, 8]);
+  }
+
+  #[test]
+  fn elide_middle_keep_head_and_tail() {
+    assert_eq!(elide_middle("short", 10, "…"), "short");
+    assert_eq!(elide_middle("my-cargo.global", 9, "…"), "my-c…obal");
+    assert_eq!(elide_middle("my-cargo.global", 9, "..."), "my-...bal");
+  }
+
+  #[test]
+  fn json_pretty_flags() {
+    assert!(resolve_json_pretty(false, false, true));
+    assert!(!resolve_json_pretty(false, false, false));
+    assert!(resolve_json_pretty(true, false, false));
+    assert!(!resolve_json_pretty(false, true, true));
+  }
+
+  #[test]
+  fn utf8_locale() {
+    assert!(is_utf8_locale(Some("en_US.UTF-8".into())));
+    assert!(is_utf8_locale(Some("C.utf8".into())));
+    assert!(!is_utf8_locale(Some("C".into())));
+    assert!(!is_utf8_locale(Some("fr_FR.ISO-8859-1".into())));
+    assert!(!is_utf8_locale(None));
+  }
+
+  #[test]
+  fn to_toml_nested_null() {
+    let config = serde_json::json!({
+      "Name": "my-cargo",
+      "Container": {
+        "Image": "nginx:latest",
+        "Env": null,
+      },
+    });
+    let err = to_toml(&config).unwrap_err();
+    assert_eq!(err.context(), Some("Print toml"));
+    assert!(err.to_string().contains("-o yaml"));
+    assert!(print_toml(&config).is_err());
+  }
+
+  #[test]
+  fn to_toml_table() {
+    let config = serde_json::json!({
+      "Name": "my-cargo",
+      "Container": {
+        "Image": "nginx:latest",
+      },
+    });
+    assert!(to_toml(&config).is_ok());
   }
 }
@@ -0,0 +1,92 @@
+use std::io::IsTerminal;
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+use super::math::calculate_percentage;
+
+/// ## TransferProgress
+///
+/// Report the progress of a transfer of bytes.
+/// When stderr is a terminal a progress bar with the speed and the eta is drawn,
+/// otherwise a line is printed every 10 percent so logs stay clean.
+/// The progress bar is cleared when dropped, so it's also cleared on error.
+///
+pub enum TransferProgress {
+  Bar(ProgressBar),
+  Lines {
+    /// Name of the transfered element
+    name: String,
+    /// Total number of bytes to transfer
+    total: u64,
+    /// Number of bytes transfered
+    current: u64,
+    /// Last percentage printed
+    reported: u64,
+  },
+}
+
+impl TransferProgress {
+  /// ## New
+  ///
+  /// Create a new transfer progress
+  ///
+  /// ## Arguments
+  ///
+  /// * [name](str) The name of the transfered element
+  /// * [total](u64) The total number of bytes to transfer
+  ///
+  pub fn new(name: &str, total: u64) -> Self {
+    if !std::io::stderr().is_terminal() {
+      return Self::Lines {
+        name: name.to_owned(),
+        total,
+        current: 0,
+        reported: 0,
+      };
+    }
+    let pg = ProgressBar::new(total);
+    let style = ProgressStyle::with_template(
+      "[{elapsed_precise}] [{bar:20.cyan/blue}] {bytes}/{total_bytes} ({binary_bytes_per_sec}, {eta}) {msg}",
+    )
+    .unwrap()
+    .progress_chars("=> ");
+    pg.set_style(style);
+    pg.set_message(name.to_owned());
+    Self::Bar(pg)
+  }
+
+  /// ## Inc
+  ///
+  /// Increment the number of bytes transfered
+  ///
+  /// ## Arguments
+  ///
+  /// * [bytes](u64) The number of bytes transfered since the last call
+  ///
+  pub fn inc(&mut self, bytes: u64) {
+    match self {
+      Self::Bar(pg) => pg.inc(bytes),
+      Self::Lines {
+        name,
+        total,
+        current,
+        reported,
+      } => {
+        *current += bytes;
+        let percent = calculate_percentage(*current, *total).min(100);
+        if percent >= *reported + 10 || (percent == 100 && *reported < 100) {
+          eprintln!("{name}: {percent}%");
+          *reported = percent;
+        }
+      }
+    }
+  }
+}
+
+impl Drop for TransferProgress {
+  fn drop(&mut self) {
+    if let Self::Bar(pg) = self {
+      pg.finish_and_clear();
+    }
+  }
+}
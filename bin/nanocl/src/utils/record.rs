@@ -0,0 +1,178 @@
+use std::fs::File;
+use std::time::Instant;
+use std::io::{BufWriter, Write};
+
+use nanocl_utils::io_error::{FromIo, IoResult};
+use nanocld_client::stubs::cargo::OutputKind;
+
+use crate::models::VmRecordFormat;
+
+/// ## Recorder
+///
+/// Write the output of a console session to a file while it's displayed.
+/// Every output is flushed once written so the recording is complete
+/// even when the session is dropped or the process is interrupted.
+///
+/// An asciinema v2 cast record stdout and console outputs as `o` events
+/// and stderr outputs as `e` events, as described by the `streams` field
+/// of its header
+///
+pub struct Recorder {
+  /// The file of the recording
+  writer: BufWriter<File>,
+  /// The format of the recording
+  format: VmRecordFormat,
+  /// The start of the recording, cast events are timed from it
+  start: Instant,
+}
+
+impl Recorder {
+  /// ## Create
+  ///
+  /// Create the recording file, `cast` is used by default for a `.cast` file
+  /// and its header written right away
+  ///
+  /// ## Arguments
+  ///
+  /// * [path](str) The path of the recording
+  /// * [format](Option<VmRecordFormat>) The format of the recording
+  /// * [title](str) The title of a cast
+  ///
+  /// ## Return
+  ///
+  /// * [Result](Result) The result of the operation
+  ///   * [Ok](Recorder) The recorder
+  ///   * [Err](IoError) The file cannot be created
+  ///
+  pub fn create(
+    path: &str,
+    format: &Option<VmRecordFormat>,
+    title: &str,
+  ) -> IoResult<Self> {
+    let format = format.clone().unwrap_or(match path.ends_with(".cast") {
+      true => VmRecordFormat::Cast,
+      false => VmRecordFormat::Raw,
+    });
+    let file = File::create(path)
+      .map_err(|err| err.map_err_context(|| format!("Record {path}")))?;
+    let mut recorder = Self {
+      writer: BufWriter::new(file),
+      format,
+      start: Instant::now(),
+    };
+    if recorder.format == VmRecordFormat::Cast {
+      let (height, width) = dialoguer::console::Term::stdout().size();
+      let header = serde_json::json!({
+        "version": 2,
+        "width": width,
+        "height": height,
+        "timestamp": chrono::Utc::now().timestamp(),
+        "title": title,
+        "streams": { "o": "stdout", "e": "stderr" },
+      });
+      recorder.write_line(&header.to_string())?;
+    }
+    Ok(recorder)
+  }
+
+  /// ## Write line
+  ///
+  /// Write a line of a cast and flush it
+  ///
+  /// ## Arguments
+  ///
+  /// * [line](str) The line to write
+  ///
+  /// ## Return
+  ///
+  /// * [Result](Result) The result of the operation
+  ///   * [Ok](()) The line was written
+  ///   * [Err](IoError) The file cannot be written
+  ///
+  fn write_line(&mut self, line: &str) -> IoResult<()> {
+    writeln!(self.writer, "{line}")?;
+    self.writer.flush()?;
+    Ok(())
+  }
+
+  /// ## Record
+  ///
+  /// Append an output of the session to the recording,
+  /// inputs are not recorded
+  ///
+  /// ## Arguments
+  ///
+  /// * [kind](OutputKind) The kind of the output
+  /// * [data](str) The output
+  ///
+  /// ## Return
+  ///
+  /// * [Result](Result) The result of the operation
+  ///   * [Ok](()) The output was recorded
+  ///   * [Err](IoError) The file cannot be written
+  ///
+  pub fn record(&mut self, kind: &OutputKind, data: &str) -> IoResult<()> {
+    let code = match kind {
+      OutputKind::StdOut | OutputKind::Console => "o",
+      OutputKind::StdErr => "e",
+      OutputKind::StdIn => return Ok(()),
+    };
+    match self.format {
+      VmRecordFormat::Raw => {
+        self.writer.write_all(data.as_bytes())?;
+        self.writer.flush()?;
+        Ok(())
+      }
+      VmRecordFormat::Cast => {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let event = serde_json::json!([elapsed, code, data]);
+        self.write_line(&event.to_string())
+      }
+    }
+  }
+}
+
+impl Drop for Recorder {
+  fn drop(&mut self) {
+    let _ = self.writer.flush();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn record_cast() {
+    let path = "/tmp/nanocl-record-test.cast";
+    let mut recorder = Recorder::create(path, &None, "test").unwrap();
+    recorder.record(&OutputKind::Console, "hello\r\n").unwrap();
+    recorder.record(&OutputKind::StdErr, "oops").unwrap();
+    recorder.record(&OutputKind::StdIn, "ignored").unwrap();
+    drop(recorder);
+    let content = std::fs::read_to_string(path).unwrap();
+    let lines = content.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    let header = serde_json::from_str::<serde_json::Value>(lines[0]).unwrap();
+    assert_eq!(header["version"], 2);
+    assert_eq!(header["streams"]["e"], "stderr");
+    let event = serde_json::from_str::<serde_json::Value>(lines[1]).unwrap();
+    assert_eq!(event[1], "o");
+    assert_eq!(event[2], "hello\r\n");
+    let event = serde_json::from_str::<serde_json::Value>(lines[2]).unwrap();
+    assert_eq!(event[1], "e");
+    std::fs::remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn record_raw() {
+    let path = "/tmp/nanocl-record-test.log";
+    let mut recorder = Recorder::create(path, &None, "test").unwrap();
+    recorder.record(&OutputKind::Console, "hello ").unwrap();
+    recorder.record(&OutputKind::StdErr, "world").unwrap();
+    drop(recorder);
+    let content = std::fs::read_to_string(path).unwrap();
+    assert_eq!(content, "hello world");
+    std::fs::remove_file(path).unwrap();
+  }
+}
@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 use regex::Regex;
 use liquid::ObjectView;
@@ -8,7 +9,7 @@ use nanocld_client::stubs::state::{StateMeta, StateStream, StateStreamStatus};
 
 use nanocl_utils::io_error::{IoError, IoResult, FromIo};
 
-use crate::models::{DisplayFormat, StateRef};
+use crate::models::{DisplayFormat, StateRef, StateApplySummary};
 
 /// ## Get state ref
 ///
@@ -101,6 +102,31 @@ where
   }
 }
 
+/// ## Detect ext
+///
+/// Detect the format of a Statefile by sniffing its content
+/// when there is no file extension to rely on, eg: when reading from stdin
+///
+/// ## Arguments
+///
+/// * [raw](str) The raw data of the Statefile
+///
+/// ## Return
+///
+/// * [str](str) The detected extension eg: yaml, json, toml
+///
+pub fn detect_ext(raw: &str) -> &'static str {
+  if serde_json::from_str::<serde_json::Value>(raw).is_ok() {
+    return "json";
+  }
+  // A yaml document is never a valid toml document with at least one key
+  // so toml must be tested before falling back to yaml
+  match toml::from_str::<toml::Table>(raw) {
+    Ok(table) if !table.is_empty() => "toml",
+    _ => "yaml",
+  }
+}
+
 /// ## serialize_ext
 ///
 /// Serialize a Statefile for given format eg: yaml, json, toml and given data
@@ -137,6 +163,10 @@ where
         &err.to_string(),
       )
     })?),
+    DisplayFormat::Env | DisplayFormat::Wide => Err(IoError::invalid_input(
+      "Unable to deserialize state file",
+      &format!("{} format is output only", format.to_string()),
+    )),
   }
 }
 
@@ -173,6 +203,135 @@ pub fn compile(raw: &str, obj: &dyn ObjectView) -> IoResult<String> {
   Ok(output)
 }
 
+/// ## Expand env
+///
+/// Replace `${VAR}` and `${VAR:-default}` placeholders of a Statefile
+/// with the value of the environment variable, `${{ }}` templates are untouched
+///
+/// ## Arguments
+///
+/// * [raw](str) The raw data of the Statefile
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](String) The expanded data
+///   * [Err](IoError) An error occured listing unset variables
+///
+pub fn expand_env(raw: &str) -> IoResult<String> {
+  expand_vars(raw, |name| std::env::var(name).ok())
+}
+
+/// ## Expand vars
+///
+/// Replace `${VAR}` and `${VAR:-default}` placeholders of a Statefile
+/// with the value returned by the lookup, the default is used
+/// when the lookup has none
+///
+/// ## Arguments
+///
+/// * [raw](str) The raw data of the Statefile
+/// * [lookup](Fn) Return the value of a variable
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](String) The expanded data
+///   * [Err](IoError) An error occured listing unset variables
+///
+pub fn expand_vars<F>(raw: &str, lookup: F) -> IoResult<String>
+where
+  F: Fn(&str) -> Option<String>,
+{
+  let reg = Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)(:-([^}]*))?\}")
+    .map_err(|err| IoError::invalid_data("Regex", &format!("{err}")))?;
+  let mut missing = BTreeSet::new();
+  let output = reg.replace_all(raw, |caps: &regex::Captures| {
+    let name = &caps[1];
+    match (lookup(name), caps.get(3)) {
+      (Some(value), _) => value,
+      (None, Some(default)) => default.as_str().to_owned(),
+      (None, None) => {
+        missing.insert(name.to_owned());
+        String::new()
+      }
+    }
+  });
+  if !missing.is_empty() {
+    let missing = missing.into_iter().collect::<Vec<_>>().join(", ");
+    return Err(IoError::invalid_input(
+      "Statefile",
+      format!("unset variables without default: {missing}").as_str(),
+    ));
+  }
+  Ok(output.to_string())
+}
+
+/// ## Read values
+///
+/// Parse a values file of `state template`, a yaml mapping
+/// of variable names to strings, numbers or booleans
+///
+/// ## Arguments
+///
+/// * [raw](str) The content of the values file
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](HashMap<String, String>) The value of each variable
+///   * [Err](IoError) The file isn't a mapping of scalars
+///
+pub fn read_values(raw: &str) -> IoResult<HashMap<String, String>> {
+  let values: HashMap<String, serde_yaml::Value> = serde_yaml::from_str(raw)
+    .map_err(|err| err.map_err_context(|| "Unable to parse values"))?;
+  values
+    .into_iter()
+    .map(|(name, value)| {
+      let value = match value {
+        serde_yaml::Value::String(value) => value,
+        serde_yaml::Value::Number(value) => value.to_string(),
+        serde_yaml::Value::Bool(value) => value.to_string(),
+        _ => {
+          return Err(IoError::invalid_data(
+            format!("Value {name}"),
+            "expected a string, a number or a boolean".to_owned(),
+          ))
+        }
+      };
+      Ok((name, value))
+    })
+    .collect()
+}
+
+/// ## New spinner
+///
+/// Add a new spinner to a multiprogress bar
+///
+/// ## Arguments
+///
+/// * [multiprogress](MultiProgress) The multiprogress bar
+/// * [message](str) The message of the spinner
+///
+/// ## Return
+///
+/// * [ProgressBar](ProgressBar) The spinner
+///
+pub fn new_spinner(
+  multiprogress: &MultiProgress,
+  message: &str,
+) -> ProgressBar {
+  let spinner_style =
+    ProgressStyle::with_template("{spinner} {prefix:.bold} {wide_msg}")
+      .unwrap()
+      .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈-");
+  let pg = multiprogress.add(ProgressBar::new(1));
+  pg.enable_steady_tick(std::time::Duration::from_millis(50));
+  pg.set_style(spinner_style);
+  pg.set_message(message.to_owned());
+  pg
+}
+
 /// ## Update progress
 ///
 /// Update the progress bar for a given state stream
@@ -204,14 +363,7 @@ pub fn update_progress(
       pg.finish();
     }
   } else {
-    let spinner_style =
-      ProgressStyle::with_template("{spinner} {prefix:.bold} {wide_msg}")
-        .unwrap()
-        .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈-");
-    let pg = multiprogress.add(ProgressBar::new(1));
-    pg.enable_steady_tick(std::time::Duration::from_millis(50));
-    pg.set_style(spinner_style);
-    pg.set_message(state_stream.key.to_string());
+    let pg = new_spinner(multiprogress, &state_stream.key);
     pg.set_prefix(format!(
       "{:#?}:{}",
       &state_stream.status, &state_stream.kind
@@ -219,3 +371,922 @@ pub fn update_progress(
     layers.insert(id.to_owned(), pg);
   }
 }
+
+/// ## Record apply
+///
+/// Count a status of the apply stream in the summary of `state apply --quiet`,
+/// failures are printed as they happen
+///
+/// ## Arguments
+///
+/// * [summary](StateApplySummary) The summary to update
+/// * [state_stream](StateStream) The status of an element
+/// * [created](HashSet<String>) The keys of the elements that didn't exist
+///
+pub fn record_apply(
+  summary: &mut StateApplySummary,
+  state_stream: &StateStream,
+  created: &HashSet<String>,
+) {
+  match state_stream.status {
+    StateStreamStatus::Success if created.contains(&state_stream.key) => {
+      summary.created += 1
+    }
+    StateStreamStatus::Success => summary.updated += 1,
+    StateStreamStatus::UnChanged => summary.unchanged += 1,
+    StateStreamStatus::Failed => {
+      summary.failed += 1;
+      eprintln!(
+        "Failed to apply {} {}: {}",
+        state_stream.kind,
+        state_stream.key,
+        state_stream.context.clone().unwrap_or_default()
+      );
+    }
+    StateStreamStatus::Pending | StateStreamStatus::NotFound => {}
+  }
+}
+
+/// ## Diff fields
+///
+/// Compare the current value of an element with the wanted one
+/// and return the path of every field that differ, eg: `Container.Image`
+///
+/// ## Arguments
+///
+/// * [current](serde_json::Value) The current value
+/// * [wanted](serde_json::Value) The wanted value
+///
+/// ## Return
+///
+/// * [Vec<String>](Vec<String>) The path of the fields that differ
+///
+pub fn diff_fields(
+  current: &serde_json::Value,
+  wanted: &serde_json::Value,
+) -> Vec<String> {
+  let mut changes = Vec::new();
+  push_diff("", current, wanted, &mut changes);
+  changes.into_iter().map(|(path, _, _)| path).collect()
+}
+
+/// ## Diff lines
+///
+/// Render the fields that differ between the current and the wanted value
+/// of an element as the lines of a unified diff, without colors
+///
+/// ## Arguments
+///
+/// * [name](str) The name of the element in the headers eg: `Cargo web`
+/// * [current](Option<serde_json::Value>) The current value if the element exists
+/// * [wanted](serde_json::Value) The wanted value
+///
+/// ## Return
+///
+/// * [Vec<String>](Vec<String>) The lines of the diff, empty without differences
+///
+pub fn diff_lines(
+  name: &str,
+  current: Option<&serde_json::Value>,
+  wanted: &serde_json::Value,
+) -> Vec<String> {
+  diff_lines_labeled(name, current, wanted, ("cluster", "Statefile"))
+}
+
+/// ## Diff lines labeled
+///
+/// Same as [diff_lines](diff_lines) with the labels of both sides
+/// in the headers eg: `("current", "revision 1.0")`
+///
+/// ## Arguments
+///
+/// * [name](str) The name of the element in the headers eg: `Cargo web`
+/// * [current](Option<serde_json::Value>) The current value if the element exists
+/// * [wanted](serde_json::Value) The wanted value
+/// * [labels]((str, str)) The labels of the current and the wanted value
+///
+/// ## Return
+///
+/// * [Vec<String>](Vec<String>) The lines of the diff, empty without differences
+///
+pub fn diff_lines_labeled(
+  name: &str,
+  current: Option<&serde_json::Value>,
+  wanted: &serde_json::Value,
+  labels: (&str, &str),
+) -> Vec<String> {
+  let missing = serde_json::json!({});
+  let mut changes = Vec::new();
+  push_diff("", current.unwrap_or(&missing), wanted, &mut changes);
+  if changes.is_empty() {
+    return Vec::new();
+  }
+  let mut lines = vec![
+    match current {
+      Some(_) => format!("--- {name} ({})", labels.0),
+      None => "--- /dev/null".to_owned(),
+    },
+    format!("+++ {name} ({})", labels.1),
+  ];
+  for (path, current, wanted) in changes {
+    if !current.is_null() {
+      lines.push(format!("-{path}: {current}"));
+    }
+    if !wanted.is_null() {
+      lines.push(format!("+{path}: {wanted}"));
+    }
+  }
+  lines
+}
+
+fn push_diff(
+  path: &str,
+  current: &serde_json::Value,
+  wanted: &serde_json::Value,
+  changes: &mut Vec<(String, serde_json::Value, serde_json::Value)>,
+) {
+  match (current, wanted) {
+    (serde_json::Value::Object(current), serde_json::Value::Object(wanted)) => {
+      let keys = current.keys().chain(wanted.keys()).collect::<BTreeSet<_>>();
+      for key in keys {
+        let path = match path {
+          "" => key.to_owned(),
+          _ => format!("{path}.{key}"),
+        };
+        push_diff(
+          &path,
+          current.get(key).unwrap_or(&serde_json::Value::Null),
+          wanted.get(key).unwrap_or(&serde_json::Value::Null),
+          changes,
+        );
+      }
+    }
+    (current, wanted) if current != wanted => {
+      changes.push((path.to_owned(), current.clone(), wanted.clone()))
+    }
+    _ => {}
+  }
+}
+
+/// ## Unknown fields
+///
+/// Compare a value with the same value once deserialized and serialized back
+/// to find the fields that were ignored because they are unknown.
+/// Null and empty values are never reported since they may be skipped when serialized
+///
+/// ## Arguments
+///
+/// * [raw](serde_json::Value) The value as written by the user
+/// * [parsed](serde_json::Value) The value serialized back
+///
+/// ## Return
+///
+/// * [Vec<String>](Vec<String>) The path of the unknown fields
+///
+pub fn unknown_fields(
+  raw: &serde_json::Value,
+  parsed: &serde_json::Value,
+) -> Vec<String> {
+  let mut fields = Vec::new();
+  push_unknown("", raw, parsed, &mut fields);
+  fields
+}
+
+fn push_unknown(
+  path: &str,
+  raw: &serde_json::Value,
+  parsed: &serde_json::Value,
+  fields: &mut Vec<String>,
+) {
+  let serde_json::Value::Object(raw) = raw else {
+    return;
+  };
+  for (key, value) in raw {
+    let path = match path {
+      "" => key.to_owned(),
+      _ => format!("{path}.{key}"),
+    };
+    let is_empty = match value {
+      serde_json::Value::Null => true,
+      serde_json::Value::Array(array) => array.is_empty(),
+      serde_json::Value::Object(object) => object.is_empty(),
+      _ => false,
+    };
+    match parsed.get(key) {
+      Some(parsed) => push_unknown(&path, value, parsed, fields),
+      None if !is_empty => fields.push(path),
+      None => {}
+    }
+  }
+}
+
+/// ## Merge values
+///
+/// Merge a value over another one, objects are merged recursively
+/// and any other value is replaced
+///
+/// ## Arguments
+///
+/// * [base](serde_json::Value) The value to merge into
+/// * [patch](serde_json::Value) The value to merge
+///
+pub fn merge_values(base: &mut serde_json::Value, patch: serde_json::Value) {
+  match (base, patch) {
+    (serde_json::Value::Object(base), serde_json::Value::Object(patch)) => {
+      for (key, value) in patch {
+        match base.get_mut(&key) {
+          Some(base) => merge_values(base, value),
+          None => {
+            base.insert(key, value);
+          }
+        }
+      }
+    }
+    (base, patch) => *base = patch,
+  }
+}
+
+/// ## Merge patch
+///
+/// Apply a JSON Merge Patch (RFC 7396) to a value:
+/// objects are merged recursively, a `null` removes the field
+/// and any other value, arrays included, is replaced
+///
+/// ## Arguments
+///
+/// * [target](serde_json::Value) The value to patch
+/// * [patch](serde_json::Value) The merge patch
+///
+pub fn merge_patch(target: &mut serde_json::Value, patch: serde_json::Value) {
+  let serde_json::Value::Object(patch) = patch else {
+    *target = patch;
+    return;
+  };
+  if !target.is_object() {
+    *target = serde_json::json!({});
+  }
+  let Some(target) = target.as_object_mut() else {
+    return;
+  };
+  for (key, value) in patch {
+    if value.is_null() {
+      target.remove(&key);
+      continue;
+    }
+    merge_patch(target.entry(key).or_insert(serde_json::Value::Null), value);
+  }
+}
+
+/// ## State file extensions
+///
+/// Extensions of the files picked up when a directory is given to `nanocl state apply`
+///
+const STATE_FILE_EXTENSIONS: [&str; 4] = ["yml", "yaml", "json", "toml"];
+
+/// ## List state files
+///
+/// List recursively the Statefiles of a directory in lexicographic order
+///
+/// ## Arguments
+///
+/// * [dir](Path) The directory to walk
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Vec<PathBuf>) The paths of the Statefiles
+///   * [Err](IoError) An error occured
+///
+pub fn list_state_files(dir: &Path) -> IoResult<Vec<PathBuf>> {
+  let mut files = Vec::new();
+  let mut dirs = vec![dir.to_path_buf()];
+  while let Some(dir) = dirs.pop() {
+    let entries = std::fs::read_dir(&dir).map_err(|err| {
+      err.map_err_context(|| format!("Statefile {}", dir.display()))
+    })?;
+    for entry in entries {
+      let path = entry
+        .map_err(|err| {
+          err.map_err_context(|| format!("Statefile {}", dir.display()))
+        })?
+        .path();
+      if path.is_dir() {
+        dirs.push(path);
+        continue;
+      }
+      let is_state_file = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| STATE_FILE_EXTENSIONS.contains(&ext))
+        .unwrap_or(false);
+      if is_state_file {
+        files.push(path);
+      }
+    }
+  }
+  files.sort();
+  Ok(files)
+}
+
+/// ## Check duplicates
+///
+/// Ensure an element eg: a cargo is not defined in multiple Statefiles
+/// before applying any of them
+///
+/// ## Arguments
+///
+/// * [states](Vec<(String, StateRef)>) The Statefiles with their location
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) Every element is defined once
+///   * [Err](IoError) The list of elements defined more than once
+///
+pub fn check_duplicates(
+  states: &[(String, StateRef<serde_yaml::Value>)],
+) -> IoResult<()> {
+  let mut locations: HashMap<String, Vec<&str>> = HashMap::new();
+  for (location, state_ref) in states {
+    let namespace = state_ref
+      .data
+      .get("Namespace")
+      .and_then(|namespace| namespace.as_str())
+      .unwrap_or("global");
+    for (key, kind, namespaced) in [
+      ("Cargoes", "Cargo", true),
+      ("VirtualMachines", "VirtualMachine", true),
+      ("Resources", "Resource", false),
+    ] {
+      let Some(elements) = state_ref
+        .data
+        .get(key)
+        .and_then(|elements| elements.as_sequence())
+      else {
+        continue;
+      };
+      for name in elements
+        .iter()
+        .filter_map(|element| element.get("Name")?.as_str())
+      {
+        let element = match namespaced {
+          true => format!("{kind} {name} in namespace {namespace}"),
+          false => format!("{kind} {name}"),
+        };
+        locations.entry(element).or_default().push(location);
+      }
+    }
+  }
+  let mut duplicates = locations
+    .into_iter()
+    .filter(|(_, locations)| locations.len() > 1)
+    .map(|(element, locations)| {
+      format!("{element} is defined in {}", locations.join(", "))
+    })
+    .collect::<Vec<_>>();
+  if duplicates.is_empty() {
+    return Ok(());
+  }
+  duplicates.sort();
+  Err(IoError::invalid_input(
+    "Statefile",
+    duplicates.join("\n").as_str(),
+  ))
+}
+
+/// ## State names
+///
+/// List the names of the elements of a given key eg: `Cargoes` of a Statefile
+///
+/// ## Arguments
+///
+/// * [data](serde_yaml::Value) The data of the Statefile
+/// * [key](str) The key of the list
+///
+/// ## Return
+///
+/// * [Vec<String>](Vec<String>) The names, empty if the key is missing
+///
+pub fn state_names(data: &serde_yaml::Value, key: &str) -> Vec<String> {
+  data
+    .get(key)
+    .and_then(|elements| elements.as_sequence())
+    .map(|elements| {
+      elements
+        .iter()
+        .filter_map(|element| element.get("Name")?.as_str())
+        .map(|name| name.to_owned())
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// ## Has label
+///
+/// Check if the labels of an element match the `--prune-label` option
+///
+/// ## Arguments
+///
+/// * [labels](Option<HashMap<String, String>>) The labels of the element
+/// * [label](Option<(String, String)>) The label to match, any element match without it
+///
+/// ## Return
+///
+/// * [bool](bool) True if the element has the label
+///
+pub fn has_label(
+  labels: &Option<HashMap<String, String>>,
+  label: &Option<(String, String)>,
+) -> bool {
+  let Some((key, value)) = label else {
+    return true;
+  };
+  labels
+    .as_ref()
+    .and_then(|labels| labels.get(key))
+    .is_some_and(|found| found == value)
+}
+
+/// ## Take depends on
+///
+/// Remove the `DependsOn` list of the cargoes and virtual machines
+/// of a Statefile since the daemon doesn't know it,
+/// and return the dependencies of every of them by name
+///
+/// ## Arguments
+///
+/// * [data](serde_yaml::Value) The data of the Statefile
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](BTreeMap<String, Vec<String>>) The dependencies of each element
+///   * [Err](IoError) A `DependsOn` is not a list of names
+///
+pub fn take_depends_on(
+  data: &mut serde_yaml::Value,
+) -> IoResult<BTreeMap<String, Vec<String>>> {
+  let mut depends_on: BTreeMap<String, Vec<String>> = BTreeMap::new();
+  for key in ["Cargoes", "VirtualMachines"] {
+    let Some(elements) = data
+      .get_mut(key)
+      .and_then(|elements| elements.as_sequence_mut())
+    else {
+      continue;
+    };
+    for element in elements {
+      let Some(name) = element.get("Name").and_then(|name| name.as_str())
+      else {
+        continue;
+      };
+      let name = name.to_owned();
+      let dependencies = match element
+        .as_mapping_mut()
+        .and_then(|element| element.remove("DependsOn"))
+      {
+        None => Vec::new(),
+        Some(dependencies) => serde_yaml::from_value(dependencies)
+          .map_err(|err| err.map_err_context(|| format!("{name} DependsOn")))?,
+      };
+      depends_on.entry(name).or_default().extend(dependencies);
+    }
+  }
+  Ok(depends_on)
+}
+
+/// ## Find cycle
+///
+/// Depth first search of a dependency cycle from an element
+///
+/// ## Arguments
+///
+/// * [depends_on](BTreeMap<String, Vec<String>>) The dependencies of each element
+/// * [name](str) The element to visit
+/// * [visited](HashSet<String>) The elements without cycle
+/// * [path](Vec<String>) The elements being visited
+///
+/// ## Return
+///
+/// * [Option](Option<Vec<String>>) The cycle from and to the same element
+///
+fn find_cycle(
+  depends_on: &BTreeMap<String, Vec<String>>,
+  name: &str,
+  visited: &mut HashSet<String>,
+  path: &mut Vec<String>,
+) -> Option<Vec<String>> {
+  if let Some(start) = path.iter().position(|element| element == name) {
+    let mut cycle = path[start..].to_vec();
+    cycle.push(name.to_owned());
+    return Some(cycle);
+  }
+  if visited.contains(name) {
+    return None;
+  }
+  path.push(name.to_owned());
+  for dependency in depends_on.get(name).into_iter().flatten() {
+    if let Some(cycle) = find_cycle(depends_on, dependency, visited, path) {
+      return Some(cycle);
+    }
+  }
+  path.pop();
+  visited.insert(name.to_owned());
+  None
+}
+
+/// ## Dependency layers
+///
+/// Order the cargoes and virtual machines of a Statefile by their `DependsOn`:
+/// every element of a layer only depends on elements of the previous layers.
+/// Dependencies must be elements of the same Statefile without cycle.
+///
+/// ## Arguments
+///
+/// * [depends_on](BTreeMap<String, Vec<String>>) The dependencies of each element
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Vec<Vec<String>>) The layers, empty when nothing declare dependencies
+///   * [Err](IoError) A dependency is unknown or there is a cycle
+///
+pub fn dependency_layers(
+  depends_on: &BTreeMap<String, Vec<String>>,
+) -> IoResult<Vec<Vec<String>>> {
+  if depends_on
+    .values()
+    .all(|dependencies| dependencies.is_empty())
+  {
+    return Ok(Vec::new());
+  }
+  for (name, dependencies) in depends_on {
+    if let Some(unknown) = dependencies
+      .iter()
+      .find(|dependency| !depends_on.contains_key(*dependency))
+    {
+      return Err(IoError::invalid_input(
+        format!("{name} DependsOn"),
+        format!(
+          "{unknown} is not a cargo or a virtual machine of the Statefile"
+        ),
+      ));
+    }
+  }
+  let mut visited = HashSet::new();
+  for name in depends_on.keys() {
+    if let Some(cycle) =
+      find_cycle(depends_on, name, &mut visited, &mut Vec::new())
+    {
+      return Err(IoError::invalid_input(
+        "Statefile".to_owned(),
+        format!("dependency cycle {}", cycle.join(" -> ")),
+      ));
+    }
+  }
+  let mut layers: Vec<Vec<String>> = Vec::new();
+  let mut placed = HashSet::new();
+  while placed.len() < depends_on.len() {
+    let layer = depends_on
+      .iter()
+      .filter(|(name, dependencies)| {
+        !placed.contains(*name)
+          && dependencies
+            .iter()
+            .all(|dependency| placed.contains(dependency))
+      })
+      .map(|(name, _)| name.clone())
+      .collect::<Vec<_>>();
+    placed.extend(layer.iter().cloned());
+    layers.push(layer);
+  }
+  Ok(layers)
+}
+
+/// ## Layer data
+///
+/// Keep only the cargoes and virtual machines of a layer in a Statefile,
+/// the resources are kept in the last layer so they are applied last
+/// like the daemon does
+///
+/// ## Arguments
+///
+/// * [data](serde_yaml::Value) The data of the Statefile
+/// * [layer](Vec<String>) The names of the elements of the layer
+/// * [last](bool) Whether it's the last layer
+///
+/// ## Return
+///
+/// * [serde_yaml::Value](serde_yaml::Value) The data of the layer
+///
+pub fn layer_data(
+  data: &serde_yaml::Value,
+  layer: &[String],
+  last: bool,
+) -> serde_yaml::Value {
+  let mut data = data.clone();
+  for key in ["Cargoes", "VirtualMachines"] {
+    if let Some(elements) = data
+      .get_mut(key)
+      .and_then(|elements| elements.as_sequence_mut())
+    {
+      elements.retain(|element| {
+        element
+          .get("Name")
+          .and_then(|name| name.as_str())
+          .is_some_and(|name| layer.iter().any(|element| element == name))
+      });
+    }
+  }
+  if !last {
+    if let Some(data) = data.as_mapping_mut() {
+      data.remove("Resources");
+    }
+  }
+  data
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn apply_summary() {
+    let stream = |key: &str, status| StateStream {
+      key: key.to_owned(),
+      kind: "Cargo".to_owned(),
+      context: None,
+      status,
+    };
+    let created = HashSet::from(["new.global".to_owned()]);
+    let mut summary = StateApplySummary::default();
+    for (key, status) in [
+      ("new.global", StateStreamStatus::Pending),
+      ("new.global", StateStreamStatus::Success),
+      ("old.global", StateStreamStatus::Success),
+      ("same.global", StateStreamStatus::UnChanged),
+    ] {
+      record_apply(&mut summary, &stream(key, status), &created);
+    }
+    assert_eq!(summary.to_string(), "created 1, updated 1, unchanged 1");
+    record_apply(
+      &mut summary,
+      &stream("bad.global", StateStreamStatus::Failed),
+      &created,
+    );
+    summary.pruned = 2;
+    assert_eq!(
+      summary.to_string(),
+      "created 1, updated 1, unchanged 1, failed 1, pruned 2"
+    );
+  }
+
+  #[test]
+  fn state_names_and_labels() {
+    let data: serde_yaml::Value = serde_yaml::from_str(
+      "Cargoes:\n  - Name: web\n  - Name: db\nVirtualMachines: []\n",
+    )
+    .unwrap();
+    assert_eq!(state_names(&data, "Cargoes"), vec!["web", "db"]);
+    assert!(state_names(&data, "VirtualMachines").is_empty());
+    assert!(state_names(&data, "Resources").is_empty());
+    let labels = Some(HashMap::from([("app".to_owned(), "web".to_owned())]));
+    assert!(has_label(&labels, &None));
+    assert!(has_label(&None, &None));
+    assert!(has_label(&labels, &Some(("app".into(), "web".into()))));
+    assert!(!has_label(&labels, &Some(("app".into(), "db".into()))));
+    assert!(!has_label(&None, &Some(("app".into(), "web".into()))));
+  }
+
+  #[test]
+  fn detect_ext_from_content() {
+    let yaml = "ApiVersion: v0.10\nKind: Deployment\nCargoes: []\n";
+    assert_eq!(detect_ext(yaml), "yaml");
+    let json = r#"{ "ApiVersion": "v0.10", "Kind": "Deployment" }"#;
+    assert_eq!(detect_ext(json), "json");
+    let toml = "ApiVersion = \"v0.10\"\nKind = \"Deployment\"\n";
+    assert_eq!(detect_ext(toml), "toml");
+  }
+
+  #[test]
+  fn diff_fields_nested() {
+    let current = serde_json::json!({
+      "Name": "web",
+      "Container": { "Image": "nginx:1.24", "Env": ["A=1"] },
+    });
+    let wanted = serde_json::json!({
+      "Name": "web",
+      "Container": { "Image": "nginx:1.25", "Env": ["A=1"], "Cmd": ["run"] },
+    });
+    assert_eq!(
+      diff_fields(&current, &wanted),
+      vec!["Container.Cmd", "Container.Image"]
+    );
+    assert!(diff_fields(&current, &current).is_empty());
+  }
+
+  #[test]
+  fn diff_lines_unified() {
+    let current = serde_json::json!({
+      "Name": "web",
+      "Container": { "Image": "nginx:1.24", "Env": ["A=1"] },
+    });
+    let wanted = serde_json::json!({
+      "Name": "web",
+      "Container": { "Image": "nginx:1.25", "Env": ["A=1"], "Cmd": ["run"] },
+    });
+    assert_eq!(
+      diff_lines("Cargo web", Some(&current), &wanted),
+      vec![
+        "--- Cargo web (cluster)",
+        "+++ Cargo web (Statefile)",
+        "+Container.Cmd: [\"run\"]",
+        "-Container.Image: \"nginx:1.24\"",
+        "+Container.Image: \"nginx:1.25\"",
+      ]
+    );
+    assert!(diff_lines("Cargo web", Some(&current), &current).is_empty());
+    let created = diff_lines("Cargo web", None, &current);
+    assert_eq!(created[0], "--- /dev/null");
+    assert_eq!(created[3], "+Name: \"web\"");
+  }
+
+  #[test]
+  fn expand_vars_with_default() {
+    let lookup = |name: &str| match name {
+      "TAG" => Some("1.25".to_owned()),
+      _ => None,
+    };
+    let raw = "Image: nginx:${TAG}\nPort: ${PORT:-80}\nName: ${{ Args.n }}";
+    assert_eq!(
+      expand_vars(raw, lookup).unwrap(),
+      "Image: nginx:1.25\nPort: 80\nName: ${{ Args.n }}"
+    );
+    let err = expand_vars("${SECRET} ${TOKEN} ${SECRET}", lookup).unwrap_err();
+    assert!(err.to_string().contains("SECRET, TOKEN"));
+  }
+
+  #[test]
+  fn unknown_fields_nested() {
+    let raw = serde_json::json!({
+      "Name": "vm",
+      "Labels": {},
+      "HostConfig": { "Cpu": 2, "Cpus": 4 },
+      "Typo": "value",
+    });
+    let parsed = serde_json::json!({
+      "Name": "vm",
+      "HostConfig": { "Cpu": 2 },
+    });
+    assert_eq!(
+      unknown_fields(&raw, &parsed),
+      vec!["HostConfig.Cpus", "Typo"]
+    );
+  }
+
+  #[test]
+  fn read_values_scalars() {
+    let values =
+      read_values("TAG: \"1.25\"\nREPLICAS: 3\nDEBUG: false\n").unwrap();
+    assert_eq!(values["TAG"], "1.25");
+    assert_eq!(values["REPLICAS"], "3");
+    assert_eq!(values["DEBUG"], "false");
+    assert!(read_values("PORTS:\n  - 80\n").is_err());
+    assert!(read_values("- TAG\n").is_err());
+  }
+
+  #[test]
+  fn merge_values_nested() {
+    let mut base = serde_json::json!({
+      "Name": "vm",
+      "HostConfig": { "Cpu": 1, "Memory": 512 },
+    });
+    let patch = serde_json::json!({ "HostConfig": { "Cpu": 2 } });
+    merge_values(&mut base, patch);
+    assert_eq!(
+      base,
+      serde_json::json!({
+        "Name": "vm",
+        "HostConfig": { "Cpu": 2, "Memory": 512 },
+      })
+    );
+  }
+
+  #[test]
+  fn merge_patch_rfc7396() {
+    let mut target = serde_json::json!({
+      "Name": "web",
+      "Container": {
+        "Image": "nginx:1.24",
+        "Env": ["A=1", "B=2"],
+        "Labels": { "team": "web" },
+      },
+    });
+    let patch = serde_json::json!({
+      "Container": {
+        "Image": "nginx:1.25",
+        "Env": ["A=2"],
+        "Labels": null,
+        "Cmd": ["run"],
+      },
+    });
+    merge_patch(&mut target, patch);
+    assert_eq!(
+      target,
+      serde_json::json!({
+        "Name": "web",
+        "Container": {
+          "Image": "nginx:1.25",
+          "Env": ["A=2"],
+          "Cmd": ["run"],
+        },
+      })
+    );
+    let mut target = serde_json::json!({ "a": "b" });
+    merge_patch(&mut target, serde_json::json!({ "a": { "b": null } }));
+    assert_eq!(target, serde_json::json!({ "a": {} }));
+    merge_patch(&mut target, serde_json::json!(["c"]));
+    assert_eq!(target, serde_json::json!(["c"]));
+  }
+
+  #[test]
+  fn list_state_files_sorted() {
+    let dir = std::env::temp_dir().join("nanocl-list-state-files");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("b")).unwrap();
+    for file in ["c.yml", "a.json", "b/a.toml", "b/README.md", "d.yaml"] {
+      std::fs::write(dir.join(file), "").unwrap();
+    }
+    let files = list_state_files(&dir).unwrap();
+    let files = files
+      .iter()
+      .map(|file| file.strip_prefix(&dir).unwrap().to_str().unwrap())
+      .collect::<Vec<_>>();
+    assert_eq!(files, vec!["a.json", "b/a.toml", "c.yml", "d.yaml"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn check_duplicates_across_files() {
+    let state = |raw: &str| get_state_ref::<serde_yaml::Value>("yml", raw);
+    let web = "ApiVersion: v0.10\nKind: Deployment\nCargoes:\n- Name: web\n";
+    let other = "ApiVersion: v0.10\nKind: Deployment\nNamespace: other\nCargoes:\n- Name: web\n";
+    let states = vec![
+      ("a.yml".to_owned(), state(web).unwrap()),
+      ("b.yml".to_owned(), state(other).unwrap()),
+    ];
+    assert!(check_duplicates(&states).is_ok());
+    let states = vec![
+      ("a.yml".to_owned(), state(web).unwrap()),
+      ("b.yml".to_owned(), state(web).unwrap()),
+    ];
+    let err = check_duplicates(&states).unwrap_err();
+    assert!(err
+      .to_string()
+      .contains("Cargo web in namespace global is defined in a.yml, b.yml"));
+  }
+
+  #[test]
+  fn dependency_order() {
+    let raw = "ApiVersion: v0.10\nKind: Deployment\nCargoes:\n\
+      - Name: app\n  DependsOn: [db, cache]\n\
+      - Name: db\n\
+      - Name: cache\n  DependsOn: [db]\n\
+      VirtualMachines:\n- Name: vm\n\
+      Resources:\n- Name: rule\n";
+    let mut data: serde_yaml::Value = serde_yaml::from_str(raw).unwrap();
+    let depends_on = take_depends_on(&mut data).unwrap();
+    assert!(data["Cargoes"][0].get("DependsOn").is_none());
+    let layers = dependency_layers(&depends_on).unwrap();
+    assert_eq!(layers, vec![vec!["db", "vm"], vec!["cache"], vec!["app"]]);
+    let first = layer_data(&data, &layers[0], false);
+    assert_eq!(state_names(&first, "Cargoes"), vec!["db"]);
+    assert_eq!(state_names(&first, "VirtualMachines"), vec!["vm"]);
+    assert!(first.get("Resources").is_none());
+    let last = layer_data(&data, &layers[2], true);
+    assert_eq!(state_names(&last, "Cargoes"), vec!["app"]);
+    assert_eq!(state_names(&last, "Resources"), vec!["rule"]);
+    let mut data: serde_yaml::Value =
+      serde_yaml::from_str("Cargoes:\n- Name: a\n- Name: b\n").unwrap();
+    let depends_on = take_depends_on(&mut data).unwrap();
+    assert!(dependency_layers(&depends_on).unwrap().is_empty());
+  }
+
+  #[test]
+  fn dependency_errors() {
+    let layers = |raw: &str| {
+      let mut data: serde_yaml::Value = serde_yaml::from_str(raw).unwrap();
+      dependency_layers(&take_depends_on(&mut data)?)
+    };
+    let err = layers(
+      "Cargoes:\n- Name: a\n  DependsOn: [b]\n\
+      - Name: b\n  DependsOn: [c]\n- Name: c\n  DependsOn: [a]\n",
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("a -> b -> c -> a"));
+    let err = layers("Cargoes:\n- Name: a\n  DependsOn: [a]\n").unwrap_err();
+    assert!(err.to_string().contains("a -> a"));
+    let err = layers("Cargoes:\n- Name: a\n  DependsOn: [db]\n").unwrap_err();
+    assert!(err.to_string().contains("is not a cargo"));
+    assert!(layers("Cargoes:\n- Name: a\n  DependsOn: db\n").is_err());
+  }
+}
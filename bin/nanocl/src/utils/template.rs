@@ -0,0 +1,124 @@
+use nanocl_utils::io_error::{IoError, IoResult, FromIo};
+
+/// ## Lookup path
+///
+/// Resolve a dotted path like `.Config.Container.Image` in a json value.
+/// Numeric segments index into arrays and `.` alone returns the value itself
+///
+/// ## Arguments
+///
+/// * [value](serde_json::Value) The value to walk
+/// * [path](str) The dotted path
+///
+/// ## Return
+///
+/// * [Option](Option) The value found at the given path if any
+///
+fn lookup_path<'a>(
+  value: &'a serde_json::Value,
+  path: &str,
+) -> Option<&'a serde_json::Value> {
+  path
+    .split('.')
+    .skip(1)
+    .filter(|segment| !segment.is_empty())
+    .try_fold(value, |current, segment| match current {
+      serde_json::Value::Object(map) => map.get(segment),
+      serde_json::Value::Array(items) => segment
+        .parse::<usize>()
+        .ok()
+        .and_then(|index| items.get(index)),
+      _ => None,
+    })
+}
+
+/// ## Render template
+///
+/// Render a Go-template-like string such as `{{.Name}} {{.Spec.Image}}`
+/// over the serialized json value of the given data.
+/// Strings are printed as is, other values are printed as json.
+///
+/// ## Arguments
+///
+/// * [template](str) The template to render
+/// * [data](serde::Serialize) The data to render
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](String) The rendered template
+///   * [Err](IoError) The template is invalid or reference an unknown field
+///
+pub fn render_template<T>(template: &str, data: T) -> IoResult<String>
+where
+  T: serde::Serialize,
+{
+  let value = serde_json::to_value(data)
+    .map_err(|err| err.map_err_context(|| "Template"))?;
+  let mut output = String::new();
+  let mut rest = template;
+  while let Some(start) = rest.find("{{") {
+    output.push_str(&rest[..start]);
+    let after = &rest[start + 2..];
+    let end = after.find("}}").ok_or_else(|| {
+      IoError::invalid_input(
+        "Template",
+        format!("unclosed expression `{}`", &rest[start..]).as_str(),
+      )
+    })?;
+    let expr = after[..end].trim();
+    if !expr.starts_with('.') {
+      return Err(IoError::invalid_input(
+        "Template",
+        format!("unsupported expression `{{{{{expr}}}}}` expected a path")
+          .as_str(),
+      ));
+    }
+    let found = lookup_path(&value, expr).ok_or_else(|| {
+      IoError::invalid_input(
+        "Template",
+        format!("unknown field in expression `{{{{{expr}}}}}`").as_str(),
+      )
+    })?;
+    match found {
+      serde_json::Value::String(s) => output.push_str(s),
+      serde_json::Value::Null => output.push_str("<none>"),
+      other => output.push_str(&other.to_string()),
+    }
+    rest = &after[end + 2..];
+  }
+  output.push_str(rest);
+  Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn render_fields() {
+    let data = serde_json::json!({
+      "Name": "nginx",
+      "Config": {
+        "Container": { "Image": "nginx:latest" },
+        "Replicas": 2,
+        "Ports": ["80", "443"],
+      },
+    });
+    let template = "{{.Name}} {{ .Config.Container.Image }} \
+      {{.Config.Replicas}} {{.Config.Ports.1}}";
+    let output = render_template(template, &data).unwrap();
+    assert_eq!(output, "nginx nginx:latest 2 443");
+  }
+
+  #[test]
+  fn render_errors() {
+    let data = serde_json::json!({ "Name": "nginx" });
+    let err = render_template("{{.Name}} {{.Config.Image}}", &data)
+      .unwrap_err()
+      .to_string();
+    assert!(err.contains("{{.Config.Image}}"));
+    assert!(render_template("{{Name}}", &data).is_err());
+    assert!(render_template("{{.Name", &data).is_err());
+  }
+}
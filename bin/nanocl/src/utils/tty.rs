@@ -0,0 +1,286 @@
+use std::rc::Rc;
+use std::thread;
+use std::sync::Mutex;
+use std::cell::RefCell;
+use std::time::Duration;
+use std::io::{Read, Write};
+use std::os::fd::AsRawFd;
+
+use ntex::rt;
+use ntex::ws;
+use ntex::time;
+use ntex::io::Base;
+use ntex::util::Bytes;
+use ntex::ws::WsConnection;
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use termios::{TCSANOW, tcsetattr, Termios, ICANON, ECHO};
+
+use nanocl_utils::io_error::{IoError, IoResult, FromIo};
+use nanocld_client::stubs::cargo::{OutputLog, OutputKind};
+
+use super::record::Recorder;
+
+/// ## Original termios
+///
+/// Terminal settings saved while a [RawModeGuard](RawModeGuard) is alive,
+/// so they can be restored from a signal handler that never runs the guard `Drop`
+///
+static ORIGINAL_TERMIOS: Mutex<Option<Termios>> = Mutex::new(None);
+
+/// ## RawModeGuard
+///
+/// Disable the canonical mode and the echo of the terminal attached to stdin,
+/// so every key is forwarded as soon as it's pressed.
+/// The original terminal settings are restored when the guard is dropped,
+/// including on early returns and panics.
+/// On SIGINT or SIGTERM the handler installed in `main` restore them
+/// with [restore_saved_mode](restore_saved_mode) before exiting
+///
+pub struct RawModeGuard {
+  original_termios: Termios,
+}
+
+impl RawModeGuard {
+  /// ## New
+  ///
+  /// Enable the raw mode of the terminal attached to stdin
+  ///
+  /// ## Return
+  ///
+  /// * [Result](Result) The result of the operation
+  ///   * [Ok](RawModeGuard) The guard restoring the terminal once dropped
+  ///   * [Err](IoError) An error occured
+  ///
+  pub fn new() -> IoResult<Self> {
+    let fd = std::io::stdin().as_raw_fd();
+    // Get the current terminal settings
+    let mut termios = Termios::from_fd(fd)?;
+    // Save a copy of the original terminal settings
+    let original_termios = termios;
+    if let Ok(mut saved) = ORIGINAL_TERMIOS.lock() {
+      *saved = Some(original_termios);
+    }
+    // Disable canonical mode and echo
+    termios.c_lflag &= !(ICANON | ECHO);
+    // Apply the new terminal settings
+    tcsetattr(fd, TCSANOW, &termios)?;
+    Ok(Self { original_termios })
+  }
+}
+
+impl Drop for RawModeGuard {
+  fn drop(&mut self) {
+    if let Ok(mut saved) = ORIGINAL_TERMIOS.lock() {
+      saved.take();
+    }
+    let fd = std::io::stdin().as_raw_fd();
+    let _ = tcsetattr(fd, TCSANOW, &self.original_termios);
+  }
+}
+
+/// ## Restore saved mode
+///
+/// Restore the terminal settings saved by a [RawModeGuard](RawModeGuard) still alive,
+/// used when the process exit without dropping it eg: on SIGINT or SIGTERM
+///
+pub fn restore_saved_mode() {
+  let Ok(mut saved) = ORIGINAL_TERMIOS.lock() else {
+    return;
+  };
+  if let Some(original_termios) = saved.take() {
+    let fd = std::io::stdin().as_raw_fd();
+    let _ = tcsetattr(fd, TCSANOW, &original_termios);
+  }
+}
+
+/// ## Forward stdin
+///
+/// Read stdin until it's closed and send it as text messages
+/// to the current websocket connection, if any.
+/// The connection can be replaced while stdin is forwarded.
+///
+/// ## Arguments
+///
+/// * [current_sink](Rc) The sink where stdin is sent
+///
+pub fn forward_stdin(current_sink: Rc<RefCell<Option<ws::WsSink>>>) {
+  let (mut tx, mut rx) = mpsc::unbounded();
+  // start console read loop
+  thread::spawn(move || loop {
+    let mut input = [0; 1];
+    match std::io::stdin().read(&mut input) {
+      // stdin is closed
+      Ok(0) => return,
+      Ok(_) => {}
+      Err(_) => {
+        eprintln!("Unable to read stdin");
+        return;
+      }
+    }
+    let Ok(s) = std::str::from_utf8(&input) else {
+      continue;
+    };
+    // send text to server
+    if futures::executor::block_on(tx.send(ws::Message::Text(s.into())))
+      .is_err()
+    {
+      return;
+    }
+  });
+  // read console commands and send them to the current connection
+  rt::spawn(async move {
+    while let Some(msg) = rx.next().await {
+      let sink = current_sink.borrow().clone();
+      if let Some(sink) = sink {
+        let _ = sink.send(msg).await;
+      }
+    }
+  });
+}
+
+/// Default interval between heartbeat pings
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// ## Heartbeat interval
+///
+/// Resolve how often heartbeat pings are sent to a websocket session,
+/// an interval given on the command line take precedence over
+/// the `NANOCL_HEARTBEAT_INTERVAL` environment variable which is parsed with
+/// [parse_interval](crate::utils::duration::parse_interval)
+///
+/// ## Arguments
+///
+/// * [interval](Option<Duration>) The interval given on the command line
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](Duration) The interval to use
+///   * [Err](IoError) The `NANOCL_HEARTBEAT_INTERVAL` variable is invalid
+///
+fn heartbeat_interval(interval: Option<Duration>) -> IoResult<Duration> {
+  if let Some(interval) = interval {
+    return Ok(interval);
+  }
+  match std::env::var("NANOCL_HEARTBEAT_INTERVAL") {
+    Ok(interval) => crate::utils::duration::parse_interval(interval.trim())
+      .map_err(|err| {
+        IoError::invalid_input("NANOCL_HEARTBEAT_INTERVAL".to_owned(), err)
+      }),
+    Err(_) => Ok(HEARTBEAT_INTERVAL),
+  }
+}
+
+/// ## Dispatch
+///
+/// Forward the output of a websocket session (vm console, cargo exec)
+/// to the terminal until the connection ends.
+///
+/// ## Arguments
+///
+/// * [conn](WsConnection) The websocket connection of the session
+/// * [current_sink](Rc) The sink where stdin is sent
+/// * [recorder](Option<Recorder>) Where the output is also written,
+///   it stops recording with a warning if the file cannot be written
+/// * [heartbeat](Option<Duration>) The interval between heartbeat pings,
+///   see [heartbeat_interval](heartbeat_interval) when none
+/// * [idle_timeout](Duration) How long without receiving anything before
+///   the session ends, zero to wait forever
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](bool) True if the connection was dropped,
+///     false if it was closed or idle for too long
+///   * [Err](IoError) An error occured
+///
+pub async fn dispatch(
+  conn: WsConnection<Base>,
+  current_sink: &Rc<RefCell<Option<ws::WsSink>>>,
+  mut recorder: Option<&mut Recorder>,
+  heartbeat: Option<Duration>,
+  idle_timeout: Duration,
+) -> IoResult<bool> {
+  let heartbeat_interval = heartbeat_interval(heartbeat)?;
+  // start heartbeat task
+  let sink = conn.sink();
+  rt::spawn(async move {
+    loop {
+      time::sleep(heartbeat_interval).await;
+      if sink.send(ws::Message::Ping(Bytes::new())).await.is_err() {
+        return;
+      }
+    }
+  });
+  // send stdin to this connection
+  *current_sink.borrow_mut() = Some(conn.sink());
+  let mut stderr = std::io::stderr();
+  let mut stdout = std::io::stdout();
+  // run ws dispatcher
+  let sink = conn.sink();
+  let mut rx = conn.seal().receiver();
+  loop {
+    let frame = if idle_timeout.is_zero() {
+      rx.next().await
+    } else {
+      match time::timeout(idle_timeout, rx.next()).await {
+        Ok(frame) => frame,
+        Err(_) => {
+          eprintln!(
+            "No data received for {}s, disconnecting",
+            idle_timeout.as_secs_f64()
+          );
+          current_sink.borrow_mut().take();
+          return Ok(false);
+        }
+      }
+    };
+    let Some(frame) = frame else {
+      break;
+    };
+    match frame {
+      Ok(ws::Frame::Binary(text)) => {
+        let output =
+          serde_json::from_slice::<OutputLog>(&text).map_err(|err| {
+            err.map_err_context(|| "Unable to serialize output")
+          })?;
+        match &output.kind {
+          OutputKind::StdOut => {
+            stdout.write_all(output.data.as_bytes())?;
+            stdout.flush()?;
+          }
+          OutputKind::StdErr => {
+            stderr.write_all(output.data.as_bytes())?;
+            stderr.flush()?;
+          }
+          OutputKind::Console => {
+            stdout.write_all(output.data.as_bytes())?;
+            stdout.flush()?;
+          }
+          _ => {}
+        }
+        if let Some(rec) = recorder.as_deref_mut() {
+          if let Err(err) = rec.record(&output.kind, &output.data) {
+            eprintln!("WARN: Stopped recording the session: {err}");
+            recorder = None;
+          }
+        }
+      }
+      Ok(ws::Frame::Ping(msg)) => {
+        sink
+          .send(ws::Message::Pong(msg))
+          .await
+          .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+      }
+      Ok(ws::Frame::Close(_)) => break,
+      Err(_) => {
+        current_sink.borrow_mut().take();
+        return Ok(true);
+      }
+      _ => (),
+    }
+  }
+  current_sink.borrow_mut().take();
+  Ok(false)
+}
@@ -0,0 +1,219 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use serde::{Serialize, Deserialize};
+
+use nanocl_utils::io_error::{IoResult, FromIo};
+
+/// Number of bytes sent between two saves of the upload state
+pub const SAVE_INTERVAL: u64 = 8 * 1024 * 1024;
+
+/// ## UploadState
+///
+/// State of an upload saved in a `.part` file next to the uploaded file,
+/// so an interrupted upload of the same file can be resumed
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct UploadState {
+  /// Name the file is uploaded as
+  pub name: String,
+  /// Size of the uploaded file
+  pub size: u64,
+  /// Last modification of the uploaded file in seconds since epoch
+  pub modified: u64,
+  /// Number of bytes sent so far
+  pub sent: u64,
+}
+
+impl UploadState {
+  /// ## New
+  ///
+  /// Create the state of a new upload of a file
+  ///
+  /// ## Arguments
+  ///
+  /// * [name](str) The name the file is uploaded as
+  /// * [metadata](std::fs::Metadata) The metadata of the file
+  ///
+  pub fn new(name: &str, metadata: &std::fs::Metadata) -> Self {
+    let modified = metadata
+      .modified()
+      .ok()
+      .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+      .map(|time| time.as_secs())
+      .unwrap_or_default();
+    Self {
+      name: name.to_owned(),
+      size: metadata.len(),
+      modified,
+      sent: 0,
+    }
+  }
+
+  /// ## Same upload
+  ///
+  /// True if both states are uploads of the same unchanged file
+  /// with the same name
+  ///
+  pub fn same_upload(&self, other: &Self) -> bool {
+    self.name == other.name
+      && self.size == other.size
+      && self.modified == other.modified
+  }
+}
+
+/// ## Part path
+///
+/// Path of the `.part` state file of an uploaded file
+///
+pub fn part_path(file: &Path) -> PathBuf {
+  let mut path = file.as_os_str().to_owned();
+  path.push(".part");
+  PathBuf::from(path)
+}
+
+/// ## Read state
+///
+/// Read the state of an interrupted upload of a file
+///
+/// ## Return
+///
+/// * [Option](Option<UploadState>) The state or none if missing or invalid
+///
+pub fn read_state(file: &Path) -> Option<UploadState> {
+  let content = std::fs::read_to_string(part_path(file)).ok()?;
+  serde_yaml::from_str(&content).ok()
+}
+
+/// ## Write state
+///
+/// Save the state of an upload next to the uploaded file
+///
+/// ## Arguments
+///
+/// * [file](Path) The uploaded file
+/// * [state](UploadState) The state of the upload
+///
+/// ## Return
+///
+/// * [Result](Result) The result of the operation
+///   * [Ok](()) The state was saved
+///   * [Err](IoError) The state couldn't be saved
+///
+pub fn write_state(file: &Path, state: &UploadState) -> IoResult<()> {
+  let path = part_path(file);
+  let content = serde_yaml::to_string(state)
+    .map_err(|err| err.map_err_context(|| path.display().to_string()))?;
+  std::fs::write(&path, content)
+    .map_err(|err| err.map_err_context(|| path.display().to_string()))?;
+  Ok(())
+}
+
+/// ## Remove state
+///
+/// Remove the state of a completed upload, a missing state is ignored
+///
+pub fn remove_state(file: &Path) {
+  let _ = std::fs::remove_file(part_path(file));
+}
+
+/// ## Throttle
+///
+/// Limit the bandwidth of a transfer to a number of bytes per second
+///
+pub struct Throttle {
+  /// Maximum number of bytes per second
+  limit: u64,
+  /// When the transfer started
+  start: Instant,
+  /// Number of bytes transfered
+  sent: u64,
+}
+
+impl Throttle {
+  /// ## New
+  ///
+  /// Create a throttle starting now
+  ///
+  /// ## Arguments
+  ///
+  /// * [limit](u64) The maximum number of bytes per second
+  ///
+  pub fn new(limit: u64) -> Self {
+    Self {
+      limit: limit.max(1),
+      start: Instant::now(),
+      sent: 0,
+    }
+  }
+
+  /// ## Delay
+  ///
+  /// Count the bytes about to be transfered
+  /// and return how long to wait before transfering them
+  ///
+  /// ## Arguments
+  ///
+  /// * [bytes](u64) The number of bytes about to be transfered
+  ///
+  pub fn delay(&mut self, bytes: u64) -> Duration {
+    let elapsed = self.start.elapsed();
+    self.delay_after(bytes, elapsed)
+  }
+
+  /// ## Delay after
+  ///
+  /// Same as [delay](Throttle::delay) when the transfer started
+  /// `elapsed` ago
+  ///
+  fn delay_after(&mut self, bytes: u64, elapsed: Duration) -> Duration {
+    self.sent += bytes;
+    let expected =
+      Duration::from_secs_f64(self.sent as f64 / self.limit as f64);
+    expected.saturating_sub(elapsed)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn throttle() {
+    let mut throttle = Throttle::new(1000);
+    assert_eq!(throttle.delay_after(500, Duration::ZERO).as_millis(), 500);
+    assert_eq!(
+      throttle
+        .delay_after(500, Duration::from_millis(500))
+        .as_millis(),
+      500
+    );
+    assert_eq!(
+      throttle.delay_after(1000, Duration::from_secs(5)),
+      Duration::ZERO
+    );
+  }
+
+  #[test]
+  fn upload_state() {
+    let dir = std::env::temp_dir().join("nanocl-upload-state-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("image.img");
+    std::fs::write(&file, "content").unwrap();
+    let metadata = std::fs::metadata(&file).unwrap();
+    assert_eq!(part_path(&file), dir.join("image.img.part"));
+    assert_eq!(read_state(&file), None);
+    let mut state = UploadState::new("image", &metadata);
+    assert_eq!(state.size, 7);
+    state.sent = 4;
+    write_state(&file, &state).unwrap();
+    let saved = read_state(&file).unwrap();
+    assert_eq!(saved, state);
+    assert!(saved.same_upload(&UploadState::new("image", &metadata)));
+    assert!(!saved.same_upload(&UploadState::new("other", &metadata)));
+    remove_state(&file);
+    assert_eq!(read_state(&file), None);
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
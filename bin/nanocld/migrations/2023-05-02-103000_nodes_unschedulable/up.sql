@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE "nodes" ADD COLUMN IF NOT EXISTS "unschedulable" BOOLEAN NOT NULL DEFAULT FALSE;
@@ -5,6 +5,7 @@ use nanocl_stubs::config::DaemonConfig;
 
 use crate::{event, utils};
 use crate::models::DaemonState;
+use crate::idempotency::IdempotencyStore;
 
 use crate::version::VERSION;
 
@@ -61,6 +62,7 @@ pub async fn init(daemon_conf: &DaemonConfig) -> IoResult<DaemonState> {
     docker_api: docker.clone(),
     config: daemon_conf.to_owned(),
     event_emitter: event::EventEmitter::new(),
+    idempotency: IdempotencyStore::new(),
     version: VERSION.to_owned(),
   };
   utils::system::register_namespace("system", false, &daemon_state).await?;
@@ -0,0 +1,217 @@
+use std::sync::Arc;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use std::time::Instant;
+use std::collections::HashMap;
+
+use ntex::web::HttpRequest;
+
+use nanocl_utils::http_error::HttpError;
+
+/// Header holding the idempotency key of a create request
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Header set on a response replayed from its idempotency key
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+/// How long a response is kept for its idempotency key
+const IDEMPOTENCY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Responses by key with when they were created,
+/// none while the request of the key is in progress
+type Responses = HashMap<String, (Instant, Option<serde_json::Value>)>;
+
+/// ## IdempotencyStore
+///
+/// Remember the response of create requests by idempotency key,
+/// a request retried after the client timed out get the original response
+/// instead of a conflict. Responses are kept in memory for a day.
+/// A key is reserved before the item is created so concurrent requests
+/// with the same key can't create it twice.
+///
+#[derive(Clone, Default)]
+pub struct IdempotencyStore {
+  inner: Arc<Mutex<Responses>>,
+}
+
+/// ## Reservation
+///
+/// Result of [reserve](IdempotencyStore::reserve)
+///
+pub enum Reservation {
+  /// The key already has a response to replay
+  Replay(serde_json::Value),
+  /// The key is reserved for the current request
+  Reserved(IdempotencyGuard),
+}
+
+/// ## IdempotencyGuard
+///
+/// Hold the reservation of an idempotency key until its response is
+/// remembered, the key is released if dropped before
+/// so a failed request can be retried
+///
+pub struct IdempotencyGuard {
+  store: IdempotencyStore,
+  key: String,
+  remembered: bool,
+}
+
+impl IdempotencyGuard {
+  /// ## Remember
+  ///
+  /// Remember the response of the reserved idempotency key
+  ///
+  /// ## Arguments
+  ///
+  /// - [response](serde::Serialize) - The response to replay
+  ///
+  /// ## Returns
+  ///
+  /// - [Result](Result) - The result of the operation
+  ///   - [Ok](()) - The response is remembered
+  ///   - [Err](HttpError) - The response can't be serialized
+  ///
+  pub fn remember<T>(mut self, response: &T) -> Result<(), HttpError>
+  where
+    T: serde::Serialize,
+  {
+    let response = serde_json::to_value(response).map_err(|err| {
+      HttpError::internal_server_error(format!(
+        "Unable to remember idempotent response: {err}"
+      ))
+    })?;
+    let mut inner = self.store.lock()?;
+    inner.insert(self.key.clone(), (Instant::now(), Some(response)));
+    self.remembered = true;
+    Ok(())
+  }
+}
+
+impl Drop for IdempotencyGuard {
+  fn drop(&mut self) {
+    if self.remembered {
+      return;
+    }
+    if let Ok(mut inner) = self.store.lock() {
+      inner.remove(&self.key);
+    }
+  }
+}
+
+impl IdempotencyStore {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// ## Request key
+  ///
+  /// Get the idempotency key of a request scoped by the kind of the created
+  /// item, so the same key sent to create another kind isn't replayed
+  ///
+  /// ## Arguments
+  ///
+  /// - [req](HttpRequest) - The create request
+  /// - [kind](str) - The kind of the created item like `cargo`
+  ///
+  /// ## Returns
+  ///
+  /// - [Option](Option<String>) - The scoped key when the header is set
+  ///
+  pub fn request_key(req: &HttpRequest, kind: &str) -> Option<String> {
+    let key = req.headers().get(IDEMPOTENCY_KEY_HEADER)?.to_str().ok()?;
+    if key.is_empty() {
+      return None;
+    }
+    Some(format!("{kind}/{key}"))
+  }
+
+  /// ## Reserve
+  ///
+  /// Get the response remembered for an idempotency key
+  /// or reserve the key for the current request in the same lock,
+  /// expired responses are dropped at the same time
+  ///
+  /// ## Arguments
+  ///
+  /// - [key](str) - The scoped idempotency key
+  ///
+  /// ## Returns
+  ///
+  /// - [Result](Result) - The result of the operation
+  ///   - [Ok](Reservation) - The response to replay or the reserved key
+  ///   - [Err](HttpError) - A request with the same key is in progress
+  ///
+  pub fn reserve(&self, key: &str) -> Result<Reservation, HttpError> {
+    let mut inner = self.lock()?;
+    inner.retain(|_, (created_at, _)| created_at.elapsed() < IDEMPOTENCY_TTL);
+    match inner.get(key) {
+      Some((_, Some(response))) => Ok(Reservation::Replay(response.clone())),
+      Some((_, None)) => Err(HttpError::conflict(format!(
+        "A request with the idempotency key {key} is in progress"
+      ))),
+      None => {
+        inner.insert(key.to_owned(), (Instant::now(), None));
+        Ok(Reservation::Reserved(IdempotencyGuard {
+          store: self.clone(),
+          key: key.to_owned(),
+          remembered: false,
+        }))
+      }
+    }
+  }
+
+  /// ## Lock
+  ///
+  /// Lock the responses of the store
+  ///
+  fn lock(&self) -> Result<MutexGuard<Responses>, HttpError> {
+    self.inner.lock().map_err(|err| {
+      HttpError::internal_server_error(format!(
+        "Unable to lock idempotency store: {err}"
+      ))
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use ntex::web::test::TestRequest;
+
+  fn replay(store: &IdempotencyStore, key: &str) -> Option<serde_json::Value> {
+    match store.reserve(key).unwrap() {
+      Reservation::Replay(response) => Some(response),
+      Reservation::Reserved(_) => None,
+    }
+  }
+
+  #[test]
+  fn replay_by_scoped_key() {
+    let req = TestRequest::default()
+      .header(IDEMPOTENCY_KEY_HEADER, "8a4b")
+      .to_http_request();
+    let key = IdempotencyStore::request_key(&req, "cargo").unwrap();
+    assert_eq!(key, "cargo/8a4b");
+    let req = TestRequest::default().to_http_request();
+    assert!(IdempotencyStore::request_key(&req, "cargo").is_none());
+    let store = IdempotencyStore::new();
+    let Reservation::Reserved(guard) = store.reserve(&key).unwrap() else {
+      panic!("expected the key to be reserved");
+    };
+    assert!(store.reserve(&key).is_err());
+    guard.remember(&"my-cargo").unwrap();
+    assert_eq!(replay(&store, &key), Some(serde_json::json!("my-cargo")));
+    assert!(replay(&store, "vm/8a4b").is_none());
+  }
+
+  #[test]
+  fn release_on_drop() {
+    let store = IdempotencyStore::new();
+    let reservation = store.reserve("cargo/8a4b").unwrap();
+    assert!(store.reserve("cargo/8a4b").is_err());
+    drop(reservation);
+    assert!(replay(&store, "cargo/8a4b").is_none());
+  }
+}
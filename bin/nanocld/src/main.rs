@@ -14,6 +14,7 @@ mod node;
 mod boot;
 mod utils;
 mod event;
+mod idempotency;
 mod schema;
 mod models;
 mod config;
@@ -19,6 +19,8 @@ pub struct NodeDbModel {
   pub(crate) name: String,
   /// The ip address of the node
   pub(crate) ip_address: String,
+  /// The node is cordoned, no new workloads are created on it
+  pub(crate) unschedulable: bool,
 }
 
 impl NodeDbModel {
@@ -4,6 +4,7 @@ use nanocl_stubs::{
 };
 
 use crate::event::EventEmitter;
+use crate::idempotency::IdempotencyStore;
 
 use super::Pool;
 
@@ -23,6 +24,8 @@ pub struct DaemonState {
   pub(crate) config: DaemonConfig,
   /// The event emitter
   pub(crate) event_emitter: EventEmitter,
+  /// The responses of create requests by idempotency key
+  pub(crate) idempotency: IdempotencyStore,
   /// Latest version of the daemon or version of current request
   #[allow(dead_code)]
   pub(crate) version: String,
@@ -80,6 +80,7 @@ impl From<VmImageDbModel> for VmImage {
       format: db_model.format,
       size_actual: db_model.size_actual,
       size_virtual: db_model.size_virtual,
+      parent: db_model.parent,
     }
   }
 }
@@ -215,11 +215,39 @@ pub async fn register(daemon_state: &DaemonState) -> IoResult<()> {
   let node = NodeDbModel {
     name: daemon_state.config.hostname.clone(),
     ip_address: daemon_state.config.gateway.clone(),
+    unschedulable: false,
   };
   repositories::node::create_if_not_exists(&node, &daemon_state.pool).await?;
   Ok(())
 }
 
+/// ## Ensure schedulable
+///
+/// Refuse to create workloads on this node when it's cordoned
+/// with `nanocl node cordon`, a node not registered yet is schedulable
+///
+/// ## Arguments
+///
+/// - [state](DaemonState) - The daemon state
+///
+/// ## Returns
+///
+/// - [Result](Result) - The result of the operation
+///   - [Ok](()) - Workloads can be created on the node
+///   - [Err](HttpError) - The node is cordoned
+///
+pub async fn ensure_schedulable(state: &DaemonState) -> Result<(), HttpError> {
+  let node =
+    repositories::node::find_by_name(&state.config.hostname, &state.pool).await;
+  match node {
+    Ok(node) if node.unschedulable => Err(HttpError::conflict(format!(
+      "Node {} is cordoned, run `nanocl node uncordon {}` to create workloads on it",
+      node.name, node.name
+    ))),
+    _ => Ok(()),
+  }
+}
+
 pub async fn join_cluster(state: &DaemonState) -> IoResult<()> {
   let state = state.clone();
   let (tx, mut rx) = mpsc::unbounded();
@@ -94,6 +94,42 @@ pub async fn create_if_not_exists(
   }
 }
 
+/// ## Update unschedulable
+///
+/// Cordon or uncordon a node in database
+///
+/// ## Arguments
+///
+/// - [name](str) - Node name
+/// - [unschedulable](bool) - True to cordon the node
+/// - [pool](Pool) - Database connection pool
+///
+/// ## Returns
+///
+/// - [Result](Result) - The result of the operation
+///   - [Ok](NodeDbModel) - The updated node item
+///   - [Err](IoError) - Error during the operation
+///
+pub async fn update_unschedulable(
+  name: &str,
+  unschedulable: bool,
+  pool: &Pool,
+) -> IoResult<NodeDbModel> {
+  use crate::schema::nodes::dsl;
+  let name = name.to_owned();
+  let pool = pool.clone();
+  let item = web::block(move || {
+    let mut conn = utils::store::get_pool_conn(&pool)?;
+    let item = diesel::update(dsl::nodes.filter(dsl::name.eq(name)))
+      .set(dsl::unschedulable.eq(unschedulable))
+      .get_result(&mut conn)
+      .map_err(|err| err.map_err_context(|| "nodes"))?;
+    Ok::<_, IoError>(item)
+  })
+  .await?;
+  Ok(item)
+}
+
 /// ## List
 ///
 /// List all nodes in database
@@ -89,6 +89,70 @@ pub async fn get_version(
   Ok(item)
 }
 
+/// ## Find latest version
+///
+/// Get the last created version of a resource kind in database
+///
+/// ## Arguments
+///
+/// - [name](str) - Resource kind name
+/// - [pool](Pool) - Database connection pool
+///
+/// ## Returns
+///
+/// - [Result](Result) - The result of the operation
+///   - [Ok](ResourceKindVersionDbModel) - Resource kind version
+///   - [Err](IoError) - Error during the operation
+///
+pub async fn find_latest_version(
+  name: &str,
+  pool: &Pool,
+) -> IoResult<ResourceKindVersionDbModel> {
+  use crate::schema::resource_kind_versions::dsl;
+  let pool = pool.clone();
+  let name = name.to_owned();
+  let item = web::block(move || {
+    let mut conn = utils::store::get_pool_conn(&pool)?;
+    let item = dsl::resource_kind_versions
+      .filter(dsl::resource_kind_name.eq(&name))
+      .order(dsl::created_at.desc())
+      .first(&mut conn)
+      .map_err(|err| err.map_err_context(|| format!("Resource {name}")))?;
+    Ok::<_, IoError>(item)
+  })
+  .await?;
+  Ok(item)
+}
+
+/// ## List
+///
+/// List the resource kinds in database ordered by name
+///
+/// ## Arguments
+///
+/// - [pool](Pool) - Database connection pool
+///
+/// ## Returns
+///
+/// - [Result](Result) - The result of the operation
+///   - [Ok](Vec<ResourceKindDbModel>) - Resource kinds
+///   - [Err](IoError) - Error during the operation
+///
+pub async fn list(pool: &Pool) -> IoResult<Vec<ResourceKindDbModel>> {
+  use crate::schema::resource_kinds::dsl;
+  let pool = pool.clone();
+  let items = web::block(move || {
+    let mut conn = utils::store::get_pool_conn(&pool)?;
+    let items = dsl::resource_kinds
+      .order(dsl::name.asc())
+      .get_results(&mut conn)
+      .map_err(|err| err.map_err_context(|| "ResourceKind"))?;
+    Ok::<_, IoError>(items)
+  })
+  .await?;
+  Ok(items)
+}
+
 /// ## Find by name
 ///
 /// Find a resource kind by his name
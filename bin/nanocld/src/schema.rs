@@ -85,6 +85,7 @@ diesel::table! {
     nodes (name) {
         name -> Varchar,
         ip_address -> Varchar,
+        unschedulable -> Bool,
     }
 }
 
@@ -2,23 +2,42 @@
 * Endpoints to manipulate cargoes
 */
 
+use std::io;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::time::Instant;
+
 use ntex::rt;
+use ntex::ws;
 use ntex::web;
 use ntex::http;
+use ntex::util::Bytes;
+use ntex::channel::mpsc;
+use ntex::channel::oneshot;
+use ntex::web::{HttpRequest, Error};
+use ntex::{chain, fn_service, Service};
+use ntex::service::{fn_shutdown, map_config, fn_factory_with_config};
+use futures::StreamExt;
+use futures::future::ready;
+use tokio::io::AsyncWriteExt;
 
-use bollard_next::exec::CreateExecOptions;
+use bollard_next::exec::{CreateExecOptions, StartExecOptions, StartExecResults};
 
 use nanocl_stubs::system::Event;
 use nanocl_stubs::generic::GenericNspQuery;
 use nanocl_stubs::cargo::{
-  CargoListQuery, CargoDeleteQuery, CargoKillOptions, CargoLogQuery, CargoScale,
+  CargoListQuery, CargoDeleteQuery, CargoKillOptions, CargoLogQuery,
+  CargoStatsQuery, CargoScale, CargoExecQuery, OutputLog,
 };
 use nanocl_stubs::cargo_config::{CargoConfigPartial, CargoConfigUpdate};
 
 use nanocl_utils::http_error::HttpError;
 
 use crate::{utils, repositories};
-use crate::models::{DaemonState, CargoRevertPath};
+use crate::models::{DaemonState, CargoRevertPath, WsConState};
+use crate::idempotency::{
+  IdempotencyStore, Reservation, IDEMPOTENT_REPLAYED_HEADER,
+};
 
 /// List cargoes
 #[cfg_attr(feature = "dev", utoipa::path(
@@ -104,21 +123,41 @@ async fn inspect_cargo(
   request_body = CargoConfigPartial,
   params(
     ("Namespace" = Option<String>, Query, description = "Namespace of the cargo"),
+    ("Idempotency-Key" = Option<String>, Header, description = "Key to get the original response when the request is retried"),
   ),
   responses(
     (status = 201, description = "Cargo created", body = CargoInspect),
+    (status = 200, description = "Cargo already created with the same Idempotency-Key", body = CargoInspect),
   ),
 ))]
 #[web::post("/cargoes")]
 pub(crate) async fn create_cargo(
+  req: HttpRequest,
   web::types::Query(qs): web::types::Query<GenericNspQuery>,
   web::types::Json(payload): web::types::Json<CargoConfigPartial>,
   version: web::types::Path<String>,
   state: web::types::State<DaemonState>,
 ) -> Result<web::HttpResponse, HttpError> {
+  // The reservation is released if the creation fails
+  let reservation = match IdempotencyStore::request_key(&req, "cargo") {
+    None => None,
+    Some(key) => match state.idempotency.reserve(&key)? {
+      Reservation::Reserved(guard) => Some(guard),
+      Reservation::Replay(cargo) => {
+        return Ok(
+          web::HttpResponse::Ok()
+            .header(IDEMPOTENT_REPLAYED_HEADER, "true")
+            .json(&cargo),
+        );
+      }
+    },
+  };
   let namespace = utils::key::resolve_nsp(&qs.namespace);
   let cargo =
     utils::cargo::create(&namespace, &payload, &version, &state).await?;
+  if let Some(guard) = reservation {
+    guard.remember(&cargo)?;
+  }
   let key = cargo.key.to_owned();
   rt::spawn(async move {
     let cargo = utils::cargo::inspect_by_key(&key, &state).await.unwrap();
@@ -355,6 +394,165 @@ async fn exec_command(
   utils::cargo::exec_command(&key, &payload, &state).await
 }
 
+async fn ws_exec_service(
+  (key, qs, sink, state): (
+    String,
+    CargoExecQuery,
+    ws::WsSink,
+    web::types::State<DaemonState>,
+  ),
+) -> Result<
+  impl Service<ws::Frame, Response = Option<ws::Message>, Error = io::Error>,
+  web::Error,
+> {
+  let cmd =
+    serde_json::from_str::<Vec<String>>(&qs.cmd).map_err(|err| HttpError {
+      status: http::StatusCode::BAD_REQUEST,
+      msg: format!("Unable to parse command {err}"),
+    })?;
+  let container = match qs.replica {
+    None | Some(0) => format!("{key}.c"),
+    Some(replica) => format!("{replica}-{key}.c"),
+  };
+  let tty = qs.tty.unwrap_or_default();
+  let exec = state
+    .docker_api
+    .create_exec(
+      &container,
+      CreateExecOptions {
+        cmd: Some(cmd),
+        attach_stdin: qs.stdin,
+        attach_stdout: Some(true),
+        attach_stderr: Some(true),
+        tty: Some(tty),
+        ..Default::default()
+      },
+    )
+    .await
+    .map_err(HttpError::from)?;
+  let res = state
+    .docker_api
+    .start_exec(
+      &exec.id,
+      Some(StartExecOptions {
+        tty,
+        ..Default::default()
+      }),
+    )
+    .await
+    .map_err(HttpError::from)?;
+  let (mut output, mut input) = match res {
+    StartExecResults::Attached { output, input } => (output, input),
+    StartExecResults::Detached => {
+      return Err(
+        HttpError {
+          status: http::StatusCode::INTERNAL_SERVER_ERROR,
+          msg: format!("Exec {} is detached", exec.id),
+        }
+        .into(),
+      )
+    }
+  };
+  // start heartbeat task
+  let con_state = Rc::new(RefCell::new(WsConState::new()));
+  let (tx, rx) = oneshot::channel();
+  rt::spawn(utils::ws::heartbeat(con_state.clone(), sink.clone(), rx));
+  let (scmd, mut rcmd) = mpsc::channel::<Result<Bytes, web::Error>>();
+  rt::spawn(async move {
+    while let Some(output) = output.next().await {
+      let output = match output {
+        Ok(output) => output,
+        Err(e) => {
+          log::error!("Error reading from exec: {}", e);
+          break;
+        }
+      };
+      let outputlog: OutputLog = output.into();
+      let mut output = match serde_json::to_vec(&outputlog) {
+        Ok(output) => output,
+        Err(e) => {
+          log::error!("Error serializing output: {}", e);
+          break;
+        }
+      };
+      output.push(b'\n');
+      let msg = ws::Message::Binary(Bytes::from(output));
+      if sink.send(msg).await.is_err() {
+        return;
+      }
+    }
+    // the command exited so we close the session
+    let _ = sink.send(ws::Message::Close(None)).await;
+  });
+  rt::spawn(async move {
+    while let Some(Ok(cmd)) = rcmd.next().await {
+      if input.write_all(&cmd).await.is_err() {
+        break;
+      }
+    }
+  });
+  // handler service for incoming websockets frames
+  let service = fn_service(move |frame| {
+    let item = match frame {
+      ws::Frame::Ping(msg) => {
+        con_state.borrow_mut().hb = Instant::now();
+        Some(ws::Message::Pong(msg))
+      }
+      // update heartbeat
+      ws::Frame::Pong(_) => {
+        con_state.borrow_mut().hb = Instant::now();
+        None
+      }
+      ws::Frame::Text(text) | ws::Frame::Binary(text) => {
+        let _ = scmd.send(Ok(text));
+        None
+      }
+      ws::Frame::Close(reason) => Some(ws::Message::Close(reason)),
+      _ => Some(ws::Message::Close(None)),
+    };
+    ready(Ok(item))
+  });
+  // handler service for shutdown notification that stop heartbeat task
+  let on_shutdown = fn_shutdown(move || {
+    let _ = tx.send(());
+  });
+  Ok(chain(service).and_then(on_shutdown))
+}
+
+/// Open an interactive exec session in a cargo via websocket
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Cargoes",
+  path = "/cargoes/{Name}/exec/attach",
+  params(
+    ("Name" = String, Path, description = "Name of the cargo"),
+    ("Namespace" = Option<String>, Query, description = "Namespace of the cargo"),
+    ("Cmd" = String, Query, description = "Command to execute as a json encoded array"),
+    ("Stdin" = Option<bool>, Query, description = "Attach the stdin of the command"),
+    ("Tty" = Option<bool>, Query, description = "Allocate a pseudo-TTY"),
+    ("Replica" = Option<usize>, Query, description = "Replica where the command is executed"),
+  ),
+  responses(
+    (status = 101, description = "Websocket connection"),
+  ),
+))]
+pub(crate) async fn exec_attach(
+  web::types::Query(qs): web::types::Query<CargoExecQuery>,
+  req: HttpRequest,
+  path: web::types::Path<(String, String)>,
+  state: web::types::State<DaemonState>,
+) -> Result<web::HttpResponse, Error> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  web::ws::start(
+    req,
+    map_config(fn_factory_with_config(ws_exec_service), move |cfg| {
+      (key.clone(), qs.clone(), cfg, state.clone())
+    }),
+  )
+  .await
+}
+
 /// Send a signal to a cargo this will kill the cargo if the signal is SIGKILL
 #[cfg_attr(feature = "dev", utoipa::path(
   post,
@@ -492,6 +690,37 @@ async fn logs_cargo(
   )
 }
 
+/// Get resource usage statistics of a cargo instance
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Cargoes",
+  path = "/cargoes/{Name}/stats",
+  params(
+    ("Name" = String, Path, description = "Name of the cargo instance usually `name` or `name-number`"),
+    ("Namespace" = Option<String>, Query, description = "Namespace of the cargo"),
+    ("Stream" = Option<bool>, Query, description = "Stream the stats every second instead of returning them once"),
+  ),
+  responses(
+    (status = 200, description = "Cargo stats", content_type = "application/vdn.nanocl.raw-stream"),
+    (status = 404, description = "Cargo does not exist"),
+  ),
+))]
+#[web::get("/cargoes/{name}/stats")]
+async fn stats_cargo(
+  web::types::Query(qs): web::types::Query<CargoStatsQuery>,
+  path: web::types::Path<(String, String)>,
+  state: web::types::State<DaemonState>,
+) -> Result<web::HttpResponse, HttpError> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  let stream = utils::cargo::get_stats(&key, &qs, &state.docker_api)?;
+  Ok(
+    web::HttpResponse::Ok()
+      .content_type("application/vdn.nanocl.raw-stream")
+      .streaming(stream),
+  )
+}
+
 /// Scale or Downscale number of instances
 #[cfg_attr(feature = "dev", utoipa::path(
   patch,
@@ -542,7 +771,12 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(list_cargo_history);
   config.service(revert_cargo);
   config.service(exec_command);
+  config.service(
+    web::resource("/cargoes/{name}/exec/attach")
+      .route(web::get().to(exec_attach)),
+  );
   config.service(logs_cargo);
+  config.service(stats_cargo);
   config.service(list_cargo_instance);
   config.service(scale_cargo);
 }
@@ -557,10 +791,13 @@ mod tests {
   use futures::{TryStreamExt, StreamExt};
 
   use nanocl_stubs::generic::GenericNspQuery;
-  use nanocl_stubs::cargo_config::{CargoConfig, CargoConfigPartial};
+  use nanocl_stubs::node::Node;
+  use nanocl_stubs::cargo_config::{
+    CargoConfig, CargoConfigPartial, ReplicationMode, ReplicationPercentage,
+  };
   use nanocl_stubs::cargo::{
     Cargo, CargoSummary, CargoInspect, OutputLog, CreateExecOptions,
-    CargoDeleteQuery, CargoListQuery, CargoScale,
+    CargoDeleteQuery, CargoListQuery, CargoScale, CargoStatsQuery, Stats,
   };
 
   /// Test to create start patch stop and delete a cargo with valid data
@@ -843,4 +1080,104 @@ mod tests {
     }
     Ok(())
   }
+
+  #[ntex::test]
+  async fn stats() -> TestRet {
+    let srv = gen_server(ntex_config).await;
+
+    const CARGO_NAME: &str = "nstore";
+
+    let res = srv
+      .get(format!("/v0.9/cargoes/{CARGO_NAME}/stats"))
+      .query(&CargoStatsQuery {
+        namespace: Some("system".into()),
+        stream: Some(false),
+      })
+      .unwrap()
+      .send()
+      .await?;
+
+    assert_eq!(res.status(), http::StatusCode::OK);
+    let body = res.body().await?;
+    let _ = serde_json::from_slice::<Stats>(&body)?;
+    Ok(())
+  }
+
+  #[ntex::test]
+  async fn replication_percentage() -> TestRet {
+    let srv = gen_server(ntex_config).await;
+    ensure_test_image().await?;
+
+    const CARGO_NAME: &str = "daemon-test-percentage";
+    let mut res = srv.get("/v0.9/nodes").send().await?;
+    assert_eq!(res.status(), 200);
+    let nodes = res.json::<Vec<Node>>().await?;
+    let percentage = ReplicationPercentage { percentage: 100.0 };
+    let res = srv
+      .post("/v0.9/cargoes")
+      .send_json(&CargoConfigPartial {
+        name: CARGO_NAME.to_owned(),
+        container: bollard_next::container::Config {
+          image: Some("nexthat/nanocl-get-started:latest".to_string()),
+          ..Default::default()
+        },
+        replication: Some(ReplicationMode::Percentage(percentage.clone())),
+        ..Default::default()
+      })
+      .await?;
+    assert_eq!(res.status(), 201);
+    let mut res = srv
+      .get(format!("/v0.9/cargoes/{CARGO_NAME}/inspect"))
+      .send()
+      .await?;
+    assert_eq!(res.status(), 200);
+    let cargo = res.json::<CargoInspect>().await?;
+    assert_eq!(cargo.instance_total, percentage.replicas(nodes.len()));
+    let res = srv
+      .delete(format!("/v0.9/cargoes/{CARGO_NAME}"))
+      .send()
+      .await?;
+    assert_eq!(res.status(), 202);
+    Ok(())
+  }
+
+  #[ntex::test]
+  async fn create_idempotent() -> TestRet {
+    let srv = gen_server(ntex_config).await;
+    ensure_test_image().await?;
+
+    const CARGO_NAME: &str = "daemon-test-idempotent";
+    let cargo = CargoConfigPartial {
+      name: CARGO_NAME.to_owned(),
+      container: bollard_next::container::Config {
+        image: Some("nexthat/nanocl-get-started:latest".to_string()),
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    let mut res = srv
+      .post("/v0.9/cargoes")
+      .header("Idempotency-Key", "daemon-test-idempotent-key")
+      .send_json(&cargo)
+      .await?;
+    assert_eq!(res.status(), 201);
+    let created = res.json::<Cargo>().await?;
+    let mut res = srv
+      .post("/v0.9/cargoes")
+      .header("Idempotency-Key", "daemon-test-idempotent-key")
+      .send_json(&cargo)
+      .await?;
+    assert_eq!(res.status(), 200);
+    assert!(res.headers().contains_key("Idempotent-Replayed"));
+    let replayed = res.json::<Cargo>().await?;
+    assert_eq!(replayed.key, created.key);
+    let res = srv.post("/v0.9/cargoes").send_json(&cargo).await?;
+    assert!(res.status().is_client_error());
+    let res = srv
+      .delete(format!("/v0.9/cargoes/{CARGO_NAME}"))
+      .send()
+      .await?;
+    assert_eq!(res.status(), 202);
+    Ok(())
+  }
 }
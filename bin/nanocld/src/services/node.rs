@@ -33,6 +33,56 @@ pub(crate) async fn list_node(
   Ok(web::HttpResponse::Ok().json(&items))
 }
 
+/// Cordon a node, no new cargoes, cargo instances or virtual machines are created on it
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Nodes",
+  path = "/nodes/{Name}/cordon",
+  params(
+    ("Name" = String, Path, description = "The name of the node"),
+  ),
+  responses(
+    (status = 200, description = "The node has been cordoned", body = Node),
+    (status = 404, description = "Node does not exist", body = ApiError),
+  ),
+))]
+#[web::post("/nodes/{name}/cordon")]
+pub(crate) async fn cordon_node(
+  path: web::types::Path<(String, String)>,
+  state: web::types::State<DaemonState>,
+) -> Result<web::HttpResponse, HttpError> {
+  let node =
+    repositories::node::update_unschedulable(&path.1, true, &state.pool)
+      .await?;
+
+  Ok(web::HttpResponse::Ok().json(&node))
+}
+
+/// Uncordon a node so workloads can be created on it again
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Nodes",
+  path = "/nodes/{Name}/uncordon",
+  params(
+    ("Name" = String, Path, description = "The name of the node"),
+  ),
+  responses(
+    (status = 200, description = "The node has been uncordoned", body = Node),
+    (status = 404, description = "Node does not exist", body = ApiError),
+  ),
+))]
+#[web::post("/nodes/{name}/uncordon")]
+pub(crate) async fn uncordon_node(
+  path: web::types::Path<(String, String)>,
+  state: web::types::State<DaemonState>,
+) -> Result<web::HttpResponse, HttpError> {
+  let node =
+    repositories::node::update_unschedulable(&path.1, false, &state.pool)
+      .await?;
+
+  Ok(web::HttpResponse::Ok().json(&node))
+}
+
 async fn node_ws_service(
   (sink, state): (ws::WsSink, web::types::State<DaemonState>),
 ) -> Result<
@@ -106,5 +156,30 @@ async fn node_ws(
 
 pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(list_node);
+  config.service(cordon_node);
+  config.service(uncordon_node);
   config.service(web::resource("/nodes/ws").route(web::get().to(node_ws)));
 }
+
+#[cfg(test)]
+mod tests {
+
+  use ntex::http;
+  use nanocl_stubs::node::Node;
+
+  use crate::services::ntex_config;
+  use crate::utils::tests::*;
+
+  #[ntex::test]
+  async fn basic() -> TestRet {
+    let srv = gen_server(ntex_config).await;
+    let mut resp = srv.get("/v0.9/nodes").send().await?;
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    let _ = resp.json::<Vec<Node>>().await?;
+    let resp = srv.post("/v0.9/nodes/unknown-node/cordon").send().await?;
+    assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    let resp = srv.post("/v0.9/nodes/unknown-node/uncordon").send().await?;
+    assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    Ok(())
+  }
+}
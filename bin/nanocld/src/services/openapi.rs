@@ -26,10 +26,10 @@ use bollard_next::service::{
 };
 use nanocl_stubs::config::DaemonConfig;
 use nanocl_stubs::generic::GenericCount;
-use nanocl_stubs::system::{Version, HostInfo};
+use nanocl_stubs::system::{Version, HostInfo, DiskUsage, DiskUsageItem};
 use nanocl_stubs::metric::{Metric, MetricKind};
 use nanocl_stubs::http_metric::HttpMetric;
-use nanocl_stubs::vm_image::{VmImage, VmImageResizePayload};
+use nanocl_stubs::vm_image::{VmImage, VmImageResizePayload, VmImageImportStatus};
 use nanocl_stubs::generic::GenericDelete;
 use nanocl_stubs::node::{Node, NodeContainerSummary};
 use nanocl_stubs::namespace::{
@@ -41,7 +41,7 @@ use nanocl_stubs::cargo::{
 };
 use nanocl_stubs::cargo_config::{
   CargoConfig, CargoConfigPartial, CargoConfigUpdate, ReplicationMode,
-  ReplicationStatic,
+  ReplicationStatic, ReplicationPercentage,
 };
 use nanocl_stubs::cargo_image::CargoImagePartial;
 use nanocl_stubs::vm::{Vm, VmInspect, VmSummary};
@@ -208,6 +208,8 @@ impl Modify for VersionModifier {
   paths(
     // Node
     node::list_node,
+    node::cordon_node,
+    node::uncordon_node,
     node::node_ws,
     // System
     system::get_info,
@@ -215,6 +217,7 @@ impl Modify for VersionModifier {
     system::get_processes,
     system::get_version,
     system::get_ping,
+    system::get_disk_usage,
     // Namespace
     namespace::list_namespace,
     namespace::inspect_namespace,
@@ -232,10 +235,12 @@ impl Modify for VersionModifier {
     cargo::put_cargo,
     cargo::patch_cargo,
     cargo::exec_command,
+    cargo::exec_attach,
     cargo::kill_cargo,
     cargo::list_cargo_history,
     cargo::revert_cargo,
     cargo::logs_cargo,
+    cargo::stats_cargo,
     cargo::scale_cargo,
     // Cargo Image
     cargo_image::list_cargo_image,
@@ -246,10 +251,12 @@ impl Modify for VersionModifier {
     // VM Image
     vm_image::list_vm_images,
     vm_image::import_vm_image,
+    vm_image::import_vm_image_status,
     vm_image::delete_vm_image,
     vm_image::resize_vm_image,
     vm_image::clone_vm_image,
     vm_image::snapshot_vm_image,
+    vm_image::export_vm_image,
     // Vm
     vm::list_vm,
     vm::inspect_vm,
@@ -260,6 +267,9 @@ impl Modify for VersionModifier {
     vm::list_vm_history,
     vm::patch_vm,
     vm::vm_attach,
+    vm::snapshot_vm,
+    vm::restore_vm,
+    vm::logs_vm,
     // Resource
     resource::list_resource,
     resource::inspect_resource,
@@ -268,6 +278,7 @@ impl Modify for VersionModifier {
     resource::put_resource,
     resource::list_resource_history,
     resource::revert_resource,
+    resource::inspect_resource_kind_schema,
     // Metric
     metric::list_metric,
     // Http Metric
@@ -281,6 +292,8 @@ impl Modify for VersionModifier {
     // System
     Version,
     HostInfo,
+    DiskUsage,
+    DiskUsageItem,
     SystemInfo,
     Commit,
     Runtime,
@@ -326,6 +339,7 @@ impl Modify for VersionModifier {
     CargoConfigPartial,
     CargoConfigUpdate,
     ReplicationStatic,
+    ReplicationPercentage,
     CargoScale,
     // Container Image
     ImageSummary,
@@ -377,6 +391,7 @@ impl Modify for VersionModifier {
     // Vm Image
     VmImage,
     VmImageResizePayload,
+    VmImageImportStatus,
     // Vm
     Vm,
     VmSummary,
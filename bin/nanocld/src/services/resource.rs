@@ -4,6 +4,7 @@
 
 use ntex::rt;
 use ntex::web;
+use ntex::http;
 
 use nanocl_stubs::system::Event;
 use nanocl_stubs::resource::ResourceUpdate;
@@ -220,7 +221,56 @@ pub(crate) async fn revert_resource(
   Ok(web::HttpResponse::Ok().json(&resource))
 }
 
+/// Get the JSON schema of the last version of a resource kind
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Resources",
+  path = "/resources/kinds/{Name}/schema",
+  params(
+    ("Name" = String, Path, description = "The resource kind name")
+  ),
+  responses(
+    (status = 200, description = "The JSON schema of the resource kind", body = Any),
+    (status = 404, description = "Resource kind is not existing or has no schema", body = ApiError),
+  ),
+))]
+#[web::get("/resources/kinds/{name}/schema")]
+pub(crate) async fn inspect_resource_kind_schema(
+  path: web::types::Path<(String, String)>,
+  state: web::types::State<DaemonState>,
+) -> Result<web::HttpResponse, HttpError> {
+  let name = &path.1;
+  let Ok(kind) =
+    repositories::resource_kind::find_latest_version(name, &state.pool).await
+  else {
+    let kinds = repositories::resource_kind::list(&state.pool)
+      .await?
+      .into_iter()
+      .map(|kind| kind.name)
+      .collect::<Vec<_>>();
+    return Err(HttpError {
+      status: http::StatusCode::NOT_FOUND,
+      msg: format!(
+        "Resource kind {name} not found, available kinds: {}",
+        kinds.join(", ")
+      ),
+    });
+  };
+  match kind.schema {
+    Some(schema) => Ok(web::HttpResponse::Ok().json(&schema)),
+    None => Err(HttpError {
+      status: http::StatusCode::NOT_FOUND,
+      msg: format!(
+        "Resource kind {name} {} has no schema, its resources are validated by {}",
+        kind.version,
+        kind.url.unwrap_or_default()
+      ),
+    }),
+  }
+}
+
 pub fn ntex_config(config: &mut web::ServiceConfig) {
+  config.service(inspect_resource_kind_schema);
   config.service(create_resource);
   config.service(delete_resource);
   config.service(list_resource);
@@ -279,6 +329,22 @@ mod tests {
     assert_eq!(resource.name, "test_resource");
     assert_eq!(resource.kind, String::from("Kind"));
 
+    // Kind schema
+    let mut resp = srv
+      .get("/v0.2/resources/kinds/test_resource/schema")
+      .send()
+      .await
+      .unwrap();
+    assert_eq!(resp.status(), http::StatusCode::OK);
+    let schema = resp.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(&schema, &config["Schema"]);
+    let resp = srv
+      .get("/v0.2/resources/kinds/test_unknown_kind/schema")
+      .send()
+      .await
+      .unwrap();
+    assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
     // List
     let mut resp = srv.get("/v0.2/resources").send().await.unwrap();
     assert_eq!(resp.status(), http::StatusCode::OK);
@@ -7,7 +7,7 @@ use crate::version;
 use bollard_next::container::ListContainersOptions;
 
 use nanocl_stubs::node::NodeContainerSummary;
-use nanocl_stubs::system::{HostInfo, ProccessQuery};
+use nanocl_stubs::system::{HostInfo, ProccessQuery, DiskUsage, DiskUsageItem};
 
 use crate::repositories;
 use nanocl_utils::http_error::HttpError;
@@ -162,8 +162,57 @@ pub(crate) async fn get_processes(
   Ok(web::HttpResponse::Ok().json(&process))
 }
 
+/// Get the disk usage of cargo images, vm images and volumes
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "System",
+  path = "/disk-usage",
+  responses(
+    (status = 200, description = "Disk usage", body = DiskUsage),
+  ),
+))]
+#[web::get("/disk-usage")]
+pub(crate) async fn get_disk_usage(
+  state: web::types::State<DaemonState>,
+) -> Result<web::HttpResponse, HttpError> {
+  let df = state.docker_api.df().await?;
+  let cargo_images = df
+    .images
+    .unwrap_or_default()
+    .into_iter()
+    .map(|image| DiskUsageItem {
+      name: image.repo_tags.first().cloned().unwrap_or(image.id),
+      size: image.size,
+    })
+    .collect();
+  let volumes = df
+    .volumes
+    .unwrap_or_default()
+    .into_iter()
+    .map(|volume| DiskUsageItem {
+      size: volume.usage_data.map(|data| data.size).unwrap_or_default(),
+      name: volume.name,
+    })
+    .collect();
+  let vm_images = repositories::vm_image::list(&state.pool)
+    .await?
+    .into_iter()
+    .map(|image| DiskUsageItem {
+      name: image.name,
+      size: image.size_actual,
+    })
+    .collect();
+  let usage = DiskUsage {
+    cargo_images,
+    vm_images,
+    volumes,
+  };
+  Ok(web::HttpResponse::Ok().json(&usage))
+}
+
 pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(watch_event);
+  config.service(get_disk_usage);
   config.service(get_info);
   config.service(get_processes);
   config.service(get_ping);
@@ -175,7 +224,7 @@ mod tests {
   use crate::services::ntex_config;
 
   use ntex::http;
-  use nanocl_stubs::system::HostInfo;
+  use nanocl_stubs::system::{HostInfo, DiskUsage};
 
   use crate::utils::tests::*;
 
@@ -213,6 +262,25 @@ mod tests {
     Ok(())
   }
 
+  #[ntex::test]
+  async fn disk_usage() -> TestRet {
+    let srv = gen_server(ntex_config).await;
+    let mut resp = srv.get("/v0.2/disk-usage").send().await?;
+    let status = resp.status();
+    assert_eq!(
+      status,
+      http::StatusCode::OK,
+      "Expect status to be {} got {}",
+      http::StatusCode::OK,
+      status
+    );
+    let _ = resp
+      .json::<DiskUsage>()
+      .await
+      .expect("To receive a valid disk usage json payload");
+    Ok(())
+  }
+
   #[ntex::test]
   async fn wrong_version() {
     let srv = gen_server(ntex_config).await;
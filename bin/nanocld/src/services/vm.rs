@@ -17,7 +17,7 @@ use futures::StreamExt;
 use futures::future::ready;
 use bollard_next::container::AttachContainerOptions;
 
-use nanocl_stubs::cargo::OutputLog;
+use nanocl_stubs::cargo::{OutputLog, CargoLogQuery};
 use nanocl_stubs::generic::GenericNspQuery;
 use nanocl_stubs::vm_config::{VmConfigPartial, VmConfigUpdate};
 
@@ -25,7 +25,10 @@ use tokio::io::AsyncWriteExt;
 
 use crate::{utils, repositories};
 use nanocl_utils::http_error::HttpError;
-use crate::models::{DaemonState, WsConState};
+use crate::models::{DaemonState, WsConState, VmImageDbModel};
+use crate::idempotency::{
+  IdempotencyStore, Reservation, IDEMPOTENT_REPLAYED_HEADER,
+};
 
 /// List virtual machines
 #[cfg_attr(feature = "dev", utoipa::path(
@@ -140,6 +143,87 @@ pub(crate) async fn stop_vm(
   Ok(web::HttpResponse::Ok().finish())
 }
 
+/// Snapshot the disk of a stopped virtual machine as a vm image
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Vms",
+  path = "/vms/{Name}/snapshot/{SnapshotName}",
+  params(
+    ("Name" = String, Path, description = "The name of the virtual machine"),
+    ("SnapshotName" = String, Path, description = "The name of the snapshot"),
+    ("Namespace" = Option<String>, Query, description = "The namespace of the virtual machine"),
+  ),
+  responses(
+    (status = 200, description = "The snapshot have been created", body = VmImage),
+    (status = 409, description = "The virtual machine is running"),
+  ),
+))]
+#[web::post("/vms/{name}/snapshot/{snapshot_name}")]
+pub(crate) async fn snapshot_vm(
+  web::types::Query(qs): web::types::Query<GenericNspQuery>,
+  path: web::types::Path<(String, String, String)>,
+  state: web::types::State<DaemonState>,
+) -> Result<web::HttpResponse, HttpError> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  let snapshot_name = path.2.to_owned();
+  utils::key::validate_name(&snapshot_name)?;
+  let disk = find_stopped_vm_disk(&key, &state).await?;
+  let vm_image =
+    utils::vm_image::copy_disk(&snapshot_name, &disk, &state).await?;
+  Ok(web::HttpResponse::Ok().json(&vm_image))
+}
+
+/// Restore the disk of a stopped virtual machine from a snapshot
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Vms",
+  path = "/vms/{Name}/restore/{SnapshotName}",
+  params(
+    ("Name" = String, Path, description = "The name of the virtual machine"),
+    ("SnapshotName" = String, Path, description = "The name of the snapshot"),
+    ("Namespace" = Option<String>, Query, description = "The namespace of the virtual machine"),
+  ),
+  responses(
+    (status = 200, description = "The disk have been restored", body = VmImage),
+    (status = 409, description = "The virtual machine is running"),
+  ),
+))]
+#[web::post("/vms/{name}/restore/{snapshot_name}")]
+pub(crate) async fn restore_vm(
+  web::types::Query(qs): web::types::Query<GenericNspQuery>,
+  path: web::types::Path<(String, String, String)>,
+  state: web::types::State<DaemonState>,
+) -> Result<web::HttpResponse, HttpError> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  let disk = find_stopped_vm_disk(&key, &state).await?;
+  let snapshot =
+    repositories::vm_image::find_by_name(&path.2, &state.pool).await?;
+  let vm_image =
+    utils::vm_image::restore_disk(&disk, &snapshot, &state.pool).await?;
+  Ok(web::HttpResponse::Ok().json(&vm_image))
+}
+
+/// Find the disk of a virtual machine, ensuring the virtual machine is stopped
+async fn find_stopped_vm_disk(
+  key: &str,
+  state: &DaemonState,
+) -> Result<VmImageDbModel, HttpError> {
+  let vm =
+    utils::vm::inspect_by_key(key, &state.docker_api, &state.pool).await?;
+  if vm.instance_running > 0 {
+    return Err(HttpError {
+      status: http::StatusCode::CONFLICT,
+      msg: format!("Vm {} is running, stop it first", vm.name),
+    });
+  }
+  let disk =
+    repositories::vm_image::find_by_name(&vm.config.disk.image, &state.pool)
+      .await?;
+  Ok(disk)
+}
+
 /// Delete a virtual machine
 #[cfg_attr(feature = "dev", utoipa::path(
   delete,
@@ -176,21 +260,40 @@ pub(crate) async fn delete_vm(
   request_body = VmConfigPartial,
   params(
     ("Namespace" = Option<String>, Query, description = "The namespace of the virtual machine"),
+    ("Idempotency-Key" = Option<String>, Header, description = "Key to get the original response when the request is retried"),
   ),
   responses(
-    (status = 200, description = "The virtual machine has been created", body = Vm),
+    (status = 200, description = "The virtual machine has been created or was already created with the same Idempotency-Key", body = Vm),
   ),
 ))]
 #[web::post("/vms")]
 pub(crate) async fn create_vm(
+  req: HttpRequest,
   web::types::Query(qs): web::types::Query<GenericNspQuery>,
   web::types::Json(payload): web::types::Json<VmConfigPartial>,
   version: web::types::Path<String>,
   state: web::types::State<DaemonState>,
 ) -> Result<web::HttpResponse, HttpError> {
+  // The reservation is released if the creation fails
+  let reservation = match IdempotencyStore::request_key(&req, "vm") {
+    None => None,
+    Some(key) => match state.idempotency.reserve(&key)? {
+      Reservation::Reserved(guard) => Some(guard),
+      Reservation::Replay(item) => {
+        return Ok(
+          web::HttpResponse::Ok()
+            .header(IDEMPOTENT_REPLAYED_HEADER, "true")
+            .json(&item),
+        );
+      }
+    },
+  };
   let namespace = utils::key::resolve_nsp(&qs.namespace);
 
   let item = utils::vm::create(&payload, &namespace, &version, &state).await?;
+  if let Some(guard) = reservation {
+    guard.remember(&item)?;
+  }
 
   Ok(web::HttpResponse::Ok().json(&item))
 }
@@ -252,6 +355,41 @@ pub(crate) async fn patch_vm(
   Ok(web::HttpResponse::Ok().json(&vm))
 }
 
+/// Get logs of a virtual machine
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Vms",
+  path = "/vms/{Name}/logs",
+  params(
+    ("Name" = String, Path, description = "Name of the virtual machine"),
+    ("Namespace" = Option<String>, Query, description = "Namespace of the virtual machine"),
+    ("Since" = Option<i64>, Query, description = "Only logs returned since timestamp"),
+    ("Until" = Option<i64>, Query, description = "Only logs returned until timestamp"),
+    ("Timestamps" = Option<bool>, Query, description = "Add timestamps to every log line"),
+    ("Follow" = Option<bool>, Query, description = "Boolean to return a stream or not"),
+    ("Tail" = Option<String>, Query, description = "Only return the n last (integer) or all (\"all\") logs"),
+  ),
+  responses(
+    (status = 200, description = "Virtual machine logs", content_type = "application/vdn.nanocl.raw-stream"),
+    (status = 404, description = "Virtual machine does not exist"),
+  ),
+))]
+#[web::get("/vms/{name}/logs")]
+pub(crate) async fn logs_vm(
+  web::types::Query(qs): web::types::Query<CargoLogQuery>,
+  path: web::types::Path<(String, String)>,
+  state: web::types::State<DaemonState>,
+) -> Result<web::HttpResponse, HttpError> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  let stream = utils::vm::get_logs(&key, &qs, &state.docker_api)?;
+  Ok(
+    web::HttpResponse::Ok()
+      .content_type("application/vdn.nanocl.raw-stream")
+      .streaming(stream),
+  )
+}
+
 async fn ws_attach_service(
   (key, sink, state): (String, ws::WsSink, web::types::State<DaemonState>),
 ) -> Result<
@@ -403,6 +541,9 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(stop_vm);
   config.service(list_vm_history);
   config.service(patch_vm);
+  config.service(snapshot_vm);
+  config.service(restore_vm);
+  config.service(logs_vm);
   config.service(
     web::resource("/vms/{name}/attach").route(web::get().to(vm_attach)),
   );
@@ -1,10 +1,13 @@
-use std::io::Write;
+use std::io::{Seek, SeekFrom, Write};
 
 use ntex::web;
 use ntex::http;
+use ntex::http::body::{Body, SizedStream};
 use futures::StreamExt;
 
-use nanocl_stubs::vm_image::VmImageResizePayload;
+use nanocl_stubs::vm_image::{
+  VmImageResizePayload, VmImageImportQuery, VmImageImportStatus,
+};
 
 use crate::{utils, repositories};
 use nanocl_utils::http_error::HttpError;
@@ -28,7 +31,10 @@ pub(crate) async fn list_vm_images(
   Ok(web::HttpResponse::Ok().json(&images))
 }
 
-/// Import a virtual machine image from a file
+/// Import a virtual machine image from a file.
+/// The image is written to a `.part` file until it's fully received,
+/// an interrupted import can be resumed by sending the remaining bytes
+/// with the offset returned by the import status
 #[cfg_attr(feature = "dev", utoipa::path(
   post,
   tag = "VmImages",
@@ -36,6 +42,7 @@ pub(crate) async fn list_vm_images(
   path = "/vms/images/{Name}/import",
   params(
     ("Name" = String, Path, description = "The name of the vm image"),
+    ("Offset" = Option<u64>, Query, description = "Number of bytes already received to resume an interrupted import"),
   ),
   responses(
     (status = 200, description = "Image have been imported"),
@@ -45,6 +52,7 @@ pub(crate) async fn list_vm_images(
 pub(crate) async fn import_vm_image(
   mut payload: web::types::Payload,
   path: web::types::Path<(String, String)>,
+  web::types::Query(qs): web::types::Query<VmImageImportQuery>,
   state: web::types::State<DaemonState>,
 ) -> Result<web::HttpResponse, HttpError> {
   let name = path.1.to_owned();
@@ -64,13 +72,39 @@ pub(crate) async fn import_vm_image(
   let state_dir = state.config.state_dir.clone();
   let vm_images_dir = format!("{state_dir}/vms/images");
   let filepath = format!("{vm_images_dir}/{name}.img");
-  let fp = filepath.clone();
-  let mut f = web::block(move || std::fs::File::create(fp))
-    .await
-    .map_err(|err| HttpError {
-      status: http::StatusCode::INTERNAL_SERVER_ERROR,
-      msg: format!("Unable to create vm image {name}: {err}"),
-    })?;
+  let partpath = format!("{filepath}.part");
+  let offset = qs.offset.unwrap_or_default();
+  let fp = partpath.clone();
+  let mut f = web::block(move || {
+    if offset == 0 {
+      return std::fs::File::create(fp);
+    }
+    let mut f = std::fs::OpenOptions::new().write(true).open(fp)?;
+    let len = f.metadata()?.len();
+    if len < offset {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("only {len} bytes were received, can't resume at {offset}"),
+      ));
+    }
+    // Drop the bytes received after the offset the client resumes from
+    f.set_len(offset)?;
+    f.seek(SeekFrom::End(0))?;
+    Ok(f)
+  })
+  .await
+  .map_err(|err| HttpError {
+    status: match &err {
+      web::error::BlockingError::Error(err)
+        if err.kind() == std::io::ErrorKind::NotFound
+          || err.kind() == std::io::ErrorKind::InvalidInput =>
+      {
+        http::StatusCode::BAD_REQUEST
+      }
+      _ => http::StatusCode::INTERNAL_SERVER_ERROR,
+    },
+    msg: format!("Unable to create vm image {name}: {err}"),
+  })?;
   while let Some(bytes) = payload.next().await {
     let bytes = bytes.map_err(|err| HttpError {
       status: http::StatusCode::INTERNAL_SERVER_ERROR,
@@ -83,12 +117,54 @@ pub(crate) async fn import_vm_image(
         msg: format!("Unable to create vm image {name}: {err}"),
       })?;
   }
+  let fp = filepath.clone();
+  web::block(move || std::fs::rename(partpath, fp))
+    .await
+    .map_err(|err| HttpError {
+      status: http::StatusCode::INTERNAL_SERVER_ERROR,
+      msg: format!("Unable to create vm image {name}: {err}"),
+    })?;
 
   utils::vm_image::create(&name, &filepath, &state.pool).await?;
 
   Ok(web::HttpResponse::Ok().into())
 }
 
+/// Get the number of bytes received by an interrupted import
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "VmImages",
+  path = "/vms/images/{Name}/import",
+  params(
+    ("Name" = String, Path, description = "The name of the vm image"),
+  ),
+  responses(
+    (status = 200, description = "State of the import", body = VmImageImportStatus),
+  ),
+))]
+#[web::get("/vms/images/{name}/import")]
+pub(crate) async fn import_vm_image_status(
+  path: web::types::Path<(String, String)>,
+  state: web::types::State<DaemonState>,
+) -> Result<web::HttpResponse, HttpError> {
+  let name = path.1.to_owned();
+  utils::key::validate_name(&name)?;
+  let partpath =
+    format!("{}/vms/images/{name}.img.part", state.config.state_dir);
+  let offset = match tokio::fs::metadata(&partpath).await {
+    Ok(metadata) => metadata.len(),
+    Err(err) if err.kind() == std::io::ErrorKind::NotFound => 0,
+    Err(err) => {
+      return Err(HttpError {
+        status: http::StatusCode::INTERNAL_SERVER_ERROR,
+        msg: format!("Unable to read import of vm image {name}: {err}"),
+      })
+    }
+  };
+
+  Ok(web::HttpResponse::Ok().json(&VmImageImportStatus { offset }))
+}
+
 /// Create a snapshot of a virtual machine image
 #[cfg_attr(feature = "dev", utoipa::path(
   post,
@@ -147,6 +223,34 @@ pub(crate) async fn clone_vm_image(
   Ok(web::HttpResponse::Ok().streaming(rx))
 }
 
+/// Export a virtual machine image as a standalone qcow2 image
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "VmImages",
+  path = "/vms/images/{Name}/export",
+  params(
+    ("Name" = String, Path, description = "The name of the vm image"),
+  ),
+  responses(
+    (status = 200, description = "The content of the image", content_type = "application/octet-stream"),
+    (status = 404, description = "Vm image does not exist", body = ApiError),
+  ),
+))]
+#[web::get("/vms/images/{name}/export")]
+pub(crate) async fn export_vm_image(
+  path: web::types::Path<(String, String)>,
+  state: web::types::State<DaemonState>,
+) -> Result<web::HttpResponse, HttpError> {
+  let name = path.1.to_owned();
+  let image = repositories::vm_image::find_by_name(&name, &state.pool).await?;
+  let (size, stream) = utils::vm_image::export(&image, &state).await?;
+  Ok(
+    web::HttpResponse::Ok()
+      .content_type("application/octet-stream")
+      .body(Body::from_message(SizedStream::new(size, stream))),
+  )
+}
+
 /// Resize a virtual machine image
 #[cfg_attr(feature = "dev", utoipa::path(
   post,
@@ -201,9 +305,11 @@ pub(crate) async fn delete_vm_image(
 
 pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(import_vm_image);
+  config.service(import_vm_image_status);
   config.service(list_vm_images);
   config.service(delete_vm_image);
   config.service(snapshot_vm_image);
   config.service(clone_vm_image);
   config.service(resize_vm_image);
+  config.service(export_vm_image);
 }
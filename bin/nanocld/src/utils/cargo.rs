@@ -8,7 +8,7 @@ use futures_util::TryFutureExt;
 use futures_util::stream::FuturesUnordered;
 use bollard_next::service::ContainerCreateResponse;
 
-use bollard_next::container::LogOutput;
+use bollard_next::container::{LogOutput, Stats};
 use bollard_next::container::WaitContainerOptions;
 use bollard_next::exec::{StartExecOptions, StartExecResults};
 use bollard_next::service::{ContainerSummary, HostConfig};
@@ -19,7 +19,8 @@ use nanocl_utils::http_error::HttpError;
 use nanocl_stubs::node::NodeContainerSummary;
 use nanocl_stubs::cargo::{
   Cargo, CargoSummary, CargoInspect, OutputLog, CreateExecOptions,
-  CargoLogQuery, CargoKillOptions, GenericCargoListQuery, CargoScale,
+  CargoLogQuery, CargoStatsQuery, CargoKillOptions, GenericCargoListQuery,
+  CargoScale,
 };
 use nanocl_stubs::cargo_config::{
   CargoConfigPartial, CargoConfigUpdate, ReplicationMode,
@@ -191,6 +192,48 @@ pub async fn list_instances(
   Ok(containers)
 }
 
+/// ## Replicas number
+///
+/// Get the number of instances to create for the given replication mode
+///
+/// ## Arguments
+///
+/// - [replication](Option<ReplicationMode>) - The replication mode
+/// - [state](DaemonState) - The daemon state
+///
+/// ## Returns
+///
+/// - [Result](Result) - The result of the operation
+///   - [Ok](usize) - The number of instances
+///   - [Err](HttpError) - The nodes couldn't be listed
+///
+async fn replicas_number(
+  replication: &Option<ReplicationMode>,
+  state: &DaemonState,
+) -> Result<usize, HttpError> {
+  let number = match replication {
+    None => 1,
+    Some(ReplicationMode::Static(replication_static)) => {
+      replication_static.number
+    }
+    Some(ReplicationMode::Percentage(percentage)) => {
+      let nodes = repositories::node::list(&state.pool).await?;
+      percentage.replicas(nodes.len())
+    }
+    Some(
+      ReplicationMode::Auto
+      | ReplicationMode::Unique
+      | ReplicationMode::UniqueByNode
+      | ReplicationMode::UniqueByNodeGroups { .. }
+      | ReplicationMode::UniqueByNodeNames { .. }
+      | ReplicationMode::StaticByNodes(_)
+      | ReplicationMode::StaticByNodeGroups { .. }
+      | ReplicationMode::StaticByNodeNames { .. },
+    ) => 1,
+  };
+  Ok(number)
+}
+
 /// ## Create
 ///
 /// Create a cargo based on the given partial config
@@ -215,20 +258,11 @@ pub async fn create(
   version: &str,
   state: &DaemonState,
 ) -> Result<Cargo, HttpError> {
+  crate::node::ensure_schedulable(state).await?;
   let cargo =
     repositories::cargo::create(namespace, config, version, &state.pool)
       .await?;
-  let number = if let Some(mode) = &cargo.config.replication {
-    match mode {
-      ReplicationMode::Static(replication_static) => replication_static.number,
-      ReplicationMode::Auto => 1,
-      ReplicationMode::Unique => 1,
-      ReplicationMode::UniqueByNode => 1,
-      _ => 1,
-    }
-  } else {
-    1
-  };
+  let number = replicas_number(&cargo.config.replication, state).await?;
   if let Err(err) = create_instances(&cargo, 0, number, &state.docker_api).await
   {
     repositories::cargo::delete_by_key(&cargo.key, &state.pool).await?;
@@ -602,17 +636,7 @@ pub async fn put(
   )
   .await?;
   // Get the number of instance to create
-  let number = if let Some(mode) = &cargo.config.replication {
-    match mode {
-      ReplicationMode::Static(replication_static) => replication_static.number,
-      ReplicationMode::Auto => 1,
-      ReplicationMode::Unique => 1,
-      ReplicationMode::UniqueByNode => 1,
-      _ => 1,
-    }
-  } else {
-    1
-  };
+  let number = replicas_number(&cargo.config.replication, state).await?;
   let containers = list_instances(cargo_key, &state.docker_api).await?;
   restore_instances_backup(&containers, state).await?;
   // Create instance with the new config
@@ -1037,6 +1061,35 @@ pub fn get_logs(
   Ok(stream)
 }
 
+/// ## Get stats
+///
+/// Get the resource usage statistics of a cargo instance
+/// The cargo name can be used if the cargo has only one instance
+/// The query parameter can be used to stream the stats
+///
+/// ## Arguments
+///
+/// - [name](str): The cargo name
+/// - [query](CargoStatsQuery): The query parameters
+/// - [docker_api](bollard_next::Docker): The docker api
+///
+/// ## Returns
+///
+/// - [Result](Result) - The result of the operation
+///   - [Ok](Stream) - The stream of stats
+///   - [Err](HttpError) - The stats could not be retrieved
+///
+pub fn get_stats(
+  name: &str,
+  query: &CargoStatsQuery,
+  docker_api: &bollard_next::Docker,
+) -> Result<impl StreamExt<Item = Result<Bytes, HttpError>>, HttpError> {
+  let stream =
+    docker_api.stats(&format!("{name}.c"), Some(query.clone().into()));
+  let stream = transform_stream::<Stats, Stats>(stream);
+  Ok(stream)
+}
+
 /// ## Scale
 ///
 /// Scale a cargo instance up or down to the given number of instances (containers, replicas)
@@ -1089,6 +1142,7 @@ pub async fn scale(
       .into_iter()
       .collect::<Result<Vec<_>, HttpError>>()?;
   } else {
+    crate::node::ensure_schedulable(state).await?;
     let cargo = repositories::cargo::inspect_by_key(key, &state.pool).await?;
     let to_add = options.replicas.unsigned_abs();
     let created_instances =
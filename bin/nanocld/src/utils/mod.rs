@@ -30,6 +30,7 @@ pub mod tests {
   use crate::version::VERSION;
   use crate::services;
   use crate::event::EventEmitter;
+  use crate::idempotency::IdempotencyStore;
   use crate::models::{Pool, DaemonState};
 
   pub use ntex::web::test::TestServer;
@@ -143,6 +144,7 @@ pub mod tests {
       docker_api,
       pool,
       event_emitter,
+      idempotency: IdempotencyStore::new(),
       version: VERSION.to_owned(),
     };
     // Create test server
@@ -208,6 +208,10 @@ pub async fn sync_vm_images(
       let file = file?;
       let file_name = file.file_name();
       let file_name = file_name.to_str().unwrap_or_default();
+      // Partial imports are only registered once completed
+      if file_name.ends_with(".part") {
+        return Ok(());
+      }
       let dot_split_name = file_name.split('.').collect::<Vec<&str>>();
       let name = if dot_split_name.len() > 1 {
         dot_split_name[..dot_split_name.len() - 1].join(".")
@@ -1,14 +1,18 @@
 use std::collections::HashMap;
 
 use ntex::http;
+use ntex::util::Bytes;
+use futures::StreamExt;
 
 use bollard_next::Docker;
 use bollard_next::service::{HostConfig, DeviceMapping, ContainerSummary};
 use bollard_next::container::{
   CreateContainerOptions, StartContainerOptions, ListContainersOptions,
-  StopContainerOptions, RemoveContainerOptions,
+  StopContainerOptions, RemoveContainerOptions, LogOutput,
 };
 
+use nanocl_stubs::cargo::{OutputLog, CargoLogQuery};
+
 use nanocl_stubs::vm_config::{VmConfigPartial, VmConfigUpdate};
 use nanocl_stubs::vm::{Vm, VmSummary, VmInspect};
 
@@ -412,6 +416,7 @@ pub async fn create(
     vm.name,
     namespace
   );
+  crate::node::ensure_schedulable(state).await?;
   let vm_key = utils::key::gen_key(namespace, &vm.name);
   let mut vm = vm.clone();
   if repositories::vm::find_by_key(&vm_key, &state.pool)
@@ -554,3 +559,31 @@ pub async fn put(
   start_by_key(&vm.key, &state.docker_api).await?;
   Ok(vm)
 }
+
+/// ## Get logs
+///
+/// Get the console, stdout and stderr logs of a VM
+/// The query parameter can be used to filter the logs
+///
+/// ## Arguments
+///
+/// - [vm_key](str) - The vm key
+/// - [query](CargoLogQuery) - The query parameters
+/// - [docker_api](bollard_next::Docker) - The docker api
+///
+/// ## Returns
+///
+/// - [Result](Result) - The result of the operation
+///   - [Ok](Stream) - The stream of logs
+///   - [Err](HttpError) - The logs could not be retrieved
+///
+pub fn get_logs(
+  vm_key: &str,
+  query: &CargoLogQuery,
+  docker_api: &Docker,
+) -> Result<impl StreamExt<Item = Result<Bytes, HttpError>>, HttpError> {
+  let stream =
+    docker_api.logs(&format!("{vm_key}.v"), Some(query.clone().into()));
+  let stream = utils::stream::transform_stream::<LogOutput, OutputLog>(stream);
+  Ok(stream)
+}
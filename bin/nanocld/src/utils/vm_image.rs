@@ -5,6 +5,7 @@ use ntex::web;
 use ntex::http;
 use ntex::util::Bytes;
 use ntex::channel::mpsc::Receiver;
+use futures::{Stream, StreamExt};
 use tokio::fs;
 use tokio::io::AsyncReadExt;
 use tokio::process::Command;
@@ -179,6 +180,123 @@ pub async fn create_snap(
   Ok(snap_image)
 }
 
+/// ## Copy disk
+///
+/// Copy the disk of a virtual machine as a `Snapshot` vm image.
+/// The copy keep the same base image than the disk, so it can be restored
+/// later with [restore_disk](restore_disk).
+/// The virtual machine must be stopped to get a consistent copy.
+///
+/// ## Arguments
+///
+/// - [name](str) - The name of the snapshot
+/// - [disk](VmImageDbModel) - The disk of the virtual machine
+/// - [state](DaemonState) - The daemon state
+///
+/// ## Returns
+///
+/// - [Result](Result) - The result of the operation
+///   - [Ok](VmImageDbModel) - The created vm image
+///   - [Err](HttpError) - The vm image has not been created
+///
+pub async fn copy_disk(
+  name: &str,
+  disk: &VmImageDbModel,
+  state: &DaemonState,
+) -> Result<VmImageDbModel, HttpError> {
+  if repositories::vm_image::find_by_name(name, &state.pool)
+    .await
+    .is_ok()
+  {
+    return Err(HttpError {
+      status: http::StatusCode::CONFLICT,
+      msg: format!("Vm image {name} already used"),
+    });
+  }
+  let snapshotpath =
+    format!("{}/vms/images/{}.img", state.config.state_dir, name);
+  fs::copy(&disk.path, &snapshotpath)
+    .await
+    .map_err(|err| HttpError {
+      status: http::StatusCode::INTERNAL_SERVER_ERROR,
+      msg: format!("Failed to copy disk {}: {err}", disk.path),
+    })?;
+  let res = async {
+    let image_info = get_info(&snapshotpath).await?;
+    let snap_image = VmImageDbModel {
+      name: name.to_owned(),
+      created_at: chrono::Utc::now().naive_utc(),
+      kind: "Snapshot".into(),
+      path: snapshotpath.clone(),
+      format: image_info.format,
+      size_actual: image_info.actual_size,
+      size_virtual: image_info.virtual_size,
+      parent: disk.parent.clone(),
+    };
+    let snap_image =
+      repositories::vm_image::create(&snap_image, &state.pool).await?;
+    Ok::<_, HttpError>(snap_image)
+  }
+  .await;
+  // Don't leave an unknown copy on the disk if the image isn't saved
+  if res.is_err() {
+    let _ = fs::remove_file(&snapshotpath).await;
+  }
+  res
+}
+
+/// ## Restore disk
+///
+/// Overwrite the disk of a virtual machine with a snapshot
+/// created by [copy_disk](copy_disk).
+/// The snapshot must share the same base image than the disk.
+/// The virtual machine must be stopped.
+///
+/// ## Arguments
+///
+/// - [disk](VmImageDbModel) - The disk of the virtual machine
+/// - [snapshot](VmImageDbModel) - The snapshot to restore
+/// - [pool](Pool) - The database pool
+///
+/// ## Returns
+///
+/// - [Result](Result) - The result of the operation
+///   - [Ok](VmImageDbModel) - The restored disk
+///   - [Err](HttpError) - The disk has not been restored
+///
+pub async fn restore_disk(
+  disk: &VmImageDbModel,
+  snapshot: &VmImageDbModel,
+  pool: &Pool,
+) -> Result<VmImageDbModel, HttpError> {
+  if snapshot.kind != "Snapshot" || snapshot.parent != disk.parent {
+    return Err(HttpError {
+      status: http::StatusCode::BAD_REQUEST,
+      msg: format!(
+        "Vm image {} is not a snapshot of the base image of {}",
+        snapshot.name, disk.name
+      ),
+    });
+  }
+  fs::copy(&snapshot.path, &disk.path)
+    .await
+    .map_err(|err| HttpError {
+      status: http::StatusCode::INTERNAL_SERVER_ERROR,
+      msg: format!("Failed to restore disk {}: {err}", disk.path),
+    })?;
+  let image_info = get_info(&disk.path).await?;
+  let res = repositories::vm_image::update_by_name(
+    &disk.name,
+    &VmImageUpdateDbModel {
+      size_actual: image_info.actual_size,
+      size_virtual: image_info.virtual_size,
+    },
+    pool,
+  )
+  .await?;
+  Ok(res)
+}
+
 /// ## Clone
 ///
 /// Clone a vm image snapshot from a `Snapshot` vm image.
@@ -334,6 +452,83 @@ pub async fn clone(
   Ok(rx)
 }
 
+/// ## Export
+///
+/// Open a vm image to stream it as a standalone qcow2 image.
+/// A `Snapshot` is first converted with its backing images
+/// into a temporary file removed once opened, so the exported image
+/// doesn't depend on images of this node.
+///
+/// ## Arguments
+///
+/// - [image](VmImageDbModel) - The vm image to export
+/// - [state](DaemonState) - The daemon state
+///
+/// ## Returns
+///
+/// - [Result](Result) - The result of the operation
+///   - [Ok]((u64, Stream)) - The size of the exported image and its content
+///   - [Err](HttpError) - The vm image cannot be exported
+///
+pub async fn export(
+  image: &VmImageDbModel,
+  state: &DaemonState,
+) -> Result<
+  (u64, impl Stream<Item = Result<Bytes, HttpError>> + Unpin),
+  HttpError,
+> {
+  let file = match &image.parent {
+    None => fs::File::open(&image.path).await.map_err(|err| HttpError {
+      status: http::StatusCode::INTERNAL_SERVER_ERROR,
+      msg: format!("Failed to open {}: {err}", image.path),
+    })?,
+    Some(_) => {
+      let exportpath = format!(
+        "{}/vms/images/{}.export.img",
+        state.config.state_dir, image.name
+      );
+      let output = Command::new("qemu-img")
+        .args(["convert", "-O", "qcow2", &image.path, &exportpath])
+        .output()
+        .await
+        .map_err(|err| HttpError {
+          status: http::StatusCode::INTERNAL_SERVER_ERROR,
+          msg: format!("Failed to convert {}: {err}", image.path),
+        })?;
+      if !output.status.success() {
+        let _ = fs::remove_file(&exportpath).await;
+        return Err(HttpError {
+          status: http::StatusCode::INTERNAL_SERVER_ERROR,
+          msg: format!("Failed to convert {}: {output:#?}", image.path),
+        });
+      }
+      let file = fs::File::open(&exportpath).await;
+      let _ = fs::remove_file(&exportpath).await;
+      file.map_err(|err| HttpError {
+        status: http::StatusCode::INTERNAL_SERVER_ERROR,
+        msg: format!("Failed to open {exportpath}: {err}"),
+      })?
+    }
+  };
+  let size = file
+    .metadata()
+    .await
+    .map_err(|err| HttpError {
+      status: http::StatusCode::INTERNAL_SERVER_ERROR,
+      msg: format!("Failed to read the size of {}: {err}", image.name),
+    })?
+    .len();
+  let stream = tokio_util::io::ReaderStream::new(file).map(|chunk| {
+    chunk
+      .map(|chunk| Bytes::copy_from_slice(&chunk))
+      .map_err(|err| HttpError {
+        status: http::StatusCode::INTERNAL_SERVER_ERROR,
+        msg: format!("Failed to read vm image: {err}"),
+      })
+  });
+  Ok((size, stream))
+}
+
 /// ## Resize
 ///
 /// Resize a vm image to a new size
@@ -1,7 +1,11 @@
 #[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
 
-use bollard_next::container::{LogOutput, KillContainerOptions, LogsOptions};
+use bollard_next::container::{
+  LogOutput, KillContainerOptions, LogsOptions, StatsOptions,
+};
+
+pub use bollard_next::container::Stats;
 
 pub use bollard_next::exec::CreateExecOptions;
 
@@ -267,6 +271,43 @@ impl From<CargoLogQuery> for LogsOptions<String> {
   }
 }
 
+/// Stats cargo query
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct CargoStatsQuery {
+  /// Name of the namespace
+  pub namespace: Option<String>,
+  /// Bool, if set stream the stats every second instead of returning once
+  pub stream: Option<bool>,
+}
+
+impl From<CargoStatsQuery> for StatsOptions {
+  fn from(query: CargoStatsQuery) -> StatsOptions {
+    StatsOptions {
+      stream: query.stream.unwrap_or_default(),
+      one_shot: false,
+    }
+  }
+}
+
+/// Query to open an interactive exec session in a cargo
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct CargoExecQuery {
+  /// Name of the namespace
+  pub namespace: Option<String>,
+  /// Command to execute as a json encoded array of arguments
+  pub cmd: String,
+  /// Bool, if set attach the stdin of the command
+  pub stdin: Option<bool>,
+  /// Bool, if set allocate a pseudo-TTY
+  pub tty: Option<bool>,
+  /// Replica of the cargo where the command is executed, default to the first
+  pub replica: Option<usize>,
+}
+
 /// Payload for the cargo scale endpoint
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -35,6 +35,44 @@ pub enum ReplicationMode {
   StaticByNodeGroups { groups: Vec<String>, number: i64 },
   /// NumberByNodeNames is used to manually set the number of replicas in each node name
   StaticByNodeNames { names: Vec<String>, number: i64 },
+  /// Percentage is used to run replicas on a percentage of the available nodes
+  /// The number of nodes is rounded up and at least 1 replica will exist
+  Percentage(ReplicationPercentage),
+}
+
+impl ReplicationMode {
+  /// Validate the replication values
+  /// Return a message describing the first constraint that failed
+  pub fn validate(&self) -> Result<(), String> {
+    match self {
+      ReplicationMode::UniqueByNodeGroups { groups }
+      | ReplicationMode::StaticByNodeGroups { groups, .. }
+        if groups.is_empty() =>
+      {
+        Err("groups must not be empty".to_owned())
+      }
+      ReplicationMode::UniqueByNodeNames { names }
+      | ReplicationMode::StaticByNodeNames { names, .. }
+        if names.is_empty() =>
+      {
+        Err("names must not be empty".to_owned())
+      }
+      ReplicationMode::StaticByNodeGroups { number, .. }
+      | ReplicationMode::StaticByNodeNames { number, .. }
+        if *number < 0 =>
+      {
+        Err(format!("number must not be negative got {number}"))
+      }
+      ReplicationMode::Percentage(ReplicationPercentage { percentage })
+        if *percentage == 0.0 || !(0.0..=100.0).contains(percentage) =>
+      {
+        Err(format!(
+          "percentage must be greater than 0 and at most 100 got {percentage}"
+        ))
+      }
+      _ => Ok(()),
+    }
+  }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -45,6 +83,35 @@ pub struct ReplicationStatic {
   pub number: usize,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct ReplicationPercentage {
+  /// Percentage of the available nodes between 0 and 100
+  pub percentage: f64,
+}
+
+/// Percentages are compared by their bits so NaN equals itself
+/// and `ReplicationMode` can keep implementing `Eq`
+impl PartialEq for ReplicationPercentage {
+  fn eq(&self, other: &Self) -> bool {
+    self.percentage.to_bits() == other.percentage.to_bits()
+  }
+}
+
+impl Eq for ReplicationPercentage {}
+
+impl ReplicationPercentage {
+  /// Number of replicas for the given number of available nodes
+  /// The result is rounded up and is at least 1
+  pub fn replicas(&self, nodes: usize) -> usize {
+    let percentage = self.percentage.clamp(0.0, 100.0);
+    let replicas = (nodes as f64 * percentage / 100.0).ceil() as usize;
+    replicas.max(1)
+  }
+}
+
 /// A cargo config partial is used to create a Cargo
 #[derive(Debug, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
@@ -148,3 +215,91 @@ impl From<CargoInspect> for CargoConfigPartial {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[cfg(feature = "serde")]
+  fn assert_round_trip(mode: ReplicationMode, expected: serde_json::Value) {
+    let value = serde_json::to_value(&mode).unwrap();
+    assert_eq!(value, expected);
+    let parsed = serde_json::from_value::<ReplicationMode>(value).unwrap();
+    assert_eq!(parsed, mode);
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn replication_mode_auto() {
+    assert_round_trip(
+      ReplicationMode::Auto,
+      serde_json::json!({ "Mode": "Auto" }),
+    );
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn replication_mode_static() {
+    assert_round_trip(
+      ReplicationMode::Static(ReplicationStatic { number: 3 }),
+      serde_json::json!({ "Mode": "Static", "Number": 3 }),
+    );
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn replication_mode_percentage() {
+    assert_round_trip(
+      ReplicationMode::Percentage(ReplicationPercentage { percentage: 50.0 }),
+      serde_json::json!({ "Mode": "Percentage", "Percentage": 50.0 }),
+    );
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn replication_mode_unknown() {
+    let err = serde_json::from_value::<ReplicationMode>(
+      serde_json::json!({ "Mode": "Unknown" }),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("unknown variant `Unknown`"));
+  }
+
+  #[test]
+  fn replication_percentage_replicas() {
+    let half = ReplicationPercentage { percentage: 50.0 };
+    assert_eq!(half.replicas(0), 1);
+    assert_eq!(half.replicas(1), 1);
+    assert_eq!(half.replicas(3), 2);
+    assert_eq!(half.replicas(4), 2);
+    let all = ReplicationPercentage { percentage: 100.0 };
+    assert_eq!(all.replicas(5), 5);
+  }
+
+  #[test]
+  fn replication_mode_validate() {
+    let static_by_names = |number| ReplicationMode::StaticByNodeNames {
+      names: vec!["node1".to_owned()],
+      number,
+    };
+    assert!(static_by_names(0).validate().is_ok());
+    assert!(static_by_names(2).validate().is_ok());
+    let err = static_by_names(-1).validate().unwrap_err();
+    assert_eq!(err, "number must not be negative got -1");
+    let err = ReplicationMode::StaticByNodeGroups {
+      groups: vec![],
+      number: 1,
+    }
+    .validate()
+    .unwrap_err();
+    assert_eq!(err, "groups must not be empty");
+    let percentage = |percentage| {
+      ReplicationMode::Percentage(ReplicationPercentage { percentage })
+    };
+    assert!(percentage(100.0).validate().is_ok());
+    assert!(percentage(0.5).validate().is_ok());
+    assert!(percentage(0.0).validate().is_err());
+    assert!(percentage(100.5).validate().is_err());
+    assert!(percentage(f64::NAN).validate().is_err());
+  }
+}
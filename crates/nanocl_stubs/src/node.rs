@@ -10,6 +10,9 @@ use serde::{Serialize, Deserialize};
 pub struct Node {
   pub name: String,
   pub ip_address: String,
+  /// The node is cordoned, no new workloads are created on it
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub unschedulable: bool,
 }
 
 #[derive(Clone, Debug)]
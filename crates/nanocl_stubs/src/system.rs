@@ -24,6 +24,32 @@ pub struct HostInfo {
   pub config: DaemonConfig,
 }
 
+/// DiskUsageItem is the disk consumption of an image or a volume
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct DiskUsageItem {
+  /// Name of the image or the volume
+  pub name: String,
+  /// Size in bytes
+  pub size: i64,
+}
+
+/// DiskUsage contains the disk consumption of the node
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct DiskUsage {
+  /// Container images used by cargoes
+  pub cargo_images: Vec<DiskUsageItem>,
+  /// Images used by virtual machines
+  pub vm_images: Vec<DiskUsageItem>,
+  /// Volumes
+  pub volumes: Vec<DiskUsageItem>,
+}
+
 /// Version contain details about the current version nanocl
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -20,6 +20,9 @@ pub struct VmImage {
   pub size_actual: i64,
   /// The virtual size of the image in bytes
   pub size_virtual: i64,
+  /// The image this one is based on, its backing file
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub parent: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -43,3 +46,22 @@ pub enum VmImageCloneStream {
   /// The result of the clone operation
   Done(VmImage),
 }
+
+/// Query of a vm image import
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct VmImageImportQuery {
+  /// Number of bytes already received to resume an interrupted import
+  pub offset: Option<u64>,
+}
+
+/// State of an interrupted vm image import
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct VmImageImportStatus {
+  /// Number of bytes received so far, the import can resume from there
+  pub offset: u64,
+}
@@ -1,6 +1,11 @@
+use ntex::rt;
+use ntex::ws;
+use ntex::io::Base;
+use ntex::ws::WsConnection;
 use ntex::channel::mpsc;
 use ntex::channel::mpsc::Receiver;
 
+use nanocl_utils::io_error::FromIo;
 use nanocl_utils::http_error::HttpError;
 use nanocl_utils::http_client_error::HttpClientError;
 
@@ -8,7 +13,8 @@ use bollard_next::service::ContainerSummary;
 use nanocl_stubs::generic::GenericNspQuery;
 use nanocl_stubs::cargo::{
   Cargo, CargoSummary, CargoInspect, CreateExecOptions, OutputLog,
-  CargoKillOptions, CargoDeleteQuery, CargoLogQuery,
+  CargoKillOptions, CargoDeleteQuery, CargoLogQuery, CargoExecQuery,
+  CargoStatsQuery, CargoScale, Stats,
 };
 use nanocl_stubs::cargo_config::{
   CargoConfigUpdate, CargoConfigPartial, CargoConfig,
@@ -56,7 +62,39 @@ impl NanocldClient {
         Some(&GenericNspQuery { namespace }),
       )
       .await?;
-    Self::res_json(res).await
+    self.res_json(res).await
+  }
+
+  /// ## Create a cargo idempotently
+  /// Create a new cargo with an idempotency key, when a request timed out
+  /// it can be retried with the same key to get the cargo created
+  /// by the first request instead of a conflict
+  ///
+  /// ## Arguments
+  /// * [item](CargoConfigPartial) - The cargo config to create
+  /// * [namespace](Option<String>) - The namespace to create the cargo in
+  /// * [idempotency_key](str) - A unique key like an uuid shared by the retries
+  ///
+  /// ## Returns
+  /// * [Result](Result)
+  ///   * [Ok](Ok) - The created [cargo](Cargo) and true if it already existed
+  ///   * [Err](HttpClientError) - The cargo could not be created
+  ///
+  pub async fn create_cargo_idempotent(
+    &self,
+    item: &CargoConfigPartial,
+    namespace: Option<String>,
+    idempotency_key: &str,
+  ) -> Result<(Cargo, bool), HttpClientError> {
+    let (res, replayed) = self
+      .send_post_idempotent(
+        format!("/{}/cargoes", &self.version),
+        item,
+        Some(&GenericNspQuery { namespace }),
+        idempotency_key,
+      )
+      .await?;
+    Ok((self.res_json(res).await?, replayed))
   }
 
   /// ## Delete a cargo
@@ -123,7 +161,7 @@ impl NanocldClient {
       )
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   /// ## Start a cargo
@@ -264,7 +302,7 @@ impl NanocldClient {
       )
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   /// ## Patch a cargo
@@ -399,6 +437,56 @@ impl NanocldClient {
     Ok(Self::res_stream(res).await)
   }
 
+  /// ## Attach exec cargo
+  ///
+  /// Open an interactive exec session in a cargo over a websocket.
+  /// Binary frames received are json encoded [OutputLog](OutputLog),
+  /// text frames sent are written to the stdin of the command.
+  ///
+  /// ## Arguments
+  ///
+  /// * [name](str) - The name of the cargo to exec the command in
+  /// * [query](CargoExecQuery) - The command to execute and his options
+  ///
+  /// ## Returns
+  ///
+  /// * [Result](Result)
+  ///   * [Ok](Ok) - The [websocket connection](WsConnection) of the session
+  ///   * [Err](HttpClientError) - The session could not be opened
+  ///
+  pub async fn attach_exec_cargo(
+    &self,
+    name: &str,
+    query: &CargoExecQuery,
+  ) -> Result<WsConnection<Base>, HttpClientError> {
+    let qs = serde_urlencoded::to_string(query)
+      .map_err(|err| err.map_err_context(|| "Exec query"))?;
+    let url = format!(
+      "{}/{}/cargoes/{name}/exec/attach?{qs}",
+      self.url, &self.version
+    );
+    self.log_websocket(&url);
+    // open websockets connection over http transport
+    let con = match &self.unix_socket {
+      Some(path) => ws::WsClient::build(&url)
+        .connector(ntex::service::fn_service(|_| async move {
+          Ok::<_, _>(rt::unix_connect(&path).await?)
+        }))
+        .finish()
+        .map_err(|err| err.map_err_context(|| path))?
+        .connect()
+        .await
+        .map_err(|err| err.map_err_context(|| path))?,
+      None => ws::WsClient::build(&url)
+        .finish()
+        .map_err(|err| err.map_err_context(|| &self.url))?
+        .connect()
+        .await
+        .map_err(|err| err.map_err_context(|| &self.url))?,
+    };
+    Ok(con)
+  }
+
   /// ## List all the cargo histories
   ///
   /// ## Arguments
@@ -433,7 +521,7 @@ impl NanocldClient {
       )
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   /// ## Revert a cargo to a specific history
@@ -473,7 +561,7 @@ impl NanocldClient {
       )
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   /// ## Get the logs of a cargo
@@ -499,6 +587,29 @@ impl NanocldClient {
     Ok(Self::res_stream(res).await)
   }
 
+  /// ## Get the stats of a cargo
+  /// The stats are streamed as a [Receiver](Receiver) of [Stats](Stats)
+  ///
+  /// ## Arguments
+  ///
+  /// * [name](str) - The name of the cargo to get the stats
+  /// * [query](CargoStatsQuery) - The namespace and whether to stream the stats
+  ///
+  pub async fn stats_cargo(
+    &self,
+    name: &str,
+    query: &CargoStatsQuery,
+  ) -> Result<Receiver<Result<Stats, HttpError>>, HttpClientError> {
+    let res = self
+      .send_get(
+        format!("/{}/cargoes/{name}/stats", &self.version),
+        Some(query),
+      )
+      .await?;
+
+    Ok(Self::res_stream(res).await)
+  }
+
   pub async fn kill_cargo(
     &self,
     name: &str,
@@ -528,7 +639,34 @@ impl NanocldClient {
       )
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
+  }
+
+  /// ## Scale a cargo
+  /// Add or remove instances of a cargo on the node the client is connected to,
+  /// a negative number of replicas remove instances
+  ///
+  /// ## Arguments
+  ///
+  /// * [name](str) - The name of the cargo to scale
+  /// * [options](CargoScale) - The number of replicas to add or remove
+  /// * [namespace](Option<String>) - The namespace of the cargo
+  ///
+  pub async fn scale_cargo(
+    &self,
+    name: &str,
+    options: &CargoScale,
+    namespace: Option<String>,
+  ) -> Result<(), HttpClientError> {
+    self
+      .send_patch(
+        format!("/{}/cargoes/{name}/scale", &self.version),
+        Some(options),
+        Some(GenericNspQuery { namespace }),
+      )
+      .await?;
+
+    Ok(())
   }
 }
 
@@ -592,6 +730,36 @@ mod tests {
       .unwrap();
   }
 
+  #[ntex::test]
+  async fn create_idempotent() {
+    const CARGO_NAME: &str = "client-test-idempotent";
+    let client = NanocldClient::connect_to("http://localhost:8585", None);
+    let new_cargo = CargoConfigPartial {
+      name: CARGO_NAME.into(),
+      container: bollard_next::container::Config {
+        image: Some("nexthat/nanocl-get-started:latest".into()),
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    let key = "client-test-idempotent-key";
+    let (cargo, replayed) = client
+      .create_cargo_idempotent(&new_cargo, None, key)
+      .await
+      .unwrap();
+    assert!(!replayed);
+    let (retried, replayed) = client
+      .create_cargo_idempotent(&new_cargo, None, key)
+      .await
+      .unwrap();
+    assert!(replayed);
+    assert_eq!(retried.key, cargo.key);
+    client
+      .delete_cargo(CARGO_NAME, &CargoDeleteQuery::default())
+      .await
+      .unwrap();
+  }
+
   #[ntex::test]
   async fn create_cargo_wrong_image() {
     let client = NanocldClient::connect_to("http://localhost:8585", None);
@@ -665,4 +833,16 @@ mod tests {
       .unwrap();
     let _out = rx.next().await.unwrap().unwrap();
   }
+
+  #[ntex::test]
+  async fn stats_cargo() {
+    let client = NanocldClient::connect_to("http://localhost:8585", None);
+
+    let query = CargoStatsQuery {
+      namespace: Some("system".into()),
+      stream: Some(false),
+    };
+    let mut rx = client.stats_cargo("nstore", &query).await.unwrap();
+    let _stats = rx.next().await.unwrap().unwrap();
+  }
 }
@@ -35,7 +35,7 @@ impl NanocldClient {
     let res = self
       .send_get(format!("/{}/cargoes/images", &self.version), opts)
       .await?;
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   /// ## Create a cargo image
@@ -154,7 +154,7 @@ impl NanocldClient {
         None::<String>,
       )
       .await?;
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   pub async fn import_cargo_image_from_tar<S, E>(
@@ -8,21 +8,45 @@ use ntex::channel::mpsc::Receiver;
 use ntex::connect::openssl::SslMethod;
 use futures::{StreamExt, TryStreamExt};
 
-use nanocl_utils::io_error::FromIo;
+use nanocl_utils::io_error::{FromIo, IoError, IoResult};
 use nanocl_utils::http_error::HttpError;
 use nanocl_utils::http_client_error::HttpClientError;
-use openssl::ssl::SslConnector;
+use openssl::ssl::{SslConnector, SslFiletype};
 
 use crate::error::is_api_error;
+use crate::verbose;
 
 const NANOCLD_DEFAULT_VERSION: &str = "0.9.0";
 
+/// Header holding the idempotency key of a create request
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Header set by the daemon on a response replayed from its idempotency key
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+/// Schemes supported by [NanocldClient::connect_to](NanocldClient::connect_to)
+pub const SUPPORTED_SCHEMES: [&str; 4] =
+  ["unix://", "tcp://", "http://", "https://"];
+
+/// Tls files used to connect to a daemon over `https://`
+#[derive(Clone, Debug, Default)]
+pub struct SslConfig {
+  /// Path to the client certificate in PEM format
+  pub cert: Option<String>,
+  /// Path to the client private key in PEM format
+  pub key: Option<String>,
+  /// Path to the certificate authority in PEM format
+  pub ca: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct NanocldClient {
   pub client: http::client::Client,
   pub url: String,
   pub version: String,
   pub unix_socket: Option<String>,
+  /// Print requests on stderr, from 2 their redacted json bodies too
+  pub verbosity: u8,
 }
 
 impl std::fmt::Display for NanocldClient {
@@ -50,14 +74,62 @@ impl NanocldClient {
       unix_socket: Some(String::from("/run/nanocl/nanocl.sock")),
       version: format!("v{NANOCLD_DEFAULT_VERSION}"),
       url: String::from("http://localhost"),
+      verbosity: 0,
     }
   }
 
   pub fn connect_to(url: &'static str, version: Option<String>) -> Self {
-    let builder = SslConnector::builder(SslMethod::tls()).unwrap().build();
+    match Self::connect_to_with_ssl(url, version, None) {
+      Ok(client) => client,
+      Err(err) => panic!("{err}"),
+    }
+  }
 
+  fn ssl_connector(ssl: Option<&SslConfig>) -> IoResult<SslConnector> {
+    let ssl_err = |err: openssl::error::ErrorStack| {
+      IoError::new(
+        "Ssl",
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, err),
+      )
+    };
+    let mut builder =
+      SslConnector::builder(SslMethod::tls()).map_err(ssl_err)?;
+    if let Some(ssl) = ssl {
+      if let Some(ca) = &ssl.ca {
+        builder.set_ca_file(ca).map_err(ssl_err)?;
+      }
+      if let Some(cert) = &ssl.cert {
+        builder
+          .set_certificate_file(cert, SslFiletype::PEM)
+          .map_err(ssl_err)?;
+      }
+      if let Some(key) = &ssl.key {
+        builder
+          .set_private_key_file(key, SslFiletype::PEM)
+          .map_err(ssl_err)?;
+        builder.check_private_key().map_err(ssl_err)?;
+      }
+    }
+    Ok(builder.build())
+  }
+
+  pub fn connect_to_with_ssl(
+    url: &'static str,
+    version: Option<String>,
+    ssl: Option<SslConfig>,
+  ) -> IoResult<Self> {
+    let builder = Self::ssl_connector(ssl.as_ref())?;
     match url {
-      url if url.starts_with("http://") || url.starts_with("https://") => {
+      url
+        if url.starts_with("http://")
+          || url.starts_with("https://")
+          || url.starts_with("tcp://") =>
+      {
+        // tcp:// is an alias of http://
+        let url = match url.strip_prefix("tcp://") {
+          Some(addr) => format!("http://{addr}"),
+          None => url.to_owned(),
+        };
         let client = http::client::Client::build()
           .connector(
             http::client::Connector::default()
@@ -67,12 +139,13 @@ impl NanocldClient {
           )
           .timeout(ntex::time::Millis::from_secs(100))
           .finish();
-        NanocldClient {
-          url: url.into(),
+        Ok(NanocldClient {
+          url,
           client,
           unix_socket: None,
           version: version.unwrap_or(format!("v{NANOCLD_DEFAULT_VERSION}")),
-        }
+          verbosity: 0,
+        })
       }
       url if url.starts_with("unix://") => {
         let path = url.trim_start_matches("unix://");
@@ -89,14 +162,21 @@ impl NanocldClient {
           )
           .timeout(ntex::time::Millis::from_secs(100))
           .finish();
-        NanocldClient {
+        Ok(NanocldClient {
           url: "http://localhost".into(),
           client,
           unix_socket: Some(path.into()),
           version: version.unwrap_or(format!("v{NANOCLD_DEFAULT_VERSION}")),
-        }
+          verbosity: 0,
+        })
       }
-      _ => panic!("Invalid url: {}", url),
+      _ => Err(IoError::invalid_input(
+        "Host".to_owned(),
+        format!(
+          "invalid url {url}, supported schemes are {}",
+          SUPPORTED_SCHEMES.join(", ")
+        ),
+      )),
     }
   }
 
@@ -104,6 +184,46 @@ impl NanocldClient {
     self.version = format!("v{version}")
   }
 
+  pub fn set_verbosity(&mut self, verbosity: u8) {
+    self.verbosity = verbosity
+  }
+
+  /// Print the method and the url of a request on stderr when verbose,
+  /// the returned line is printed again with the response status
+  fn log_request(&self, req: &http::client::ClientRequest) -> Option<String> {
+    if self.verbosity == 0 {
+      return None;
+    }
+    let line = format!("{} {}", req.get_method(), req.get_uri());
+    eprintln!("> {line}");
+    Some(line)
+  }
+
+  /// Print the url of a websocket connection on stderr when verbose
+  pub(crate) fn log_websocket(&self, url: &str) {
+    if self.verbosity > 0 {
+      eprintln!("> GET {url} (websocket)");
+    }
+  }
+
+  /// Print the redacted json body of a request on stderr from a verbosity of 2
+  fn log_body<B>(&self, body: &B)
+  where
+    B: serde::Serialize,
+  {
+    if self.verbosity < 2 {
+      return;
+    }
+    eprintln!("> {}", verbose::redacted_body(body));
+  }
+
+  /// Print the status of a response on stderr when verbose
+  fn log_response(&self, line: &Option<String>, status: &http::StatusCode) {
+    if let Some(line) = line {
+      eprintln!("< {status} {line}");
+    }
+  }
+
   fn send_error(
     &self,
     err: http::client::error::SendRequestError,
@@ -134,6 +254,7 @@ impl NanocldClient {
       unix_socket: Some(String::from("/run/nanocl/nanocl.sock")),
       version: version.to_owned(),
       url: String::from("http://localhost"),
+      verbosity: 0,
     }
   }
 
@@ -196,9 +317,11 @@ impl NanocldClient {
         .query(&query)
         .map_err(|err| err.map_err_context(|| "Query"))?;
     }
+    let line = self.log_request(&req);
     let mut res = req.send().await.map_err(|err| self.send_error(err))?;
 
     let status = res.status();
+    self.log_response(&line, &status);
     is_api_error(&mut res, &status).await?;
     Ok(res)
   }
@@ -209,25 +332,82 @@ impl NanocldClient {
     body: Option<B>,
     query: Option<Q>,
   ) -> Result<http::client::ClientResponse, HttpClientError>
+  where
+    B: serde::Serialize,
+    Q: serde::Serialize,
+  {
+    self.send_post_with_headers(url, body, query, &[]).await
+  }
+
+  /// ## Send post idempotent
+  ///
+  /// Send a create request with an `Idempotency-Key` header,
+  /// the daemon reply with the original response when a request
+  /// with the same key is retried instead of a conflict
+  ///
+  /// ## Returns
+  ///
+  /// * [Result](Result)
+  ///   * [Ok](Ok) - The response and true if it was replayed from the key
+  ///   * [Err](HttpClientError) - The request failed
+  ///
+  pub(crate) async fn send_post_idempotent<Q, B>(
+    &self,
+    url: String,
+    body: &B,
+    query: Option<Q>,
+    idempotency_key: &str,
+  ) -> Result<(http::client::ClientResponse, bool), HttpClientError>
+  where
+    B: serde::Serialize,
+    Q: serde::Serialize,
+  {
+    let res = self
+      .send_post_with_headers(
+        url,
+        Some(body),
+        query,
+        &[(IDEMPOTENCY_KEY_HEADER, idempotency_key)],
+      )
+      .await?;
+    let replayed = res.headers().contains_key(IDEMPOTENT_REPLAYED_HEADER);
+    Ok((res, replayed))
+  }
+
+  async fn send_post_with_headers<Q, B>(
+    &self,
+    url: String,
+    body: Option<B>,
+    query: Option<Q>,
+    headers: &[(&str, &str)],
+  ) -> Result<http::client::ClientResponse, HttpClientError>
   where
     B: serde::Serialize,
     Q: serde::Serialize,
   {
     let mut req = self.post(url);
+    for (name, value) in headers {
+      req = req.header(*name, *value);
+    }
     if let Some(query) = query {
       req = req
         .query(&query)
         .map_err(|err| err.map_err_context(|| "Query"))?;
     }
+    let line = self.log_request(&req);
     let mut res = match body {
       None => req.send().await.map_err(|err| self.send_error(err))?,
-      Some(body) => req
-        .send_json(&body)
-        .await
-        .map_err(|err| self.send_error(err))?,
+      Some(body) => {
+        self.log_body(&body);
+        req
+          .send_json(&body)
+          .await
+          .map_err(|err| self.send_error(err))?
+      }
     };
 
     let status = res.status();
+    self.log_response(&line, &status);
     is_api_error(&mut res, &status).await?;
     Ok(res)
   }
@@ -249,12 +429,14 @@ impl NanocldClient {
         .query(&query)
         .map_err(|err| err.map_err_context(|| "Query"))?;
     }
+    let line = self.log_request(&req);
     let mut res = req
       .send_stream(stream)
       .await
       .map_err(|err| self.send_error(err))?;
 
     let status = res.status();
+    self.log_response(&line, &status);
     is_api_error(&mut res, &status).await?;
 
     Ok(res)
@@ -274,9 +456,11 @@ impl NanocldClient {
         .query(&query)
         .map_err(|err| err.map_err_context(|| "Query"))?;
     }
+    let line = self.log_request(&req);
     let mut res = req.send().await.map_err(|err| self.send_error(err))?;
 
     let status = res.status();
+    self.log_response(&line, &status);
     is_api_error(&mut res, &status).await?;
 
     Ok(res)
@@ -298,15 +482,20 @@ impl NanocldClient {
         .query(&query)
         .map_err(|err| err.map_err_context(|| "Query"))?;
     }
+    let line = self.log_request(&req);
     let mut res = match body {
       None => req.send().await.map_err(|err| self.send_error(err))?,
-      Some(body) => req
-        .send_json(&body)
-        .await
-        .map_err(|err| self.send_error(err))?,
+      Some(body) => {
+        self.log_body(&body);
+        req
+          .send_json(&body)
+          .await
+          .map_err(|err| self.send_error(err))?
+      }
     };
 
     let status = res.status();
+    self.log_response(&line, &status);
     is_api_error(&mut res, &status).await?;
 
     Ok(res)
@@ -327,9 +516,11 @@ impl NanocldClient {
         .map_err(|err| err.map_err_context(|| "Query"))?;
     }
 
+    let line = self.log_request(&req);
     let mut res = req.send().await.map_err(|err| self.send_error(err))?;
 
     let status = res.status();
+    self.log_response(&line, &status);
     is_api_error(&mut res, &status).await?;
 
     Ok(res)
@@ -351,26 +542,45 @@ impl NanocldClient {
         .query(&query)
         .map_err(|err| err.map_err_context(|| "Query"))?;
     }
+    let line = self.log_request(&req);
     let mut res = match body {
       None => req.send().await.map_err(|err| self.send_error(err))?,
-      Some(body) => req
-        .send_json(&body)
-        .await
-        .map_err(|err| self.send_error(err))?,
+      Some(body) => {
+        self.log_body(&body);
+        req
+          .send_json(&body)
+          .await
+          .map_err(|err| self.send_error(err))?
+      }
     };
 
     let status = res.status();
+    self.log_response(&line, &status);
     is_api_error(&mut res, &status).await?;
 
     Ok(res)
   }
 
   pub(crate) async fn res_json<R>(
+    &self,
     mut res: http::client::ClientResponse,
   ) -> Result<R, HttpClientError>
   where
     R: serde::de::DeserializeOwned + Send + 'static,
   {
+    if self.verbosity >= 2 {
+      let body = res
+        .body()
+        .limit(20_000_000)
+        .await
+        .map_err(|err| err.map_err_context(|| "Payload limit 20_000_000"))?;
+      let body = serde_json::from_slice::<serde_json::Value>(&body)
+        .map_err(|err| err.map_err_context(|| "Response body"))?;
+      eprintln!("< {}", verbose::redacted_body(&body));
+      let body = serde_json::from_value::<R>(body)
+        .map_err(|err| err.map_err_context(|| "Response body"))?;
+      return Ok(body);
+    }
     let body = res
       .json::<R>()
       .limit(20_000_000)
@@ -389,7 +599,7 @@ impl NanocldClient {
     let (tx, rx) = ntex::channel::mpsc::channel();
     rt::spawn(async move {
       let mut payload: Vec<u8> = Vec::new();
-      while let Some(item) = stream.next().await {
+      'stream: while let Some(item) = stream.next().await {
         let bytes = match item {
           Ok(bytes) => bytes,
           Err(e) => {
@@ -401,22 +611,26 @@ impl NanocldClient {
           }
         };
         payload.extend(bytes.to_vec());
-        if bytes.last() != Some(&b'\n') {
-          continue;
-        }
-        let t = match serde_json::from_slice::<R>(&payload) {
-          Ok(t) => t,
-          Err(e) => {
-            let _ = tx.send(Err(HttpError {
-              status: http::StatusCode::INTERNAL_SERVER_ERROR,
-              msg: format!("Unable to parse stream got error : {e}"),
-            }));
-            break;
+        // A chunk can contain several lines and end with a partial one
+        // so only complete lines are parsed and the rest is kept for later
+        while let Some(pos) = payload.iter().position(|b| *b == b'\n') {
+          let line = payload.drain(..=pos).collect::<Vec<u8>>();
+          if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+          }
+          let t = match serde_json::from_slice::<R>(&line) {
+            Ok(t) => t,
+            Err(e) => {
+              let _ = tx.send(Err(HttpError {
+                status: http::StatusCode::INTERNAL_SERVER_ERROR,
+                msg: format!("Unable to parse stream got error : {e}"),
+              }));
+              break 'stream;
+            }
+          };
+          if tx.send(Ok(t)).is_err() {
+            break 'stream;
           }
-        };
-        payload.clear();
-        if tx.send(Ok(t)).is_err() {
-          break;
         }
       }
       tx.close();
@@ -424,3 +638,29 @@ impl NanocldClient {
     rx
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[ntex::test]
+  async fn connect_to_schemes() {
+    let client =
+      NanocldClient::connect_to_with_ssl("tcp://localhost:8585", None, None)
+        .unwrap();
+    assert_eq!(client.url, "http://localhost:8585");
+    assert!(client.unix_socket.is_none());
+    let client =
+      NanocldClient::connect_to_with_ssl("https://localhost:8585", None, None)
+        .unwrap();
+    assert_eq!(client.url, "https://localhost:8585");
+    let client =
+      NanocldClient::connect_to_with_ssl("unix:///run/nanocl.sock", None, None)
+        .unwrap();
+    assert_eq!(client.unix_socket.as_deref(), Some("/run/nanocl.sock"));
+    let err = NanocldClient::connect_to_with_ssl("ftp://localhost", None, None)
+      .err()
+      .unwrap();
+    assert!(err.to_string().contains("tcp://"));
+  }
+}
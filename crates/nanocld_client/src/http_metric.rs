@@ -12,7 +12,7 @@ impl NanocldClient {
       .send_get(format!("/{}/http_metrics", &self.version), query)
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 }
 
@@ -1,4 +1,5 @@
 mod http_client;
+mod verbose;
 
 pub(crate) mod namespace;
 pub(crate) mod cargo;
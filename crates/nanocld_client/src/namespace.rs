@@ -28,7 +28,7 @@ impl NanocldClient {
       .send_get(format!("/{}/namespaces", &self.version), None::<String>)
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   /// ## Create a new namespace
@@ -56,7 +56,7 @@ impl NanocldClient {
       )
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   /// ## Inspect a namespace
@@ -93,7 +93,7 @@ impl NanocldClient {
       )
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   /// ## Delete a namespace
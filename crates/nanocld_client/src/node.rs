@@ -10,7 +10,48 @@ impl NanocldClient {
       .send_get(format!("/{}/nodes", &self.version), None::<String>)
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
+  }
+
+  /// ## Cordon a node
+  /// No new cargoes, cargo instances or virtual machines are created on it
+  ///
+  /// ## Arguments
+  ///
+  /// * [name](str) - The name of the node
+  ///
+  pub async fn cordon_node(&self, name: &str) -> Result<Node, HttpClientError> {
+    let res = self
+      .send_post(
+        format!("/{}/nodes/{name}/cordon", &self.version),
+        None::<String>,
+        None::<String>,
+      )
+      .await?;
+
+    self.res_json(res).await
+  }
+
+  /// ## Uncordon a node
+  /// Workloads can be created on the node again
+  ///
+  /// ## Arguments
+  ///
+  /// * [name](str) - The name of the node
+  ///
+  pub async fn uncordon_node(
+    &self,
+    name: &str,
+  ) -> Result<Node, HttpClientError> {
+    let res = self
+      .send_post(
+        format!("/{}/nodes/{name}/uncordon", &self.version),
+        None::<String>,
+        None::<String>,
+      )
+      .await?;
+
+    self.res_json(res).await
   }
 }
 
@@ -23,5 +64,7 @@ mod tests {
     let client = NanocldClient::connect_to("http://localhost:8585", None);
     let node = client.list_node().await;
     assert!(node.is_ok());
+    assert!(client.cordon_node("unknown-node").await.is_err());
+    assert!(client.uncordon_node("unknown-node").await.is_err());
   }
 }
@@ -34,7 +34,7 @@ impl NanocldClient {
       .send_get(format!("/{}/resources", &self.version), query)
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   /// ## Create resource
@@ -78,7 +78,7 @@ impl NanocldClient {
       )
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   /// ## Inspect resource
@@ -115,7 +115,44 @@ impl NanocldClient {
       )
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
+  }
+
+  /// ## Inspect resource kind schema
+  ///
+  /// Get the JSON schema of the last version of a resource kind
+  ///
+  /// ## Arguments
+  ///
+  /// * [kind](str) - The name of the resource kind
+  ///
+  /// ## Returns
+  ///
+  /// * [Result](Result) - The result of the operation
+  ///   * [Ok](serde_json::Value) - The JSON schema of the kind
+  ///   * [Err](HttpClientError) - An error if the operation failed
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let schema = client.inspect_resource_kind_schema("ProxyRule").await;
+  /// ```
+  ///
+  pub async fn inspect_resource_kind_schema(
+    &self,
+    kind: &str,
+  ) -> Result<serde_json::Value, HttpClientError> {
+    let res = self
+      .send_get(
+        format!("/{}/resources/kinds/{kind}/schema", &self.version),
+        None::<String>,
+      )
+      .await?;
+
+    self.res_json(res).await
   }
 
   /// ## Patch resource
@@ -155,7 +192,7 @@ impl NanocldClient {
       )
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   /// ## Delete resource
@@ -206,7 +243,7 @@ impl NanocldClient {
       )
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   pub async fn revert_resource(
@@ -222,7 +259,7 @@ impl NanocldClient {
       )
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 }
 
@@ -275,6 +312,13 @@ mod tests {
     assert_eq!(resource.name, "test_resource2");
     assert_eq!(resource.kind, String::from("Kind"));
 
+    // kind schema
+    let schema = client
+      .inspect_resource_kind_schema("test_resource2")
+      .await
+      .unwrap();
+    assert_eq!(&schema, &config["Schema"]);
+
     let new_resource = ResourceUpdate {
       version: "v0.0.2".to_owned(),
       config: config.clone(),
@@ -4,7 +4,7 @@ use nanocl_utils::http_error::HttpError;
 use nanocl_utils::http_client_error::HttpClientError;
 
 use nanocl_stubs::node::NodeContainerSummary;
-use nanocl_stubs::system::{Event, Version, HostInfo, ProccessQuery};
+use nanocl_stubs::system::{Event, Version, HostInfo, ProccessQuery, DiskUsage};
 
 use super::http_client::NanocldClient;
 
@@ -31,7 +31,7 @@ impl NanocldClient {
       .send_get(format!("/{}/version", &self.version), None::<String>)
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   /// ## Watch events
@@ -118,7 +118,7 @@ impl NanocldClient {
       .send_get(format!("/{}/info", &self.version), None::<String>)
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   pub async fn process(
@@ -129,7 +129,34 @@ impl NanocldClient {
       .send_get(format!("/{}/processes", &self.version), opts)
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
+  }
+
+  /// ## Disk usage
+  ///
+  /// Get the disk usage of cargo images, vm images and volumes
+  ///
+  /// ## Returns
+  ///
+  /// * [Result](Result)
+  ///   * [Ok](Ok) - The [DiskUsage](DiskUsage)
+  ///   * [Err](HttpClientError) - The disk usage could not be retrieved
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let usage = client.disk_usage().await.unwrap();
+  /// ```
+  ///
+  pub async fn disk_usage(&self) -> Result<DiskUsage, HttpClientError> {
+    let res = self
+      .send_get(format!("/{}/disk-usage", &self.version), None::<String>)
+      .await?;
+
+    self.res_json(res).await
   }
 }
 
@@ -160,4 +187,12 @@ mod tests {
 
     assert!(info.docker.containers.unwrap() > 0);
   }
+
+  #[ntex::test]
+  async fn disk_usage() {
+    let client = NanocldClient::connect_to("http://localhost:8585", None);
+    let usage = client.disk_usage().await.unwrap();
+
+    assert!(!usage.cargo_images.is_empty());
+  }
 }
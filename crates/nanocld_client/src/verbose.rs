@@ -0,0 +1,91 @@
+/// Replacement of the redacted values
+const REDACTED: &str = "<redacted>";
+
+/// Parts of a field or variable name holding a secret
+const SECRET_NAMES: [&str; 7] = [
+  "password",
+  "passwd",
+  "secret",
+  "token",
+  "credential",
+  "private",
+  "auth",
+];
+
+/// Check if a field or variable name looks like it holds a secret
+fn is_secret(name: &str) -> bool {
+  let name = name.to_lowercase();
+  SECRET_NAMES.iter().any(|secret| name.contains(secret))
+}
+
+/// Redact in place the values of a json body that look like secrets,
+/// fields with a secret name and `NAME=value` strings like env variables
+pub(crate) fn redact(value: &mut serde_json::Value) {
+  match value {
+    serde_json::Value::Object(map) => {
+      for (key, value) in map.iter_mut() {
+        if is_secret(key) && !value.is_null() {
+          *value = serde_json::Value::String(REDACTED.to_owned());
+          continue;
+        }
+        redact(value);
+      }
+    }
+    serde_json::Value::Array(items) => items.iter_mut().for_each(redact),
+    serde_json::Value::String(string) => {
+      if let Some((name, _)) = string.split_once('=') {
+        if is_secret(name) {
+          *string = format!("{name}={REDACTED}");
+        }
+      }
+    }
+    _ => {}
+  }
+}
+
+/// Serialize a body for the verbose output with its secrets redacted
+pub(crate) fn redacted_body<B>(body: &B) -> String
+where
+  B: serde::Serialize,
+{
+  match serde_json::to_value(body) {
+    Ok(mut value) => {
+      redact(&mut value);
+      value.to_string()
+    }
+    Err(err) => format!("<unserializable body: {err}>"),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn redact_secrets() {
+    let mut value = serde_json::json!({
+      "Name": "db",
+      "Config": {
+        "Password": "hunter2",
+        "AuthConfig": { "Username": "me" },
+        "Env": ["POSTGRES_PASSWORD=hunter2", "PGDATA=/data", "API_TOKEN"],
+        "Cmd": ["--secret=abc", "run"],
+        "Token": null,
+      },
+    });
+    redact(&mut value);
+    assert_eq!(
+      value,
+      serde_json::json!({
+        "Name": "db",
+        "Config": {
+          "Password": "<redacted>",
+          "AuthConfig": "<redacted>",
+          "Env": ["POSTGRES_PASSWORD=<redacted>", "PGDATA=/data", "API_TOKEN"],
+          "Cmd": ["--secret=<redacted>", "run"],
+          "Token": null,
+        },
+      })
+    );
+  }
+}
@@ -2,12 +2,16 @@ use ntex::rt;
 use ntex::ws;
 use ntex::io::Base;
 use ntex::ws::WsConnection;
+use ntex::channel::mpsc::Receiver;
 
 use nanocl_utils::io_error::FromIo;
+use nanocl_utils::http_error::HttpError;
 use nanocl_utils::http_client_error::HttpClientError;
 
 use nanocl_stubs::generic::GenericNspQuery;
+use nanocl_stubs::cargo::{OutputLog, CargoLogQuery};
 use nanocl_stubs::vm::{Vm, VmSummary, VmInspect};
+use nanocl_stubs::vm_image::VmImage;
 use nanocl_stubs::vm_config::{VmConfigPartial, VmConfigUpdate};
 
 use crate::NanocldClient;
@@ -26,7 +30,38 @@ impl NanocldClient {
       )
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
+  }
+
+  /// ## Create a vm idempotently
+  /// Create a new virtual machine with an idempotency key,
+  /// see [create_cargo_idempotent](NanocldClient::create_cargo_idempotent)
+  ///
+  /// ## Arguments
+  /// * [vm](VmConfigPartial) - The virtual machine config to create
+  /// * [namespace](Option<String>) - The namespace to create the vm in
+  /// * [idempotency_key](str) - A unique key like an uuid shared by the retries
+  ///
+  /// ## Returns
+  /// * [Result](Result)
+  ///   * [Ok](Ok) - The created [vm](Vm) and true if it already existed
+  ///   * [Err](HttpClientError) - The vm could not be created
+  ///
+  pub async fn create_vm_idempotent(
+    &self,
+    vm: &VmConfigPartial,
+    namespace: Option<String>,
+    idempotency_key: &str,
+  ) -> Result<(Vm, bool), HttpClientError> {
+    let (res, replayed) = self
+      .send_post_idempotent(
+        format!("/{}/vms", self.version),
+        vm,
+        Some(&GenericNspQuery { namespace }),
+        idempotency_key,
+      )
+      .await?;
+    Ok((self.res_json(res).await?, replayed))
   }
 
   pub async fn list_vm(
@@ -40,7 +75,7 @@ impl NanocldClient {
       )
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   pub async fn delete_vm(
@@ -70,7 +105,7 @@ impl NanocldClient {
       )
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   pub async fn start_vm(
@@ -122,6 +157,52 @@ impl NanocldClient {
     Ok(())
   }
 
+  pub async fn logs_vm(
+    &self,
+    name: &str,
+    query: &CargoLogQuery,
+  ) -> Result<Receiver<Result<OutputLog, HttpError>>, HttpClientError> {
+    let res = self
+      .send_get(format!("/{}/vms/{name}/logs", &self.version), Some(query))
+      .await?;
+
+    Ok(Self::res_stream(res).await)
+  }
+
+  pub async fn snapshot_vm(
+    &self,
+    name: &str,
+    snapshot_name: &str,
+    namespace: Option<String>,
+  ) -> Result<VmImage, HttpClientError> {
+    let res = self
+      .send_post(
+        format!("/{}/vms/{name}/snapshot/{snapshot_name}", self.version),
+        None::<String>,
+        Some(&GenericNspQuery { namespace }),
+      )
+      .await?;
+
+    self.res_json(res).await
+  }
+
+  pub async fn restore_vm(
+    &self,
+    name: &str,
+    snapshot_name: &str,
+    namespace: Option<String>,
+  ) -> Result<VmImage, HttpClientError> {
+    let res = self
+      .send_post(
+        format!("/{}/vms/{name}/restore/{snapshot_name}", self.version),
+        None::<String>,
+        Some(&GenericNspQuery { namespace }),
+      )
+      .await?;
+
+    self.res_json(res).await
+  }
+
   pub async fn attach_vm(
     &self,
     name: &str,
@@ -133,6 +214,7 @@ impl NanocldClient {
       "".to_string()
     };
     let url = format!("{}/{}/vms/{name}/attach{qs}", self.url, &self.version);
+    self.log_websocket(&url);
     // open websockets connection over http transport
     let con = match &self.unix_socket {
       Some(path) => ws::WsClient::build(&url)
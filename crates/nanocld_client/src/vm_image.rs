@@ -1,13 +1,17 @@
 use std::error::Error;
 
+use ntex::http;
 use ntex::util::Bytes;
 use ntex::channel::mpsc;
-use futures::Stream;
+use futures::{Stream, StreamExt, TryStreamExt};
 
 use nanocl_utils::http_error::HttpError;
 use nanocl_utils::http_client_error::HttpClientError;
 
-use nanocl_stubs::vm_image::{VmImage, VmImageCloneStream, VmImageResizePayload};
+use nanocl_stubs::vm_image::{
+  VmImage, VmImageCloneStream, VmImageResizePayload, VmImageImportQuery,
+  VmImageImportStatus,
+};
 
 use crate::NanocldClient;
 
@@ -31,12 +35,66 @@ impl NanocldClient {
     Ok(())
   }
 
+  /// ## Resume vm image import
+  ///
+  /// Send the remaining bytes of an interrupted vm image import
+  ///
+  /// ## Arguments
+  ///
+  /// * [name](str) The name of the vm image
+  /// * [offset](u64) The number of bytes already received by the daemon
+  /// * [stream](Stream) The bytes of the vm image after the offset
+  ///
+  pub async fn resume_vm_image_import<S, E>(
+    &self,
+    name: &str,
+    offset: u64,
+    stream: S,
+  ) -> Result<(), HttpClientError>
+  where
+    S: Stream<Item = Result<Bytes, E>> + Unpin + 'static,
+    E: Error + 'static,
+  {
+    self
+      .send_post_stream(
+        format!("/{}/vms/images/{name}/import", self.version),
+        stream,
+        Some(VmImageImportQuery {
+          offset: Some(offset),
+        }),
+      )
+      .await?;
+    Ok(())
+  }
+
+  /// ## Inspect vm image import
+  ///
+  /// Get the number of bytes received by an interrupted vm image import
+  ///
+  /// ## Arguments
+  ///
+  /// * [name](str) The name of the vm image
+  ///
+  pub async fn inspect_vm_image_import(
+    &self,
+    name: &str,
+  ) -> Result<VmImageImportStatus, HttpClientError> {
+    let res = self
+      .send_get(
+        format!("/{}/vms/images/{name}/import", self.version),
+        None::<String>,
+      )
+      .await?;
+
+    self.res_json(res).await
+  }
+
   pub async fn list_vm_image(&self) -> Result<Vec<VmImage>, HttpClientError> {
     let res = self
       .send_get(format!("/{}/vms/images", self.version), None::<String>)
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 
   pub async fn delete_vm_image(
@@ -72,6 +130,47 @@ impl NanocldClient {
     Ok(Self::res_stream(res).await)
   }
 
+  /// ## Export a vm image
+  /// The image is streamed as a standalone qcow2 image
+  ///
+  /// ## Arguments
+  ///
+  /// * [name](str) - The name of the vm image to export
+  ///
+  /// ## Return
+  ///
+  /// The size of the exported image and a stream of its content
+  ///
+  pub async fn export_vm_image(
+    &self,
+    name: &str,
+  ) -> Result<
+    (
+      u64,
+      impl Stream<Item = Result<Bytes, std::io::Error>> + 'static,
+    ),
+    HttpClientError,
+  > {
+    let res = self
+      .send_get(
+        format!("/{}/vms/images/{name}/export", self.version),
+        None::<String>,
+      )
+      .await?;
+    let size = res
+      .headers()
+      .get(http::header::CONTENT_LENGTH)
+      .and_then(|size| size.to_str().ok())
+      .and_then(|size| size.parse::<u64>().ok())
+      .unwrap_or_default();
+    let stream = res.into_stream().map(|chunk| {
+      chunk.map_err(|err| {
+        std::io::Error::new(std::io::ErrorKind::UnexpectedEof, err.to_string())
+      })
+    });
+    Ok((size, stream))
+  }
+
   pub async fn resize_vm_image(
     &self,
     name: &str,
@@ -85,6 +184,6 @@ impl NanocldClient {
       )
       .await?;
 
-    Self::res_json(res).await
+    self.res_json(res).await
   }
 }